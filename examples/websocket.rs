@@ -75,11 +75,11 @@ fn echo_handler(
           info!("Received binary data, echoing data back as is");
           sender.send(WebsocketMessage::Binary(binary))?;
         }
-        WebsocketMessage::Ping => {
+        WebsocketMessage::Ping(payload) => {
           info!("Received ping, responding with pong");
-          sender.send(WebsocketMessage::Pong)?;
+          sender.send(WebsocketMessage::Pong(payload))?;
         }
-        WebsocketMessage::Pong => {
+        WebsocketMessage::Pong(_) => {
           info!("Received pong");
         }
       },
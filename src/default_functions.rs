@@ -1,20 +1,135 @@
+use crate::http::headers::HeaderName;
+use crate::http::method::Method;
+use crate::http::mime::{AcceptMimeType, MimeType};
 use crate::http::request_context::RequestContext;
-use crate::http::{Response, StatusCode};
-use crate::tii_error::{TiiError, TiiResult};
+use crate::http::{RequestHead, Response, StatusCode};
+use crate::tii_error::{
+  BodyParsingError, PathParamError, RequestHeadParsingError, TiiError, TiiResult,
+};
 use crate::tii_router::{Routeable, RoutingDecision};
 use crate::{error_log, info_log};
 use std::collections::HashSet;
+use std::io;
 
 pub(crate) fn default_pre_routing_filter(_request: &RequestContext) -> TiiResult<bool> {
   Ok(true)
 }
 
+/// Methods a POST may be overridden to by `method_override_filter`.
+const OVERRIDABLE_METHODS: &[Method] = &[Method::Put, Method::Patch, Method::Delete];
+
+/// Largest url encoded form `method_override_filter` reads to find the `_method` field.
+const METHOD_OVERRIDE_FORM_LIMIT: u64 = 8 * 1024;
+
+/// Pre routing filter that changes the method of a POST request to the one given in the
+/// `X-HTTP-Method-Override` header or the `_method` field of an url encoded form.
+pub(crate) fn method_override_filter(request: &mut RequestContext) -> TiiResult<Option<Response>> {
+  if request.request_head().method() != &Method::Post {
+    return Ok(None);
+  }
+
+  let mut method = request
+    .request_head()
+    .get_header("X-HTTP-Method-Override")
+    .map(|value| value.trim().to_string());
+
+  if method.is_none()
+    && request.request_head().get_content_type().map(MimeType::as_str)
+      == Some("application/x-www-form-urlencoded")
+  {
+    // Forms that are too large or malformed are left to the route, they just don't override the method.
+    method = request
+      .form_params_with_limit(METHOD_OVERRIDE_FORM_LIMIT)
+      .ok()
+      .and_then(|params| params.into_iter().find(|(key, _)| key == "_method"))
+      .map(|(_, value)| value.trim().to_string());
+  }
+
+  let Some(method) = method.map(|method| Method::from(&method.to_ascii_uppercase())) else {
+    return Ok(None);
+  };
+
+  if !OVERRIDABLE_METHODS.contains(&method) {
+    info_log!("Ignoring method override of POST {} to {}", request.request_head().path(), method);
+    return Ok(None);
+  }
+
+  request.request_head_mut().set_method(method);
+  Ok(None)
+}
+
 /// The default error handler for every Tii app.
 /// This can be overridden by using the `with_error_handler` method when building the app.
 pub(crate) fn default_error_handler(
   request: &mut RequestContext,
   error: TiiError,
 ) -> TiiResult<Response> {
+  // Errors of the body reader are wrapped in an io::Error.
+  let body_error = error.downcast_ref::<BodyParsingError>().or_else(|| {
+    error
+      .downcast_ref::<io::Error>()
+      .and_then(|err| err.get_ref())
+      .and_then(|err| err.downcast_ref::<BodyParsingError>())
+  });
+
+  match body_error {
+    Some(BodyParsingError::UnsupportedMediaType(_) | BodyParsingError::UnsupportedCharset(_)) => {
+      info_log!(
+        "Unsupported Media Type {} {} {}",
+        &request.request_head().method(),
+        request.request_head().path(),
+        error
+      );
+      return Ok(Response::new(StatusCode::UnsupportedMediaType));
+    }
+    Some(
+      BodyParsingError::MissingBody
+      | BodyParsingError::Malformed(_)
+      | BodyParsingError::TrailerTooLarge(_)
+      | BodyParsingError::InvalidChunkSize(_),
+    ) => {
+      info_log!(
+        "Bad Request {} {} {}",
+        &request.request_head().method(),
+        request.request_head().path(),
+        error
+      );
+      return Ok(Response::new(StatusCode::BadRequest));
+    }
+    Some(BodyParsingError::TooManyChunks(_) | BodyParsingError::TooLarge(_)) => {
+      info_log!(
+        "Content Too Large {} {} {}",
+        &request.request_head().method(),
+        request.request_head().path(),
+        error
+      );
+      return Ok(Response::new(StatusCode::ContentTooLarge));
+    }
+    _ => (),
+  }
+
+  if let Some(RequestHeadParsingError::MissingSecWebSocketKeyHeader) =
+    error.downcast_ref::<RequestHeadParsingError>()
+  {
+    info_log!(
+      "Bad WebSocket handshake {} {} {}",
+      &request.request_head().method(),
+      request.request_head().path(),
+      error
+    );
+    return Ok(Response::new(StatusCode::BadRequest));
+  }
+
+  if error.downcast_ref::<PathParamError>().is_some() {
+    info_log!(
+      "Bad path parameter {} {} {}",
+      &request.request_head().method(),
+      request.request_head().path(),
+      error
+    );
+    return Ok(Response::new(StatusCode::BadRequest));
+  }
+
   error_log!(
     "Internal Server Error {} {} {:?}",
     &request.request_head().method(),
@@ -77,6 +192,22 @@ pub(crate) fn default_method_not_allowed_handler(
   Ok(Response::method_not_allowed(methods.as_slice()))
 }
 
+pub(crate) fn default_method_not_implemented_handler(
+  request: &mut RequestContext,
+  routes: &[Routeable],
+) -> TiiResult<Response> {
+  info_log!(
+    "Method not implemented {} {}",
+    &request.request_head().method(),
+    request.request_head().path()
+  );
+  let mut methods = routes.iter().map(Routeable::method).cloned().collect::<Vec<_>>();
+  methods.sort();
+  methods.dedup();
+
+  Ok(Response::not_implemented(methods.as_slice()))
+}
+
 pub(crate) fn default_unsupported_media_type_handler(
   request: &mut RequestContext,
   _: &[Routeable],
@@ -89,3 +220,44 @@ pub(crate) fn default_unsupported_media_type_handler(
   );
   Ok(Response::unsupported_media_type_no_body())
 }
+
+/// The built-in error body renderer, see `TiiBuilder::with_negotiated_error_bodies`.
+pub(crate) fn negotiated_error_body(request: &RequestHead, response: &mut Response) {
+  let preferred = request.get_accept().iter().find_map(|accept| match accept.get_type() {
+    AcceptMimeType::Specific(mime @ (MimeType::TextHtml | MimeType::ApplicationJson)) => Some(mime),
+    _ => None,
+  });
+
+  let code = response.status_code.code();
+  let (body, mime) = match preferred {
+    Some(MimeType::TextHtml) => {
+      let title = format!("{} {}", code, escape_html(response.status_code.status_line()));
+      (format!("<!DOCTYPE html><html><head><title>{title}</title></head><body><h1>{title}</h1></body></html>"), MimeType::TextHtml)
+    }
+    Some(MimeType::ApplicationJson) => {
+      let error = escape_json(response.status_code.status_line());
+      (format!("{{\"error\":\"{error}\",\"status\":{code}}}"), MimeType::ApplicationJson)
+    }
+    _ => return,
+  };
+
+  response.headers.set(HeaderName::ContentType, mime.as_str());
+  response.body = Some(body.into());
+}
+
+fn escape_html(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn escape_json(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for char in text.chars() {
+    match char {
+      '"' => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      char if char.is_control() => escaped.push_str(format!("\\u{:04x}", char as u32).as_str()),
+      char => escaped.push(char),
+    }
+  }
+  escaped
+}
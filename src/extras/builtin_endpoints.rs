@@ -3,12 +3,15 @@ use crate::http::headers::HeaderName;
 use crate::http::response_body::ResponseBody;
 use crate::http::{Response, StatusCode};
 
-use crate::http::mime::MimeType;
+use crate::http::mime::{AcceptQualityMimeType, MimeType};
 use crate::http::request_context::RequestContext;
 use crate::tii_error::TiiResult;
-use std::fs::{metadata, File};
+use crate::util;
+use std::fmt::Write;
+use std::fs::{metadata, read_dir, File};
 use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 const INDEX_FILES: [&str; 2] = ["index.html", "index.htm"];
 
@@ -20,26 +23,69 @@ pub enum LocatedPath {
   File(PathBuf),
 }
 
-fn try_file_open(path: &PathBuf) -> TiiResult<Response> {
+/// Strength of the ETag that file endpoints derive from the size and modification time of a file.
+/// Together with the `Last-Modified` header this allows clients to revalidate cached files,
+/// unchanged files are answered with `304 Not Modified` without a body.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum ETagStrength {
+  /// `W/"..."`, the file is only claimed to be semantically equivalent.
+  /// This is the safe choice as the ETag is not computed from the content itself.
+  #[default]
+  Weak,
+  /// `"..."`, the file is claimed to be byte for byte identical, this permits range requests to be combined.
+  /// Only use this if files are never modified without changing their size or modification time.
+  Strong,
+}
+
+fn try_file_open(path: &PathBuf, etag: ETagStrength) -> TiiResult<Response> {
   let mime = MimeType::from_extension(
     path.extension().map(|a| a.to_string_lossy().to_string()).unwrap_or("".to_string()).as_str(),
   );
-  Ok(File::open(path).and_then(ResponseBody::from_file).map(|a| Response::ok(a, mime)).or_else(
-    |e| {
-      if e.kind() == ErrorKind::NotFound {
-        Ok(Response::not_found_no_body())
-      } else {
-        Err(e)
-      }
-    },
-  )?)
+  let file = match File::open(path) {
+    Ok(file) => file,
+    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Response::not_found_no_body()),
+    Err(e) => return Err(e.into()),
+  };
+
+  let modified = file
+    .metadata()
+    .and_then(|meta| meta.modified())
+    .ok()
+    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+  let mut response = Response::ok(ResponseBody::from_file(file)?, mime);
+  if let (Some(modified), Some(size)) =
+    (modified, response.body().and_then(ResponseBody::content_length))
+  {
+    let prefix = match etag {
+      ETagStrength::Weak => "W/",
+      ETagStrength::Strong => "",
+    };
+    response.set_header(
+      HeaderName::ETag,
+      format!("{}\"{:x}-{:x}\"", prefix, size, modified.as_nanos()),
+    )?;
+    response.set_header(HeaderName::LastModified, util::http_date(modified.as_secs()))?;
+  }
+
+  Ok(response)
 }
 
 /// Serve the specified file, or a default error 404 if not found.
+/// The file is copied to the connection in fixed size chunks and never read into memory as a whole.
 pub fn serve_file(file_path: &'static str) -> impl Fn(&RequestContext) -> TiiResult<Response> {
   let path_buf = PathBuf::from(file_path);
 
-  move |_| try_file_open(&path_buf)
+  move |_| try_file_open(&path_buf, ETagStrength::Weak)
+}
+
+/// Serve the specified file like `serve_file` with an ETag of the given strength.
+pub fn serve_file_with_etag(
+  file_path: &'static str,
+  etag: ETagStrength,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  let path_buf = PathBuf::from(file_path);
+
+  move |_| try_file_open(&path_buf, etag)
 }
 
 /// Treat the request URI as a file path relative to the given directory and serve files from there.
@@ -47,7 +93,7 @@ pub fn serve_file(file_path: &'static str) -> impl Fn(&RequestContext) -> TiiRes
 /// ## Examples
 /// - directory path of `.` will serve files relative to the current directory
 /// - directory path of `./static` will serve files from the static directory but with their whole URI,
-///     for example a request to `/images/ferris.png` will map to the file `./static/images/ferris.png`.
+///   for example a request to `/images/ferris.png` will map to the file `./static/images/ferris.png`.
 ///
 /// This is **not** equivalent to `serve_dir`, as `serve_dir` respects index files within nested directories.
 pub fn serve_as_file_path(
@@ -61,7 +107,7 @@ pub fn serve_as_file_path(
 
     let path_buf = PathBuf::from(path);
 
-    try_file_open(&path_buf)
+    try_file_open(&path_buf, ETagStrength::Weak)
   }
 }
 
@@ -70,30 +116,228 @@ pub fn serve_as_file_path(
 /// Respects index files with the following rules:
 ///   - requests to `/directory` will return either the file `directory`, 301 redirect to `/directory/` if it is a directory, or return 404
 ///   - requests to `/directory/` will return either the file `/directory/index.html` or `/directory/index.htm`, or return 404
+///
+/// The route is stripped of its trailing wildcard and the remainder of the request path is resolved relative to the directory.
+/// This also applies to the root of the mount:
+///   - mounted at `/*` a request to `/` returns the index file of the directory itself
+///   - mounted at `/static/*` a request to `/static/` returns the index file of the directory itself
+///     and a request to `/static` is redirected to `/static/`
+///
+/// Files are served with a weak ETag and a `Last-Modified` header, see `ETagStrength`.
 pub fn serve_dir(directory_path: &'static str) -> impl Fn(&RequestContext) -> TiiResult<Response> {
   move |request: &RequestContext| {
-    let route = request.routed_path();
-    let route_without_wildcard = route.strip_suffix('*').unwrap_or(route);
-    let uri_without_route = request
-      .request_head()
-      .path()
-      .strip_prefix(route_without_wildcard)
-      .unwrap_or(request.routed_path());
-
-    let located = try_find_path(directory_path, uri_without_route, &INDEX_FILES);
-
-    if let Some(located) = located {
-      match located {
-        LocatedPath::Directory => Ok(
-          Response::new(StatusCode::MovedPermanently)
-            .with_header(HeaderName::Location, format!("{}/", &request.request_head().path()))?,
-        ),
-        LocatedPath::File(path) => try_file_open(&path),
+    serve_dir_impl(std::slice::from_ref(&directory_path), request, false, false, ETagStrength::Weak)
+  }
+}
+
+/// Serves a directory of files like `serve_dir` with ETags of the given strength.
+pub fn serve_dir_with_etag(
+  directory_path: &'static str,
+  etag: ETagStrength,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |request: &RequestContext| {
+    serve_dir_impl(std::slice::from_ref(&directory_path), request, false, false, etag)
+  }
+}
+
+/// Serves files from a chain of directories like `serve_dir`, the first directory that contains a match wins.
+///
+/// This allows layered static assets, for example a site specific override directory
+/// followed by a directory with the default assets:
+/// `serve_dirs(&["./site", "./default"])` serves `./site/style.css` if it exists and `./default/style.css` otherwise.
+/// A directory match in an earlier directory also shadows later directories, it is redirected like in `serve_dir`.
+pub fn serve_dirs(
+  directory_paths: &'static [&'static str],
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |request: &RequestContext| {
+    serve_dir_impl(directory_paths, request, false, false, ETagStrength::Weak)
+  }
+}
+
+/// Serves a directory of files like `serve_dir` and lists directories without an index file as JSON.
+///
+/// Requests to `/directory/` where the directory contains no index file return a JSON array
+/// if the client prefers `application/json` over `text/html` in its `Accept` header.
+/// Each entry has the form `{"name":"file.txt","size":12,"is_dir":false,"modified":1700000000}`,
+/// `modified` is in seconds since the unix epoch or `null` if the platform does not provide it.
+/// Entries are sorted by name. All other clients receive 404 as with `serve_dir`.
+pub fn serve_dir_with_listing(
+  directory_path: &'static str,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |request: &RequestContext| {
+    serve_dir_impl(std::slice::from_ref(&directory_path), request, true, false, ETagStrength::Weak)
+  }
+}
+
+/// Serves a directory of files like `serve_dir` and prefers language variants of files.
+///
+/// For a request that resolves to `page.html` the languages of the `Accept-Language` header are tried
+/// in order of preference, for example `page.fr-CA.html` and then `page.fr.html` for `fr-CA`.
+/// The first variant that exists is served, otherwise `page.html` itself.
+/// Responses carry `Vary: Accept-Language` so caches keep the variants apart.
+pub fn serve_dir_localized(
+  directory_path: &'static str,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |request: &RequestContext| {
+    serve_dir_impl(std::slice::from_ref(&directory_path), request, false, true, ETagStrength::Weak)
+  }
+}
+
+fn serve_dir_impl(
+  directory_paths: &[&str],
+  request: &RequestContext,
+  json_listing: bool,
+  localized: bool,
+  etag: ETagStrength,
+) -> TiiResult<Response> {
+  let route = request.routed_path();
+  let route_without_wildcard = route.strip_suffix('*').unwrap_or(route);
+  let path = request.request_head().path();
+  let Some(uri_without_route) = path.strip_prefix(route_without_wildcard) else {
+    if route_without_wildcard.strip_suffix('/') == Some(path) {
+      // The root of the mount without the trailing slash.
+      return Response::new(StatusCode::MovedPermanently)
+        .with_header(HeaderName::Location, format!("{}/", path));
+    }
+
+    return Ok(Response::new(StatusCode::NotFound));
+  };
+
+  let located = directory_paths
+    .iter()
+    .find_map(|directory_path| try_find_path(directory_path, uri_without_route, &INDEX_FILES));
+
+  if let Some(located) = located {
+    match located {
+      LocatedPath::Directory => Ok(
+        Response::new(StatusCode::MovedPermanently)
+          .with_header(HeaderName::Location, format!("{}/", &request.request_head().path()))?,
+      ),
+      LocatedPath::File(path) if localized => {
+        let path = try_find_language_variant(&path, request).unwrap_or(path);
+        let mut response = try_file_open(&path, etag)?;
+        response.add_header("Vary", "Accept-Language")?;
+        Ok(response)
+      }
+      LocatedPath::File(path) => try_file_open(&path, etag),
+    }
+  } else if json_listing && prefers_json(request.request_head().get_accept()) {
+    let listed = directory_paths
+      .iter()
+      .find_map(|directory_path| try_find_listed_directory(directory_path, uri_without_route));
+    match listed {
+      Some(path) => list_directory_json(&path),
+      None => Ok(Response::new(StatusCode::NotFound)),
+    }
+  } else {
+    Ok(Response::new(StatusCode::NotFound))
+  }
+}
+
+/// Finds the variant of the file for the most preferred language of the request that exists.
+/// The primary language is tried after each language with a region, `page.en.html` after `page.en-US.html`.
+fn try_find_language_variant(path: &Path, request: &RequestContext) -> Option<PathBuf> {
+  let stem = path.file_stem()?.to_str()?;
+  let extension = path.extension().and_then(|extension| extension.to_str());
+
+  for (language, _) in request.preferred_languages() {
+    // Only plain language tags, this also keeps path separators out of the file name.
+    if language.is_empty() || !language.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+      continue;
+    }
+
+    let primary = language.split('-').next().unwrap_or_default();
+    let fallback = (primary != language).then_some(primary);
+    for tag in std::iter::once(language.as_str()).chain(fallback) {
+      let file_name = match extension {
+        Some(extension) => format!("{stem}.{tag}.{extension}"),
+        None => format!("{stem}.{tag}"),
+      };
+      let variant = path.with_file_name(file_name);
+      if metadata(&variant).is_ok_and(|meta| meta.is_file()) {
+        return Some(variant);
       }
-    } else {
-      Ok(Response::new(StatusCode::NotFound))
     }
   }
+
+  None
+}
+
+/// Returns true if the most preferred accepted mime type that permits json or html only permits json.
+fn prefers_json(accept: &[AcceptQualityMimeType]) -> bool {
+  accept
+    .iter()
+    .map(AcceptQualityMimeType::get_type)
+    .find(|accept| {
+      accept.permits_specific(MimeType::ApplicationJson)
+        || accept.permits_specific(MimeType::TextHtml)
+    })
+    .is_some_and(|accept| !accept.permits_specific(MimeType::TextHtml))
+}
+
+/// Attempts to find a directory that should be listed.
+/// Only paths ending with '/' refer to the directory, consistent with the index file lookup.
+fn try_find_listed_directory(directory: &str, request_path: &str) -> Option<PathBuf> {
+  // Avoid path traversal exploits
+  if request_path.contains("..") || request_path.contains(':') {
+    return None;
+  }
+
+  let request_path = request_path.trim_start_matches('/');
+  if !request_path.ends_with('/') && !request_path.is_empty() {
+    return None;
+  }
+
+  let path = PathBuf::from(format!("{}/{}", directory.trim_end_matches('/'), request_path));
+  metadata(&path).ok()?.is_dir().then_some(path)
+}
+
+fn list_directory_json(path: &PathBuf) -> TiiResult<Response> {
+  let mut entries = Vec::new();
+  for entry in read_dir(path)? {
+    let entry = entry?;
+    let meta = entry.metadata()?;
+    let modified = meta
+      .modified()
+      .ok()
+      .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+      .map(|modified| modified.as_secs().to_string())
+      .unwrap_or_else(|| "null".to_string());
+    entries.push((entry.file_name().to_string_lossy().to_string(), meta, modified));
+  }
+  entries.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+  let mut json = String::from("[");
+  for (idx, (name, meta, modified)) in entries.iter().enumerate() {
+    if idx != 0 {
+      json.push(',');
+    }
+    json.push_str("{\"name\":");
+    write_json_string(&mut json, name);
+    _ = write!(
+      json,
+      ",\"size\":{},\"is_dir\":{},\"modified\":{}}}",
+      meta.len(),
+      meta.is_dir(),
+      modified
+    );
+  }
+  json.push(']');
+
+  Ok(Response::ok(json, MimeType::ApplicationJson))
+}
+
+fn write_json_string(json: &mut String, value: &str) {
+  json.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => json.push_str("\\\""),
+      '\\' => json.push_str("\\\\"),
+      c if c.is_control() => _ = write!(json, "\\u{:04x}", c as u32),
+      c => json.push(c),
+    }
+  }
+  json.push('"');
 }
 
 /// Attempts to find a given path.
@@ -132,7 +376,59 @@ fn try_find_path(directory: &str, request_path: &str, index_files: &[&str]) -> O
   None
 }
 
-/// Redirects requests to the given location with status code 301.
+/// Redirects requests to the given location with status code 308, the same as `redirect_permanent`.
 pub fn redirect(location: &'static str) -> impl Fn(&RequestContext) -> TiiResult<Response> {
   move |_| Ok(Response::permanent_redirect_no_body(location))
 }
+
+/// Redirects requests to the given location with status code 302 Found.
+/// Clients may change the method of the redirected request to GET.
+pub fn redirect_found(location: &'static str) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |_| Ok(Response::found_no_body(location))
+}
+
+/// Redirects requests to the given location with status code 303 See Other.
+/// Clients always follow with a GET request, use this after handling a form POST.
+pub fn redirect_see_other(
+  location: &'static str,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |_| Ok(Response::see_other_no_body(location))
+}
+
+/// Redirects requests to the given location with status code 307 Temporary Redirect.
+/// Clients repeat the request with the same method and body.
+pub fn redirect_temporary(
+  location: &'static str,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |_| Ok(Response::temporary_redirect_no_body(location))
+}
+
+/// Redirects requests to the given location with status code 308 Permanent Redirect.
+/// Clients repeat the request with the same method and body.
+pub fn redirect_permanent(
+  location: &'static str,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |_| Ok(Response::permanent_redirect_no_body(location))
+}
+
+/// Liveness probe for container orchestration, always answers `200 OK` with the body `OK`.
+pub fn health() -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  |_| Ok(Response::ok("OK", MimeType::TextPlain))
+}
+
+/// Readiness probe for container orchestration.
+/// Calls the check for every request and answers `200 OK` with the body `READY` if it returns true,
+/// `503 Service Unavailable` with the body `NOT READY` otherwise.
+pub fn readiness(
+  check: impl Fn() -> bool + Send + Sync,
+) -> impl Fn(&RequestContext) -> TiiResult<Response> {
+  move |_| {
+    if check() {
+      return Ok(Response::ok("READY", MimeType::TextPlain));
+    }
+
+    Response::new(StatusCode::ServiceUnavailable)
+      .with_body_string("NOT READY")
+      .with_header(HeaderName::ContentType, MimeType::TextPlain.as_str())
+  }
+}
@@ -0,0 +1,143 @@
+use crate::http::response_body::ResponseBody;
+use crate::http::{Response, StatusCode};
+use crate::{error_log, warn_log};
+use std::io;
+use std::io::Read;
+use std::process::{Child, ChildStderr, Command, ExitStatus, Stdio};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Only this many bytes of stderr are retained for logging, the rest is discarded.
+const MAX_STDERR_LOG: usize = 0x1_00_00;
+
+/// How often the exit status of the child is polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Spawns the command and streams its stdout as chunked response body.
+/// This is meant for endpoints that shell out to other programs, for example to convert an image.
+///
+/// The status code is decided once the child has either written its first output or exited:
+/// - if the child writes output the response is `200 OK`, no Content-Type is set.
+/// - if the child exits without output the response is `200 OK` with an empty body
+///   if the exit code is 0, otherwise `500 Internal Server Error`.
+/// - if the command cannot be spawned the response is `500 Internal Server Error`.
+///
+/// Once the status line is sent it can no longer be changed. If the child exits with a nonzero
+/// exit code after it wrote output then the chunked body is aborted without its terminating chunk,
+/// so the client can tell that the response is incomplete.
+///
+/// The child is killed if it has not exited after `timeout` counted from spawning it, this is treated
+/// like a nonzero exit code. Stdin of the child is closed. Stderr is captured and logged if the child fails.
+pub fn spawn_response(mut command: Command, timeout: Option<Duration>) -> Response {
+  let program = command.get_program().to_string_lossy().to_string();
+  let mut child =
+    match command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+      Ok(child) => child,
+      Err(err) => {
+        error_log!("spawn_response failed to spawn {}: {}", &program, &err);
+        return Response::new(StatusCode::InternalServerError);
+      }
+    };
+
+  let Some(mut stdout) = child.stdout.take() else {
+    crate::util::unreachable();
+  };
+  let stderr = child.stderr.take().map(capture_stderr);
+  let reaper = ChildReaper { program, reaper: reap(child, timeout), stderr };
+
+  let mut first = vec![0u8; 0x1_00_00];
+  let read = match stdout.read(first.as_mut_slice()) {
+    Ok(read) => read,
+    Err(err) => {
+      error_log!("spawn_response failed to read stdout of {}: {}", &reaper.program, &err);
+      _ = reaper.finish();
+      return Response::new(StatusCode::InternalServerError);
+    }
+  };
+
+  if read == 0 {
+    return match reaper.finish() {
+      Ok(()) => Response::new(StatusCode::OK).with_body(Vec::new()),
+      Err(_) => Response::new(StatusCode::InternalServerError),
+    };
+  }
+
+  Response::new(StatusCode::OK).with_body(ResponseBody::chunked(move |sink| {
+    let mut buffer = first;
+    let mut read = read;
+    let copied = loop {
+      if read == 0 {
+        break Ok(());
+      }
+
+      if let Err(err) = sink.write_all(buffer.get(..read).unwrap_or_default()) {
+        break Err(err);
+      }
+
+      read = match stdout.read(buffer.as_mut_slice()) {
+        Ok(read) => read,
+        Err(err) => break Err(err),
+      };
+    };
+
+    // The child is still reaped if writing to the client failed, killing it at the latest after the timeout.
+    drop(stdout);
+    let finished = reaper.finish();
+    copied?;
+    finished
+  }))
+}
+
+struct ChildReaper {
+  program: String,
+  reaper: JoinHandle<io::Result<ExitStatus>>,
+  stderr: Option<JoinHandle<Vec<u8>>>,
+}
+
+impl ChildReaper {
+  /// Waits for the child to exit. Fails if it exited with a nonzero exit code or was killed.
+  fn finish(self) -> io::Result<()> {
+    let status = self.reaper.join().unwrap_or_else(|_| Err(io::Error::other("reaper panicked")));
+    let stderr = self.stderr.and_then(|stderr| stderr.join().ok()).unwrap_or_default();
+    let error = match status {
+      Ok(status) if status.success() => return Ok(()),
+      Ok(status) => io::Error::other(format!("{} failed with {}", &self.program, status)),
+      Err(err) => err,
+    };
+
+    warn_log!("spawn_response {} stderr: {}", &error, String::from_utf8_lossy(stderr.as_slice()));
+    Err(error)
+  }
+}
+
+fn capture_stderr(mut stderr: ChildStderr) -> JoinHandle<Vec<u8>> {
+  thread::spawn(move || {
+    let mut captured = Vec::new();
+    _ = (&mut stderr).take(MAX_STDERR_LOG as u64).read_to_end(&mut captured);
+    // Keep draining so the child does not block on a full pipe.
+    _ = io::copy(&mut stderr, &mut io::sink());
+    captured
+  })
+}
+
+/// Waits for the child to exit on a separate thread, killing it once the timeout expires.
+fn reap(mut child: Child, timeout: Option<Duration>) -> JoinHandle<io::Result<ExitStatus>> {
+  let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+  thread::spawn(move || loop {
+    if let Some(status) = child.try_wait()? {
+      return Ok(status);
+    }
+
+    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+      child.kill()?;
+      child.wait()?;
+      return Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "child process timed out and was killed",
+      ));
+    }
+
+    thread::sleep(POLL_INTERVAL);
+  })
+}
@@ -0,0 +1,116 @@
+use crate::functional_traits::Clock;
+use crate::util::unwrap_poison;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// How often a queued connection checks the clock of the server for the end of its queue timeout.
+const QUEUE_CLOCK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Decides what a connector does with a new connection while the connection limit is reached.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ConnectionLimitPolicy {
+  /// Immediately respond with `503 Service Unavailable` and close the connection.
+  Reject,
+  /// Hold the accepted connection until another connection closes, but at most for the given duration.
+  /// If no capacity frees up in time the connection is answered with `503 Service Unavailable`.
+  ///
+  /// While a connection is held the connector does not accept further connections,
+  /// those queue up in the listen backlog of the OS, so the queue is bounded by the backlog.
+  /// The duration should not exceed the connection timeout of the server, as the client is already waiting.
+  /// It is measured with the clock of the server, see `TiiBuilder::with_clock`.
+  /// Shutting down the connector rejects the held connection right away.
+  Queue(Duration),
+}
+
+/// Limits the amount of connections a connector handles concurrently.
+#[derive(Debug)]
+pub struct ConnectionLimit {
+  max_connections: usize,
+  policy: ConnectionLimitPolicy,
+  active: Mutex<usize>,
+  released: Condvar,
+  shutdown: AtomicBool,
+}
+
+/// Held by a connection for as long as it is being handled. Dropping it frees up capacity.
+#[derive(Debug)]
+pub(crate) struct ConnectionPermit(Arc<ConnectionLimit>);
+
+impl Drop for ConnectionPermit {
+  fn drop(&mut self) {
+    if let Ok(mut active) = unwrap_poison(self.0.active.lock()) {
+      *active = active.saturating_sub(1);
+      self.0.released.notify_one();
+    }
+  }
+}
+
+impl ConnectionLimit {
+  /// Creates a new connection limit that permits up to `max_connections` concurrent connections.
+  pub fn new(max_connections: usize, policy: ConnectionLimitPolicy) -> Self {
+    Self {
+      max_connections: max_connections.max(1),
+      policy,
+      active: Mutex::new(0),
+      released: Condvar::new(),
+      shutdown: AtomicBool::new(false),
+    }
+  }
+
+  /// The maximum amount of concurrent connections.
+  pub fn max_connections(&self) -> usize {
+    self.max_connections
+  }
+
+  /// The policy for connections that exceed the limit.
+  pub fn policy(&self) -> ConnectionLimitPolicy {
+    self.policy
+  }
+
+  /// The amount of connections that are currently being handled.
+  pub fn active_connections(&self) -> usize {
+    unwrap_poison(self.active.lock()).map(|active| *active).unwrap_or_default()
+  }
+
+  /// Acquires a permit for a new connection according to the policy.
+  /// Returns None if the connection should be rejected.
+  pub(crate) fn acquire(self: &Arc<Self>, clock: &dyn Clock) -> Option<ConnectionPermit> {
+    let mut active = unwrap_poison(self.active.lock()).ok()?;
+    if *active < self.max_connections {
+      *active += 1;
+      return Some(ConnectionPermit(self.clone()));
+    }
+
+    let ConnectionLimitPolicy::Queue(timeout) = self.policy else {
+      return None;
+    };
+
+    let deadline = clock.now() + timeout;
+    while *active >= self.max_connections {
+      let remaining = deadline.saturating_duration_since(clock.now());
+      if remaining.is_zero() || self.shutdown.load(SeqCst) {
+        return None;
+      }
+
+      // The clock may not be the system clock, so it is checked again after a while.
+      let wait = remaining.min(QUEUE_CLOCK_INTERVAL);
+      active = unwrap_poison(self.released.wait_timeout(active, wait)).ok()?.0;
+    }
+
+    *active += 1;
+    Some(ConnectionPermit(self.clone()))
+  }
+
+  /// Rejects the connection that waits for a permit, later connections no longer wait either.
+  /// Called when the connector shuts down.
+  pub(crate) fn shutdown(&self) {
+    self.shutdown.store(true, SeqCst);
+    if let Ok(guard) = unwrap_poison(self.active.lock()) {
+      self.released.notify_all();
+      drop(guard);
+    }
+  }
+}
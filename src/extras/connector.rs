@@ -1,7 +1,10 @@
 use crate::tii_builder::ThreadAdapterJoinHandle;
-use crate::tii_server::ConnectionStreamMetadata;
+use crate::tii_server::{ConnectionDrain, ConnectionStreamMetadata};
+use crate::util::unwrap_poison;
+use crate::{error_log, info_log};
 use std::any::Any;
-use std::fmt::{Display, Formatter};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
 use std::sync::atomic::Ordering::SeqCst;
 use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::{Arc, Condvar, Mutex};
@@ -44,6 +47,14 @@ pub trait Connector {
   /// If this fn returned true then the shutdown is completed, false if timeout occurred.
   /// This fn does not stop an ongoing shutdown if it times out.
   fn join(&self, timeout: Option<Duration>) -> bool;
+
+  /// Stops accepting new connections and waits up to `deadline` for the accepted connections to finish.
+  /// A connection that is in the middle of a request is given the time to write its response,
+  /// which is sent with `Connection: close`. Keep-alive connections waiting for their next request are closed right away.
+  /// Connections that are still open after the deadline, for example websockets,
+  /// are closed forcibly and a handler still writing its response will fail.
+  /// Returns true if all connections were done before the deadline.
+  fn shutdown_graceful(&self, deadline: Duration) -> bool;
 }
 
 ///Metadata type appended by the extras Tii Connectors.
@@ -70,6 +81,19 @@ impl ConnectionStreamMetadata for ConnectorMeta {
   fn as_any(&self) -> &dyn Any {
     self
   }
+
+  fn is_secure(&self) -> bool {
+    match self {
+      ConnectorMeta::Tcp => false,
+      #[cfg(feature = "tls")]
+      ConnectorMeta::TlsTcp => true,
+      #[cfg(unix)]
+      ConnectorMeta::Unix => false,
+      #[cfg(unix)]
+      #[cfg(feature = "tls")]
+      ConnectorMeta::TlsUnix => true,
+    }
+  }
 }
 impl Display for ConnectorMeta {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -78,6 +102,108 @@ impl Display for ConnectorMeta {
   }
 }
 
+/// The connections a connector is currently handling, so they can be drained or closed forcibly.
+#[derive(Default)]
+pub(crate) struct OpenConnections(Mutex<OpenConnectionsState>);
+
+#[derive(Default)]
+struct OpenConnectionsState {
+  connections: HashMap<u128, OpenConnection>,
+  /// Set once the connections are drained, connections registered afterward are drained right away.
+  draining: bool,
+}
+
+struct OpenConnection {
+  close: Box<dyn Fn() + Send>,
+  drain: Arc<ConnectionDrain>,
+}
+
+impl Debug for OpenConnections {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let open = self.0.lock().map(|guard| guard.connections.len()).unwrap_or_default();
+    f.debug_struct("OpenConnections").field("open", &open).finish()
+  }
+}
+
+impl OpenConnections {
+  /// Registers a connection. `close` must shut down its socket.
+  /// The connection is unregistered once the returned guard is dropped.
+  pub(crate) fn register(
+    self: &Arc<Self>,
+    id: u128,
+    close: impl Fn() + Send + 'static,
+  ) -> OpenConnectionGuard {
+    let drain = Arc::new(ConnectionDrain::default());
+    if let Ok(mut guard) = unwrap_poison(self.0.lock()) {
+      if guard.draining {
+        drain.drain();
+      }
+      guard.connections.insert(id, OpenConnection { close: Box::new(close), drain: drain.clone() });
+    }
+    OpenConnectionGuard(self.clone(), id, drain)
+  }
+
+  /// Tells all open connections to end after their current request.
+  /// Connections that are waiting for a request are closed right away.
+  pub(crate) fn drain_all(&self) {
+    if let Ok(mut guard) = unwrap_poison(self.0.lock()) {
+      guard.draining = true;
+      guard
+        .connections
+        .values()
+        .filter(|connection| connection.drain.drain())
+        .for_each(|connection| (connection.close)());
+    }
+  }
+
+  /// Shuts down the sockets of all open connections.
+  pub(crate) fn close_all(&self) {
+    if let Ok(guard) = unwrap_poison(self.0.lock()) {
+      guard.connections.values().for_each(|connection| (connection.close)());
+    }
+  }
+}
+
+/// Unregisters the connection from `OpenConnections` when dropped.
+#[derive(Debug)]
+pub(crate) struct OpenConnectionGuard(Arc<OpenConnections>, u128, Arc<ConnectionDrain>);
+
+impl OpenConnectionGuard {
+  /// The drain that must be passed to `TiiServer::handle_connection_draining` for this connection.
+  pub(crate) fn drain(&self) -> Arc<ConnectionDrain> {
+    self.2.clone()
+  }
+}
+
+impl Drop for OpenConnectionGuard {
+  fn drop(&mut self) {
+    if let Ok(mut guard) = unwrap_poison(self.0 .0.lock()) {
+      guard.connections.remove(&self.1);
+    }
+  }
+}
+
+/// Implementation of `Connector::shutdown_graceful` shared by all connectors.
+pub(crate) fn shutdown_graceful(
+  connector: &dyn Connector,
+  open_connections: &OpenConnections,
+  name: &str,
+  deadline: Duration,
+) -> bool {
+  connector.shutdown();
+  open_connections.drain_all();
+  if connector.join(Some(deadline)) {
+    return true;
+  }
+
+  info_log!("{}: graceful shutdown deadline reached, closing open connections", name);
+  open_connections.close_all();
+  if !connector.join(Some(CONNECTOR_SHUTDOWN_TIMEOUT)) {
+    error_log!("{}: connections did not finish after they were closed", name);
+  }
+  false
+}
+
 #[derive(Debug)]
 pub(crate) struct ActiveConnection {
   pub(crate) id: u128,
@@ -14,6 +14,21 @@ pub use unix_connector::*;
 mod tcp_connector;
 pub use tcp_connector::*;
 
+mod rate_limit;
+pub use rate_limit::*;
+
+mod connection_limit;
+pub use connection_limit::*;
+
+mod thread_pool;
+pub use thread_pool::*;
+
+mod command_response;
+pub use command_response::*;
+
+mod sse_broadcaster;
+pub use sse_broadcaster::*;
+
 /// Websocket application that spawns 2 threads per connection.
 /// It conveniently handles the WS Heartbeats and broadcasts.
 mod websocket_broadcaster;
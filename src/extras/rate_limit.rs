@@ -0,0 +1,94 @@
+use crate::util::unwrap_poison;
+use crate::{info_log, trace_log};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Limits the rate of new connections per source ip address.
+///
+/// Each ip address gets its own token bucket that holds up to `burst` tokens and
+/// refills at `per_second` tokens per second. Every accepted connection takes one token,
+/// connections arriving while the bucket is empty are rejected.
+/// Buckets of addresses that have not connected for `idle_timeout` are evicted.
+#[derive(Debug)]
+pub struct ConnectionRateLimiter {
+  per_second: f64,
+  burst: f64,
+  idle_timeout: Duration,
+  state: Mutex<RateLimiterState>,
+}
+
+#[derive(Debug)]
+struct RateLimiterState {
+  buckets: HashMap<IpAddr, Bucket>,
+  last_eviction: Instant,
+}
+
+#[derive(Debug)]
+struct Bucket {
+  tokens: f64,
+  last_refill: Instant,
+}
+
+impl ConnectionRateLimiter {
+  /// Creates a new limiter that permits `per_second` new connections per second and ip address
+  /// with bursts of up to `burst` connections. Idle buckets are evicted after 60 seconds.
+  pub fn new(per_second: u32, burst: u32) -> Self {
+    Self::with_idle_timeout(per_second, burst, Duration::from_secs(60))
+  }
+
+  /// Same as `new` but with a custom timeout after which idle buckets are evicted.
+  pub fn with_idle_timeout(per_second: u32, burst: u32, idle_timeout: Duration) -> Self {
+    Self {
+      per_second: f64::from(per_second),
+      burst: f64::from(burst.max(1)),
+      idle_timeout,
+      state: Mutex::new(RateLimiterState {
+        buckets: HashMap::new(),
+        last_eviction: Instant::now(),
+      }),
+    }
+  }
+
+  /// Takes a token for a new connection from the given address.
+  /// Returns false if the connection exceeds the rate and should be dropped.
+  pub fn check(&self, address: IpAddr) -> bool {
+    self.check_at(address, Instant::now())
+  }
+
+  /// Same as `check` but with the current time given by the caller, for example from `TiiServer::clock`.
+  pub fn check_at(&self, address: IpAddr, now: Instant) -> bool {
+    let Ok(mut state) = unwrap_poison(self.state.lock()) else {
+      //Fail open, a poisoned limiter should not take the whole server down.
+      return true;
+    };
+
+    if now.duration_since(state.last_eviction) >= self.idle_timeout {
+      let idle_timeout = self.idle_timeout;
+      state.buckets.retain(|_, bucket| now.duration_since(bucket.last_refill) < idle_timeout);
+      state.last_eviction = now;
+      trace_log!("ConnectionRateLimiter: {} buckets remain after eviction", state.buckets.len());
+    }
+
+    let bucket =
+      state.buckets.entry(address).or_insert(Bucket { tokens: self.burst, last_refill: now });
+
+    let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+    bucket.tokens = f64::min(self.burst, bucket.tokens + elapsed * self.per_second);
+    bucket.last_refill = now;
+
+    if bucket.tokens < 1.0 {
+      info_log!("ConnectionRateLimiter: rejecting connection from {}", address);
+      return false;
+    }
+
+    bucket.tokens -= 1.0;
+    true
+  }
+
+  /// Returns the amount of ip addresses that currently have a bucket.
+  pub fn tracked_addresses(&self) -> usize {
+    unwrap_poison(self.state.lock()).map(|state| state.buckets.len()).unwrap_or_default()
+  }
+}
@@ -0,0 +1,133 @@
+use crate::http::headers::HeaderName;
+use crate::http::mime::{MimeGroup, MimeType};
+use crate::http::response_body::ResponseBody;
+use crate::http::Response;
+use crate::tii_error::TiiResult;
+use crate::trace_log;
+use crate::util::unwrap_poison;
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+
+/// A single server sent event.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SseEvent {
+  event: Option<String>,
+  id: Option<String>,
+  data: String,
+}
+
+impl SseEvent {
+  /// Creates a new unnamed event with the given data. Multi line data is sent as multiple `data:` fields,
+  /// lines may end with `\r\n`, `\r` or `\n`.
+  pub fn new(data: impl ToString) -> Self {
+    Self { event: None, id: None, data: data.to_string() }
+  }
+
+  /// Sets the name of the event, clients can listen for it with `addEventListener`.
+  /// Line breaks would end the field early and are removed.
+  pub fn with_event(mut self, event: impl ToString) -> Self {
+    self.event = Some(strip_line_breaks(event.to_string()));
+    self
+  }
+
+  /// Sets the id of the event, the client sends the last id it saw in the `Last-Event-ID` header when it reconnects.
+  /// Line breaks would end the field early and are removed.
+  pub fn with_id(mut self, id: impl ToString) -> Self {
+    self.id = Some(strip_line_breaks(id.to_string()));
+    self
+  }
+
+  /// Serializes the event in the `text/event-stream` format.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut serialized = String::with_capacity(self.data.len() + 16);
+    if let Some(event) = self.event.as_ref() {
+      serialized.push_str("event: ");
+      serialized.push_str(event);
+      serialized.push('\n');
+    }
+
+    if let Some(id) = self.id.as_ref() {
+      serialized.push_str("id: ");
+      serialized.push_str(id);
+      serialized.push('\n');
+    }
+
+    for line in self.data.replace("\r\n", "\n").split(['\r', '\n']) {
+      serialized.push_str("data: ");
+      serialized.push_str(line);
+      serialized.push('\n');
+    }
+
+    serialized.push('\n');
+    serialized.into_bytes()
+  }
+}
+
+fn strip_line_breaks(mut value: String) -> String {
+  value.retain(|c| c != '\r' && c != '\n');
+  value
+}
+
+/// Fans out server sent events to any number of subscribed SSE responses.
+///
+/// Every subscriber has its own buffer of up to `buffer` events.
+/// Broadcasting never blocks, a subscriber whose buffer is full is too slow to keep up and is dropped,
+/// its response ends once the events already in its buffer have been written.
+/// Subscribers whose client disconnected are removed by the next broadcast.
+///
+/// Cloning the broadcaster yields another handle to the same set of subscribers.
+/// All responses end once the last handle has been dropped.
+#[derive(Debug, Clone)]
+pub struct SseBroadcaster {
+  buffer: usize,
+  subscribers: Arc<Mutex<Vec<SyncSender<Vec<u8>>>>>,
+}
+
+impl SseBroadcaster {
+  /// Creates a new broadcaster that buffers up to `buffer` events per subscriber.
+  pub fn new(buffer: usize) -> Self {
+    Self { buffer: buffer.max(1), subscribers: Arc::new(Mutex::new(Vec::new())) }
+  }
+
+  /// Subscribes a new client and returns the `text/event-stream` response for it.
+  /// The response body is streamed with chunked transfer encoding and receives every event
+  /// broadcast from now on. Each event is flushed to the client as soon as it is written.
+  pub fn subscribe(&self) -> TiiResult<Response> {
+    let (sender, receiver) = sync_channel(self.buffer);
+    unwrap_poison(self.subscribers.lock())?.push(sender);
+
+    let mime = MimeType::Other(MimeGroup::Text, "text/event-stream".to_string());
+    let body = ResponseBody::chunked(move |sink| {
+      for event in receiver {
+        sink.write_all(event.as_slice())?;
+        sink.flush_to_client()?;
+      }
+      Ok(())
+    });
+
+    Response::ok(body, mime).with_header(HeaderName::CacheControl, "no-cache")
+  }
+
+  /// Sends the event to all subscribers and returns the amount of subscribers that received it.
+  /// Subscribers that are too slow or have disconnected are dropped.
+  pub fn broadcast(&self, event: &SseEvent) -> TiiResult<usize> {
+    let data = event.to_bytes();
+    let mut subscribers = unwrap_poison(self.subscribers.lock())?;
+    subscribers.retain(|subscriber| match subscriber.try_send(data.clone()) {
+      Ok(()) => true,
+      Err(TrySendError::Full(_)) => {
+        trace_log!("SseBroadcaster dropping subscriber that is too slow");
+        false
+      }
+      Err(TrySendError::Disconnected(_)) => false,
+    });
+
+    Ok(subscribers.len())
+  }
+
+  /// Returns the amount of subscribers. This may include disconnected clients that have not been
+  /// removed by a broadcast yet.
+  pub fn subscribers(&self) -> usize {
+    unwrap_poison(self.subscribers.lock()).map(|subscribers| subscribers.len()).unwrap_or_default()
+  }
+}
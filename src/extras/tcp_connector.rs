@@ -1,12 +1,19 @@
-use crate::extras::connector::{ActiveConnection, ConnWait};
-use crate::extras::{Connector, ConnectorMeta, CONNECTOR_SHUTDOWN_TIMEOUT};
+use crate::extras::connector::{
+  shutdown_graceful, ActiveConnection, ConnWait, OpenConnectionGuard, OpenConnections,
+};
+use crate::extras::{
+  ConnectionLimit, ConnectionRateLimiter, Connector, ConnectorMeta, ThreadPool,
+  CONNECTOR_SHUTDOWN_TIMEOUT,
+};
 use crate::functional_traits::{DefaultThreadAdapter, ThreadAdapter, ThreadAdapterJoinHandle};
+use crate::stream::IntoConnectionStream;
 use crate::tii_error::TiiResult;
 use crate::tii_server::TiiServer;
 use crate::{error_log, info_log, trace_log};
 use defer_heavy::defer;
 use std::io;
-use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::io::Write;
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -25,9 +32,44 @@ struct TcpConnectorInner {
   waiter: ConnWait,
   listener: TcpListener,
   shutdown_flag: AtomicBool,
+  open_connections: Arc<OpenConnections>,
   tii_server: Arc<TiiServer>,
+  options: TcpConnectorOptions,
 }
 
+/// Optional limits applied by a TcpConnector before a connection is handed to tii.
+#[derive(Debug, Default)]
+pub struct TcpConnectorOptions {
+  rate_limiter: Option<ConnectionRateLimiter>,
+  connection_limit: Option<Arc<ConnectionLimit>>,
+  linger: Option<Duration>,
+}
+
+impl TcpConnectorOptions {
+  /// Limits the rate of new connections per source ip address.
+  pub fn with_rate_limiter(mut self, rate_limiter: ConnectionRateLimiter) -> Self {
+    self.rate_limiter = Some(rate_limiter);
+    self
+  }
+
+  /// Limits the amount of connections that are handled concurrently.
+  pub fn with_connection_limit(mut self, connection_limit: ConnectionLimit) -> Self {
+    self.connection_limit = Some(Arc::new(connection_limit));
+    self
+  }
+
+  /// Sets `SO_LINGER` on every accepted connection, see `ConnectionStream::set_linger`.
+  /// With a short linger the socket of a connection is closed promptly after the response, including
+  /// rejection responses, instead of lingering in the OS. A linger of zero resets the connection on close.
+  pub fn with_linger(mut self, linger: Duration) -> Self {
+    self.linger = Some(linger);
+    self
+  }
+}
+
+const SERVICE_UNAVAILABLE: &[u8] =
+  b"HTTP/1.1 503 Service Unavailable\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n";
+
 impl TcpConnectorInner {
   #[cfg(target_os = "windows")]
   #[expect(unsafe_code)]
@@ -84,19 +126,77 @@ impl TcpConnectorInner {
         break;
       }
 
+      if let (Some(linger), Ok(stream)) = (self.options.linger, stream.as_ref()) {
+        let result = stream
+          .try_clone()
+          .and_then(|stream| stream.into_connection_stream().set_linger(Some(linger)));
+        if let Err(err) = result {
+          error_log!(
+            "tcp_connector[{}]: connection {this_connection} failed to set linger err={}",
+            &self.addr_string,
+            err
+          );
+        }
+      }
+
+      if let (Some(limiter), Ok(stream)) = (self.options.rate_limiter.as_ref(), stream.as_ref()) {
+        match stream.peer_addr() {
+          Ok(peer) if !limiter.check_at(peer.ip(), self.tii_server.clock().now()) => {
+            info_log!(
+              "tcp_connector[{}]: connection {this_connection} from {} dropped due to rate limit",
+              &self.addr_string,
+              peer
+            );
+            continue;
+          }
+          _ => (),
+        }
+      }
+
+      let permit = match (self.options.connection_limit.as_ref(), stream.as_ref()) {
+        (Some(limit), Ok(stream)) => match limit.acquire(self.tii_server.clock().as_ref()) {
+          Some(permit) => Some(permit),
+          None => {
+            info_log!(
+              "tcp_connector[{}]: connection {this_connection} rejected due to connection limit",
+              &self.addr_string
+            );
+            let mut stream = stream;
+            _ = stream.write_all(SERVICE_UNAVAILABLE);
+            _ = stream.shutdown(Shutdown::Write);
+            continue;
+          }
+        },
+        _ => None,
+      };
+
       info_log!("tcp_connector[{}]: connection {this_connection} accepted", &self.addr_string);
       let path_clone = self.addr_string.clone();
       let server_clone = self.tii_server.clone();
       let done_flag = Arc::new(AtomicBool::new(false));
       let done_clone = Arc::clone(&done_flag);
 
+      let open_guard =
+        stream.as_ref().ok().and_then(|stream| stream.try_clone().ok()).map(|clone| {
+          self
+            .open_connections
+            .register(this_connection, move || _ = clone.shutdown(Shutdown::Both))
+        });
+      let drain = open_guard.as_ref().map(OpenConnectionGuard::drain);
+
       match self.thread_adapter.spawn(Box::new(move || {
         defer! {
+          drop(open_guard);
+          drop(permit);
           done_clone.store(true, Ordering::SeqCst);
         }
         match stream {
           Ok(stream) => {
-            match server_clone.handle_connection_with_meta(stream, ConnectorMeta::Tcp) {
+            match server_clone.handle_connection_draining(
+              stream,
+              ConnectorMeta::Tcp,
+              drain.as_deref(),
+            ) {
               Ok(_) => {
                 info_log!(
                   "tcp_connector[{}]: connection {} processed successfully",
@@ -205,6 +305,11 @@ impl TcpConnectorInner {
       return;
     }
 
+    // The listener thread may be holding a connection until the limit frees up.
+    if let Some(limit) = self.options.connection_limit.as_ref() {
+      limit.shutdown();
+    }
+
     if self.waiter.is_done(1) {
       //No need to libc::shutdown() if somehow by magic the main_thread is already dead.
       return;
@@ -237,6 +342,11 @@ impl TcpConnectorInner {
       return;
     }
 
+    // The listener thread may be holding a connection until the limit frees up.
+    if let Some(limit) = self.options.connection_limit.as_ref() {
+      limit.shutdown();
+    }
+
     if !self.waiter.wait(1, Some(CONNECTOR_SHUTDOWN_TIMEOUT)) {
       error_log!(
         "tcp_connector[{}]: shutdown failed to wake up the listener thread",
@@ -255,6 +365,11 @@ impl Connector for TcpConnector {
     self.inner.shutdown();
   }
 
+  fn shutdown_graceful(&self, deadline: Duration) -> bool {
+    let name = format!("tcp_connector[{}]", self.inner.addr_string);
+    shutdown_graceful(self, &self.inner.open_connections, name.as_str(), deadline)
+  }
+
   fn is_marked_for_shutdown(&self) -> bool {
     self.inner.shutdown_flag.load(Ordering::SeqCst)
   }
@@ -323,29 +438,86 @@ impl TcpConnector {
     tii_server: Arc<TiiServer>,
     thread_adapter: impl ThreadAdapter + 'static,
   ) -> TiiResult<Self> {
-    let mut addr_string = String::new();
-    let addr_in_vec = addr.to_socket_addrs()?.collect::<Vec<SocketAddr>>();
+    Self::from_listener(TcpListener::bind(addr)?, tii_server, thread_adapter)
+  }
 
-    for addr in &addr_in_vec {
-      if !addr_string.is_empty() {
-        addr_string += ", ";
-      }
-      addr_string += addr.to_string().as_str();
-    }
+  /// Creates a new tcp connector that accepts connections from an already bound/listening TcpListener.
+  /// This is useful for systemd socket activation or passing the listener socket to a new process
+  /// as part of a zero downtime restart.
+  /// Return Err on error.
+  /// The TCP listener will be used immediately in a background thread.
+  pub fn from_listener(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    thread_adapter: impl ThreadAdapter + 'static,
+  ) -> TiiResult<Self> {
+    Self::from_listener_with_options(
+      listener,
+      tii_server,
+      thread_adapter,
+      TcpConnectorOptions::default(),
+    )
+  }
+
+  /// Creates a new tcp connector that accepts connections from an already bound/listening TcpListener.
+  /// New connections are checked against the rate limiter before any http parsing is done,
+  /// connections exceeding the rate of their source ip address are dropped immediately.
+  /// Return Err on error.
+  /// The TCP listener will be used immediately in a background thread.
+  pub fn from_listener_with_rate_limit(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    thread_adapter: impl ThreadAdapter + 'static,
+    rate_limiter: ConnectionRateLimiter,
+  ) -> TiiResult<Self> {
+    let options = TcpConnectorOptions::default().with_rate_limiter(rate_limiter);
+    Self::from_listener_with_options(listener, tii_server, thread_adapter, options)
+  }
 
+  /// Creates a new tcp connector that accepts connections from an already bound/listening TcpListener
+  /// and applies the limits of the given options to new connections before any http parsing is done.
+  /// Return Err on error.
+  /// The TCP listener will be used immediately in a background thread.
+  pub fn from_listener_with_options(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    thread_adapter: impl ThreadAdapter + 'static,
+    options: TcpConnectorOptions,
+  ) -> TiiResult<Self> {
     let thread_adapter = Arc::new(thread_adapter);
+    Self::from_listener_with_adapters(
+      listener,
+      tii_server,
+      thread_adapter.clone(),
+      thread_adapter,
+      options,
+    )
+  }
+
+  /// The listener thread is spawned using `main_adapter`, connections are handled using `thread_adapter`.
+  fn from_listener_with_adapters(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    main_adapter: Arc<dyn ThreadAdapter>,
+    thread_adapter: Arc<dyn ThreadAdapter>,
+    options: TcpConnectorOptions,
+  ) -> TiiResult<Self> {
+    let addr_string = listener.local_addr()?.to_string();
+
     let inner = Arc::new(TcpConnectorInner {
-      thread_adapter: thread_adapter.clone(),
-      listener: TcpListener::bind(addr)?,
+      thread_adapter,
+      listener,
       shutdown_flag: AtomicBool::new(false),
+      open_connections: Arc::default(),
       addr_string,
       tii_server: tii_server.clone(),
       waiter: ConnWait::default(),
+      options,
     });
 
     let main_thread = {
       let inner = inner.clone();
-      thread_adapter.spawn(Box::new(move || {
+      main_adapter.spawn(Box::new(move || {
         inner.run();
       }))?
     };
@@ -371,6 +543,93 @@ impl TcpConnector {
   pub fn start_unpooled(addr: impl ToSocketAddrs, tii_server: Arc<TiiServer>) -> TiiResult<Self> {
     Self::start(addr, tii_server, DefaultThreadAdapter)
   }
+
+  /// Create a new TcpConnector from an already bound/listening TcpListener.
+  /// When this fn returns Ok() the socket is already used in a background thread.
+  ///
+  /// Threads are created using "thread::Builder::new().spawn"
+  pub fn from_listener_unpooled(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+  ) -> TiiResult<Self> {
+    Self::from_listener(listener, tii_server, DefaultThreadAdapter)
+  }
+
+  /// Create a new rate limited TcpConnector from an already bound/listening TcpListener.
+  /// When this fn returns Ok() the socket is already used in a background thread.
+  ///
+  /// Threads are created using "thread::Builder::new().spawn"
+  pub fn from_listener_with_rate_limit_unpooled(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    rate_limiter: ConnectionRateLimiter,
+  ) -> TiiResult<Self> {
+    Self::from_listener_with_rate_limit(listener, tii_server, DefaultThreadAdapter, rate_limiter)
+  }
+
+  /// Create a new TcpConnector from an already bound/listening TcpListener that applies the given options.
+  /// When this fn returns Ok() the socket is already used in a background thread.
+  ///
+  /// Threads are created using "thread::Builder::new().spawn"
+  pub fn from_listener_with_options_unpooled(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    options: TcpConnectorOptions,
+  ) -> TiiResult<Self> {
+    Self::from_listener_with_options(listener, tii_server, DefaultThreadAdapter, options)
+  }
+
+  /// Create a new TcpConnector that handles connections on a `ThreadPool` of `pool_size` threads.
+  /// Up to `pool_size` further connections wait for an idle thread, connections beyond that are dropped.
+  /// When this fn returns Ok() the socket is already listening in a background thread.
+  /// Returns an io::Error if it was unable to bind to the socket.
+  ///
+  /// The listener thread itself is not part of the pool, it is created using "thread::Builder::new().spawn"
+  pub fn start_pooled(
+    addr: impl ToSocketAddrs,
+    tii_server: Arc<TiiServer>,
+    pool_size: usize,
+  ) -> TiiResult<Self> {
+    let pool = ThreadPool::new(pool_size, pool_size)?;
+    Self::from_listener_pooled(TcpListener::bind(addr)?, tii_server, pool)
+  }
+
+  /// Create a new TcpConnector from an already bound/listening TcpListener that handles connections on the given pool.
+  /// When this fn returns Ok() the socket is already used in a background thread.
+  ///
+  /// The listener thread itself is not part of the pool, it is created using "thread::Builder::new().spawn"
+  pub fn from_listener_pooled(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    pool: ThreadPool,
+  ) -> TiiResult<Self> {
+    Self::from_listener_with_options_pooled(
+      listener,
+      tii_server,
+      pool,
+      TcpConnectorOptions::default(),
+    )
+  }
+
+  /// Create a new TcpConnector from an already bound/listening TcpListener that applies the given options
+  /// and handles connections on the given pool.
+  /// When this fn returns Ok() the socket is already used in a background thread.
+  ///
+  /// The listener thread itself is not part of the pool, it is created using "thread::Builder::new().spawn"
+  pub fn from_listener_with_options_pooled(
+    listener: TcpListener,
+    tii_server: Arc<TiiServer>,
+    pool: ThreadPool,
+    options: TcpConnectorOptions,
+  ) -> TiiResult<Self> {
+    Self::from_listener_with_adapters(
+      listener,
+      tii_server,
+      Arc::new(DefaultThreadAdapter),
+      Arc::new(pool),
+      options,
+    )
+  }
 }
 
 #[cfg(target_os = "windows")]
@@ -0,0 +1,248 @@
+use crate::functional_traits::{ThreadAdapter, ThreadAdapterJoinHandle};
+use crate::tii_error::TiiResult;
+use crate::util::unwrap_poison;
+use std::collections::HashMap;
+use std::io;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::{AtomicU64, AtomicUsize};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+enum Job {
+  Task(Task),
+  /// Sent by `ThreadPool::resize` to stop one surplus thread.
+  Exit,
+}
+
+impl std::fmt::Debug for Job {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Job::Task(_) => f.write_str("Task"),
+      Job::Exit => f.write_str("Exit"),
+    }
+  }
+}
+
+/// A pool of threads that implements `ThreadAdapter`.
+///
+/// Tasks are executed by the first idle thread. Up to `queue` tasks wait for a thread to become idle,
+/// spawning further tasks fails immediately with `io::ErrorKind::WouldBlock`.
+/// Connectors drop connections they could not spawn a task for, so a saturated pool sheds load
+/// instead of accumulating an unbounded backlog of waiting connections.
+///
+/// Each connection occupies one thread for as long as it is open,
+/// so the pool is best suited for many short-lived requests rather than long-lived streams.
+///
+/// The amount of threads can be changed at runtime with `resize`, for example based on `stats`.
+/// Clones refer to the same threads, keep a clone to read `stats` of a pool that was handed to a connector.
+#[derive(Debug, Clone)]
+pub struct ThreadPool {
+  sender: SyncSender<Job>,
+  shared: Arc<PoolShared>,
+}
+
+#[derive(Debug)]
+struct PoolShared {
+  receiver: Mutex<Receiver<Job>>,
+  /// Amount of threads the pool should have, guards resizing.
+  threads: Mutex<usize>,
+  next_thread_idx: AtomicUsize,
+  queued: AtomicUsize,
+  busy: AtomicUsize,
+  executed: AtomicU64,
+  workers: Mutex<Workers>,
+  /// Notified whenever a thread exits.
+  worker_exited: Condvar,
+}
+
+/// Join handles of the threads of a pool.
+#[derive(Debug, Default)]
+struct Workers {
+  running: HashMap<usize, JoinHandle<()>>,
+  /// Threads that exited and were not joined yet.
+  exited: Vec<JoinHandle<()>>,
+}
+
+/// Snapshot of the utilization of a `ThreadPool`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PoolStats {
+  /// Tasks that wait for an idle thread.
+  pub queued: usize,
+  /// Threads that are currently executing a task.
+  pub busy: usize,
+  /// Tasks that finished executing since the pool was started.
+  pub executed: u64,
+}
+
+impl ThreadPool {
+  /// Starts a pool of `threads` threads with room for `queue` tasks that wait for an idle thread.
+  /// The threads stop once the pool and all its clones have been dropped and all queued tasks have been executed.
+  pub fn new(threads: usize, queue: usize) -> TiiResult<Self> {
+    let threads = threads.max(1);
+    let (sender, receiver) = sync_channel::<Job>(queue);
+    let shared = Arc::new(PoolShared {
+      receiver: Mutex::new(receiver),
+      threads: Mutex::new(threads),
+      next_thread_idx: AtomicUsize::new(0),
+      queued: AtomicUsize::new(0),
+      busy: AtomicUsize::new(0),
+      executed: AtomicU64::new(0),
+      workers: Mutex::default(),
+      worker_exited: Condvar::new(),
+    });
+
+    let pool = Self { sender, shared };
+    for _ in 0..threads {
+      pool.start_thread()?;
+    }
+
+    Ok(pool)
+  }
+
+  /// The amount of threads in the pool, this is the maximum amount of tasks executed concurrently.
+  pub fn threads(&self) -> usize {
+    unwrap_poison(self.shared.threads.lock()).map(|threads| *threads).unwrap_or_default()
+  }
+
+  /// Changes the amount of threads in the pool, a count of 0 is treated as 1.
+  ///
+  /// Growing starts the additional threads immediately.
+  /// Shrinking queues an exit signal for each surplus thread, the threads that receive them exit
+  /// after finishing their current task. Tasks queued before the call are still executed.
+  /// The signals occupy room in the queue, so this call blocks while the queue is full.
+  /// Use `join_exited` to wait for the surplus threads to exit.
+  pub fn resize(&self, threads: usize) -> TiiResult<()> {
+    let threads = threads.max(1);
+    let mut current = unwrap_poison(self.shared.threads.lock())?;
+    while *current < threads {
+      self.start_thread()?;
+      *current += 1;
+    }
+
+    while *current > threads {
+      self
+        .sender
+        .send(Job::Exit)
+        .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "thread pool is stopped"))?;
+      *current -= 1;
+    }
+
+    Ok(())
+  }
+
+  /// Waits until no more threads than `threads` are running and joins the threads that exited,
+  /// for example the surplus threads after shrinking the pool with `resize`.
+  /// Returns false if the timeout elapsed first, the threads that did exit are joined nevertheless.
+  pub fn join_exited(&self, timeout: Option<Duration>) -> bool {
+    let threads = self.threads();
+    let Ok(guard) = unwrap_poison(self.shared.workers.lock()) else {
+      return false;
+    };
+
+    let surplus = |workers: &mut Workers| workers.running.len() > threads;
+    let (mut guard, done) = match timeout {
+      Some(timeout) => {
+        match self.shared.worker_exited.wait_timeout_while(guard, timeout, surplus) {
+          Ok((guard, result)) => (guard, !result.timed_out()),
+          Err(_) => return false,
+        }
+      }
+      None => match self.shared.worker_exited.wait_while(guard, surplus) {
+        Ok(guard) => (guard, true),
+        Err(_) => return false,
+      },
+    };
+
+    let exited = std::mem::take(&mut guard.exited);
+    drop(guard);
+    for handle in exited {
+      // Tasks are run with catch_unwind, so the threads themselves do not panic.
+      _ = handle.join();
+    }
+
+    done
+  }
+
+  /// Returns the current utilization of the pool. This only reads a few atomic counters.
+  pub fn stats(&self) -> PoolStats {
+    PoolStats {
+      queued: self.shared.queued.load(SeqCst),
+      busy: self.shared.busy.load(SeqCst),
+      executed: self.shared.executed.load(SeqCst),
+    }
+  }
+
+  fn start_thread(&self) -> io::Result<()> {
+    let idx = self.shared.next_thread_idx.fetch_add(1, SeqCst);
+    let shared = self.shared.clone();
+    // Held while spawning so the thread can not exit before its handle is stored.
+    let mut workers = unwrap_poison(self.shared.workers.lock())?;
+    let handle = thread::Builder::new().name(format!("tii-pool-{idx}")).spawn(move || {
+      Self::work(&shared);
+      Self::exited(&shared, idx);
+    })?;
+    workers.running.insert(idx, handle);
+    Ok(())
+  }
+
+  fn exited(shared: &PoolShared, idx: usize) {
+    if let Ok(mut workers) = unwrap_poison(shared.workers.lock()) {
+      if let Some(handle) = workers.running.remove(&idx) {
+        workers.exited.push(handle);
+      }
+    }
+    shared.worker_exited.notify_all();
+  }
+
+  fn work(shared: &PoolShared) {
+    loop {
+      let task = match unwrap_poison(shared.receiver.lock()).map(|receiver| receiver.recv()) {
+        Ok(Ok(Job::Task(task))) => task,
+        _ => return,
+      };
+
+      shared.busy.fetch_add(1, SeqCst);
+      shared.queued.fetch_sub(1, SeqCst);
+      task();
+      shared.busy.fetch_sub(1, SeqCst);
+      shared.executed.fetch_add(1, SeqCst);
+    }
+  }
+}
+
+impl ThreadAdapter for ThreadPool {
+  fn spawn(&self, task: Box<dyn FnOnce() + Send>) -> TiiResult<ThreadAdapterJoinHandle> {
+    let (done_sender, done_receiver) = sync_channel(1);
+    let job = Job::Task(Box::new(move || {
+      _ = done_sender.send(catch_unwind(AssertUnwindSafe(task)));
+    }));
+
+    // Counted before sending so that a worker never decrements the count below zero.
+    self.shared.queued.fetch_add(1, SeqCst);
+    let result = self.sender.try_send(job);
+    if result.is_err() {
+      self.shared.queued.fetch_sub(1, SeqCst);
+    }
+
+    match result {
+      Ok(()) => (),
+      Err(TrySendError::Full(_)) => {
+        return Err(io::Error::new(io::ErrorKind::WouldBlock, "thread pool is saturated").into())
+      }
+      Err(TrySendError::Disconnected(_)) => {
+        return Err(io::Error::new(io::ErrorKind::BrokenPipe, "thread pool is stopped").into())
+      }
+    }
+
+    Ok(ThreadAdapterJoinHandle::new(Box::new(move || {
+      // The sender is only dropped without sending if the pool stopped before it ran the task.
+      done_receiver.recv().unwrap_or(Ok(()))
+    })))
+  }
+}
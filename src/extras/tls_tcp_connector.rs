@@ -1,10 +1,14 @@
-use crate::extras::connector::{ActiveConnection, ConnWait, ConnectorMeta};
+use crate::extras::connector::{
+  shutdown_graceful, ActiveConnection, ConnWait, ConnectorMeta, OpenConnectionGuard,
+  OpenConnections,
+};
 use crate::extras::{Connector, CONNECTOR_SHUTDOWN_TIMEOUT};
 use crate::functional_traits::{DefaultThreadAdapter, ThreadAdapter, ThreadAdapterJoinHandle};
 use crate::tii_error::TiiResult;
 use crate::tii_server::TiiServer;
 use crate::{error_log, info_log, trace_log, TiiTlsStream};
 use defer_heavy::defer;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use rustls::{ServerConfig, ServerConnection};
 use std::io;
 use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
@@ -27,6 +31,7 @@ struct TlsTcpConnectorInner {
   waiter: ConnWait,
   listener: TcpListener,
   shutdown_flag: AtomicBool,
+  open_connections: Arc<OpenConnections>,
   tii_server: Arc<TiiServer>,
 }
 
@@ -94,8 +99,17 @@ impl TlsTcpConnectorInner {
       let tls_config = self.config.clone();
       let thread_adapter_clone = self.thread_adapter.clone();
 
+      let open_guard =
+        stream.as_ref().ok().and_then(|stream| stream.try_clone().ok()).map(|clone| {
+          self
+            .open_connections
+            .register(this_connection, move || _ = clone.shutdown(std::net::Shutdown::Both))
+        });
+      let drain = open_guard.as_ref().map(OpenConnectionGuard::drain);
+
       match self.thread_adapter.spawn(Box::new(move || {
         defer! {
+          drop(open_guard);
           done_clone.store(true, Ordering::SeqCst);
         }
         match stream {
@@ -128,7 +142,7 @@ impl TlsTcpConnectorInner {
               }
             };
 
-            match server_clone.handle_connection_with_meta(tls_stream, ConnectorMeta::TlsTcp) {
+            match server_clone.handle_connection_draining(tls_stream, ConnectorMeta::TlsTcp, drain.as_deref()) {
               Ok(_) => {
                 info_log!(
                 "tls_tcp_connector[{}]: connection {} processed successfully",
@@ -287,6 +301,11 @@ impl Connector for TlsTcpConnector {
     self.inner.shutdown();
   }
 
+  fn shutdown_graceful(&self, deadline: Duration) -> bool {
+    let name = format!("tls_tcp_connector[{}]", self.inner.addr_string);
+    shutdown_graceful(self, &self.inner.open_connections, name.as_str(), deadline)
+  }
+
   fn is_marked_for_shutdown(&self) -> bool {
     self.inner.shutdown_flag.load(Ordering::SeqCst)
   }
@@ -375,6 +394,7 @@ impl TlsTcpConnector {
       config,
       listener: TcpListener::bind(addr)?,
       shutdown_flag: AtomicBool::new(false),
+      open_connections: Arc::default(),
       addr_string,
       tii_server: tii_server.clone(),
       waiter: ConnWait::default(),
@@ -412,4 +432,21 @@ impl TlsTcpConnector {
   ) -> TiiResult<Self> {
     Self::start(addr, tii_server, config, DefaultThreadAdapter)
   }
+
+  /// Create a new TlsConnector that presents the given certificate chain and private key to clients.
+  /// The chain starts with the certificate of the server followed by any intermediate certificates.
+  /// Client certificates are not requested. Use `start` with a custom `ServerConfig` for anything else.
+  ///
+  /// Returns an Err if the certificate does not match the key or if it was unable to bind to the socket.
+  ///
+  /// Threads are created using "thread::Builder::new().spawn"
+  pub fn start_unpooled_with_cert(
+    addr: impl ToSocketAddrs,
+    cert_chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    tii_server: Arc<TiiServer>,
+  ) -> TiiResult<Self> {
+    let config = ServerConfig::builder().with_no_client_auth().with_single_cert(cert_chain, key)?;
+    Self::start_unpooled(addr, Arc::new(config), tii_server)
+  }
 }
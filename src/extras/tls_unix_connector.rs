@@ -1,4 +1,6 @@
-use crate::extras::connector::{ActiveConnection, ConnWait};
+use crate::extras::connector::{
+  shutdown_graceful, ActiveConnection, ConnWait, OpenConnectionGuard, OpenConnections,
+};
 use crate::extras::{Connector, ConnectorMeta, CONNECTOR_SHUTDOWN_TIMEOUT};
 use crate::functional_traits::ThreadAdapter;
 use crate::tii_builder::{DefaultThreadAdapter, ThreadAdapterJoinHandle};
@@ -29,6 +31,7 @@ struct TlsUnixConnectorInner {
   listener: UnixListener,
   waiter: ConnWait,
   shutdown_flag: AtomicBool,
+  open_connections: Arc<OpenConnections>,
   tii_server: Arc<TiiServer>,
 }
 
@@ -71,6 +74,11 @@ impl Connector for TlsUnixConnector {
     self.inner.shutdown();
   }
 
+  fn shutdown_graceful(&self, deadline: Duration) -> bool {
+    let name = format!("tls_unix_connector[{}]", self.inner.path.display());
+    shutdown_graceful(self, &self.inner.open_connections, name.as_str(), deadline)
+  }
+
   fn is_marked_for_shutdown(&self) -> bool {
     self.inner.shutdown_flag.load(Ordering::SeqCst)
   }
@@ -156,8 +164,17 @@ impl TlsUnixConnectorInner {
       let thread_adapter_clone = self.thread_adapter.clone();
 
       let done_clone = Arc::clone(&done_flag);
+      let open_guard =
+        stream.as_ref().ok().and_then(|stream| stream.try_clone().ok()).map(|clone| {
+          self
+            .open_connections
+            .register(this_connection, move || _ = clone.shutdown(std::net::Shutdown::Both))
+        });
+      let drain = open_guard.as_ref().map(OpenConnectionGuard::drain);
+
       match self.thread_adapter.spawn(Box::new(move || {
         defer! {
+          drop(open_guard);
           done_clone.store(true, Ordering::SeqCst);
         }
         match stream {
@@ -190,7 +207,7 @@ impl TlsUnixConnectorInner {
               }
             };
 
-            match server_clone.handle_connection_with_meta(tls_stream, ConnectorMeta::TlsUnix) {
+            match server_clone.handle_connection_draining(tls_stream, ConnectorMeta::TlsUnix, drain.as_deref()) {
               Ok(_) => {
                 info_log!(
                 "tls_unix_connector[{}]: connection {this_connection} processed successfully",
@@ -311,6 +328,7 @@ impl TlsUnixConnector {
       listener: UnixListener::bind(path)?,
       waiter: ConnWait::default(),
       shutdown_flag: AtomicBool::new(false),
+      open_connections: Arc::default(),
       path: path.to_path_buf(),
       tii_server: tii_server.clone(),
       config,
@@ -1,4 +1,6 @@
-use crate::extras::connector::{ActiveConnection, ConnWait};
+use crate::extras::connector::{
+  shutdown_graceful, ActiveConnection, ConnWait, OpenConnectionGuard, OpenConnections,
+};
 use crate::extras::{Connector, ConnectorMeta, CONNECTOR_SHUTDOWN_TIMEOUT};
 use crate::functional_traits::ThreadAdapter;
 use crate::tii_builder::{DefaultThreadAdapter, ThreadAdapterJoinHandle};
@@ -27,6 +29,7 @@ struct UnixConnectorInner {
   listener: UnixListener,
   waiter: ConnWait,
   shutdown_flag: AtomicBool,
+  open_connections: Arc<OpenConnections>,
   tii_server: Arc<TiiServer>,
 }
 
@@ -69,6 +72,11 @@ impl Connector for UnixConnector {
     self.inner.shutdown();
   }
 
+  fn shutdown_graceful(&self, deadline: Duration) -> bool {
+    let name = format!("unix_connector[{}]", self.inner.path.display());
+    shutdown_graceful(self, &self.inner.open_connections, name.as_str(), deadline)
+  }
+
   fn is_marked_for_shutdown(&self) -> bool {
     self.inner.shutdown_flag.load(Ordering::SeqCst)
   }
@@ -149,13 +157,25 @@ impl UnixConnectorInner {
       let done_flag = Arc::new(AtomicBool::new(false));
 
       let done_clone = Arc::clone(&done_flag);
+      let open_guard =
+        stream.as_ref().ok().and_then(|stream| stream.try_clone().ok()).map(|clone| {
+          self
+            .open_connections
+            .register(this_connection, move || _ = clone.shutdown(std::net::Shutdown::Both))
+        });
+      let drain = open_guard.as_ref().map(OpenConnectionGuard::drain);
+
       match self.thread_adapter.spawn(Box::new(move || {
         defer! {
+          drop(open_guard);
           done_clone.store(true, Ordering::SeqCst);
         }
         match stream {
-          Ok(stream) => match server_clone.handle_connection_with_meta(stream, ConnectorMeta::Unix)
-          {
+          Ok(stream) => match server_clone.handle_connection_draining(
+            stream,
+            ConnectorMeta::Unix,
+            drain.as_deref(),
+          ) {
             Ok(_) => {
               info_log!(
                 "unix_connector[{}]: connection {this_connection} processed successfully",
@@ -271,6 +291,7 @@ impl UnixConnector {
       listener: UnixListener::bind(path)?,
       waiter: ConnWait::default(),
       shutdown_flag: AtomicBool::new(false),
+      open_connections: Arc::default(),
       path: path.to_path_buf(),
       tii_server: tii_server.clone(),
     });
@@ -1,10 +1,12 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, channel, Receiver, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex};
 use std::{io, thread, time::Duration};
 
 use crate::http::request_context::RequestContext;
-use crate::websocket::message::WebsocketMessage;
+use crate::websocket::message::{CloseCode, WebsocketMessage};
 use crate::websocket::stream::{ReadMessageTimeoutResult, WebsocketReceiver, WebsocketSender};
 use crate::{error_log, info_log, util, warn_log, TiiError};
 
@@ -52,12 +54,15 @@ struct State {
 
   heartbeat: Option<Duration>,
 
-  // A Vec of all the streams for broadcasting.
-  send_streams: Arc<Mutex<Vec<Sender<OutgoingMessage>>>>,
+  // Clients that sent no text or binary message within this duration are closed.
+  idle_timeout: Option<Duration>,
+
+  // A Vec of all the clients for broadcasting.
+  send_streams: Arc<Mutex<Vec<Client>>>,
   // A sender which is used by handler threads to send messages to clients.
-  broadcast_sender: Sender<WebsocketMessage>,
+  broadcast_sender: Sender<Broadcast>,
   // A receiver which receives messages from handler threads to forward to clients.
-  outgoing_broadcasts: Receiver<WebsocketMessage>,
+  outgoing_broadcasts: Receiver<Broadcast>,
 
   // The event handler called when a new client connects.
   connect_handler: Option<Box<dyn EventHandler>>,
@@ -77,17 +82,104 @@ struct State {
 /// messages back to the stream or broadcast to all streams within the WebSocketApp.
 #[derive(Debug)]
 pub struct WsHandle {
+  info: Arc<ClientInfo>,
+  sender: Sender<OutgoingMessage>,
+}
+
+/// Information the app tracks about a connected client.
+///
+/// Handlers can attach arbitrary attributes to a client (a room, an auth level, a subscription...)
+/// through its `WsHandle`. These attributes can then be used to select the recipients of `broadcast_filtered`.
+#[derive(Debug)]
+pub struct ClientInfo {
   addr: String,
+  attributes: Mutex<HashMap<String, String>>,
+}
+
+impl ClientInfo {
+  /// Create new client info without any attributes.
+  pub fn new(addr: String) -> Self {
+    Self { addr, attributes: Mutex::new(HashMap::new()) }
+  }
+
+  /// Get the address of the client.
+  pub fn peer_addr(&self) -> &str {
+    self.addr.as_str()
+  }
+
+  /// Returns the value of the attribute with the given key.
+  pub fn attribute(&self, key: impl AsRef<str>) -> Option<String> {
+    util::unwrap_poison(self.attributes.lock()).ok()?.get(key.as_ref()).cloned()
+  }
+
+  /// Returns true if the client has an attribute with the given key.
+  pub fn has_attribute(&self, key: impl AsRef<str>) -> bool {
+    util::unwrap_poison(self.attributes.lock())
+      .map(|attributes| attributes.contains_key(key.as_ref()))
+      .unwrap_or_default()
+  }
+
+  /// Sets the attribute with the given key. Returns the previous value if any.
+  pub fn set_attribute(
+    &self,
+    key: impl ToString,
+    value: impl ToString,
+  ) -> io::Result<Option<String>> {
+    Ok(util::unwrap_poison(self.attributes.lock())?.insert(key.to_string(), value.to_string()))
+  }
+
+  /// Removes the attribute with the given key. Returns the previous value if any.
+  pub fn remove_attribute(&self, key: impl AsRef<str>) -> io::Result<Option<String>> {
+    Ok(util::unwrap_poison(self.attributes.lock())?.remove(key.as_ref()))
+  }
+}
+
+/// A predicate that decides which clients receive a broadcast.
+pub struct BroadcastFilter(Box<dyn Fn(&ClientInfo) -> bool + Send>);
+
+impl BroadcastFilter {
+  /// Create a new filter from the predicate.
+  pub fn new(predicate: impl Fn(&ClientInfo) -> bool + Send + 'static) -> Self {
+    Self(Box::new(predicate))
+  }
+
+  /// Returns true if the client should receive the broadcast.
+  pub fn matches(&self, client: &ClientInfo) -> bool {
+    (self.0)(client)
+  }
+}
+
+impl Debug for BroadcastFilter {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str("BroadcastFilter")
+  }
+}
+
+// A message to be sent to every connected client, or only those matching the filter.
+type Broadcast = (Option<BroadcastFilter>, WebsocketMessage);
+
+// A connected client as seen by the broadcast thread.
+struct Client {
+  info: Arc<ClientInfo>,
   sender: Sender<OutgoingMessage>,
 }
 
 /// Represents a global sender which can be used to broadcast messages to all clients.
-pub struct BroadcastSender(Sender<WebsocketMessage>);
+pub struct BroadcastSender(Sender<Broadcast>);
 
 impl BroadcastSender {
   /// Broadcast a message to all connected clients.
   pub fn broadcast(&self, message: WebsocketMessage) {
-    self.0.send(message).ok();
+    self.0.send((None, message)).ok();
+  }
+
+  /// Broadcast a message to all connected clients for which the predicate returns true.
+  pub fn broadcast_filtered(
+    &self,
+    predicate: impl Fn(&ClientInfo) -> bool + Send + 'static,
+    message: WebsocketMessage,
+  ) {
+    self.0.send((Some(BroadcastFilter::new(predicate)), message)).ok();
   }
 }
 
@@ -98,6 +190,8 @@ pub enum OutgoingMessage {
   Message(WebsocketMessage),
   /// A message to be sent to every connected client.
   Broadcast(WebsocketMessage),
+  /// A message to be sent to every connected client that matches the filter.
+  FilteredBroadcast(BroadcastFilter, WebsocketMessage),
 }
 
 /// Represents a function able to handle a WebSocket event (a connection or disconnection).
@@ -141,6 +235,7 @@ impl Default for WsBroadcastBuilder {
       tii_link: Arc::new(Mutex::new(connect_hook)),
       state: State {
         heartbeat: Some(Duration::from_secs(5)),
+        idle_timeout: None,
         send_streams: Default::default(),
         outgoing_broadcasts,
         broadcast_sender,
@@ -199,6 +294,16 @@ impl WsBroadcastBuilder {
     self
   }
 
+  /// Closes connections that have not sent a text or binary message within the given duration.
+  ///
+  /// Pings and pongs do not count as messages, so a client that only answers heartbeats is still closed.
+  /// The connection is closed with the status code 1000 and the disconnect handler is called.
+  /// By default, connections are never closed for being idle.
+  pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+    self.state.idle_timeout = Some(idle_timeout);
+    self
+  }
+
   /// Registers a shutdown signal to gracefully shutdown the app
   ///
   /// For a full/consistent shutdown, you must set both
@@ -219,6 +324,7 @@ impl App {
     let disconnect_handler = self.state.disconnect_handler.map(Arc::new);
     let message_handler = self.state.message_handler.map(Arc::new);
     let streams = self.state.send_streams.clone();
+    let idle_timeout = self.state.idle_timeout;
 
     let timeout = {
       if let Some(hb) = self.state.heartbeat {
@@ -240,11 +346,16 @@ impl App {
         // Remove up to one idx per broadcast. They should eventually all be cleaned up because of the heartbeat.
         let mut remove_idx = None;
         match recv {
-          Ok(message) => {
-            let mut streams = util::unwrap_poison(streams.lock())?;
-            for (idx, stream) in streams.iter_mut().enumerate() {
+          Ok((filter, message)) => {
+            let streams = util::unwrap_poison(streams.lock())?;
+            for (idx, client) in streams.iter().enumerate() {
+              if let Some(filter) = &filter {
+                if !filter.matches(&client.info) {
+                  continue;
+                }
+              }
               // convert the broadcast back to message, but for each sender
-              if stream.send(OutgoingMessage::Message(message.clone())).is_err() {
+              if client.sender.send(OutgoingMessage::Message(message.clone())).is_err() {
                 remove_idx = Some(idx);
               }
             }
@@ -288,7 +399,9 @@ impl App {
 
         let sender = self.state.broadcast_sender.clone();
         let (message_sender, outgoing_messages) = channel();
-        util::unwrap_poison(self.state.send_streams.lock())?.push(message_sender.clone());
+        let info = Arc::new(ClientInfo::new(new_stream.2.clone()));
+        util::unwrap_poison(self.state.send_streams.lock())?
+          .push(Client { info: info.clone(), sender: message_sender.clone() });
 
         let connect_handler = connect_handler.clone();
         let disconnect_handler = disconnect_handler.clone();
@@ -298,6 +411,7 @@ impl App {
         threads.push(thread::spawn(move || {
           exec(ExecState {
             stream: new_stream,
+            info,
             broadcast: sender,
             message_sender,
             outgoing_messages,
@@ -305,6 +419,7 @@ impl App {
             disconnect_handler,
             message_handler,
             timeout,
+            idle_timeout,
             shutdown_signal: sd_flag,
           });
         }));
@@ -365,7 +480,12 @@ impl App {
 impl WsHandle {
   /// Create a new handle.
   pub fn new(addr: String, sender: Sender<OutgoingMessage>) -> Self {
-    Self { addr, sender }
+    Self::with_info(Arc::new(ClientInfo::new(addr)), sender)
+  }
+
+  /// Create a new handle for the given client.
+  pub fn with_info(info: Arc<ClientInfo>, sender: Sender<OutgoingMessage>) -> Self {
+    Self { info, sender }
   }
 
   /// Send a message to the client.
@@ -378,32 +498,59 @@ impl WsHandle {
     self.sender.send(OutgoingMessage::Broadcast(message)).ok();
   }
 
+  /// Broadcast a message to all connected clients for which the predicate returns true.
+  pub fn broadcast_filtered(
+    &self,
+    predicate: impl Fn(&ClientInfo) -> bool + Send + 'static,
+    message: WebsocketMessage,
+  ) {
+    self
+      .sender
+      .send(OutgoingMessage::FilteredBroadcast(BroadcastFilter::new(predicate), message))
+      .ok();
+  }
+
   /// Get the address of the stream.
   pub fn peer_addr(&self) -> String {
-    self.addr.clone()
+    self.info.peer_addr().to_string()
+  }
+
+  /// Get the information the app tracks about this client.
+  /// Attributes set here are visible to the predicates of `broadcast_filtered`.
+  pub fn info(&self) -> &ClientInfo {
+    &self.info
   }
 }
 
 struct ExecState {
   stream: WebsocketContext,
-  broadcast: Sender<WebsocketMessage>,
+  info: Arc<ClientInfo>,
+  broadcast: Sender<Broadcast>,
   message_sender: Sender<OutgoingMessage>,
   outgoing_messages: Receiver<OutgoingMessage>,
   connect_handler: Option<Arc<Box<dyn EventHandler>>>,
   disconnect_handler: Option<Arc<Box<dyn EventHandler>>>,
   message_handler: Option<Arc<Box<dyn MessageHandler>>>,
   timeout: Duration,
+  idle_timeout: Option<Duration>,
   shutdown_signal: Arc<AtomicBool>,
 }
 
 fn exec(es: ExecState) {
-  let (mut ws_receiver, ws_sender, addr) = (es.stream.0, es.stream.1, es.stream.2);
+  let (mut ws_receiver, ws_sender, info) = (es.stream.0, es.stream.1, es.info);
 
   if let Some(ch) = es.connect_handler {
-    let handle = WsHandle::new(addr.clone(), es.message_sender.clone());
+    let handle = WsHandle::with_info(info.clone(), es.message_sender.clone());
     (ch)(handle);
   }
 
+  let read_sender = ws_sender.clone();
+  if es.idle_timeout.is_some() {
+    // Automatically answered pings restart the read timeout, the read thread answers them instead
+    // so that a client that keeps pinging does not delay the idle check.
+    ws_receiver.set_auto_pong(false);
+  }
+
   // write thread
   let write_shutdown = es.shutdown_signal.clone();
   let write_thread = thread::spawn(move || loop {
@@ -418,7 +565,12 @@ fn exec(es: ExecState) {
           }
         }
         OutgoingMessage::Broadcast(message) => {
-          if es.broadcast.send(message).is_err() {
+          if es.broadcast.send((None, message)).is_err() {
+            break;
+          }
+        }
+        OutgoingMessage::FilteredBroadcast(filter, message) => {
+          if es.broadcast.send((Some(filter), message)).is_err() {
             break;
           }
         }
@@ -433,39 +585,69 @@ fn exec(es: ExecState) {
   });
 
   // read thread
-  let read_thread = thread::spawn(move || loop {
-    if es.shutdown_signal.load(Ordering::SeqCst) {
-      break;
-    }
-    let Some(ref mh) = es.message_handler else { break };
-    match ws_receiver.read_message_timeout(Some(es.timeout)) {
-      Ok(message) => match message {
-        ReadMessageTimeoutResult::Message(m) => {
-          match m {
-            WebsocketMessage::Binary(_) | WebsocketMessage::Text(_) => {
-              (mh)(WsHandle::new(addr.clone(), es.message_sender.clone()), m);
-            }
-            WebsocketMessage::Ping => {
-              if es.message_sender.send(OutgoingMessage::Message(WebsocketMessage::Pong)).is_err() {
-                break;
+  let read_thread = thread::spawn(move || {
+    let clock = ws_receiver.clock().clone();
+    let mut last_frame = clock.now();
+    let mut last_message = last_frame;
+    loop {
+      if es.shutdown_signal.load(Ordering::SeqCst) {
+        break;
+      }
+      let Some(ref mh) = es.message_handler else { break };
+
+      let mut read_timeout = es.timeout;
+      if let Some(idle_timeout) = es.idle_timeout {
+        let idle_left =
+          idle_timeout.saturating_sub(clock.now().saturating_duration_since(last_message));
+        if idle_left.is_zero() {
+          info_log!("ws_app: closing idle connection {}", info.peer_addr());
+          _ = read_sender.close_with_code(CloseCode::Normal, "idle timeout");
+          if let Some(dh) = es.disconnect_handler {
+            (dh)(WsHandle::with_info(info.clone(), es.message_sender.clone()));
+          }
+          break;
+        }
+        read_timeout = read_timeout.min(idle_left);
+      }
+
+      match ws_receiver.read_message_timeout(Some(read_timeout)) {
+        Ok(message) => match message {
+          ReadMessageTimeoutResult::Message(m) => {
+            last_frame = clock.now();
+            match m {
+              WebsocketMessage::Binary(_) | WebsocketMessage::Text(_) => {
+                last_message = last_frame;
+                (mh)(WsHandle::with_info(info.clone(), es.message_sender.clone()), m);
               }
+              WebsocketMessage::Ping(payload) => {
+                // Answered right away like an automatic pong, echoing the application data.
+                if read_sender.pong_with_payload(&payload).is_err() {
+                  break;
+                }
+              }
+              WebsocketMessage::Pong(_) => (), // do nothing
             }
-            WebsocketMessage::Pong => (), // do nothing
           }
-        }
-        ReadMessageTimeoutResult::Timeout | ReadMessageTimeoutResult::Closed => {
+          // Woke up early to check the idle timeout, the client is still alive or gets closed as idle above.
+          ReadMessageTimeoutResult::Timeout
+            if clock.now().saturating_duration_since(last_frame) < es.timeout
+              || es.idle_timeout.is_some_and(|idle_timeout| {
+                clock.now().saturating_duration_since(last_message) >= idle_timeout
+              }) => {}
+          ReadMessageTimeoutResult::Timeout | ReadMessageTimeoutResult::Closed => {
+            if let Some(dh) = es.disconnect_handler {
+              (dh)(WsHandle::with_info(info.clone(), es.message_sender.clone()));
+            }
+            break;
+          }
+        },
+        Err(e) => {
+          error_log!("ws_app read: {:?} occurred", &e);
           if let Some(dh) = es.disconnect_handler {
-            (dh)(WsHandle::new(addr.clone(), es.message_sender.clone()));
+            (dh)(WsHandle::with_info(info.clone(), es.message_sender.clone()));
           }
           break;
         }
-      },
-      Err(e) => {
-        error_log!("ws_app read: {:?} occurred", &e);
-        if let Some(dh) = es.disconnect_handler {
-          (dh)(WsHandle::new(addr.clone(), es.message_sender.clone()));
-        }
-        break;
       }
     }
   });
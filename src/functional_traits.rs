@@ -8,6 +8,7 @@ use crate::websocket::stream::{WebsocketReceiver, WebsocketSender};
 use std::fmt::{Debug, Formatter};
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Instant, SystemTime};
 
 /// Represents an opaque join handle
 pub struct ThreadAdapterJoinHandle(Box<dyn FnOnce() -> thread::Result<()> + Send>);
@@ -52,6 +53,28 @@ impl ThreadAdapter for DefaultThreadAdapter {
   }
 }
 
+/// Trait that represents the time source used for timeouts that are not enforced by socket timeouts.
+/// Tests can implement it with a clock that is advanced manually to trigger timeouts without sleeping.
+pub trait Clock: Send + Sync + Debug {
+  /// Returns the current instant, like "Instant::now".
+  fn now(&self) -> Instant;
+
+  /// Returns the current wall clock time, like "SystemTime::now".
+  /// This is used for the `Date` header, see `TiiBuilder::with_date_header`.
+  fn system_time(&self) -> SystemTime {
+    SystemTime::now()
+  }
+}
+
+/// Clock that returns the real system time.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SystemClock;
+impl Clock for SystemClock {
+  fn now(&self) -> Instant {
+    Instant::now()
+  }
+}
+
 /// Represents a function able to handle a WebSocket handshake and consequent data frames.
 pub trait WebsocketEndpoint: Send + Sync {
   /// serve the web socket request.
@@ -246,4 +269,11 @@ pub trait Router: Debug + Send + Sync {
     stream: &dyn ConnectionStream,
     request: &mut RequestContext,
   ) -> TiiResult<RouterWebSocketServingResponse>;
+
+  /// The largest request body size limit of any route of this router that overrides the limit of the server.
+  /// The server only rejects bodies before routing if they exceed both its own limit and this one.
+  /// Routers that enforce no body size limits of their own return None.
+  fn max_route_body_size(&self) -> Option<u64> {
+    None
+  }
 }
@@ -0,0 +1,151 @@
+//! Compression of response bodies negotiated with the `Accept-Encoding` header of the request.
+
+use crate::http::encoding::ContentEncoding;
+use crate::http::headers::HeaderName;
+use crate::http::mime::MimeType;
+use crate::http::request_context::RequestContext;
+use crate::http::response_body::ResponseBody;
+use crate::http::{Response, StatusCode};
+use crate::tii_error::TiiResult;
+
+/// Which response bodies are compressed, shared by all content codings.
+#[derive(Debug, Clone)]
+pub(crate) struct ResponseCompression {
+  /// Bodies smaller than this amount of bytes are sent as they are.
+  pub(crate) threshold: usize,
+  /// Only bodies of these content types are compressed.
+  pub(crate) content_types: Vec<MimeType>,
+}
+
+/// The content codings tii can produce, in order of preference if the client accepts several with the same weight.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ContentCoding {
+  #[cfg(feature = "brotli")]
+  Brotli,
+}
+
+const SUPPORTED_CODINGS: &[ContentCoding] = &[
+  #[cfg(feature = "brotli")]
+  ContentCoding::Brotli,
+];
+
+impl ContentCoding {
+  fn encoding(self) -> ContentEncoding {
+    match self {
+      #[cfg(feature = "brotli")]
+      ContentCoding::Brotli => ContentEncoding::Brotli,
+    }
+  }
+
+  #[cfg_attr(not(feature = "brotli"), expect(unused_variables))]
+  fn encode(self, data: &[u8]) -> TiiResult<Vec<u8>> {
+    match self {
+      #[cfg(feature = "brotli")]
+      ContentCoding::Brotli => {
+        let params = brotli::enc::BrotliEncoderParams { quality: 5, ..Default::default() };
+        let mut encoded = Vec::new();
+        brotli::BrotliCompress(&mut &*data, &mut encoded, &params)?;
+        Ok(encoded)
+      }
+    }
+  }
+}
+
+/// Returns the weight the `Accept-Encoding` header value gives to the coding, 0 if it is not acceptable.
+fn accepted_weight(accept_encoding: &str, token: &str) -> f32 {
+  let mut wildcard = None;
+  for entry in accept_encoding.split(',') {
+    let mut params = entry.split(';');
+    let name = params.next().unwrap_or_default().trim();
+    let weight = params
+      .filter_map(|param| param.split_once('='))
+      .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+      .map(|(_, value)| value.trim().parse::<f32>().unwrap_or(0.0))
+      .unwrap_or(1.0);
+
+    if name.eq_ignore_ascii_case(token) {
+      return weight;
+    }
+    if name == "*" {
+      wildcard = Some(weight);
+    }
+  }
+  wildcard.unwrap_or(0.0)
+}
+
+/// Picks the coding with the highest weight, ties are broken by the order of `SUPPORTED_CODINGS`.
+fn negotiate(accept_encoding: &str) -> Option<ContentCoding> {
+  let mut best: Option<(ContentCoding, f32)> = None;
+  for coding in SUPPORTED_CODINGS.iter().copied() {
+    let weight = accepted_weight(accept_encoding, coding.encoding().as_str());
+    if weight > 0.0 && best.is_none_or(|(_, best_weight)| weight > best_weight) {
+      best = Some((coding, weight));
+    }
+  }
+  best.map(|(coding, _)| coding)
+}
+
+/// Compresses the body of the response if its content type and size qualify and the client accepts a supported coding.
+/// Streamed and file bodies as well as bodies that already have a `Content-Encoding` are sent as they are.
+pub(crate) fn compress_response(
+  compression: &ResponseCompression,
+  context: &RequestContext,
+  response: &mut Response,
+) -> TiiResult<()> {
+  // Without a supported coding every client gets the same response, so there is nothing to vary on.
+  if SUPPORTED_CODINGS.is_empty() {
+    return Ok(());
+  }
+
+  if matches!(
+    response.status_code,
+    StatusCode::NoContent | StatusCode::PartialContent | StatusCode::NotModified
+  ) || response.get_header(HeaderName::ContentEncoding).is_some()
+  {
+    return Ok(());
+  }
+
+  let compressible = response.get_header(HeaderName::ContentType).is_some_and(|content_type| {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    compression.content_types.iter().any(|allowed| allowed.as_str().eq_ignore_ascii_case(mime))
+  });
+
+  let len = match response.body.as_ref() {
+    Some(ResponseBody::FixedSizeTextData(text)) => text.len(),
+    Some(ResponseBody::FixedSizeBinaryData(data)) => data.len(),
+    _ => return Ok(()),
+  };
+
+  if !compressible || len < compression.threshold {
+    return Ok(());
+  }
+
+  // The response depends on Accept-Encoding even if this client gets it uncompressed.
+  let vary = response.headers.get_all("Vary");
+  if !vary
+    .iter()
+    .flat_map(|value| value.split(','))
+    .any(|value| value.trim().eq_ignore_ascii_case("Accept-Encoding") || value.trim() == "*")
+  {
+    response.headers.add("Vary", "Accept-Encoding");
+  }
+
+  let Some(coding) =
+    context.request_head().get_header(HeaderName::AcceptEncoding).and_then(negotiate)
+  else {
+    return Ok(());
+  };
+
+  let encoded = match response.body.as_ref() {
+    Some(ResponseBody::FixedSizeTextData(text)) => coding.encode(text.as_bytes())?,
+    Some(ResponseBody::FixedSizeBinaryData(data)) => coding.encode(data)?,
+    _ => return Ok(()),
+  };
+
+  response.body = Some(ResponseBody::FixedSizeBinaryData(encoded));
+  response.headers.set(HeaderName::ContentEncoding, coding.encoding().as_str());
+  let mut meta = response.meta();
+  meta.compressed = true;
+  response.set_meta(meta);
+  Ok(())
+}
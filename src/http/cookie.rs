@@ -2,6 +2,10 @@
 
 use crate::http::headers::Header;
 
+use crate::util::unwrap_poison;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::sync::Mutex;
 use std::time::Duration;
 
 /// Represents an HTTP cookie as in the `Cookie` header.
@@ -35,7 +39,11 @@ pub struct SetCookie {
   /// Whether the cookie is HTTP-only.
   pub http_only: bool,
   /// The SameSite configuration of the cookie.
+  /// `SameSite::None` is always sent with the Secure attribute, regardless of `secure`.
   pub same_site: Option<SameSite>,
+  /// Whether the cookie is stored partitioned by the top-level site (CHIPS).
+  /// Partitioned cookies are always sent with the Secure attribute, regardless of `secure`.
+  pub partitioned: bool,
 }
 
 /// Represents the SameSite value of the cookie.
@@ -93,9 +101,18 @@ impl SetCookie {
       secure: false,
       http_only: false,
       same_site: None,
+      partitioned: false,
     }
   }
 
+  /// Create a cookie that deletes the cookie with the given name from the client.
+  /// It has an empty value and `Max-Age=0`.
+  /// The client only deletes the cookie if path and domain match those it was set with,
+  /// use `with_path` and `with_domain` if the original cookie had them.
+  pub fn delete(name: impl AsRef<str>) -> Self {
+    Self::new(name, "").with_max_age(Duration::ZERO)
+  }
+
   /// Set the expiry date of the cookie.
   ///
   /// **Warning:** This must be a valid HTTP timestamp.
@@ -139,6 +156,12 @@ impl SetCookie {
     self.same_site = Some(same_site);
     self
   }
+
+  /// Set the Partitioned flag of the cookie.
+  pub fn with_partitioned(mut self, partitioned: bool) -> Self {
+    self.partitioned = partitioned;
+    self
+  }
 }
 
 impl From<SetCookie> for Header {
@@ -161,6 +184,9 @@ impl From<SetCookie> for Header {
       value = format!("{}; Path={}", value, path);
     }
 
+    // Browsers reject SameSite=None and Partitioned cookies that are not Secure.
+    let secure = cookie.secure || cookie.partitioned || cookie.same_site == Some(SameSite::None);
+
     if let Some(same_site) = cookie.same_site {
       value = format!(
         "{}; SameSite={}",
@@ -173,7 +199,7 @@ impl From<SetCookie> for Header {
       );
     }
 
-    if cookie.secure {
+    if secure {
       value = format!("{}; Secure", value);
     }
 
@@ -181,6 +207,59 @@ impl From<SetCookie> for Header {
       value = format!("{}; HttpOnly", value);
     }
 
+    if cookie.partitioned {
+      value = format!("{}; Partitioned", value);
+    }
+
     Header::new("Set-Cookie", value)
   }
 }
+
+/// Cookies of a request together with the `Set-Cookie` operations queued by handlers.
+/// The queued operations are added to the final response by the router.
+pub struct CookieJar {
+  incoming: Vec<Cookie>,
+  pending: Mutex<Vec<SetCookie>>,
+}
+
+impl CookieJar {
+  /// Create a new cookie jar containing the given incoming cookies.
+  pub fn new(incoming: Vec<Cookie>) -> Self {
+    Self { incoming, pending: Mutex::new(Vec::new()) }
+  }
+
+  /// Returns the cookie with the given name that was sent by the client.
+  pub fn get(&self, name: impl AsRef<str>) -> Option<&Cookie> {
+    self.incoming.iter().find(|cookie| cookie.name == name.as_ref())
+  }
+
+  /// Returns all cookies that were sent by the client.
+  pub fn incoming(&self) -> &[Cookie] {
+    self.incoming.as_slice()
+  }
+
+  /// Queues the cookie to be set on the response.
+  pub fn set(&self, cookie: SetCookie) -> io::Result<()> {
+    unwrap_poison(self.pending.lock())?.push(cookie);
+    Ok(())
+  }
+
+  /// Queues the deletion of the cookie with the given name.
+  /// This is a `Set-Cookie` created by `SetCookie::delete`.
+  /// Use `set` with `SetCookie::delete` directly if the cookie was set with a specific path or domain.
+  pub fn remove(&self, name: impl AsRef<str>) -> io::Result<()> {
+    self.set(SetCookie::delete(name))
+  }
+
+  /// Takes all queued `Set-Cookie` operations out of the jar.
+  pub fn take_pending(&self) -> io::Result<Vec<SetCookie>> {
+    Ok(std::mem::take(unwrap_poison(self.pending.lock())?.as_mut()))
+  }
+}
+
+impl Debug for CookieJar {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let pending = self.pending.lock().map(|pending| pending.len()).unwrap_or_default();
+    f.write_fmt(format_args!("CookieJar(incoming={} pending={})", self.incoming.len(), pending))
+  }
+}
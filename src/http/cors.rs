@@ -0,0 +1,157 @@
+//! Cross-origin resource sharing, see `TiiRouterBuilder::with_cors`.
+//! See [Fetch Standard, CORS protocol](https://fetch.spec.whatwg.org/#http-cors-protocol).
+
+use crate::http::headers::HeaderName;
+use crate::http::method::Method;
+use crate::http::request_context::RequestContext;
+use crate::http::Response;
+use std::time::Duration;
+
+/// Which cross-origin requests browsers are allowed to make to the routes of a router.
+///
+/// The router answers preflight requests on its own and adds `Access-Control-Allow-Origin`
+/// to the responses of requests from an allowed origin.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cors {
+  /// None allows any origin.
+  origins: Option<Vec<String>>,
+  allowed_headers: Vec<String>,
+  exposed_headers: Vec<String>,
+  credentials: bool,
+  max_age: Option<Duration>,
+}
+
+impl Cors {
+  /// Allows requests from any origin. Responses carry `Access-Control-Allow-Origin: *`.
+  /// Credentials can't be allowed for any origin.
+  pub fn any_origin() -> Self {
+    Self::default()
+  }
+
+  /// Allows requests only from the given origins, for example `https://example.com`.
+  /// Origins are compared case-insensitively and the origin of the request is sent back.
+  pub fn origins(origins: impl IntoIterator<Item = impl ToString>) -> Self {
+    Self {
+      origins: Some(origins.into_iter().map(|origin| origin.to_string()).collect()),
+      ..Self::default()
+    }
+  }
+
+  /// Allows clients to send the given request header, it is listed in `Access-Control-Allow-Headers` of preflights.
+  pub fn with_allowed_header(mut self, header: impl AsRef<str>) -> Self {
+    self.allowed_headers.push(header.as_ref().to_string());
+    self
+  }
+
+  /// Allows scripts to read the given response header, it is listed in `Access-Control-Expose-Headers`.
+  pub fn with_exposed_header(mut self, header: impl AsRef<str>) -> Self {
+    self.exposed_headers.push(header.as_ref().to_string());
+    self
+  }
+
+  /// Allows requests with cookies or authorization by sending `Access-Control-Allow-Credentials: true`.
+  /// This requires a list of `origins`, otherwise every site could read credentialed responses,
+  /// `TiiRouterBuilder::with_cors` rejects credentials together with `any_origin`.
+  pub fn with_credentials(mut self, credentials: bool) -> Self {
+    self.credentials = credentials;
+    self
+  }
+
+  /// Lets browsers cache the result of a preflight for the given duration with `Access-Control-Max-Age`.
+  pub fn with_max_age(mut self, max_age: Duration) -> Self {
+    self.max_age = Some(max_age);
+    self
+  }
+
+  /// True if credentials are allowed for any origin, which `TiiRouterBuilder::with_cors` rejects.
+  pub(crate) fn is_credentials_with_any_origin(&self) -> bool {
+    self.credentials && self.origins.is_none()
+  }
+
+  /// Returns the value of `Access-Control-Allow-Origin` for the given origin, None if it is not allowed.
+  fn allow_origin<'a>(&self, origin: &'a str) -> Option<&'a str> {
+    match self.origins.as_ref() {
+      None => Some("*"),
+      Some(origins) => {
+        origins.iter().any(|allowed| allowed.eq_ignore_ascii_case(origin)).then_some(origin)
+      }
+    }
+  }
+
+  /// Answers the request if it is a preflight from an allowed origin for one of the given methods.
+  pub(crate) fn preflight(&self, request: &RequestContext, methods: &[Method]) -> Option<Response> {
+    let head = request.request_head();
+    if head.method() != &Method::Options {
+      return None;
+    }
+
+    let requested = Method::from(head.get_header(HeaderName::AccessControlRequestMethod)?.trim());
+    let allow_origin = self.allow_origin(head.get_header(HeaderName::Origin)?)?;
+    if !methods.contains(&requested) {
+      return None;
+    }
+
+    let mut response = Response::no_content();
+    self.add_origin_headers(allow_origin, &mut response);
+    let methods = methods.iter().map(Method::as_str).collect::<Vec<_>>().join(", ");
+    response.headers.set(HeaderName::AccessControlAllowMethods, methods);
+    if !self.allowed_headers.is_empty() {
+      response.headers.set(HeaderName::AccessControlAllowHeaders, self.allowed_headers.join(", "));
+    }
+    if let Some(max_age) = self.max_age {
+      response.headers.set("Access-Control-Max-Age", max_age.as_secs().to_string());
+    }
+    Some(response)
+  }
+
+  /// Adds the headers of an actual cross-origin request to the response if the origin is allowed.
+  /// Responses that already carry `Access-Control-Allow-Origin` are left as they are.
+  pub(crate) fn apply(&self, request: &RequestContext, response: &mut Response) {
+    if response.headers.get(HeaderName::AccessControlAllowOrigin).is_some() {
+      return;
+    }
+
+    // Whether the response carries `Access-Control-Allow-Origin` depends on the origin,
+    // so caches must not serve it to another origin even if this one is not allowed.
+    if self.origins.is_some() {
+      Self::add_vary_origin(response);
+    }
+
+    let Some(allow_origin) = request
+      .request_head()
+      .get_header(HeaderName::Origin)
+      .and_then(|origin| self.allow_origin(origin))
+    else {
+      return;
+    };
+
+    self.add_origin_headers(allow_origin, response);
+    if !self.exposed_headers.is_empty() {
+      response.headers.set("Access-Control-Expose-Headers", self.exposed_headers.join(", "));
+    }
+  }
+
+  fn add_origin_headers(&self, allow_origin: &str, response: &mut Response) {
+    response.headers.set(HeaderName::AccessControlAllowOrigin, allow_origin);
+    if self.credentials {
+      response.headers.set("Access-Control-Allow-Credentials", "true");
+    }
+
+    // A specific origin is sent back, so caches must not serve this response to other origins.
+    if allow_origin != "*" {
+      Self::add_vary_origin(response);
+    }
+  }
+
+  fn add_vary_origin(response: &mut Response) {
+    if !response
+      .headers
+      .get_all("Vary")
+      .iter()
+      .flat_map(|value| value.split(','))
+      .any(|value| value.trim().eq_ignore_ascii_case("Origin") || value.trim() == "*")
+    {
+      response.headers.add("Vary", "Origin");
+    }
+  }
+}
@@ -0,0 +1,134 @@
+//! Provides the codings of the `Transfer-Encoding` and `Content-Encoding` headers.
+
+use std::fmt::Display;
+
+/// Represents a transfer coding of the `Transfer-Encoding` header.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TransferEncoding {
+  /// The `chunked` transfer coding, the message body is sent as a series of chunks.
+  Chunked,
+  /// The `compress` transfer coding (LZW).
+  Compress,
+  /// The `deflate` transfer coding (zlib).
+  Deflate,
+  /// The `gzip` transfer coding.
+  Gzip,
+  /// Any other transfer coding, in lowercase.
+  Custom(String),
+}
+
+impl TransferEncoding {
+  /// Converts a transfer coding token into an enum variant, ignoring case.
+  ///
+  /// ## Example
+  /// ```
+  /// use tii::http::encoding::TransferEncoding;
+  /// assert_eq!(TransferEncoding::from("Chunked"), TransferEncoding::Chunked);
+  /// ```
+  pub fn from(token: &str) -> Self {
+    let token = token.trim().to_ascii_lowercase();
+    match token.as_str() {
+      "chunked" => Self::Chunked,
+      "compress" | "x-compress" => Self::Compress,
+      "deflate" => Self::Deflate,
+      "gzip" | "x-gzip" => Self::Gzip,
+      _ => Self::Custom(token),
+    }
+  }
+
+  /// Parses a header value like `gzip, chunked` into its codings in the order they were applied.
+  /// Parameters of a coding are ignored.
+  pub fn parse_list(value: &str) -> Vec<Self> {
+    parse_tokens(value).map(Self::from).collect()
+  }
+
+  /// returns the token of the coding as it is sent in the header.
+  pub fn as_str(&self) -> &str {
+    match self {
+      TransferEncoding::Chunked => "chunked",
+      TransferEncoding::Compress => "compress",
+      TransferEncoding::Deflate => "deflate",
+      TransferEncoding::Gzip => "gzip",
+      TransferEncoding::Custom(token) => token.as_str(),
+    }
+  }
+}
+
+impl Display for TransferEncoding {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// Represents a content coding of the `Content-Encoding` header.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ContentEncoding {
+  /// The `br` content coding (Brotli).
+  Brotli,
+  /// The `compress` content coding (LZW).
+  Compress,
+  /// The `deflate` content coding (zlib).
+  Deflate,
+  /// The `gzip` content coding.
+  Gzip,
+  /// The `zstd` content coding (Zstandard).
+  Zstd,
+  /// The `identity` content coding, the body is not encoded.
+  Identity,
+  /// Any other content coding, in lowercase.
+  Custom(String),
+}
+
+impl ContentEncoding {
+  /// Converts a content coding token into an enum variant, ignoring case.
+  ///
+  /// ## Example
+  /// ```
+  /// use tii::http::encoding::ContentEncoding;
+  /// assert_eq!(ContentEncoding::from("br"), ContentEncoding::Brotli);
+  /// ```
+  pub fn from(token: &str) -> Self {
+    let token = token.trim().to_ascii_lowercase();
+    match token.as_str() {
+      "br" => Self::Brotli,
+      "compress" | "x-compress" => Self::Compress,
+      "deflate" => Self::Deflate,
+      "gzip" | "x-gzip" => Self::Gzip,
+      "zstd" => Self::Zstd,
+      "identity" => Self::Identity,
+      _ => Self::Custom(token),
+    }
+  }
+
+  /// Parses a header value like `gzip, br` into its codings in the order they were applied.
+  pub fn parse_list(value: &str) -> Vec<Self> {
+    parse_tokens(value).map(Self::from).collect()
+  }
+
+  /// returns the token of the coding as it is sent in the header.
+  pub fn as_str(&self) -> &str {
+    match self {
+      ContentEncoding::Brotli => "br",
+      ContentEncoding::Compress => "compress",
+      ContentEncoding::Deflate => "deflate",
+      ContentEncoding::Gzip => "gzip",
+      ContentEncoding::Zstd => "zstd",
+      ContentEncoding::Identity => "identity",
+      ContentEncoding::Custom(token) => token.as_str(),
+    }
+  }
+}
+
+impl Display for ContentEncoding {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// The non-empty coding tokens of a comma separated header value without their parameters.
+fn parse_tokens(value: &str) -> impl Iterator<Item = &str> {
+  value
+    .split(',')
+    .map(|coding| coding.split(';').next().unwrap_or_default().trim())
+    .filter(|token| !token.is_empty())
+}
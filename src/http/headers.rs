@@ -156,6 +156,10 @@ pub enum HeaderName {
   From,
   /// Specifies the host to which the request is being sent, e.g. "www.example.com".
   Host,
+  /// Makes the request conditional, the resource is only sent if none of the given ETags match.
+  IfNoneMatch,
+  /// Makes the request conditional, the resource is only sent if it has been modified after the given date.
+  IfModifiedSince,
   /// Indicates the origin that caused the request.
   Origin,
   /// Contains backwards-compatible caching information.
@@ -212,7 +216,7 @@ pub enum HeaderName {
   /// Indicates that a proxy server in the connection needs authentication.
   ProxyAuthenticate,
 
-  /// Custom header with a lowercase name
+  /// Custom header, its name is kept exactly as given
   Custom(String),
 }
 
@@ -237,6 +241,8 @@ static WELL_KNOWN: &[HeaderName] = &[
   HeaderName::Forwarded,
   HeaderName::From,
   HeaderName::Host,
+  HeaderName::IfNoneMatch,
+  HeaderName::IfModifiedSince,
   HeaderName::Origin,
   HeaderName::Pragma,
   HeaderName::Referer,
@@ -308,6 +314,8 @@ impl HeaderName {
       HeaderName::Forwarded => "Forwarded",
       HeaderName::From => "From",
       HeaderName::Host => "Host",
+      HeaderName::IfNoneMatch => "If-None-Match",
+      HeaderName::IfModifiedSince => "If-Modified-Since",
       HeaderName::Origin => "Origin",
       HeaderName::Pragma => "Pragma",
       HeaderName::Referer => "Referer",
@@ -362,6 +370,8 @@ impl HeaderName {
       HeaderName::Forwarded => "Forwarded",
       HeaderName::From => "From",
       HeaderName::Host => "Host",
+      HeaderName::IfNoneMatch => "If-None-Match",
+      HeaderName::IfModifiedSince => "If-Modified-Since",
       HeaderName::Origin => "Origin",
       HeaderName::Pragma => "Pragma",
       HeaderName::Referer => "Referer",
@@ -427,6 +437,8 @@ impl From<&str> for HeaderName {
       "forwarded" => Self::Forwarded,
       "from" => Self::From,
       "host" => Self::Host,
+      "if-none-match" => Self::IfNoneMatch,
+      "if-modified-since" => Self::IfModifiedSince,
       "origin" => Self::Origin,
       "pragma" => Self::Pragma,
       "referer" => Self::Referer,
@@ -21,6 +21,9 @@ pub enum Method {
   Trace,
   /// The 'PATCH' method.
   Patch,
+  /// The `CONNECT` method, used to establish a tunnel through a proxy.
+  /// The request target is an authority like `example.com:443` instead of a path.
+  Connect,
   /// Anything else your heart desires.
   Custom(String),
 }
@@ -34,6 +37,7 @@ static WELL_KNOWN: &[Method] = &[
   Method::Options,
   Method::Trace,
   Method::Patch,
+  Method::Connect,
 ];
 
 impl Method {
@@ -54,6 +58,7 @@ impl Method {
       "OPTIONS" => Self::Options,
       "TRACE" => Self::Trace,
       "PATCH" => Self::Patch,
+      "CONNECT" => Self::Connect,
       _ => Self::Custom(name.to_ascii_uppercase()),
     }
   }
@@ -86,6 +91,7 @@ impl Method {
       Method::Options => "OPTIONS",
       Method::Trace => "TRACE",
       Method::Patch => "PATCH",
+      Method::Connect => "CONNECT",
       Method::Custom(_) => return None,
     })
   }
@@ -101,6 +107,7 @@ impl Method {
       Method::Options => "OPTIONS",
       Method::Trace => "TRACE",
       Method::Patch => "PATCH",
+      Method::Connect => "CONNECT",
       Method::Custom(meth) => meth.as_str(),
     }
   }
@@ -1,9 +1,14 @@
 //! Contains the Tii HTTP implementation.
 
+pub(crate) mod compression;
 pub mod cookie;
+pub mod cors;
+pub mod encoding;
 pub mod headers;
 pub mod method;
 pub mod mime;
+pub mod multipart;
+pub mod range;
 pub mod request;
 pub mod request_body;
 pub mod request_context;
@@ -0,0 +1,327 @@
+//! Streaming parser for `multipart/form-data` request bodies as described in RFC 7578.
+
+use crate::http::headers::{Header, HeaderName};
+use crate::http::request_body::RequestBody;
+use crate::http::request_context::RequestContext;
+use crate::tii_error::{BodyParsingError, TiiResult};
+use std::io;
+use std::io::{ErrorKind, Read};
+
+/// Maximum size of the headers of a single part including the line breaks.
+const MAX_PART_HEAD_SIZE: usize = 8192;
+
+/// Amount of bytes read from the request body at once.
+const READ_CHUNK_SIZE: usize = 0x2000;
+
+/// Reads the parts of a `multipart/form-data` request body one after another.
+///
+/// The body is never buffered as a whole, each part is streamed via its `Read` impl.
+/// The size of the body is limited by the server, see `TiiBuilder::with_max_body_size`.
+#[derive(Debug)]
+pub struct Multipart<'a> {
+  body: &'a RequestBody,
+  /// `\r\n--` followed by the boundary.
+  delimiter: Vec<u8>,
+  /// Bytes read from the body but not consumed yet.
+  buffer: Vec<u8>,
+  /// True while the body of a part (or the preamble) is being read.
+  in_part: bool,
+  /// True once the closing delimiter was read.
+  finished: bool,
+}
+
+/// Headers, name and filename of a part.
+type PartHead = (Vec<Header>, String, Option<String>);
+
+/// A single part of a `multipart/form-data` body. Reading from it yields the body of the part.
+#[derive(Debug)]
+pub struct MultipartPart<'m, 'a> {
+  multipart: &'m mut Multipart<'a>,
+  headers: Vec<Header>,
+  name: String,
+  filename: Option<String>,
+}
+
+impl<'a> Multipart<'a> {
+  /// Creates a parser for the body of the request.
+  ///
+  /// Returns a `BodyParsingError::UnsupportedMediaType` if the request is not `multipart/form-data`
+  /// or has no boundary and `BodyParsingError::MissingBody` if the request has no body.
+  pub fn new(context: &'a RequestContext) -> TiiResult<Self> {
+    let Some(content_type) = context.request_head().get_header(&HeaderName::ContentType) else {
+      return Err(BodyParsingError::UnsupportedMediaType(None).into());
+    };
+
+    let Some(boundary) = boundary_of(content_type) else {
+      return Err(BodyParsingError::UnsupportedMediaType(Some(content_type.to_string())).into());
+    };
+
+    let Some(body) = context.request_body() else {
+      return Err(BodyParsingError::MissingBody.into());
+    };
+
+    Ok(Self::with_boundary(body, boundary.as_str()))
+  }
+
+  /// Creates a parser for a body with the given boundary, without the leading `--`.
+  pub fn with_boundary(body: &'a RequestBody, boundary: &str) -> Self {
+    let mut delimiter = b"\r\n--".to_vec();
+    delimiter.extend_from_slice(boundary.as_bytes());
+    Self {
+      body,
+      delimiter,
+      //The first delimiter may directly start the body, so pretend that a line break preceded it.
+      buffer: b"\r\n".to_vec(),
+      in_part: true,
+      finished: false,
+    }
+  }
+
+  /// Returns the next part of the body or None after the last part.
+  ///
+  /// The unread remainder of the previous part is skipped.
+  /// Returns a `BodyParsingError::Malformed` if the body or the headers of the part are malformed,
+  /// after such an error all further calls return None.
+  pub fn next_part(&mut self) -> TiiResult<Option<MultipartPart<'_, 'a>>> {
+    if self.finished {
+      return Ok(None);
+    }
+
+    //Once the body is malformed there is no way to find the next part.
+    let (headers, name, filename) = match self.read_part_head() {
+      Ok(Some(head)) => head,
+      Ok(None) => {
+        self.finished = true;
+        return Ok(None);
+      }
+      Err(err) => {
+        self.finished = true;
+        return Err(err);
+      }
+    };
+
+    self.in_part = true;
+    Ok(Some(MultipartPart { multipart: self, headers, name, filename }))
+  }
+
+  /// Skips to the next delimiter and reads the head of the part that follows it.
+  /// Returns None if it is the closing delimiter.
+  fn read_part_head(&mut self) -> TiiResult<Option<PartHead>> {
+    let mut skip = [0u8; READ_CHUNK_SIZE];
+    while self.read_part(&mut skip)? > 0 {}
+
+    if !self.buffer.starts_with(&self.delimiter) {
+      return Err(malformed("body ends without closing boundary").into());
+    }
+
+    self.buffer.drain(..self.delimiter.len());
+    self.fill(2)?;
+    if self.buffer.starts_with(b"--") {
+      return Ok(None);
+    }
+
+    let mut head_size = 0usize;
+    let padding = self.read_line(&mut head_size)?;
+    if !padding.trim().is_empty() {
+      return Err(malformed("garbage after boundary").into());
+    }
+
+    let mut headers = Vec::new();
+    loop {
+      let line = self.read_line(&mut head_size)?;
+      if line.is_empty() {
+        break;
+      }
+
+      let Some((name, value)) = line.split_once(':') else {
+        return Err(malformed(format!("invalid part header {line:?}")).into());
+      };
+
+      headers.push(Header::new(name.trim(), value.trim()));
+    }
+
+    let Some(disposition) = headers
+      .iter()
+      .find(|hdr| hdr.name == HeaderName::ContentDisposition)
+      .map(|hdr| hdr.value.as_str())
+    else {
+      return Err(malformed("part has no Content-Disposition").into());
+    };
+
+    let mut params = disposition_params(disposition);
+    let Some(name) =
+      params.iter().position(|(key, _)| key == "name").map(|idx| params.swap_remove(idx).1)
+    else {
+      return Err(malformed("part has no name").into());
+    };
+    let filename = params.into_iter().find(|(key, _)| key == "filename").map(|(_, value)| value);
+
+    Ok(Some((headers, name, filename)))
+  }
+
+  /// Reads bytes of the current part, returns 0 once the delimiter that ends the part is reached.
+  fn read_part(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if !self.in_part || buf.is_empty() {
+      return Ok(0);
+    }
+
+    loop {
+      let available =
+        match self.buffer.windows(self.delimiter.len()).position(|w| w == self.delimiter) {
+          Some(0) => {
+            self.in_part = false;
+            return Ok(0);
+          }
+          Some(pos) => pos,
+          //The tail of the buffer may be the start of a delimiter.
+          None => self.buffer.len().saturating_sub(self.delimiter.len() - 1),
+        };
+
+      if available > 0 {
+        let count = available.min(buf.len());
+        for (dst, src) in buf.iter_mut().zip(self.buffer.drain(..count)) {
+          *dst = src;
+        }
+        return Ok(count);
+      }
+
+      if !self.fill_more()? {
+        return Err(io_malformed("body ends without closing boundary"));
+      }
+    }
+  }
+
+  /// Reads a line of the head of a part and removes the trailing line break.
+  fn read_line(&mut self, head_size: &mut usize) -> io::Result<String> {
+    loop {
+      if let Some(pos) = self.buffer.windows(2).position(|w| w == b"\r\n") {
+        *head_size += pos + 2;
+        if *head_size > MAX_PART_HEAD_SIZE {
+          return Err(io_malformed("part headers too large"));
+        }
+
+        let line: Vec<u8> = self.buffer.drain(..pos + 2).take(pos).collect();
+        return String::from_utf8(line).map_err(|_| io_malformed("part header is not utf-8"));
+      }
+
+      if *head_size + self.buffer.len() > MAX_PART_HEAD_SIZE {
+        return Err(io_malformed("part headers too large"));
+      }
+
+      if !self.fill_more()? {
+        return Err(io_malformed("body ends in part headers"));
+      }
+    }
+  }
+
+  /// Reads until at least `len` bytes are buffered, the body ending before that is malformed.
+  fn fill(&mut self, len: usize) -> io::Result<()> {
+    while self.buffer.len() < len {
+      if !self.fill_more()? {
+        return Err(io_malformed("body ends without closing boundary"));
+      }
+    }
+    Ok(())
+  }
+
+  /// Reads more bytes from the body into the buffer, returns false if the body has ended.
+  fn fill_more(&mut self) -> io::Result<bool> {
+    let mut chunk = [0u8; READ_CHUNK_SIZE];
+    let count = self.body.read(&mut chunk)?;
+    self.buffer.extend_from_slice(chunk.get(..count).unwrap_or_default());
+    Ok(count > 0)
+  }
+}
+
+impl MultipartPart<'_, '_> {
+  /// The name of the form field from the `Content-Disposition` header.
+  pub fn name(&self) -> &str {
+    self.name.as_str()
+  }
+
+  /// The file name from the `Content-Disposition` header, only present for file uploads.
+  pub fn filename(&self) -> Option<&str> {
+    self.filename.as_deref()
+  }
+
+  /// All headers of the part.
+  pub fn headers(&self) -> &[Header] {
+    self.headers.as_slice()
+  }
+
+  /// Returns the value of the first header of the part with the given name.
+  pub fn get_header(&self, name: impl AsRef<str>) -> Option<&str> {
+    let name = HeaderName::from(name.as_ref());
+    self.headers.iter().find(|hdr| hdr.name == name).map(|hdr| hdr.value.as_str())
+  }
+}
+
+impl Read for MultipartPart<'_, '_> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.multipart.read_part(buf)
+  }
+}
+
+fn malformed(msg: impl ToString) -> BodyParsingError {
+  BodyParsingError::Malformed(msg.to_string())
+}
+
+fn io_malformed(msg: &str) -> io::Error {
+  io::Error::new(ErrorKind::InvalidData, malformed(msg))
+}
+
+/// Returns the boundary of a `multipart/form-data` content type.
+fn boundary_of(content_type: &str) -> Option<String> {
+  let (mime, params) = content_type.split_once(';')?;
+  if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+    return None;
+  }
+
+  let boundary = parse_params(params).into_iter().find(|(key, _)| key == "boundary")?.1;
+  (!boundary.is_empty() && boundary.len() <= 70).then_some(boundary)
+}
+
+/// Returns the parameters of a `Content-Disposition` header like `form-data; name="file"; filename="a.txt"`.
+fn disposition_params(value: &str) -> Vec<(String, String)> {
+  match value.split_once(';') {
+    Some((_, params)) => parse_params(params),
+    None => Vec::new(),
+  }
+}
+
+/// Parses `; `-separated `key=value` parameters, values may be quoted strings.
+/// Keys are returned in lowercase.
+fn parse_params(params: &str) -> Vec<(String, String)> {
+  let mut result = Vec::new();
+  let mut rest = params;
+  loop {
+    rest = rest.trim_start_matches(|c: char| c == ';' || c.is_ascii_whitespace());
+    let Some((key, tail)) = rest.split_once('=') else {
+      return result;
+    };
+
+    let key = key.trim().to_ascii_lowercase();
+    let tail = tail.trim_start();
+    let mut value = String::new();
+    if let Some(quoted) = tail.strip_prefix('"') {
+      let mut chars = quoted.char_indices();
+      rest = "";
+      while let Some((idx, c)) = chars.next() {
+        match c {
+          '\\' => value.extend(chars.next().map(|(_, c)| c)),
+          '"' => {
+            rest = quoted.get(idx + 1..).unwrap_or_default();
+            break;
+          }
+          c => value.push(c),
+        }
+      }
+    } else {
+      let end = tail.find(';').unwrap_or(tail.len());
+      value.push_str(tail.get(..end).unwrap_or_default().trim());
+      rest = tail.get(end..).unwrap_or_default();
+    }
+
+    result.push((key, value));
+  }
+}
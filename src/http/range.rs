@@ -0,0 +1,98 @@
+//! Parsing of the `Range` request header.
+
+use crate::tii_error::RangeError;
+
+/// A range of bytes of a resource. Both `start` and `end` are inclusive, just like in the header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ByteRange {
+  /// Offset of the first byte of the range.
+  pub start: u64,
+  /// Offset of the last byte of the range.
+  pub end: u64,
+}
+
+impl ByteRange {
+  /// The amount of bytes in the range.
+  pub const fn len(&self) -> u64 {
+    self.end - self.start + 1
+  }
+
+  /// Always false, a range contains at least one byte.
+  pub const fn is_empty(&self) -> bool {
+    false
+  }
+
+  /// Returns the value of the `Content-Range` header for a response containing this range,
+  /// for example `bytes 0-499/1234`.
+  pub fn content_range(&self, resource_len: u64) -> String {
+    format!("bytes {}-{}/{}", self.start, self.end, resource_len)
+  }
+}
+
+/// Parses the value of a `Range` header like `bytes=0-499, 1000-, -500` for a resource of `resource_len` bytes.
+///
+/// Ranges are clamped to the length of the resource and returned in the order of the header.
+/// Ranges that start beyond the end of the resource are dropped.
+/// Returns `RangeError::Unsatisfiable` if no range overlaps the resource, the response should then be a
+/// `416 Range Not Satisfiable`. For other errors the header should be ignored and the entire resource served.
+pub fn parse(header: &str, resource_len: u64) -> Result<Vec<ByteRange>, RangeError> {
+  let Some((unit, specs)) = header.trim().split_once('=') else {
+    return Err(RangeError::Malformed(header.to_string()));
+  };
+
+  if !unit.trim().eq_ignore_ascii_case("bytes") {
+    return Err(RangeError::UnsupportedUnit(unit.trim().to_string()));
+  }
+
+  let mut ranges = Vec::new();
+  let mut any = false;
+  for spec in specs.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+    any = true;
+    let malformed = || RangeError::Malformed(header.to_string());
+    let (first, last) = spec.split_once('-').ok_or_else(malformed)?;
+    let (first, last) = (first.trim(), last.trim());
+
+    let range = if first.is_empty() {
+      // Suffix range, the last n bytes.
+      let suffix = parse_offset(last).ok_or_else(malformed)?;
+      if suffix == 0 || resource_len == 0 {
+        continue;
+      }
+      ByteRange { start: resource_len.saturating_sub(suffix), end: resource_len - 1 }
+    } else {
+      let start = parse_offset(first).ok_or_else(malformed)?;
+      let end =
+        if last.is_empty() { u64::MAX } else { parse_offset(last).ok_or_else(malformed)? };
+
+      if end < start {
+        return Err(malformed());
+      }
+
+      if start >= resource_len {
+        continue;
+      }
+
+      ByteRange { start, end: end.min(resource_len - 1) }
+    };
+
+    ranges.push(range);
+  }
+
+  if !any {
+    return Err(RangeError::Malformed(header.to_string()));
+  }
+
+  if ranges.is_empty() {
+    return Err(RangeError::Unsatisfiable);
+  }
+
+  Ok(ranges)
+}
+
+fn parse_offset(value: &str) -> Option<u64> {
+  if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+
+  value.parse().ok()
+}
@@ -1,16 +1,19 @@
 //! Provides functionality for handling HTTP requests.
 
 use crate::http::cookie::Cookie;
+use crate::http::encoding::{ContentEncoding, TransferEncoding};
 use crate::http::headers::{Header, HeaderName, Headers};
 use crate::http::method::Method;
 
 use crate::http::mime::{AcceptQualityMimeType, MimeType, QValue};
 use crate::stream::ConnectionStream;
 use crate::tii_error::{RequestHeadParsingError, TiiError, TiiResult, UserError};
-use crate::util::{unwrap_ok, unwrap_some};
+use crate::util::unwrap_some;
 use crate::warn_log;
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 use std::io::ErrorKind;
+use std::sync::{Arc, OnceLock};
 
 /// Enum for http versions tii supports.
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -106,8 +109,42 @@ pub struct RequestHead {
 
   /// A list of headers included in the request.
   headers: Headers,
+
+  /// The trailers of a chunked body, filled in once its last chunk was read.
+  trailers: Option<Arc<OnceLock<Vec<Header>>>>,
 }
 
+/// Fields that must not be sent in a trailer because they are needed to frame, route, authenticate
+/// or otherwise process the request before the body is read, see
+/// [RFC 9110 Section 6.5.1](https://datatracker.ietf.org/doc/html/rfc9110#section-6.5.1).
+/// Trailers with these names are not merged into the headers of the request.
+const FORBIDDEN_TRAILERS: &[&str] = &[
+  "Accept",
+  "Authorization",
+  "Cache-Control",
+  "Connection",
+  "Content-Encoding",
+  "Content-Length",
+  "Content-Range",
+  "Content-Type",
+  "Cookie",
+  "Expect",
+  "Host",
+  "If-Match",
+  "If-Modified-Since",
+  "If-None-Match",
+  "If-Range",
+  "If-Unmodified-Since",
+  "Max-Forwards",
+  "Pragma",
+  "Proxy-Authorization",
+  "Range",
+  "TE",
+  "Trailer",
+  "Transfer-Encoding",
+  "Upgrade",
+];
+
 fn parse_status_line(start_line_buf: &Vec<u8>) -> TiiResult<&str> {
   for n in start_line_buf {
     // https://en.wikipedia.org/wiki/Percent-encoding#Types_of_URI_characters
@@ -159,84 +196,76 @@ fn parse_status_line(start_line_buf: &Vec<u8>) -> TiiResult<&str> {
   )
 }
 
-fn parse_raw_query(raw_query: &str) -> TiiResult<Vec<(String, String)>> {
-  if raw_query.is_empty() {
-    return Ok(Vec::new());
+/// Bytes allowed in a raw query parameter, `&` separates parameters and is not included.
+fn is_query_byte(n: u8) -> bool {
+  match n {
+    b'=' | b'%' | b'!' | b'$' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b'-' | b'.' | b'/'
+    | b':' | b';' | b'@' | b'_' | b'~' => true,
+    other => other.is_ascii_alphanumeric(),
   }
+}
 
-  let mut query = Vec::new();
-  let mut current_key = Vec::new();
-  let mut current_value = Vec::new();
-  let mut matching_value = false;
-  for n in raw_query.as_bytes() {
-    match *n {
-      b'=' => {
-        if matching_value {
-          return Err(RequestHeadParsingError::InvalidQueryString(raw_query.to_string()).into());
-        }
-        matching_value = true;
-      }
-      b'&' => {
-        if !matching_value {
-          return Err(RequestHeadParsingError::InvalidQueryString(raw_query.to_string()).into());
-        }
-
-        let key = urlencoding::decode(unwrap_ok(std::str::from_utf8(current_key.as_slice())))
-          .map_err(|_| RequestHeadParsingError::InvalidQueryString(raw_query.to_string()))?
-          .to_string();
-
-        let value = urlencoding::decode(unwrap_ok(std::str::from_utf8(current_value.as_slice())))
-          .map_err(|_| RequestHeadParsingError::InvalidQueryString(raw_query.to_string()))?
-          .to_string();
+/// Parses the raw query string into key value pairs in order of appearance, see `parse_urlencoded`.
+fn parse_raw_query(raw_query: &str) -> TiiResult<Vec<(String, String)>> {
+  parse_urlencoded(raw_query).ok_or_else(|| {
+    TiiError::from(RequestHeadParsingError::InvalidQueryString(raw_query.to_string()))
+  })
+}
 
-        query.push((key, value));
+/// Parses `application/x-www-form-urlencoded` data (like a query string) into key value pairs in order of appearance.
+///
+/// Keys and values are percent decoded and `+` decodes to a space.
+/// A parameter without `=` yields an empty value and empty parameters (`a=1&&b=2`) are skipped.
+/// Returns None if the data is malformed.
+pub(crate) fn parse_urlencoded(raw: &str) -> Option<Vec<(String, String)>> {
+  let mut params = Vec::new();
+  for param in raw.split('&') {
+    if param.is_empty() {
+      continue;
+    }
 
-        matching_value = false;
-        current_key = Vec::new();
-        current_value = Vec::new()
-      }
-      b'!' | b'$' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b'-' | b'.' | b'/' | b':' | b';'
-      | b'@' | b'_' | b'~' => {
-        if matching_value {
-          current_value.push(*n);
-        } else {
-          current_key.push(*n);
-        }
-      }
-      other => {
-        if !other.is_ascii_alphanumeric() {
-          return Err(RequestHeadParsingError::InvalidQueryString(raw_query.to_string()).into());
-        }
+    if !param.bytes().all(is_query_byte) {
+      return None;
+    }
 
-        if matching_value {
-          current_value.push(*n);
-        } else {
-          current_key.push(*n);
-        }
-      }
+    let (key, value) = param.split_once('=').unwrap_or((param, ""));
+    if value.contains('=') {
+      return None;
     }
-  }
 
-  if !matching_value {
-    return Err(RequestHeadParsingError::InvalidQueryString(raw_query.to_string()).into());
+    let key = urlencoding::decode(key.replace('+', " ").as_str()).ok()?.to_string();
+    let value = urlencoding::decode(value.replace('+', " ").as_str()).ok()?.to_string();
+    params.push((key, value));
   }
 
-  let key = urlencoding::decode(unwrap_ok(std::str::from_utf8(current_key.as_slice())))
-    .map_err(|_| RequestHeadParsingError::InvalidQueryString(raw_query.to_string()))?
-    .to_string();
-
-  let value = urlencoding::decode(unwrap_ok(std::str::from_utf8(current_value.as_slice())))
-    .map_err(|_| RequestHeadParsingError::InvalidQueryString(raw_query.to_string()))?
-    .to_string();
-
-  query.push((key, value));
-
-  Ok(query)
+  Some(params)
 }
 
 impl RequestHead {
   /// Attempts to read and parse one HTTP request from the given reader.
   pub fn new(stream: &dyn ConnectionStream, max_head_buffer_size: usize) -> TiiResult<Self> {
+    Self::new_with_latin1_fallback(stream, max_head_buffer_size, false)
+  }
+
+  /// Same as `new` but header lines that are not valid UTF-8 are decoded as ISO-8859-1
+  /// instead of failing with `HeaderLineIsNotUsAscii` if `latin1_fallback` is true.
+  /// Some clients send Latin-1 in header values, for example in `Content-Disposition` filenames.
+  pub fn new_with_latin1_fallback(
+    stream: &dyn ConnectionStream,
+    max_head_buffer_size: usize,
+    latin1_fallback: bool,
+  ) -> TiiResult<Self> {
+    Self::read(stream, max_head_buffer_size, latin1_fallback, None)
+  }
+
+  /// Same as `new_with_latin1_fallback` but fails with `PathTooLong` if the percent decoded path
+  /// is longer than `max_path_length` bytes.
+  pub(crate) fn read(
+    stream: &dyn ConnectionStream,
+    max_head_buffer_size: usize,
+    latin1_fallback: bool,
+    max_path_length: Option<usize>,
+  ) -> TiiResult<Self> {
     let mut start_line_buf: Vec<u8> = Vec::with_capacity(256);
     let count = stream.read_until(0xA, max_head_buffer_size, &mut start_line_buf)?;
 
@@ -248,6 +277,10 @@ impl RequestHead {
       return Err(RequestHeadParsingError::StatusLineTooLong(start_line_buf).into());
     }
 
+    if !start_line_buf.ends_with(b"\n") {
+      return Err(RequestHeadParsingError::HeadTruncated.into());
+    }
+
     let start_line_string = parse_status_line(&start_line_buf)?;
 
     let status_line =
@@ -278,6 +311,10 @@ impl RequestHead {
       })?
       .to_string();
 
+    if max_path_length.is_some_and(|max| path.len() > max) {
+      return Err(RequestHeadParsingError::PathTooLong(path.len()).into());
+    }
+
     let raw_query = uri_iter.next().unwrap_or("");
     let query = parse_raw_query(raw_query)?;
 
@@ -296,6 +333,7 @@ impl RequestHead {
         query,
         version,
         headers,
+        trailers: None,
         content_type: None,
         accept: vec![AcceptQualityMimeType::from_mime(MimeType::TextHtml, QValue::default())], // Http 0.9 only accepts html.
         status_line: status_line.to_string(),
@@ -310,8 +348,16 @@ impl RequestHead {
         return Err(RequestHeadParsingError::HeaderLineTooLong(line_buf).into());
       }
 
-      let line = std::str::from_utf8(&line_buf)
-        .map_err(|_| RequestHeadParsingError::HeaderLineIsNotUsAscii)?;
+      if !line_buf.ends_with(b"\n") {
+        return Err(RequestHeadParsingError::HeadTruncated.into());
+      }
+
+      let line = match std::str::from_utf8(&line_buf) {
+        Ok(line) => Cow::Borrowed(line),
+        // Every byte is the code point of the same value in ISO-8859-1.
+        Err(_) if latin1_fallback => Cow::Owned(line_buf.iter().copied().map(char::from).collect()),
+        Err(_) => return Err(RequestHeadParsingError::HeaderLineIsNotUsAscii.into()),
+      };
 
       if line == "\r\n" {
         break;
@@ -367,6 +413,7 @@ impl RequestHead {
       query,
       version,
       headers,
+      trailers: None,
       accept,
       content_type,
       status_line: status_line.to_string(),
@@ -447,20 +494,33 @@ impl RequestHead {
   }
 
   /// Get the cookies from the request.
+  /// Values are split from the name at the first `=`, so they may contain `=` themselves,
+  /// and values wrapped in double quotes are returned without the quotes.
+  /// Pairs without `=` or with an empty name are skipped, see `get_cookie_header` for the unparsed value.
   pub fn get_cookies(&self) -> Vec<Cookie> {
     self
       .headers
-      .get(HeaderName::Cookie)
-      .map(|cookies| {
-        cookies
-          .split(';')
-          .filter_map(|cookie| {
-            let (k, v) = cookie.split_once('=')?;
-            Some(Cookie::new(k.trim(), v.trim()))
-          })
-          .collect()
+      .get_all(HeaderName::Cookie)
+      .into_iter()
+      .flat_map(|cookies| cookies.split(';'))
+      .filter_map(|cookie| {
+        let (k, v) = cookie.split_once('=')?;
+        let k = k.trim();
+        if k.is_empty() {
+          return None;
+        }
+        let v = v.trim();
+        let v = v.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(v);
+        Some(Cookie::new(k, v))
       })
-      .unwrap_or_default()
+      .collect()
+  }
+
+  /// Returns the raw value of the `Cookie` header, including cookies `get_cookies` could not parse.
+  /// Multiple `Cookie` headers are joined with `; `.
+  pub fn get_cookie_header(&self) -> Option<String> {
+    let values = self.headers.get_all(HeaderName::Cookie);
+    (!values.is_empty()).then(|| values.join("; "))
   }
 
   /// Attempts to get a specific cookie from the request.
@@ -505,18 +565,60 @@ impl RequestHead {
   }
 
   /// Returns an iterator over all headers.
+  /// Once a chunked body was read to the end this includes its trailers after the headers,
+  /// except for fields that are not permitted in a trailer.
   pub fn get_all_headers(&self) -> impl Iterator<Item = &Header> {
-    self.headers.iter()
+    self.headers.iter().chain(self.merged_trailers())
   }
 
   /// Returns the first header or None
+  /// Once a chunked body was read to the end this also finds its trailers, see `get_all_headers`.
   pub fn get_header(&self, name: impl AsRef<str>) -> Option<&str> {
-    self.headers.get(name)
+    let name = HeaderName::from(name.as_ref());
+    self.get_all_headers().find(|header| header.name == name).map(|header| header.value.as_str())
   }
 
   /// Returns the all header values of empty Vec.
+  /// Once a chunked body was read to the end this also includes its trailers, see `get_all_headers`.
   pub fn get_headers(&self, name: impl AsRef<str>) -> Vec<&str> {
-    self.headers.get_all(name)
+    let name = HeaderName::from(name.as_ref());
+    self
+      .get_all_headers()
+      .filter(|header| header.name == name)
+      .map(|header| header.value.as_str())
+      .collect()
+  }
+
+  /// The trailers of the chunked body that may be merged into the headers, empty until the body was read to the end.
+  fn merged_trailers(&self) -> impl Iterator<Item = &Header> {
+    self.trailers.as_ref().and_then(|trailers| trailers.get()).into_iter().flatten().filter(
+      |trailer| {
+        !FORBIDDEN_TRAILERS.iter().any(|name| trailer.name.to_str().eq_ignore_ascii_case(name))
+      },
+    )
+  }
+
+  /// Merges the trailers of the chunked body into the headers once they have been read.
+  pub(crate) fn set_trailers(&mut self, trailers: Arc<OnceLock<Vec<Header>>>) {
+    self.trailers = Some(trailers);
+  }
+
+  /// Returns the codings of all `Transfer-Encoding` headers in the order they were applied, empty if there are none.
+  pub fn transfer_encodings(&self) -> Vec<TransferEncoding> {
+    self
+      .get_headers(&HeaderName::TransferEncoding)
+      .into_iter()
+      .flat_map(TransferEncoding::parse_list)
+      .collect()
+  }
+
+  /// Returns the codings of all `Content-Encoding` headers in the order they were applied, empty if there are none.
+  pub fn content_encodings(&self) -> Vec<ContentEncoding> {
+    self
+      .get_headers(&HeaderName::ContentEncoding)
+      .into_iter()
+      .flat_map(ContentEncoding::parse_list)
+      .collect()
   }
 
   /// Removes all instances of a particular header.
@@ -2,15 +2,28 @@
 //! TODO docs before release
 #![allow(missing_docs)]
 
+use crate::http::headers::Header;
+use crate::tii_error::BodyParsingError;
 use crate::util::{unwrap_poison, unwrap_some};
 use std::fmt::{Debug, Formatter};
 use std::io;
-use std::io::{Cursor, Error, ErrorKind, Read, Take};
+use std::io::{Cursor, Error, ErrorKind, Read, Take, Write};
 use std::ops::DerefMut;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Arc, Mutex, OnceLock};
 
-#[derive(Debug, Clone)]
-pub struct RequestBody(Arc<Mutex<RequestBodyInner>>);
+/// Same as the default maximum size of the request head.
+const DEFAULT_MAX_TRAILER_SIZE: usize = 8192;
+
+#[derive(Clone)]
+pub struct RequestBody(Arc<Mutex<RequestBodyInner>>, Arc<OnceLock<Vec<Header>>>, Arc<AtomicU64>);
+
+impl Debug for RequestBody {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("RequestBody").field(&self.0).finish()
+  }
+}
 
 impl RequestBody {
   pub fn new_with_data_ref<T: AsRef<[u8]>>(data: T) -> RequestBody {
@@ -24,24 +37,167 @@ impl RequestBody {
   }
 
   pub fn new_with_content_length<T: Read + Send + 'static>(read: T, len: u64) -> RequestBody {
-    RequestBody(Arc::new(Mutex::new(RequestBodyInner::WithContentLength(
-      RequestBodyWithContentLength {
+    let (read, wire_len) = CountingRead::new(read);
+    RequestBody(
+      Arc::new(Mutex::new(RequestBodyInner::WithContentLength(RequestBodyWithContentLength {
         err: false,
         data: (Box::new(read) as Box<dyn Read + Send>).take(len),
-      },
-    ))))
+      }))),
+      Arc::new(OnceLock::new()),
+      wire_len,
+    )
   }
   pub fn new_chunked<T: Read + Send + 'static>(read: T) -> RequestBody {
-    RequestBody(Arc::new(Mutex::new(RequestBodyInner::Chunked(RequestBodyChunked {
-      read: Box::new(read) as Box<dyn Read + Send>,
-      eof: false,
+    Self::new_chunked_with_max_chunks(read, None)
+  }
+
+  /// Creates a chunked body that fails once the client sends more than `max_chunks` data chunks.
+  /// The error is an `InvalidData` io error that contains `BodyParsingError::TooManyChunks`.
+  pub fn new_chunked_with_max_chunks<T: Read + Send + 'static>(
+    read: T,
+    max_chunks: Option<u64>,
+  ) -> RequestBody {
+    Self::new_chunked_with_limits(read, max_chunks, DEFAULT_MAX_TRAILER_SIZE)
+  }
+
+  /// Creates a chunked body that fails once the client sends more than `max_chunks` data chunks,
+  /// or trailer header lines that are larger than `max_trailer_size` bytes in total.
+  /// The error is an `InvalidData` io error that contains `BodyParsingError::TooManyChunks`
+  /// or `BodyParsingError::TrailerTooLarge` respectively. Trailers are available from `trailers`
+  /// once the last chunk was read.
+  pub fn new_chunked_with_limits<T: Read + Send + 'static>(
+    read: T,
+    max_chunks: Option<u64>,
+    max_trailer_size: usize,
+  ) -> RequestBody {
+    Self::new_chunked_with_max_len(read, max_chunks, max_trailer_size, None)
+  }
+
+  /// Like `new_chunked_with_limits`, additionally fails with `BodyParsingError::TooLarge`
+  /// once the chunks announce more than `max_len` bytes of data in total.
+  pub(crate) fn new_chunked_with_max_len<T: Read + Send + 'static>(
+    read: T,
+    max_chunks: Option<u64>,
+    max_trailer_size: usize,
+    max_len: Option<u64>,
+  ) -> RequestBody {
+    let trailers = Arc::new(OnceLock::new());
+    let (read, wire_len) = CountingRead::new(read);
+    RequestBody(
+      Arc::new(Mutex::new(RequestBodyInner::Chunked(RequestBodyChunked {
+        read: Box::new(read) as Box<dyn Read + Send>,
+        eof: false,
+        err: false,
+        remaining_chunk_length: 0,
+        chunks: 0,
+        max_chunks,
+        len: 0,
+        max_len,
+        max_trailer_size,
+        trailers: trailers.clone(),
+      }))),
+      trailers,
+      wire_len,
+    )
+  }
+
+  /// Returns the trailer headers a chunked body sent after its last chunk.
+  /// Returns None if the body is not chunked or was not read to the end yet.
+  /// Trailers that are permitted in a trailer are also merged into the headers of the request,
+  /// see `RequestHead::get_all_headers`.
+  pub fn trailers(&self) -> Option<&[Header]> {
+    self.1.get().map(Vec::as_slice)
+  }
+
+  pub(crate) fn trailer_slot(&self) -> Arc<OnceLock<Vec<Header>>> {
+    self.1.clone()
+  }
+
+  /// Returns how many bytes were read from the underlying stream so far.
+  /// For chunked bodies this includes the chunk framing and trailers.
+  pub(crate) fn wire_len(&self) -> u64 {
+    self.2.load(Relaxed)
+  }
+
+  /// Reads the rest of the body into memory and returns it.
+  /// The body is replaced by the bytes that were read, so reading it afterward yields them again.
+  /// Fails with an `InvalidData` io error that contains `BodyParsingError::TooLarge`
+  /// if the rest of the body is larger than `max_len` bytes. The bytes read until then are
+  /// still replayed in front of the rest of the body.
+  pub fn read_and_replay(&self, max_len: u64) -> io::Result<Arc<[u8]>> {
+    let mut guard = unwrap_poison(self.0.lock())?;
+    let mut data = Vec::new();
+    let read = guard.deref_mut().take(max_len.saturating_add(1)).read_to_end(&mut data)?;
+    if read as u64 > max_len {
+      let empty = RequestBodyInner::WithContentLength(RequestBodyWithContentLength {
+        err: false,
+        data: (Box::new(io::empty()) as Box<dyn Read + Send>).take(0),
+      });
+      let inner = std::mem::replace(guard.deref_mut(), empty);
+      *guard = RequestBodyInner::Replay(RequestBodyReplay {
+        replay: Cursor::new(data),
+        inner: Box::new(inner),
+      });
+      return Err(Error::new(io::ErrorKind::InvalidData, BodyParsingError::TooLarge(max_len)));
+    }
+
+    let data = Arc::<[u8]>::from(data);
+    *guard = RequestBodyInner::WithContentLength(RequestBodyWithContentLength {
       err: false,
-      remaining_chunk_length: 0,
-    }))))
+      data: (Box::new(Cursor::new(data.clone())) as Box<dyn Read + Send>).take(read as u64),
+    });
+    Ok(data)
+  }
+
+  /// Mirrors all bytes that are read from this body from now on into a side buffer.
+  /// Only the first `cap` bytes are retained, anything beyond that still flows to the reader
+  /// but is not captured. The returned handle can be inspected after the body has been consumed.
+  /// Calling this more than once nests the tees, each with its own buffer.
+  pub fn tee(&self, cap: usize) -> io::Result<RequestBodyTee> {
+    let tee = RequestBodyTee(Arc::new(Mutex::new(Vec::new())));
+    let mut guard = unwrap_poison(self.0.lock())?;
+    let empty = RequestBodyInner::WithContentLength(RequestBodyWithContentLength {
+      err: false,
+      data: (Box::new(io::empty()) as Box<dyn Read + Send>).take(0),
+    });
+    let inner = std::mem::replace(guard.deref_mut(), empty);
+    *guard = RequestBodyInner::Tee(RequestBodyTeeReader {
+      inner: Box::new(inner),
+      buffer: tee.0.clone(),
+      cap,
+    });
+    Ok(tee)
   }
 }
 
 impl RequestBody {
+  /// Defers the `100 Continue` interim response to a client that sent `Expect: 100-continue`
+  /// until the body is read for the first time. The client only sends the body after receiving it.
+  pub(crate) fn send_continue_on_first_read(
+    &self,
+    interim: Box<dyn Write + Send + Sync>,
+  ) -> io::Result<()> {
+    let mut guard = unwrap_poison(self.0.lock())?;
+    let empty = RequestBodyInner::WithContentLength(RequestBodyWithContentLength {
+      err: false,
+      data: (Box::new(io::empty()) as Box<dyn Read + Send>).take(0),
+    });
+    let inner = std::mem::replace(guard.deref_mut(), empty);
+    *guard = RequestBodyInner::Continue(RequestBodyContinue {
+      inner: Box::new(inner),
+      interim: Some(interim),
+      cancelled: false,
+    });
+    Ok(())
+  }
+
+  /// Returns true if the `100 Continue` interim response was never sent because the body was not read.
+  /// The body is treated as empty from then on, the client has not sent it and must not send it anymore,
+  /// so the connection must be closed after the final response.
+  pub(crate) fn cancel_pending_continue(&self) -> io::Result<bool> {
+    Ok(unwrap_poison(self.0.lock())?.cancel_pending_continue())
+  }
+
   pub fn as_read(&self) -> impl Read + '_ {
     Box::new(self)
   }
@@ -50,6 +206,9 @@ impl RequestBody {
     match unwrap_poison(self.0.lock())?.deref_mut() {
       RequestBodyInner::WithContentLength(body) => body.read(buf),
       RequestBodyInner::Chunked(body) => body.read(buf),
+      RequestBodyInner::Tee(body) => body.read(buf),
+      RequestBodyInner::Continue(body) => body.read(buf),
+      RequestBodyInner::Replay(body) => body.read(buf),
     }
   }
 
@@ -57,6 +216,9 @@ impl RequestBody {
     match unwrap_poison(self.0.lock())?.deref_mut() {
       RequestBodyInner::WithContentLength(body) => body.read_to_end(buf),
       RequestBodyInner::Chunked(body) => body.read_to_end(buf),
+      RequestBodyInner::Tee(body) => body.read_to_end(buf),
+      RequestBodyInner::Continue(body) => body.read_to_end(buf),
+      RequestBodyInner::Replay(body) => body.read_to_end(buf),
     }
   }
 
@@ -64,14 +226,41 @@ impl RequestBody {
     match unwrap_poison(self.0.lock())?.deref_mut() {
       RequestBodyInner::WithContentLength(body) => body.read_exact(buf),
       RequestBodyInner::Chunked(body) => body.read_exact(buf),
+      RequestBodyInner::Tee(body) => body.read_exact(buf),
+      RequestBodyInner::Continue(body) => body.read_exact(buf),
+      RequestBodyInner::Replay(body) => body.read_exact(buf),
     }
   }
 
   pub fn remaining(&self) -> io::Result<Option<u64>> {
-    Ok(match unwrap_poison(self.0.lock())?.deref_mut() {
-      RequestBodyInner::WithContentLength(wc) => Some(wc.data.limit()),
-      _ => None,
-    })
+    Ok(unwrap_poison(self.0.lock())?.remaining())
+  }
+
+  /// Changes the size limit of a chunked body, bodies with a Content-Length are checked before they are read.
+  pub(crate) fn set_max_len(&self, max_len: Option<u64>) -> io::Result<()> {
+    unwrap_poison(self.0.lock())?.set_max_len(max_len);
+    Ok(())
+  }
+}
+
+/// Handle to the bytes captured by [`RequestBody::tee`].
+#[derive(Debug, Clone)]
+pub struct RequestBodyTee(Arc<Mutex<Vec<u8>>>);
+
+impl RequestBodyTee {
+  /// Returns a copy of the bytes captured so far.
+  pub fn captured(&self) -> io::Result<Vec<u8>> {
+    Ok(unwrap_poison(self.0.lock())?.clone())
+  }
+
+  /// Returns the amount of bytes captured so far.
+  pub fn len(&self) -> io::Result<usize> {
+    Ok(unwrap_poison(self.0.lock())?.len())
+  }
+
+  /// Returns true if nothing was captured so far.
+  pub fn is_empty(&self) -> io::Result<bool> {
+    Ok(unwrap_poison(self.0.lock())?.is_empty())
   }
 }
 
@@ -84,8 +273,146 @@ impl Read for &RequestBody {
 #[derive(Debug)]
 enum RequestBodyInner {
   WithContentLength(RequestBodyWithContentLength),
-  Chunked(RequestBodyChunked), //Gzipped(...)
-                               //...
+  Chunked(RequestBodyChunked),
+  Tee(RequestBodyTeeReader),
+  Continue(RequestBodyContinue),
+  Replay(RequestBodyReplay), //Gzipped(...)
+                             //...
+}
+
+impl RequestBodyInner {
+  fn remaining(&self) -> Option<u64> {
+    match self {
+      RequestBodyInner::WithContentLength(wc) => Some(wc.data.limit()),
+      RequestBodyInner::Chunked(_) => None,
+      RequestBodyInner::Tee(tee) => tee.inner.remaining(),
+      RequestBodyInner::Continue(body) if body.cancelled => Some(0),
+      RequestBodyInner::Continue(body) => body.inner.remaining(),
+      RequestBodyInner::Replay(body) => {
+        let replay = (body.replay.get_ref().len() as u64).saturating_sub(body.replay.position());
+        body.inner.remaining().map(|remaining| remaining.saturating_add(replay))
+      }
+    }
+  }
+
+  fn set_max_len(&mut self, max_len: Option<u64>) {
+    match self {
+      RequestBodyInner::WithContentLength(_) => (),
+      RequestBodyInner::Chunked(body) => body.max_len = max_len,
+      RequestBodyInner::Tee(tee) => tee.inner.set_max_len(max_len),
+      RequestBodyInner::Continue(body) => body.inner.set_max_len(max_len),
+      RequestBodyInner::Replay(body) => body.inner.set_max_len(max_len),
+    }
+  }
+
+  fn cancel_pending_continue(&mut self) -> bool {
+    match self {
+      RequestBodyInner::WithContentLength(_) | RequestBodyInner::Chunked(_) => false,
+      RequestBodyInner::Tee(tee) => tee.inner.cancel_pending_continue(),
+      RequestBodyInner::Replay(body) => body.inner.cancel_pending_continue(),
+      RequestBodyInner::Continue(body) => {
+        if body.interim.take().is_none() {
+          return false;
+        }
+        body.cancelled = true;
+        true
+      }
+    }
+  }
+}
+
+impl Read for RequestBodyInner {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    match self {
+      RequestBodyInner::WithContentLength(body) => body.read(buf),
+      RequestBodyInner::Chunked(body) => body.read(buf),
+      RequestBodyInner::Tee(body) => body.read(buf),
+      RequestBodyInner::Continue(body) => body.read(buf),
+      RequestBodyInner::Replay(body) => body.read(buf),
+    }
+  }
+}
+
+struct RequestBodyContinue {
+  inner: Box<RequestBodyInner>,
+  interim: Option<Box<dyn Write + Send + Sync>>,
+  cancelled: bool,
+}
+
+impl Read for RequestBodyContinue {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if self.cancelled {
+      return Ok(0);
+    }
+
+    if let Some(mut interim) = self.interim.take() {
+      interim.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+      interim.flush()?;
+    }
+
+    self.inner.read(buf)
+  }
+}
+
+impl Debug for RequestBodyContinue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "RequestBodyContinue(pending={} inner={:?})",
+      self.interim.is_some(),
+      self.inner
+    ))
+  }
+}
+
+/// Yields bytes that were already read from the body before the rest of the body.
+struct RequestBodyReplay {
+  replay: Cursor<Vec<u8>>,
+  inner: Box<RequestBodyInner>,
+}
+
+impl Read for RequestBodyReplay {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let read = self.replay.read(buf)?;
+    if read > 0 || buf.is_empty() {
+      return Ok(read);
+    }
+
+    self.inner.read(buf)
+  }
+}
+
+impl Debug for RequestBodyReplay {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "RequestBodyReplay(replay={} inner={:?})",
+      self.replay.get_ref().len(),
+      self.inner
+    ))
+  }
+}
+
+struct RequestBodyTeeReader {
+  inner: Box<RequestBodyInner>,
+  buffer: Arc<Mutex<Vec<u8>>>,
+  cap: usize,
+}
+
+impl Read for RequestBodyTeeReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let read = self.inner.read(buf)?;
+    let mut buffer = unwrap_poison(self.buffer.lock())?;
+    let to_copy = usize::min(read, self.cap.saturating_sub(buffer.len()));
+    if let Some(data) = buf.get(..to_copy) {
+      buffer.extend_from_slice(data);
+    }
+    Ok(read)
+  }
+}
+
+impl Debug for RequestBodyTeeReader {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!("RequestBodyTee(cap={} inner={:?})", self.cap, self.inner))
+  }
 }
 
 struct RequestBodyWithContentLength {
@@ -116,6 +443,12 @@ struct RequestBodyChunked {
   eof: bool,
   err: bool,
   remaining_chunk_length: u64,
+  chunks: u64,
+  max_chunks: Option<u64>,
+  len: u64,
+  max_len: Option<u64>,
+  max_trailer_size: usize,
+  trailers: Arc<OnceLock<Vec<Header>>>,
 }
 
 impl Debug for RequestBodyChunked {
@@ -169,16 +502,13 @@ impl RequestBodyChunked {
     loop {
       if n >= 17 {
         //If the client prefixes the chunks with '0' characters then we just don't support that.
-        return Err(Error::new(
-          io::ErrorKind::InvalidData,
-          "Chunk size is larger than 2^64 or malformed",
-        ));
+        return Err(invalid_chunk_size(&small_buffer[..n]));
       }
       self.read.read_exact(&mut small_buffer[n..n + 1])?;
       if small_buffer[n] == b'\r' {
         self.read.read_exact(&mut small_buffer[n..n + 1])?;
         if small_buffer[n] != b'\n' {
-          return Err(Error::new(io::ErrorKind::InvalidData, "Chunk size is malformed"));
+          return Err(invalid_chunk_size(&small_buffer[..n]));
         }
         break;
       }
@@ -186,26 +516,33 @@ impl RequestBodyChunked {
       n += 1;
     }
 
-    if n == 0 {
-      return Err(Error::new(io::ErrorKind::InvalidData, "Chunk size is malformed"));
+    let size_line = &small_buffer[0..n];
+    let chunk_len = std::str::from_utf8(size_line)
+      .ok()
+      .filter(|str| !str.is_empty())
+      .and_then(|str| u64::from_str_radix(str, 16).ok())
+      .ok_or_else(|| invalid_chunk_size(size_line))?;
+    if chunk_len == 0 {
+      self.read_trailer()?;
+      self.eof = true;
+      return Ok(0);
     }
 
-    let str = std::str::from_utf8(&small_buffer[0..n])
-      .map_err(|_| Error::new(io::ErrorKind::InvalidData, "Chunk size is malformed"))?;
-    let chunk_len = u64::from_str_radix(str, 16)
-      .map_err(|_| Error::new(io::ErrorKind::InvalidData, "Chunk size is malformed"))?;
-    if chunk_len == 0 {
-      self.read.read_exact(&mut small_buffer[n..n + 1])?;
-      if small_buffer[n] != b'\r' {
-        return Err(Error::new(io::ErrorKind::InvalidData, "Chunk trailer is malformed"));
-      }
-      self.read.read_exact(&mut small_buffer[n..n + 1])?;
-      if small_buffer[n] != b'\n' {
-        return Err(Error::new(io::ErrorKind::InvalidData, "Chunk trailer is malformed"));
+    self.chunks += 1;
+    if let Some(max_chunks) = self.max_chunks {
+      if self.chunks > max_chunks {
+        return Err(Error::new(
+          io::ErrorKind::InvalidData,
+          BodyParsingError::TooManyChunks(max_chunks),
+        ));
       }
+    }
 
-      self.eof = true;
-      return Ok(0);
+    self.len = self.len.saturating_add(chunk_len);
+    if let Some(max_len) = self.max_len {
+      if self.len > max_len {
+        return Err(Error::new(io::ErrorKind::InvalidData, BodyParsingError::TooLarge(max_len)));
+      }
     }
 
     self.remaining_chunk_length = chunk_len;
@@ -213,6 +550,81 @@ impl RequestBodyChunked {
   }
 }
 
+/// Counts the bytes that are read through it, the count is shared with the `RequestBody`.
+struct CountingRead<T: Read> {
+  read: T,
+  count: Arc<AtomicU64>,
+}
+
+impl<T: Read> CountingRead<T> {
+  fn new(read: T) -> (Self, Arc<AtomicU64>) {
+    let count = Arc::new(AtomicU64::new(0));
+    (Self { read, count: count.clone() }, count)
+  }
+}
+
+impl<T: Read> Read for CountingRead<T> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let read = self.read.read(buf)?;
+    self.count.fetch_add(read as u64, Relaxed);
+    Ok(read)
+  }
+}
+
+fn invalid_chunk_size(size_line: &[u8]) -> Error {
+  Error::new(
+    io::ErrorKind::InvalidData,
+    BodyParsingError::InvalidChunkSize(String::from_utf8_lossy(size_line).to_string()),
+  )
+}
+
+impl RequestBodyChunked {
+  /// Reads the trailer header lines after the last chunk up to and including the final empty line.
+  fn read_trailer(&mut self) -> io::Result<()> {
+    let malformed = || Error::new(io::ErrorKind::InvalidData, "Chunk trailer is malformed");
+    let mut byte = [0u8; 1];
+    let mut size = 0usize;
+    let mut line = Vec::new();
+    let mut trailers = Vec::new();
+    loop {
+      self.read.read_exact(&mut byte)?;
+      size += 1;
+      if size > self.max_trailer_size {
+        return Err(Error::new(
+          io::ErrorKind::InvalidData,
+          BodyParsingError::TrailerTooLarge(self.max_trailer_size),
+        ));
+      }
+
+      match byte {
+        [b'\r'] => {
+          self.read.read_exact(&mut byte)?;
+          if byte != [b'\n'] {
+            return Err(malformed());
+          }
+
+          if line.is_empty() {
+            _ = self.trailers.set(trailers);
+            return Ok(());
+          }
+
+          // The line only contains ascii characters and starts with the name.
+          let line = String::from_utf8_lossy(std::mem::take(&mut line).as_slice()).to_string();
+          let (name, value) = line.split_once(':').ok_or_else(malformed)?;
+          trailers.push(Header::new(name, value.trim()));
+          size += 1;
+        }
+        [b':' | b'\n'] if line.is_empty() => return Err(malformed()),
+        [b'\n'] => return Err(malformed()),
+        [other] if !other.is_ascii() || (other.is_ascii_control() && other != b'\t') => {
+          return Err(malformed())
+        }
+        [other] => line.push(other),
+      }
+    }
+  }
+}
+
 impl Read for RequestBodyChunked {
   fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
     if self.err {
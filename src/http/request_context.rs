@@ -1,19 +1,28 @@
 //! Contains all state that's needed to process a request.
 
+use crate::functional_traits::{Clock, SystemClock};
+use crate::http::cookie::CookieJar;
+use crate::http::encoding::TransferEncoding;
 use crate::http::headers::HeaderName;
+use crate::http::method::Method;
+use crate::http::mime::MimeType;
 use crate::http::request::HttpVersion;
 use crate::http::request_body::RequestBody;
 use crate::http::RequestHead;
-use crate::stream::ConnectionStream;
-use crate::tii_error::{RequestHeadParsingError, TiiError, TiiResult};
+use crate::stream::{ConnectionStream, UnixCredentials};
+use crate::tii_error::{PathParamError, RequestHeadParsingError, TiiError, TiiResult};
 use crate::tii_server::ConnectionStreamMetadata;
 use crate::util;
 use crate::util::unwrap_some;
 use std::any::Any;
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::io;
 use std::io::ErrorKind;
-use std::sync::Arc;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 
 /// This struct contains all information needed to process a request as well as all state
 /// for a single request.
@@ -22,63 +31,181 @@ pub struct RequestContext {
   id: u128,
   peer_address: String,
   local_address: String,
+  sni_hostname: Option<String>,
+  connection: Box<dyn ConnectionStream>,
   request: RequestHead,
   body: Option<RequestBody>,
+  raw_body: OnceLock<Arc<[u8]>>,
   force_connection_close: bool,
+  max_body_size: Option<u64>,
+  clock: Arc<dyn Clock>,
   stream_meta: Option<Arc<dyn ConnectionStreamMetadata>>,
 
   routed_path: Option<String>,
 
   path_params: Option<HashMap<String, String>>,
 
+  allowed_methods: Option<Vec<Method>>,
+
+  negotiated_content_type: Option<MimeType>,
+
+  cookies: CookieJar,
+
+  /// True if the peer is a trusted reverse proxy whose forwarding headers are honored.
+  trusted_proxy: bool,
+
+  /// The client address taken from forwarding headers, None = the peer is the client.
+  client_address: Option<String>,
+
   ///TODO the key may be a candidate for `Rc<str>` instead of "String"?
   properties: Option<HashMap<String, Box<dyn Any + Send>>>,
 }
 
+/// Limits and settings used by `RequestContext::new` to read a request.
+/// The server uses the values configured with the corresponding `TiiBuilder` methods.
+#[derive(Debug, Clone)]
+pub struct RequestContextOptions {
+  max_head_buffer_size: usize,
+  max_body_chunks: Option<u64>,
+  max_body_size: Option<u64>,
+  clock: Arc<dyn Clock>,
+  latin1_header_values: bool,
+  max_path_length: Option<usize>,
+}
+
+impl Default for RequestContextOptions {
+  fn default() -> Self {
+    Self {
+      max_head_buffer_size: 8192,
+      max_body_chunks: None,
+      max_body_size: None,
+      clock: Arc::new(SystemClock),
+      latin1_header_values: false,
+      max_path_length: None,
+    }
+  }
+}
+
+impl RequestContextOptions {
+  /// Maximum size of the request head, see `TiiBuilder::with_max_head_buffer_size`.
+  pub fn with_max_head_buffer_size(mut self, size: usize) -> Self {
+    self.max_head_buffer_size = size;
+    self
+  }
+
+  /// Maximum amount of chunks of a chunked body, see `TiiBuilder::with_max_body_chunks`.
+  pub fn with_max_body_chunks(mut self, max_body_chunks: Option<u64>) -> Self {
+    self.max_body_chunks = max_body_chunks;
+    self
+  }
+
+  /// Maximum size of a chunked body, see `TiiBuilder::with_max_body_size`.
+  pub fn with_max_body_size(mut self, max_body_size: Option<u64>) -> Self {
+    self.max_body_size = max_body_size;
+    self
+  }
+
+  /// The clock of the request, see `TiiBuilder::with_clock`.
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  /// Accept header values that are not valid UTF-8 as Latin-1, see `TiiBuilder::with_latin1_header_values`.
+  pub fn with_latin1_header_values(mut self, latin1_header_values: bool) -> Self {
+    self.latin1_header_values = latin1_header_values;
+    self
+  }
+
+  /// Maximum length of the request path, see `TiiBuilder::with_max_path_length`.
+  pub fn with_max_path_length(mut self, max_path_length: Option<usize>) -> Self {
+    self.max_path_length = max_path_length;
+    self
+  }
+}
+
 impl RequestContext {
   /// Create a new RequestContext from a stream. This will parse RequestHead but not any part of the potencial request body.
   /// Errors on IO-Error or malformed RequestHead.
   pub fn new(
     stream: &dyn ConnectionStream,
     stream_meta: Option<Arc<dyn ConnectionStreamMetadata>>,
-    max_head_buffer_size: usize,
+    options: &RequestContextOptions,
   ) -> TiiResult<RequestContext> {
+    let RequestContextOptions {
+      max_head_buffer_size,
+      max_body_chunks,
+      max_body_size,
+      clock,
+      latin1_header_values,
+      max_path_length,
+    } = options.clone();
     let id = util::next_id();
     let peer_address = stream.peer_addr()?;
     let local_address = stream.local_addr()?;
+    let sni_hostname = stream.sni_hostname();
+    let connection = stream.new_ref();
 
-    let req = RequestHead::new(stream, max_head_buffer_size)?;
+    let mut req =
+      RequestHead::read(stream, max_head_buffer_size, latin1_header_values, max_path_length)?;
+    let cookies = CookieJar::new(req.get_cookies());
 
     if req.version() == HttpVersion::Http09 {
       return Ok(RequestContext {
         id,
         peer_address,
         local_address,
+        sni_hostname,
+        connection,
         request: req,
         body: None,
+        raw_body: OnceLock::new(),
         force_connection_close: true,
+        max_body_size,
+        clock,
         properties: None,
         routed_path: None,
         stream_meta,
         path_params: None,
+        allowed_methods: None,
+        negotiated_content_type: None,
+        cookies,
+        trusted_proxy: false,
+        client_address: None,
       });
     }
 
     if req.version() == HttpVersion::Http11 {
       match req.get_header(&HeaderName::TransferEncoding) {
-        Some("chunked") => {
-          let body = RequestBody::new_chunked(stream.new_ref_read());
+        Some(_) if req.transfer_encodings() == [TransferEncoding::Chunked] => {
+          let body = RequestBody::new_chunked_with_max_len(
+            stream.new_ref_read(),
+            max_body_chunks,
+            max_head_buffer_size,
+            max_body_size,
+          );
+          req.set_trailers(body.trailer_slot());
           return Ok(RequestContext {
             id,
             peer_address,
             local_address,
+            sni_hostname,
+            connection,
             request: req,
             body: Some(body),
+            raw_body: OnceLock::new(),
             force_connection_close: false,
+            max_body_size,
+            clock,
             properties: None,
             routed_path: None,
             stream_meta,
             path_params: None,
+            allowed_methods: None,
+            negotiated_content_type: None,
+            cookies,
+            trusted_proxy: false,
+            client_address: None,
           });
         }
         Some(other) => {
@@ -102,13 +229,23 @@ impl RequestContext {
           id,
           peer_address,
           local_address,
+          sni_hostname,
+          connection,
           request: req,
           body: None,
+          raw_body: OnceLock::new(),
           force_connection_close: is_http_10,
+          max_body_size,
+          clock,
           properties: None,
           routed_path: None,
           stream_meta,
           path_params: None,
+          allowed_methods: None,
+          negotiated_content_type: None,
+          cookies,
+          trusted_proxy: false,
+          client_address: None,
         });
       }
 
@@ -117,13 +254,23 @@ impl RequestContext {
         id,
         peer_address,
         local_address,
+        sni_hostname,
+        connection,
         request: req,
         body: Some(body),
+        raw_body: OnceLock::new(),
         force_connection_close: is_http_10,
+        max_body_size,
+        clock,
         properties: None,
         routed_path: None,
         stream_meta,
         path_params: None,
+        allowed_methods: None,
+        negotiated_content_type: None,
+        cookies,
+        trusted_proxy: false,
+        client_address: None,
       });
     }
 
@@ -131,13 +278,23 @@ impl RequestContext {
       id,
       peer_address,
       local_address,
+      sni_hostname,
+      connection,
       request: req,
       body: None,
+      raw_body: OnceLock::new(),
       force_connection_close: true,
+      max_body_size,
+      clock,
       properties: None,
       routed_path: None,
       stream_meta,
       path_params: None,
+      allowed_methods: None,
+      negotiated_content_type: None,
+      cookies,
+      trusted_proxy: false,
+      client_address: None,
     })
   }
 
@@ -156,6 +313,173 @@ impl RequestContext {
     self.local_address.as_str()
   }
 
+  /// The server name the client requested via SNI during the TLS handshake.
+  /// This can differ from the `Host` header. Returns None for plain connections or if the client sent no SNI.
+  pub fn sni_hostname(&self) -> Option<&str> {
+    self.sni_hostname.as_deref()
+  }
+
+  /// Returns true if the client is known to have closed the connection while the request is being handled.
+  /// Long-running handlers can poll this to abandon work whose response nobody will read.
+  /// A client that only shut down its sending half after the request is also reported as disconnected.
+  /// Streams that can not detect this, such as TLS or custom streams, always return false.
+  pub fn is_disconnected(&self) -> bool {
+    self.connection.is_disconnected()
+  }
+
+  /// The uid, gid and pid of the process that connected, if the connection is a unix socket.
+  /// Yields None for all other connections, see `UnixCredentials` for the platform requirements.
+  pub fn peer_unix_credentials(&self) -> Option<UnixCredentials> {
+    self.connection.peer_unix_credentials()
+  }
+
+  /// The clock of the server, see `TiiBuilder::with_clock`.
+  pub fn clock(&self) -> &Arc<dyn Clock> {
+    &self.clock
+  }
+
+  /// The maximum size of the request body in bytes, None if unlimited.
+  /// This is the limit of the route if it has one, otherwise the limit configured by `TiiBuilder::with_max_body_size`.
+  pub fn max_body_size(&self) -> Option<u64> {
+    self.max_body_size
+  }
+
+  /// Changes the maximum size of the request body, chunked bodies are limited accordingly.
+  pub(crate) fn set_max_body_size(&mut self, max_body_size: Option<u64>) -> io::Result<()> {
+    self.max_body_size = max_body_size;
+    if let Some(body) = self.body.as_ref() {
+      body.set_max_len(max_body_size)?;
+    }
+    Ok(())
+  }
+
+  /// Returns true if the connection itself is secure (for example TLS).
+  /// This is decided by the stream metadata. Connections without metadata are never secure.
+  pub fn is_secure(&self) -> bool {
+    self.stream_meta.as_ref().map(|meta| meta.is_secure()).unwrap_or_default()
+  }
+
+  /// Returns true if the peer is a trusted reverse proxy.
+  /// Only then are headers like `X-Forwarded-Proto` or `X-Forwarded-Host` honored by tii.
+  pub fn is_trusted_proxy(&self) -> bool {
+    self.trusted_proxy
+  }
+
+  pub(crate) fn set_trusted_proxy(&mut self, trusted_proxy: bool) {
+    self.trusted_proxy = trusted_proxy;
+  }
+
+  /// The IP address of the client without port.
+  /// If the peer is a trusted proxy then this is taken from the `Forwarded` header, or the `X-Forwarded-For` header
+  /// if there is no `Forwarded` header. The rightmost address that is not a trusted proxy is the client,
+  /// addresses further left could have been made up by the client.
+  /// Otherwise, or if that entry is not an IP address (for example `unknown`), this is the host of `peer_address`.
+  pub fn client_address(&self) -> &str {
+    self.client_address.as_deref().unwrap_or_else(|| util::address_host(&self.peer_address))
+  }
+
+  /// Resolves `client_address` from the forwarding headers, only call this if the peer is a trusted proxy.
+  pub(crate) fn resolve_client_address(&mut self, trusted_proxies: &[String]) {
+    let mut chain = Vec::new();
+    for value in self.request.get_headers(&HeaderName::Forwarded) {
+      for element in value.split(',') {
+        let address = element
+          .split(';')
+          .filter_map(|pair| pair.split_once('='))
+          .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+          .map(|(_, value)| value.trim().trim_matches('"'))
+          .unwrap_or_default();
+        chain.push(address);
+      }
+    }
+
+    if chain.is_empty() {
+      for value in self.request.get_headers("X-Forwarded-For") {
+        chain.extend(value.split(',').map(str::trim));
+      }
+    }
+
+    let client = chain
+      .iter()
+      .rev()
+      .map(|address| util::address_host(address))
+      .find(|address| !trusted_proxies.iter().any(|proxy| proxy == address))
+      .or_else(|| chain.first().map(|address| util::address_host(address)));
+
+    self.client_address =
+      client.and_then(|address| address.parse::<IpAddr>().ok()).map(|address| address.to_string());
+  }
+
+  /// Returns the first value of a forwarding header, but only if the peer is a trusted proxy.
+  fn forwarded_header(&self, name: &str) -> Option<&str> {
+    if !self.trusted_proxy {
+      return None;
+    }
+
+    self
+      .request
+      .get_header(name)
+      .and_then(|value| value.split(',').next())
+      .map(str::trim)
+      .filter(|value| !value.is_empty())
+  }
+
+  /// Returns the scheme the client used to make the request. Either "http" or "https".
+  /// If the peer is a trusted proxy then the `X-Forwarded-Proto` header is honored.
+  pub fn effective_scheme(&self) -> &str {
+    if let Some(proto) = self.forwarded_header("X-Forwarded-Proto") {
+      if proto.eq_ignore_ascii_case("https") {
+        return "https";
+      }
+      if proto.eq_ignore_ascii_case("http") {
+        return "http";
+      }
+    }
+
+    if self.is_secure() {
+      "https"
+    } else {
+      "http"
+    }
+  }
+
+  /// Returns the host (and port if any) the client used to make the request.
+  /// If the peer is a trusted proxy then the `X-Forwarded-Host` and `X-Forwarded-Port` headers are honored.
+  /// Otherwise, this is the value of the Host header or our local address if the client did not send a Host header.
+  pub fn effective_host(&self) -> String {
+    if let Some(host) = self.forwarded_header("X-Forwarded-Host") {
+      let Some(port) = self.forwarded_header("X-Forwarded-Port") else {
+        return host.to_string();
+      };
+
+      let default_port = match self.effective_scheme() {
+        "https" => "443",
+        _ => "80",
+      };
+
+      let has_port = host
+        .rsplit_once(']')
+        .map(|(_, rest)| rest.contains(':'))
+        .unwrap_or_else(|| host.contains(':'));
+
+      if has_port || port == default_port {
+        return host.to_string();
+      }
+
+      return format!("{}:{}", host, port);
+    }
+
+    self.request.get_header(&HeaderName::Host).unwrap_or(self.local_address.as_str()).to_string()
+  }
+
+  /// Builds an absolute url for the given path using the effective scheme and host.
+  /// This is useful for the Location header of redirects or links in emails when tii is behind a reverse proxy.
+  pub fn absolute_url(&self, path: impl AsRef<str>) -> String {
+    let path = path.as_ref();
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    format!("{}://{}{}{}", self.effective_scheme(), self.effective_host(), separator, path)
+  }
+
   /// True if the request contains the specified property.
   pub fn contains_property<K: AsRef<str>>(&self, key: K) -> bool {
     if let Some(prop) = self.properties.as_ref() {
@@ -220,7 +544,7 @@ impl RequestContext {
   /// Returns an iterator over property keys.
   pub fn get_property_keys(&self) -> Box<dyn Iterator<Item = &String> + '_> {
     match self.properties.as_ref() {
-      Some(props) => Box::new(props.iter().map(|(k, _)| k)),
+      Some(props) => Box::new(props.keys()),
       None => Box::new(std::iter::empty()),
     }
   }
@@ -240,15 +564,288 @@ impl RequestContext {
     self.body.as_ref()
   }
 
+  /// Returns the exact bytes of the request body as they were received, see `raw_body_with_limit`.
+  pub fn raw_body(&self) -> TiiResult<&[u8]> {
+    self.raw_body_with_limit(u64::MAX)
+  }
+
+  /// Returns the exact bytes of the request body as they were received, for example to verify the signature of a webhook.
+  /// A request without a body yields an empty slice.
+  ///
+  /// The body is read into memory on the first call and cached. Afterward the request body can still be read,
+  /// for example by `parse_body`, it yields the same bytes again.
+  /// Bytes of the body that were already read before the first call are not part of the result.
+  /// If the body exceeds `max_len` bytes a `BodyParsingError::TooLarge` is returned,
+  /// which the default error handler turns into a 413.
+  pub fn raw_body_with_limit(&self, max_len: u64) -> TiiResult<&[u8]> {
+    use crate::tii_error::BodyParsingError;
+
+    if let Some(raw) = self.raw_body.get() {
+      if raw.len() as u64 > max_len {
+        return Err(BodyParsingError::TooLarge(max_len).into());
+      }
+      return Ok(raw);
+    }
+
+    let raw = match self.body.as_ref() {
+      Some(body) => body.read_and_replay(max_len)?,
+      None => Arc::from([]),
+    };
+
+    Ok(self.raw_body.get_or_init(|| raw))
+  }
+
+  /// Returns the decoded key value pairs of an `application/x-www-form-urlencoded` body, see `form_params_with_limit`.
+  pub fn form_params(&self) -> TiiResult<Vec<(String, String)>> {
+    self.form_params_with_limit(u64::MAX)
+  }
+
+  /// Reads an `application/x-www-form-urlencoded` body, as sent by classic html forms, and returns the key value
+  /// pairs in order of appearance. Keys and values are percent decoded and `+` decodes to a space.
+  /// A request without a body yields no pairs.
+  ///
+  /// The body is read like `raw_body_with_limit`, so it can still be read afterward.
+  /// The returned errors contain a `BodyParsingError` which the default error handler turns into
+  /// a 415 for other content types, a 413 if the body exceeds `max_len` bytes and a 400 for malformed bodies.
+  pub fn form_params_with_limit(&self, max_len: u64) -> TiiResult<Vec<(String, String)>> {
+    use crate::tii_error::BodyParsingError;
+
+    let mime = self.request.get_content_type().map(|mime| mime.as_str());
+    if mime != Some("application/x-www-form-urlencoded") {
+      return Err(BodyParsingError::UnsupportedMediaType(mime.map(ToString::to_string)).into());
+    }
+
+    let raw = self.raw_body_with_limit(max_len)?;
+    let raw = std::str::from_utf8(raw)
+      .map_err(|_| BodyParsingError::Malformed("form body is not utf-8".to_string()))?;
+    crate::http::request::parse_urlencoded(raw.trim_end_matches(['\r', '\n'])).ok_or_else(|| {
+      BodyParsingError::Malformed("invalid application/x-www-form-urlencoded body".to_string())
+        .into()
+    })
+  }
+
+  /// Reads the request body and deserializes it depending on the `Content-Type` of the request,
+  /// see `parse_body_with_limit`.
+  #[cfg(feature = "serde")]
+  pub fn parse_body<T: serde::de::DeserializeOwned>(&self) -> TiiResult<T> {
+    self.parse_body_with_limit(u64::MAX)
+  }
+
+  /// Reads a request body of at most `max_len` bytes and deserializes it depending on the `Content-Type` of the request.
+  /// - `application/json` is parsed as json, like `body_json_with_limit`.
+  /// - `application/x-www-form-urlencoded` is parsed as url encoded form.
+  ///
+  /// `multipart/form-data` is not supported, its parts are read one by one with `http::multipart::Multipart`.
+  ///
+  /// Only utf-8 (or us-ascii) bodies are supported.
+  /// The body is read like `raw_body_with_limit`, so it can still be read afterward.
+  /// The returned errors contain a `BodyParsingError` which the default error handler turns into
+  /// a 415 for unsupported content types/charsets, a 413 if the body exceeds `max_len` bytes
+  /// and a 400 for missing or malformed bodies.
+  #[cfg(feature = "serde")]
+  pub fn parse_body_with_limit<T: serde::de::DeserializeOwned>(
+    &self,
+    max_len: u64,
+  ) -> TiiResult<T> {
+    use crate::tii_error::BodyParsingError;
+
+    match self.request.get_content_type().map(|mime| mime.as_str()) {
+      Some("application/json") => self.body_json_with_limit(max_len),
+      Some("application/x-www-form-urlencoded") => {
+        self.check_utf8_charset()?;
+
+        if self.body.is_none() {
+          return Err(BodyParsingError::MissingBody.into());
+        }
+
+        let raw = self.raw_body_with_limit(max_len)?;
+        serde_urlencoded::from_bytes(raw)
+          .map_err(|e| BodyParsingError::Malformed(e.to_string()).into())
+      }
+      mime => Err(BodyParsingError::UnsupportedMediaType(mime.map(ToString::to_string)).into()),
+    }
+  }
+
+  /// Reads an `application/json` body and deserializes it, see `body_json_with_limit`.
+  #[cfg(feature = "serde")]
+  pub fn body_json<T: serde::de::DeserializeOwned>(&self) -> TiiResult<T> {
+    self.body_json_with_limit(u64::MAX)
+  }
+
+  /// Reads an `application/json` body of at most `max_len` bytes and deserializes it.
+  ///
+  /// The body is read like `raw_body_with_limit`, so it can still be read afterward.
+  /// The returned errors contain a `BodyParsingError` which the default error handler turns into
+  /// a 415 for other content types or charsets, a 413 if the body exceeds `max_len` bytes
+  /// and a 400 for missing or malformed bodies.
+  #[cfg(feature = "serde")]
+  pub fn body_json_with_limit<T: serde::de::DeserializeOwned>(&self, max_len: u64) -> TiiResult<T> {
+    use crate::tii_error::BodyParsingError;
+
+    let mime = self.request.get_content_type().map(|mime| mime.as_str());
+    if mime != Some("application/json") {
+      return Err(BodyParsingError::UnsupportedMediaType(mime.map(ToString::to_string)).into());
+    }
+
+    self.check_utf8_charset()?;
+
+    if self.body.is_none() {
+      return Err(BodyParsingError::MissingBody.into());
+    }
+
+    let raw = self.raw_body_with_limit(max_len)?;
+    serde_json::from_slice(raw).map_err(|e| BodyParsingError::Malformed(e.to_string()).into())
+  }
+
+  /// Fails with `BodyParsingError::UnsupportedCharset` if the `Content-Type` names a charset other than utf-8.
+  #[cfg(feature = "serde")]
+  fn check_utf8_charset(&self) -> TiiResult<()> {
+    use crate::tii_error::BodyParsingError;
+
+    if let Some(charset) = self.request.get_header(&HeaderName::ContentType).and_then(|ctype| {
+      ctype.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches('"'))
+      })
+    }) {
+      if !charset.eq_ignore_ascii_case("utf-8") && !charset.eq_ignore_ascii_case("us-ascii") {
+        return Err(BodyParsingError::UnsupportedCharset(charset.to_string()).into());
+      }
+    }
+    Ok(())
+  }
+
+  /// Streams the request body into the file at the given path and returns the amount of bytes written.
+  /// See `body_to_file_with_limit`.
+  pub fn body_to_file(&self, path: impl AsRef<Path>) -> TiiResult<u64> {
+    self.body_to_file_with_limit(path, u64::MAX)
+  }
+
+  /// Streams the request body into the file at the given path and returns the amount of bytes written.
+  /// A request without a body results in an empty file.
+  ///
+  /// The body is first written to a temporary file next to the target which is then renamed to the target,
+  /// so the target either contains the complete body or is not touched at all.
+  /// If the body exceeds `max_len` bytes or anything else fails the temporary file is removed.
+  /// Exceeding the limit yields a `BodyParsingError::TooLarge` which the default error handler turns into a 413.
+  pub fn body_to_file_with_limit(&self, path: impl AsRef<Path>, max_len: u64) -> TiiResult<u64> {
+    use crate::tii_error::BodyParsingError;
+    use std::fs::{remove_file, rename, File};
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let file_name = path
+      .file_name()
+      .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path does not name a file"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", self.id));
+    let temp_path = path.with_file_name(temp_name);
+
+    let write_temp = || -> TiiResult<u64> {
+      let mut file = File::create_new(&temp_path)?;
+      let mut written = 0u64;
+      if let Some(body) = self.body.as_ref() {
+        let mut buffer = vec![0u8; 0x1_00_00];
+        loop {
+          let read = body.read(buffer.as_mut_slice())?;
+          if read == 0 {
+            break;
+          }
+
+          written = written.saturating_add(read as u64);
+          if written > max_len {
+            return Err(BodyParsingError::TooLarge(max_len).into());
+          }
+
+          file.write_all(buffer.get(..read).ok_or_else(|| io::Error::other("buffer overflow"))?)?;
+        }
+      }
+
+      file.sync_all()?;
+      Ok(written)
+    };
+
+    let written = write_temp().and_then(|written| Ok(rename(&temp_path, path).map(|_| written)?));
+    if written.is_err() {
+      _ = remove_file(&temp_path);
+    }
+
+    written
+  }
+
+  /// The cookies sent by the client.
+  /// `Set-Cookie` operations queued in this jar are added to the response by the router.
+  pub fn cookies(&self) -> &CookieJar {
+    &self.cookies
+  }
+
+  /// The languages of the `Accept-Language` headers with their weight, most preferred first.
+  /// Languages with equal weight keep the order of the header, languages with a weight of 0 are not acceptable and omitted.
+  /// The wildcard `*` is returned as is. Yields an empty Vec if the client sent no `Accept-Language` header.
+  pub fn preferred_languages(&self) -> Vec<(String, f32)> {
+    let mut languages = Vec::new();
+    for value in self.request.get_headers(&HeaderName::AcceptLanguage) {
+      for entry in value.split(',') {
+        let mut params = entry.split(';');
+        let language = params.next().unwrap_or_default().trim();
+        if language.is_empty() {
+          continue;
+        }
+
+        let weight = params
+          .filter_map(|param| param.split_once('='))
+          .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+          .map(|(_, value)| value.trim().parse::<f32>().unwrap_or(0.0))
+          .unwrap_or(1.0);
+        if weight > 0.0 {
+          languages.push((language.to_string(), weight.min(1.0)));
+        }
+      }
+    }
+
+    languages.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    languages
+  }
+
   /// Get the routed path, yields "" before routing.
   pub fn routed_path(&self) -> &str {
     self.routed_path.as_deref().unwrap_or("")
   }
 
+  /// Returns the methods that have a route for the path of this request.
+  /// This is only computed by the router when an OPTIONS request is routed to a handler,
+  /// yields an empty Vec otherwise.
+  pub fn allowed_methods_for_path(&self) -> Vec<Method> {
+    self.allowed_methods.clone().unwrap_or_default()
+  }
+
+  /// Sets the methods that have a route for the path of this request.
+  /// This is called by the router before an OPTIONS handler is invoked.
+  pub fn set_allowed_methods_for_path(&mut self, methods: Vec<Method>) {
+    self.allowed_methods.replace(methods);
+  }
+
+  /// Returns the content type the router negotiated between the `Accept` header of the request
+  /// and the types the route `produces`. A handler that produces several types can use this
+  /// to decide what to render.
+  ///
+  /// Yields None if the route does not declare what it produces or if both the accepted
+  /// and the produced type are wildcards, for example `*/*`.
+  pub fn negotiated_content_type(&self) -> Option<&MimeType> {
+    self.negotiated_content_type.as_ref()
+  }
+
+  /// Sets the negotiated content type.
+  /// This is called by the router before the handler is invoked.
+  pub fn set_negotiated_content_type(&mut self, content_type: Option<MimeType>) {
+    self.negotiated_content_type = content_type;
+  }
+
   /// get the path param keys.
   pub fn get_path_param_keys(&self) -> Box<dyn Iterator<Item = &str> + '_> {
     match self.path_params.as_ref() {
-      Some(props) => Box::new(props.iter().map(|(k, _)| k.as_str())),
+      Some(props) => Box::new(props.keys().map(|k| k.as_str())),
       None => Box::new(std::iter::empty()),
     }
   }
@@ -270,6 +867,21 @@ impl RequestContext {
     None
   }
 
+  /// Gets a path param parsed into `T`, for example a numeric id of the route `/user/{id}`.
+  /// Returning the error from an endpoint with `?` results in a `400 Bad Request` from the default error handler.
+  pub fn path_param_parsed<T>(&self, name: impl AsRef<str>) -> Result<T, PathParamError>
+  where
+    T: FromStr,
+    T::Err: Display,
+  {
+    let name = name.as_ref();
+    let value =
+      self.get_path_param(name).ok_or_else(|| PathParamError::Missing(name.to_string()))?;
+    value.parse::<T>().map_err(|err| {
+      PathParamError::Malformed(name.to_string(), value.to_string(), err.to_string())
+    })
+  }
+
   /// Sets a path param.
   pub fn set_path_param(&mut self, key: impl ToString, value: impl ToString) -> Option<String> {
     if let Some(path) = self.path_params.as_mut() {
@@ -296,6 +908,7 @@ impl RequestContext {
       consume_body(old_body)?
     }
     self.body = body;
+    self.raw_body = OnceLock::new();
     Ok(())
   }
 
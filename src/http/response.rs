@@ -1,6 +1,7 @@
 //! Provides functionality for handling HTTP responses.
 
 use crate::http::cookie::SetCookie;
+use crate::http::encoding::ContentEncoding;
 use crate::http::headers::{Header, HeaderName, Headers};
 use crate::http::status::StatusCode;
 
@@ -11,6 +12,7 @@ use crate::http::response_body::{ReadAndSeek, ResponseBody};
 use crate::stream::ConnectionStreamWrite;
 use crate::tii_error::{TiiResult, UserError};
 use std::io;
+use std::time::Duration;
 
 /// Represents a response from the server.
 /// Implements `Into<Vec<u8>>` so can be serialised into bytes to transmit.
@@ -37,6 +39,31 @@ pub struct Response {
   pub(crate) headers: Headers,
   /// The body of the response.
   pub body: Option<ResponseBody>,
+  /// Information on how the response was produced.
+  meta: ResponseMeta,
+  /// True if the connection is closed after this response was sent.
+  close_connection: bool,
+}
+
+/// How the body of a response relates to the full representation of the resource.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResponseKind {
+  /// The full representation, for example a 200 OK.
+  Full,
+  /// A part of the representation, i.e. a 206 Partial Content.
+  Partial,
+  /// The router short-circuited a conditional request with 304 Not Modified.
+  NotModified,
+}
+
+/// Information on how a response was produced.
+/// Intended for response filters that do access logging or metrics, for example to measure cache hit ratios.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ResponseMeta {
+  /// Full, partial or conditional short-circuit.
+  pub kind: ResponseKind,
+  /// True if the body was compressed by tii.
+  pub compressed: bool,
 }
 
 /// An error which occurred during the parsing of a response.
@@ -56,12 +83,100 @@ impl std::fmt::Display for ResponseError {
 
 impl std::error::Error for ResponseError {}
 
+/// Headers that only apply to a single connection and are not forwarded by proxies,
+/// see [RFC 9110 Section 7.6.1](https://datatracker.ietf.org/doc/html/rfc9110#section-7.6.1).
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+  "Connection",
+  "Keep-Alive",
+  "Proxy-Authenticate",
+  "Proxy-Authorization",
+  "Proxy-Connection",
+  "TE",
+  "Trailer",
+  "Transfer-Encoding",
+  "Upgrade",
+  "Content-Length",
+];
+
 impl Response {
   /// Creates a new response object with the given status code.
   /// Automatically sets the HTTP version to "HTTP/1.1", sets no headers, and creates an empty body.
   pub fn new(status_code: impl Into<StatusCode>) -> Self {
     let status_code = status_code.into();
-    Self { status_code, headers: Headers::new(), body: None }
+    let kind = match status_code {
+      StatusCode::PartialContent => ResponseKind::Partial,
+      _ => ResponseKind::Full,
+    };
+    Self {
+      status_code,
+      headers: Headers::new(),
+      body: None,
+      meta: ResponseMeta { kind, compressed: false },
+      close_connection: false,
+    }
+  }
+
+  /// Returns information on how this response was produced.
+  pub fn meta(&self) -> ResponseMeta {
+    self.meta
+  }
+
+  /// Sets the information on how this response was produced.
+  pub fn set_meta(&mut self, meta: ResponseMeta) {
+    self.meta = meta;
+  }
+
+  /// Creates a response that relays the given upstream response, for example in a reverse proxy.
+  /// Status, headers and body are moved without copying the body.
+  /// Hop-by-hop headers (`Connection`, `Keep-Alive`, `Upgrade`, ... and every header listed in `Connection`)
+  /// only apply to the upstream connection and are dropped.
+  /// Use `with_rewritten_origin` to point `Location` and `Set-Cookie` headers at the proxy.
+  pub fn relay_from(upstream: Response) -> Response {
+    let connection_tokens = upstream
+      .headers
+      .get_all(HeaderName::Connection)
+      .into_iter()
+      .flat_map(|value| value.split(','))
+      .map(|token| token.trim().to_string())
+      .filter(|token| !token.is_empty())
+      .collect::<Vec<_>>();
+
+    let mut headers = Headers::new();
+    for header in upstream.headers.iter() {
+      let name = header.name.to_str();
+      if HOP_BY_HOP_HEADERS
+        .iter()
+        .copied()
+        .chain(connection_tokens.iter().map(String::as_str))
+        .any(|hop| hop.eq_ignore_ascii_case(name))
+      {
+        continue;
+      }
+      headers.push(header.clone());
+    }
+
+    Self {
+      status_code: upstream.status_code,
+      headers,
+      body: upstream.body,
+      meta: upstream.meta,
+      close_connection: false,
+    }
+  }
+
+  /// Response with the given status and the value serialized as json body, `Content-Type` is `application/json`.
+  /// Fails if the value can not be serialized, for example a map with non string keys.
+  #[cfg(feature = "serde")]
+  pub fn json<T: serde::Serialize + ?Sized>(
+    status_code: impl Into<StatusCode>,
+    value: &T,
+  ) -> TiiResult<Response> {
+    let body = serde_json::to_vec(value)?;
+    Ok(
+      Self::new(status_code)
+        .with_body(body)
+        .with_header_unchecked(HeaderName::ContentType, MimeType::ApplicationJson.as_str()),
+    )
   }
 
   /// HTTP 200 OK with body.
@@ -163,6 +278,10 @@ impl Response {
   }
 
   /// HTTP 303 See Other
+  ///
+  /// This is the redirect to use after a successfully processed form POST (the POST/redirect/GET pattern).
+  /// Clients always follow a `303` with a GET request to the location, so reloading the resulting page
+  /// does not submit the form again. A `302` does not guarantee this, clients may repeat the original method.
   pub fn see_other(
     location: impl AsRef<str>,
     body: impl Into<ResponseBody>,
@@ -174,7 +293,7 @@ impl Response {
       .with_body(body.into())
   }
 
-  /// HTTP 303 See Other without body
+  /// HTTP 303 See Other without body, see `see_other`.
   pub fn see_other_no_body(location: impl AsRef<str>) -> Response {
     Self::new(StatusCode::SeeOther).with_header_unchecked(HeaderName::Location, location)
   }
@@ -273,19 +392,7 @@ impl Response {
 
   /// HTTP 405 Method Not Allowed
   pub fn method_not_allowed(allowed_methods: &[Method]) -> Self {
-    if allowed_methods.is_empty() {
-      return Self::new(StatusCode::MethodNotAllowed);
-    }
-
-    let mut buf = String::new();
-    for method in allowed_methods {
-      if !buf.is_empty() {
-        buf += ", ";
-      }
-      buf += method.as_str();
-    }
-
-    Self::new(StatusCode::MethodNotAllowed).with_header_unchecked(HeaderName::Allow, buf.as_str())
+    Self::new(StatusCode::MethodNotAllowed).with_allow_header(allowed_methods)
   }
 
   /// HTTP 406 Not Acceptable
@@ -379,6 +486,47 @@ impl Response {
     Self::new(StatusCode::UnsupportedMediaType)
   }
 
+  /// HTTP 417 Expectation Failed without body
+  pub fn expectation_failed_no_body() -> Response {
+    Self::new(StatusCode::ExpectationFailed)
+  }
+
+  /// HTTP 505 HTTP Version Not Supported with body
+  pub fn version_not_supported(
+    body: impl Into<ResponseBody>,
+    mime: impl Into<MimeType>,
+  ) -> Response {
+    Self::new(StatusCode::VersionNotSupported)
+      .with_body(body.into())
+      .with_header_unchecked(HeaderName::ContentType, mime.into().as_str())
+  }
+
+  /// HTTP 505 HTTP Version Not Supported without body
+  pub fn version_not_supported_no_body() -> Response {
+    Self::new(StatusCode::VersionNotSupported)
+  }
+
+  /// HTTP 501 Not Implemented, the Allow header lists the methods the server does implement.
+  pub fn not_implemented(implemented_methods: &[Method]) -> Response {
+    Self::new(StatusCode::NotImplemented).with_allow_header(implemented_methods)
+  }
+
+  fn with_allow_header(self, methods: &[Method]) -> Self {
+    if methods.is_empty() {
+      return self;
+    }
+
+    let mut buf = String::new();
+    for method in methods {
+      if !buf.is_empty() {
+        buf += ", ";
+      }
+      buf += method.as_str();
+    }
+
+    self.with_header_unchecked(HeaderName::Allow, buf.as_str())
+  }
+
   ///Removes the body from the response
   pub fn without_body(mut self) -> Self {
     self.body = None;
@@ -419,6 +567,9 @@ impl Response {
   }
 
   /// Adds the given header to the response.
+  /// Well known header names are always written in their conventional casing no matter how
+  /// they are cased here, i.e. `content-type` is written as `Content-Type`.
+  /// Custom header names are written exactly as given.
   /// Returns itself for use in a builder pattern.
   pub fn with_header(mut self, header: impl AsRef<str>, value: impl AsRef<str>) -> TiiResult<Self> {
     self.add_header(header, value)?;
@@ -485,6 +636,23 @@ impl Response {
     self.headers.get_all(name)
   }
 
+  /// Returns the codings of all `Content-Encoding` headers in the order they were applied, empty if there are none.
+  pub fn content_encodings(&self) -> Vec<ContentEncoding> {
+    self
+      .get_headers(HeaderName::ContentEncoding)
+      .into_iter()
+      .flat_map(ContentEncoding::parse_list)
+      .collect()
+  }
+
+  /// Sets the `Content-Encoding` header, replacing previous values.
+  /// The body must already be encoded with the given coding, tii does not encode it.
+  /// Returns itself for use in a builder pattern.
+  pub fn with_content_encoding(mut self, encoding: ContentEncoding) -> Self {
+    self.headers.set(HeaderName::ContentEncoding, encoding.as_str());
+    self
+  }
+
   /// Adds the given cookie to the response in the `Set-Cookie` header.
   /// Returns itself for use in a builder pattern.
   pub fn with_cookie(mut self, cookie: SetCookie) -> Self {
@@ -492,6 +660,135 @@ impl Response {
     self
   }
 
+  /// Adds a `Set-Cookie` header that deletes the cookie with the given name, see `SetCookie::delete`.
+  /// Path and domain must be those the cookie was set with, otherwise the client keeps the cookie.
+  /// Returns itself for use in a builder pattern.
+  pub fn delete_cookie(
+    self,
+    name: impl AsRef<str>,
+    path: Option<&str>,
+    domain: Option<&str>,
+  ) -> Self {
+    let mut cookie = SetCookie::delete(name);
+    cookie.path = path.map(ToString::to_string);
+    cookie.domain = domain.map(ToString::to_string);
+    self.with_cookie(cookie)
+  }
+
+  /// Adds a `Warning` header, for example `110 tii "Response is Stale"`.
+  /// The code must have 3 digits. An empty agent is sent as the `-` pseudonym.
+  /// Quotes and backslashes in the text are escaped.
+  /// Returns itself for use in a builder pattern.
+  pub fn with_warning(
+    self,
+    code: u16,
+    agent: impl AsRef<str>,
+    text: impl AsRef<str>,
+  ) -> TiiResult<Self> {
+    self.with_warning_value(code, agent.as_ref(), text.as_ref(), None)
+  }
+
+  /// Same as `with_warning` but also carries the date the warning was generated at.
+  ///
+  /// **Warning:** The date must be a valid HTTP timestamp.
+  pub fn with_warning_date(
+    self,
+    code: u16,
+    agent: impl AsRef<str>,
+    text: impl AsRef<str>,
+    date: impl AsRef<str>,
+  ) -> TiiResult<Self> {
+    self.with_warning_value(code, agent.as_ref(), text.as_ref(), Some(date.as_ref()))
+  }
+
+  fn with_warning_value(
+    self,
+    code: u16,
+    agent: &str,
+    text: &str,
+    date: Option<&str>,
+  ) -> TiiResult<Self> {
+    if !(100..=999).contains(&code) {
+      return UserError::InvalidWarningCode(code).into();
+    }
+
+    let agent = if agent.is_empty() { "-" } else { agent };
+    let text = text.replace('\\', "\\\\").replace('"', "\\\"");
+    let value = match date {
+      Some(date) => format!("{code} {agent} \"{text}\" \"{date}\""),
+      None => format!("{code} {agent} \"{text}\""),
+    };
+
+    Ok(self.with_header_unchecked(HeaderName::Warning, value))
+  }
+
+  /// Sets the `Age` header to the given duration in whole seconds.
+  /// Returns itself for use in a builder pattern.
+  pub fn with_age(mut self, age: Duration) -> Self {
+    self.headers.set(HeaderName::Age, age.as_secs().to_string());
+    self
+  }
+
+  /// Rewrites headers of a relayed response that refer to the upstream server so that they refer to the proxy.
+  /// Both origins are given as `scheme://host[:port]`, the public origin may carry a path prefix for
+  /// path based proxying, for example `http://backend:8080` and `https://example.com/app`.
+  ///
+  /// - `Location` values starting with the upstream origin get the public origin instead,
+  ///   absolute paths like `/login` get the path prefix.
+  /// - `Set-Cookie` attributes `Domain` matching the upstream host get the public host,
+  ///   `Path` attributes get the path prefix.
+  ///
+  /// Returns itself for use in a builder pattern.
+  pub fn with_rewritten_origin(
+    mut self,
+    upstream_origin: impl AsRef<str>,
+    public_origin: impl AsRef<str>,
+  ) -> Self {
+    let upstream_origin = upstream_origin.as_ref().trim_end_matches('/');
+    let public_origin = public_origin.as_ref().trim_end_matches('/');
+    let (upstream_host, _) = split_origin(upstream_origin);
+    let (public_host, public_path) = split_origin(public_origin);
+
+    let mut headers = Headers::new();
+    for header in self.headers.iter() {
+      let value = match header.name {
+        HeaderName::Location => {
+          rewrite_location(&header.value, upstream_origin, public_origin, public_path)
+        }
+        HeaderName::SetCookie => {
+          rewrite_set_cookie(&header.value, upstream_host, public_host, public_path)
+        }
+        _ => None,
+      };
+
+      match value {
+        Some(value) => headers.push(Header { name: header.name.clone(), value }),
+        None => headers.push(header.clone()),
+      }
+    }
+
+    self.headers = headers;
+    self
+  }
+
+  /// Closes the connection after this response was sent, even if the client requested keep-alive.
+  /// The response is sent with `Connection: Close`.
+  /// Returns itself for use in a builder pattern.
+  pub fn with_connection_close(mut self) -> Self {
+    self.close_connection = true;
+    self
+  }
+
+  /// Closes the connection after this response was sent, even if the client requested keep-alive.
+  pub fn set_connection_close(&mut self) {
+    self.close_connection = true;
+  }
+
+  /// Returns true if the connection will be closed after this response was sent.
+  pub fn is_connection_close(&self) -> bool {
+    self.close_connection
+  }
+
   /// Returns the body as text, if possible.
   pub fn body(&self) -> Option<&ResponseBody> {
     self.body.as_ref()
@@ -513,6 +810,17 @@ impl Response {
       return Ok(());
     }
 
+    if version == HttpVersion::Http10 {
+      if let Some(body) = self.body.as_mut() {
+        // Small bodies read from a reader are buffered before anything is written,
+        // so a failing reader does not leave a partial head behind.
+        body.downgrade_chunked()?;
+        if body.is_close_delimited() {
+          self.headers.set(HeaderName::Connection, "Close");
+        }
+      }
+    }
+
     destination.write(version.as_net_str().as_bytes())?;
     destination.write(b" ")?;
     destination.write(self.status_code.code_as_utf())?;
@@ -546,6 +854,9 @@ impl Response {
 
       if let Some(len) = body.content_length() {
         destination.write(format!("\r\nContent-Length: {}\r\n\r\n", len).as_bytes())?;
+      } else {
+        // Close delimited, the client reads until the connection is closed.
+        destination.write(b"\r\n\r\n")?;
       }
 
       body.write_to(destination)?;
@@ -553,8 +864,84 @@ impl Response {
       return Ok(());
     }
 
+    if self.status_code == StatusCode::NotModified {
+      // 304 must not claim a Content-Length that differs from the unconditional response.
+      destination.write(b"\r\n\r\n")?;
+      destination.flush()?;
+      return Ok(());
+    }
+
     destination.write(b"\r\nContent-Length: 0\r\n\r\n")?;
     destination.flush()?;
     Ok(())
   }
 }
+
+/// Splits `scheme://host[:port][/path]` into the host without port and the path.
+fn split_origin(origin: &str) -> (&str, &str) {
+  let rest = origin.split_once("://").map_or(origin, |(_, rest)| rest);
+  let (authority, path) = rest.find('/').map_or((rest, ""), |idx| rest.split_at(idx));
+  let host = match authority.find(']') {
+    Some(end) => authority.get(..=end).unwrap_or(authority),
+    None => authority.split_once(':').map_or(authority, |(host, _)| host),
+  };
+  (host, path)
+}
+
+fn rewrite_location(
+  location: &str,
+  upstream_origin: &str,
+  public_origin: &str,
+  public_path: &str,
+) -> Option<String> {
+  if let Some(rest) = location.strip_prefix(upstream_origin) {
+    if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+      return Some(format!("{public_origin}{rest}"));
+    }
+    return None;
+  }
+
+  if !public_path.is_empty() && location.starts_with('/') && !location.starts_with("//") {
+    return Some(format!("{public_path}{location}"));
+  }
+
+  None
+}
+
+fn rewrite_set_cookie(
+  set_cookie: &str,
+  upstream_host: &str,
+  public_host: &str,
+  public_path: &str,
+) -> Option<String> {
+  let mut changed = false;
+  let mut parts = Vec::new();
+  for (idx, part) in set_cookie.split(';').enumerate() {
+    let part = part.trim();
+    let attribute = (idx > 0).then(|| part.split_once('=')).flatten();
+    match attribute {
+      Some((key, domain))
+        if key.trim().eq_ignore_ascii_case("domain")
+          && domain.trim().trim_start_matches('.').eq_ignore_ascii_case(upstream_host) =>
+      {
+        changed = true;
+        parts.push(format!("Domain={public_host}"));
+      }
+      Some((key, path))
+        if key.trim().eq_ignore_ascii_case("path")
+          && !public_path.is_empty()
+          && path.trim().starts_with('/') =>
+      {
+        changed = true;
+        let path = path.trim();
+        match path {
+          "/" => parts.push(format!("Path={public_path}")),
+          _ => parts.push(format!("Path={public_path}{path}")),
+        }
+      }
+      _ => parts.push(part.to_string()),
+    }
+  }
+
+  changed.then(|| parts.join("; "))
+}
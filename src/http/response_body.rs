@@ -6,6 +6,7 @@ use crate::stream::ConnectionStreamWrite;
 use std::fmt::{Debug, Formatter};
 use std::io;
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::mpsc::Receiver;
 
 pub type ResponseBodyHandler = dyn FnOnce(&dyn ResponseBodySink) -> io::Result<()>;
 pub enum ResponseBody {
@@ -28,8 +29,16 @@ pub enum ResponseBody {
   //Causes the response to be sent as chunked transfer encoding
   //All required headers for this will be set automatically.
   ChunkedStream(Option<Box<ResponseBodyHandler>>),
+
+  //Streams everything the reader yields as chunked transfer encoding.
+  //Unlike ChunkedStream the body can be buffered for HTTP/1.0 clients if it turns out to be small.
+  ChunkedReader(Option<Box<dyn Read>>),
 }
 
+/// Bodies of `ResponseBody::from_reader_chunked` up to this size are sent to HTTP/1.0 clients with a Content-Length header,
+/// larger ones are sent close delimited.
+pub(crate) const HTTP10_BUFFER_LIMIT: usize = 0x10_00_00;
+
 impl Debug for ResponseBody {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     match self {
@@ -44,6 +53,7 @@ impl Debug for ResponseBody {
       }
       ResponseBody::Stream(_) => f.write_str("ResponseBody::Stream(handler)"),
       ResponseBody::ChunkedStream(_) => f.write_str("ResponseBody::ChunkedStream(handler)"),
+      ResponseBody::ChunkedReader(_) => f.write_str("ResponseBody::ChunkedReader(reader)"),
     }
   }
 }
@@ -57,7 +67,27 @@ pub trait ResponseBodySink: Write {
   fn write(&self, buffer: &[u8]) -> io::Result<usize>;
   fn write_all(&self, buffer: &[u8]) -> io::Result<()>;
 
+  /// Pushes everything written so far to the client instead of waiting for the response to be complete.
+  /// Long-lived streams like server sent events call this after every message.
+  fn flush_to_client(&self) -> io::Result<()>;
+
   fn as_write(&self) -> &dyn Write;
+
+  /// Returns an error that aborts the response when it is returned from the body handler.
+  /// Use this if the body cannot be completed, for example because a database read failed halfway.
+  /// Everything written so far is sent, then the connection is closed without ending the body properly.
+  /// For chunked bodies the terminating zero chunk is omitted, so the client can tell the body is incomplete.
+  ///
+  /// ```
+  /// use tii::http::response_body::{ResponseBody, ResponseBodySink};
+  /// let body = ResponseBody::chunked(|sink| {
+  ///   sink.write_all(b"first part")?;
+  ///   Err(sink.abort("database read failed"))
+  /// });
+  /// ```
+  fn abort(&self, reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, format!("response body aborted: {reason}"))
+  }
 }
 impl ResponseBody {
   pub fn from_data(data: Vec<u8>) -> Self {
@@ -86,6 +116,37 @@ impl ResponseBody {
     Self::Stream(Some(Box::new(streamer)))
   }
 
+  /// Streams everything the reader yields without a Content-Length header or chunked transfer encoding.
+  /// The end of the body is signaled to the client by closing the connection after the response,
+  /// so keep alive is not possible. This is the only way to stream to HTTP/1.0 clients,
+  /// which do not understand chunked transfer encoding.
+  pub fn from_reader_close_delimited<T: Read + 'static>(mut reader: T) -> Self {
+    Self::streamed(move |sink| copy_to_sink(&mut reader, sink))
+  }
+
+  /// Streams everything the reader yields as chunked transfer encoding,
+  /// the length of the body does not need to be known in advance.
+  /// HTTP/1.0 clients do not understand chunked transfer encoding,
+  /// for them a body of up to 1 MiB is read into memory first and sent with a Content-Length header.
+  /// Larger bodies are sent to them close delimited, see `from_reader_close_delimited`.
+  pub fn from_reader_chunked<T: Read + 'static>(reader: T) -> Self {
+    Self::ChunkedReader(Some(Box::new(reader)))
+  }
+
+  /// Streams the data received from the channel as chunked transfer encoding.
+  /// The body ends when all senders of the channel have been dropped.
+  /// Every message is flushed to the client as soon as it is received.
+  /// If writing to the client fails then the receiver is dropped, causing the next send of the producer to fail.
+  pub fn from_channel(receiver: Receiver<Vec<u8>>) -> Self {
+    Self::chunked(move |sink| {
+      for data in receiver {
+        sink.write_all(data.as_slice())?;
+        sink.flush_to_client()?;
+      }
+      Ok(())
+    })
+  }
+
   pub fn write_to<T: ConnectionStreamWrite + ?Sized>(&mut self, stream: &T) -> io::Result<()> {
     match self {
       ResponseBody::FixedSizeBinaryData(data) => stream.write_all(data.as_slice()),
@@ -95,30 +156,25 @@ impl ResponseBody {
         let mut io_buf = [0u8; 0x1_00_00];
         let mut written = 0u64;
         file.seek(io::SeekFrom::Start(0))?;
-        loop {
-          let read = file.read(io_buf.as_mut_slice())?;
+        while written < *size {
+          // Never send more than the announced content length, even if the file grew in the meantime.
+          let remaining = usize::try_from(*size - written).unwrap_or(usize::MAX);
+          let chunk = io_buf.len().min(remaining);
+          let read =
+            file.read(io_buf.get_mut(..chunk).ok_or(io::Error::other("buffer overflow"))?)?;
           if read == 0 {
-            if written != *size {
-              return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "size of the file changed while writing it to network",
-              ));
-            }
-            return Ok(());
+            return Err(io::Error::new(
+              io::ErrorKind::InvalidData,
+              "size of the file changed while writing it to network",
+            ));
           }
 
-          stream.write_all(
-            io_buf
-              .get_mut(..read)
-              .ok_or(io::Error::new(io::ErrorKind::Other, "buffer overflow"))?,
-          )?;
+          stream.write_all(io_buf.get_mut(..read).ok_or(io::Error::other("buffer overflow"))?)?;
           written = written
-            .checked_add(
-              u64::try_from(read)
-                .map_err(|_| io::Error::new(io::ErrorKind::Other, "usize->u64 failed"))?,
-            )
-            .ok_or(io::Error::new(io::ErrorKind::Other, "u64 overflow"))?;
+            .checked_add(u64::try_from(read).map_err(|_| io::Error::other("usize->u64 failed"))?)
+            .ok_or(io::Error::other("u64 overflow"))?;
         }
+        Ok(())
       }
       ResponseBody::Stream(handler) => handler.take().ok_or_else(|| {
         io::Error::new(io::ErrorKind::UnexpectedEof, "stream can only be written once")
@@ -128,14 +184,59 @@ impl ResponseBody {
         let sink = ChunkedSink(stream.as_stream_write());
         handler.take().ok_or_else(|| {
           io::Error::new(io::ErrorKind::UnexpectedEof, "stream can only be written once")
-        })?(&sink)?;
+        })?(&sink)
+        .inspect_err(|_| {
+          // The client should receive the partial body, the missing zero chunk tells it that it is incomplete.
+          _ = sink.flush_to_client();
+        })?;
         sink.finish()
       }
+
+      ResponseBody::ChunkedReader(reader) => {
+        let mut reader = reader.take().ok_or_else(|| {
+          io::Error::new(io::ErrorKind::UnexpectedEof, "stream can only be written once")
+        })?;
+        *self = Self::chunked(move |sink| copy_to_sink(&mut reader, sink));
+        self.write_to(stream)
+      }
     }
   }
 
+  /// Replaces a chunked body with one that HTTP/1.0 clients understand, they have no chunked transfer encoding.
+  /// Bodies of `from_reader_chunked` are buffered up to `HTTP10_BUFFER_LIMIT` and sent with a Content-Length header.
+  /// Larger ones and all other chunked bodies, which may never end like a server sent event stream,
+  /// are sent close delimited instead.
+  pub(crate) fn downgrade_chunked(&mut self) -> io::Result<()> {
+    match self {
+      ResponseBody::ChunkedStream(handler) => {
+        *self = ResponseBody::Stream(handler.take());
+      }
+      ResponseBody::ChunkedReader(reader) => {
+        let mut reader = reader.take().ok_or_else(|| {
+          io::Error::new(io::ErrorKind::UnexpectedEof, "stream can only be written once")
+        })?;
+        let mut buffer = Vec::new();
+        // One more byte than the limit tells whether the body fits.
+        reader.by_ref().take(HTTP10_BUFFER_LIMIT as u64 + 1).read_to_end(&mut buffer)?;
+        if buffer.len() <= HTTP10_BUFFER_LIMIT {
+          *self = ResponseBody::FixedSizeBinaryData(buffer);
+        } else {
+          let mut rest = io::Cursor::new(buffer).chain(reader);
+          *self = Self::streamed(move |sink| copy_to_sink(&mut rest, sink));
+        }
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
   pub fn is_chunked(&self) -> bool {
-    matches!(self, ResponseBody::ChunkedStream(_))
+    matches!(self, ResponseBody::ChunkedStream(_) | ResponseBody::ChunkedReader(_))
+  }
+
+  /// Returns true if the end of the body can only be signaled by closing the connection.
+  pub fn is_close_delimited(&self) -> bool {
+    matches!(self, ResponseBody::Stream(_))
   }
 
   pub fn content_length(&self) -> Option<u64> {
@@ -148,6 +249,18 @@ impl ResponseBody {
   }
 }
 
+fn copy_to_sink(reader: &mut dyn Read, sink: &dyn ResponseBodySink) -> io::Result<()> {
+  let mut io_buf = vec![0u8; 0x1_00_00];
+  loop {
+    let read = reader.read(io_buf.as_mut_slice())?;
+    if read == 0 {
+      return Ok(());
+    }
+
+    sink.write_all(io_buf.get(..read).ok_or(io::Error::other("buffer overflow"))?)?;
+  }
+}
+
 struct StreamSink<'a>(&'a dyn ConnectionStreamWrite);
 
 impl Write for StreamSink<'_> {
@@ -170,6 +283,10 @@ impl ResponseBodySink for StreamSink<'_> {
     self.0.write_all(buffer)
   }
 
+  fn flush_to_client(&self) -> io::Result<()> {
+    self.0.flush()
+  }
+
   fn as_write(&self) -> &dyn Write {
     self
   }
@@ -206,6 +323,10 @@ impl ResponseBodySink for ChunkedSink<'_> {
     self.0.write_all(b"\r\n")
   }
 
+  fn flush_to_client(&self) -> io::Result<()> {
+    self.0.flush()
+  }
+
   fn as_write(&self) -> &dyn Write {
     self
   }
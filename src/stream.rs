@@ -29,6 +29,64 @@ pub trait ConnectionStream: ConnectionStreamRead + ConnectionStreamWrite {
 
   fn peer_addr(&self) -> io::Result<String>;
   fn local_addr(&self) -> io::Result<String>;
+
+  /// The server name the client requested via SNI during the TLS handshake.
+  /// Returns None for streams that are not TLS or if the client did not send SNI.
+  fn sni_hostname(&self) -> Option<String> {
+    None
+  }
+
+  /// Checks without blocking whether the peer has closed the connection.
+  /// Data that arrives in the meantime is buffered for the next read.
+  /// Returns false if this can not be determined, which is the default for streams that do not implement it.
+  fn is_disconnected(&self) -> bool {
+    false
+  }
+
+  /// Corks or uncorks the connection. While corked the kernel only sends full segments,
+  /// uncorking sends what is left. This is `TCP_CORK` for tcp connections on linux.
+  /// Does nothing on other streams and platforms, which is the default.
+  fn set_cork(&self, _cork: bool) -> io::Result<()> {
+    Ok(())
+  }
+
+  /// Sets `SO_LINGER` of the connection.
+  ///
+  /// With `Some(duration)` closing the socket blocks for at most the duration until unsent data has been transmitted,
+  /// after that the connection is reset. `Some(Duration::ZERO)` resets the connection immediately on close,
+  /// which means the socket does not enter `TIME_WAIT`. The duration is rounded down to whole seconds.
+  /// `None` restores the default behavior of the OS, closing returns immediately and the OS sends the data in the background.
+  /// Does nothing on streams other than tcp connections with the `extras` feature, which is the default.
+  fn set_linger(&self, _linger: Option<Duration>) -> io::Result<()> {
+    Ok(())
+  }
+
+  /// The credentials of the process on the other end of a unix socket, see `UnixCredentials`.
+  /// Returns None for other streams, which is the default.
+  fn peer_unix_credentials(&self) -> Option<UnixCredentials> {
+    None
+  }
+}
+
+/// Credentials of the process that connected to a unix socket, as reported by `SO_PEERCRED`.
+/// They are captured by the kernel when the connection was made.
+/// Only available on linux with the `extras` feature.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UnixCredentials {
+  /// The user id of the peer process.
+  pub uid: u32,
+  /// The group id of the peer process.
+  pub gid: u32,
+  /// The process id of the peer process.
+  pub pid: i32,
+}
+
+/// Interprets the result of a non-blocking `ensure_readable`, EOF and hard errors mean the peer is gone.
+fn peer_gone(result: io::Result<bool>) -> bool {
+  match result {
+    Ok(readable) => !readable,
+    Err(err) => !matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted),
+  }
 }
 
 pub trait ConnectionStreamRead: Sync + Send + Debug + Read {
@@ -256,6 +314,98 @@ mod tcp {
     fn local_addr(&self) -> io::Result<String> {
       Ok(format!("{}", self.0.stream.local_addr()?))
     }
+
+    fn is_disconnected(&self) -> bool {
+      // A concurrent reader is blocked on the socket and will notice the disconnect itself.
+      let Ok(mut guard) = self.0.read_mutex.try_lock() else {
+        return false;
+      };
+      if guard.available() > 0 {
+        return false;
+      }
+      if self.0.stream.set_nonblocking(true).is_err() {
+        return false;
+      }
+      let result = guard.ensure_readable(&mut &self.0.stream);
+      _ = self.0.stream.set_nonblocking(false);
+      super::peer_gone(result)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "extras"))]
+    #[expect(unsafe_code)]
+    fn set_cork(&self, cork: bool) -> io::Result<()> {
+      use std::os::fd::AsRawFd;
+      let value = libc::c_int::from(cork);
+      // SAFETY: The fd is owned by the TcpStream and the option value is a c_int as required by TCP_CORK.
+      let result = unsafe {
+        libc::setsockopt(
+          self.0.stream.as_raw_fd(),
+          libc::IPPROTO_TCP,
+          libc::TCP_CORK,
+          std::ptr::from_ref(&value).cast(),
+          size_of::<libc::c_int>() as libc::socklen_t,
+        )
+      };
+      if result == -1 {
+        return Err(io::Error::last_os_error());
+      }
+      Ok(())
+    }
+
+    #[cfg(all(unix, feature = "extras"))]
+    #[expect(unsafe_code)]
+    fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+      use std::os::fd::AsRawFd;
+      let value = libc::linger {
+        l_onoff: libc::c_int::from(linger.is_some()),
+        l_linger: linger
+          .map(|linger| libc::c_int::try_from(linger.as_secs()).unwrap_or(libc::c_int::MAX))
+          .unwrap_or_default(),
+      };
+      // SAFETY: The fd is owned by the TcpStream and the option value is a linger struct as required by SO_LINGER.
+      let result = unsafe {
+        libc::setsockopt(
+          self.0.stream.as_raw_fd(),
+          libc::SOL_SOCKET,
+          libc::SO_LINGER,
+          std::ptr::from_ref(&value).cast(),
+          size_of::<libc::linger>() as libc::socklen_t,
+        )
+      };
+      if result == -1 {
+        return Err(io::Error::last_os_error());
+      }
+      Ok(())
+    }
+
+    #[cfg(all(target_os = "windows", feature = "extras"))]
+    #[expect(unsafe_code)]
+    fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+      use std::os::windows::io::AsRawSocket;
+      use windows_sys::Win32::Networking::WinSock::{
+        setsockopt, WSAGetLastError, LINGER, SOCKET_ERROR, SOL_SOCKET, SO_LINGER,
+      };
+      let value = LINGER {
+        l_onoff: u16::from(linger.is_some()),
+        l_linger: linger
+          .map(|linger| u16::try_from(linger.as_secs()).unwrap_or(u16::MAX))
+          .unwrap_or_default(),
+      };
+      // SAFETY: The socket is owned by the TcpStream and the option value is a LINGER struct as required by SO_LINGER.
+      let result = unsafe {
+        setsockopt(
+          self.0.stream.as_raw_socket() as usize,
+          SOL_SOCKET,
+          SO_LINGER,
+          std::ptr::from_ref(&value).cast(),
+          size_of::<LINGER>() as i32,
+        )
+      };
+      if result == SOCKET_ERROR {
+        return Err(io::Error::from_raw_os_error(unsafe { WSAGetLastError() }));
+      }
+      Ok(())
+    }
   }
 }
 
@@ -567,5 +717,519 @@ mod unix {
         .local_addr()
         .map(|a| a.as_pathname().map(|a| a.to_string_lossy().to_string()).unwrap_or_default())
     }
+
+    fn is_disconnected(&self) -> bool {
+      // A concurrent reader is blocked on the socket and will notice the disconnect itself.
+      let Ok(mut guard) = self.0.read_mutex.try_lock() else {
+        return false;
+      };
+      if guard.available() > 0 {
+        return false;
+      }
+      if self.0.stream.set_nonblocking(true).is_err() {
+        return false;
+      }
+      let result = guard.ensure_readable(&mut &self.0.stream);
+      _ = self.0.stream.set_nonblocking(false);
+      super::peer_gone(result)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "extras"))]
+    #[expect(unsafe_code)]
+    fn peer_unix_credentials(&self) -> Option<super::UnixCredentials> {
+      use std::os::fd::AsRawFd;
+      let mut credentials = libc::ucred { pid: 0, uid: 0, gid: 0 };
+      let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+      // SAFETY: The fd is owned by the UnixStream, credentials and len describe a valid ucred as required by SO_PEERCRED.
+      let result = unsafe {
+        libc::getsockopt(
+          self.0.stream.as_raw_fd(),
+          libc::SOL_SOCKET,
+          libc::SO_PEERCRED,
+          std::ptr::from_mut(&mut credentials).cast(),
+          &mut len,
+        )
+      };
+      if result == -1 {
+        return None;
+      }
+
+      Some(super::UnixCredentials {
+        uid: credentials.uid,
+        gid: credentials.gid,
+        pid: credentials.pid,
+      })
+    }
+  }
+}
+
+/// Wraps a connection so that reading a request can be limited by a total deadline,
+/// see `TiiBuilder::with_request_deadline`.
+pub(crate) mod deadline {
+  use crate::functional_traits::Clock;
+  use crate::stream::{ConnectionStream, ConnectionStreamRead, ConnectionStreamWrite};
+  use crate::util::unwrap_poison;
+  use std::io;
+  use std::io::{ErrorKind, Read, Write};
+  use std::sync::{Arc, Mutex};
+  use std::time::{Duration, Instant};
+
+  pub(crate) fn new(
+    stream: Box<dyn ConnectionStream>,
+    clock: Arc<dyn Clock>,
+  ) -> (Box<dyn ConnectionStream>, RequestDeadline) {
+    let inner =
+      Arc::new(DeadlineStreamInner { stream, clock, state: Mutex::new(DeadlineState::default()) });
+    (Box::new(DeadlineStreamOuter(inner.clone())), RequestDeadline(inner))
+  }
+
+  /// Handle to start and clear the deadline of the wrapped connection.
+  #[derive(Debug)]
+  pub(crate) struct RequestDeadline(Arc<DeadlineStreamInner>);
+
+  impl RequestDeadline {
+    /// Every read after `timeout` has elapsed from now fails with `TimedOut`.
+    pub(crate) fn start(&self, timeout: Duration) -> io::Result<()> {
+      let mut state = unwrap_poison(self.0.state.lock())?;
+      state.deadline = Some(self.0.clock.now() + timeout);
+      Ok(())
+    }
+
+    /// Lifts the deadline and restores the read timeout of the connection.
+    /// Once the deadline was exceeded the connection stays unreadable.
+    pub(crate) fn clear(&self) -> io::Result<()> {
+      let mut state = unwrap_poison(self.0.state.lock())?;
+      if state.deadline.take().is_some() && !state.expired {
+        self.0.stream.set_read_timeout(state.read_timeout)?;
+      }
+      Ok(())
+    }
+  }
+
+  #[derive(Debug, Default)]
+  struct DeadlineState {
+    deadline: Option<Instant>,
+    expired: bool,
+    /// True if the read timeout was lowered to the time left until the deadline.
+    limited: bool,
+    /// The read timeout set by the server, the deadline only ever shortens it.
+    read_timeout: Option<Duration>,
+  }
+
+  #[derive(Debug)]
+  struct DeadlineStreamInner {
+    stream: Box<dyn ConnectionStream>,
+    clock: Arc<dyn Clock>,
+    state: Mutex<DeadlineState>,
+  }
+
+  impl DeadlineStreamInner {
+    /// Returns true if a deadline is active. The read timeout is lowered to the time left until the deadline.
+    fn arm(&self) -> io::Result<bool> {
+      let mut state = unwrap_poison(self.state.lock())?;
+      if state.expired {
+        return Err(io::Error::new(ErrorKind::TimedOut, "request deadline exceeded"));
+      }
+
+      let Some(deadline) = state.deadline else {
+        return Ok(false);
+      };
+
+      let remaining = deadline.saturating_duration_since(self.clock.now());
+      if remaining.is_zero() {
+        state.expired = true;
+        return Err(io::Error::new(ErrorKind::TimedOut, "request deadline exceeded"));
+      }
+
+      let timeout = state.read_timeout.map_or(remaining, |timeout| timeout.min(remaining));
+      state.limited = timeout == remaining;
+      self.stream.set_read_timeout(Some(timeout))?;
+      Ok(true)
+    }
+
+    /// A read that timed out because of the lowered read timeout exceeded the deadline.
+    /// Depending on the platform the socket reports this as `WouldBlock` or `TimedOut`.
+    fn expire_on_timeout<T>(&self, result: io::Result<T>) -> io::Result<T> {
+      let err = match result {
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => err,
+        other => return other,
+      };
+
+      let mut state = unwrap_poison(self.state.lock())?;
+      if state.deadline.is_none() || !state.limited {
+        return Err(err);
+      }
+      state.expired = true;
+      Err(io::Error::new(ErrorKind::TimedOut, "request deadline exceeded"))
+    }
+
+    /// Makes sure at least 1 byte is buffered, returns false on EOF.
+    fn fill(&self) -> io::Result<bool> {
+      if self.stream.available() > 0 {
+        return Ok(true);
+      }
+
+      self.arm()?;
+      self.expire_on_timeout(self.stream.ensure_readable())
+    }
+  }
+
+  #[derive(Debug, Clone)]
+  struct DeadlineStreamOuter(Arc<DeadlineStreamInner>);
+
+  impl Read for DeadlineStreamOuter {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      ConnectionStreamRead::read(self, buf)
+    }
+  }
+
+  impl ConnectionStreamRead for DeadlineStreamOuter {
+    fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+      if self.0.stream.available() == 0 && self.0.arm()? {
+        return self.0.expire_on_timeout(ConnectionStreamRead::read(self.0.stream.as_ref(), buf));
+      }
+      ConnectionStreamRead::read(self.0.stream.as_ref(), buf)
+    }
+
+    fn ensure_readable(&self) -> io::Result<bool> {
+      self.0.fill()
+    }
+
+    fn available(&self) -> usize {
+      self.0.stream.available()
+    }
+
+    fn read_until(&self, end: u8, limit: usize, buf: &mut Vec<u8>) -> io::Result<usize> {
+      if !self.0.arm()? {
+        return self.0.stream.read_until(end, limit, buf);
+      }
+
+      // Only buffered bytes are handed to the inner stream, so the deadline is checked before every read.
+      let mut count = 0;
+      while count < limit && self.0.fill()? {
+        let chunk = (limit - count).min(self.0.stream.available());
+        let read = self.0.stream.read_until(end, chunk, buf)?;
+        count += read;
+        if read == 0 || buf.last() == Some(&end) {
+          break;
+        }
+      }
+      Ok(count)
+    }
+
+    fn read_exact(&self, mut buf: &mut [u8]) -> io::Result<()> {
+      if !self.0.arm()? {
+        return ConnectionStreamRead::read_exact(self.0.stream.as_ref(), buf);
+      }
+
+      while !buf.is_empty() {
+        if !self.0.fill()? {
+          return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+        let read = ConnectionStreamRead::read(self.0.stream.as_ref(), buf)?;
+        buf = buf.get_mut(read..).unwrap_or_default();
+      }
+      Ok(())
+    }
+
+    fn new_ref_read(&self) -> Box<dyn Read + Send + Sync> {
+      Box::new(self.clone()) as Box<dyn Read + Send + Sync>
+    }
+
+    fn as_stream_read(&self) -> &dyn ConnectionStreamRead {
+      self
+    }
+
+    fn new_ref_stream_read(&self) -> Box<dyn ConnectionStreamRead> {
+      Box::new(self.clone()) as Box<dyn ConnectionStreamRead>
+    }
+
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+      unwrap_poison(self.0.state.lock())?.read_timeout = dur;
+      self.0.stream.set_read_timeout(dur)
+    }
+
+    fn get_read_timeout(&self) -> io::Result<Option<Duration>> {
+      Ok(unwrap_poison(self.0.state.lock())?.read_timeout)
+    }
+  }
+
+  impl ConnectionStreamWrite for DeadlineStreamOuter {
+    fn write(&self, buf: &[u8]) -> io::Result<usize> {
+      ConnectionStreamWrite::write(self.0.stream.as_ref(), buf)
+    }
+
+    fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+      ConnectionStreamWrite::write_all(self.0.stream.as_ref(), buf)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+      ConnectionStreamWrite::flush(self.0.stream.as_ref())
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+      self.0.stream.set_write_timeout(dur)
+    }
+
+    fn get_write_timeout(&self) -> io::Result<Option<Duration>> {
+      self.0.stream.get_write_timeout()
+    }
+
+    fn new_ref_write(&self) -> Box<dyn Write + Send + Sync> {
+      Box::new(self.clone()) as Box<dyn Write + Send + Sync>
+    }
+
+    fn new_ref_stream_write(&self) -> Box<dyn ConnectionStreamWrite> {
+      Box::new(self.clone()) as Box<dyn ConnectionStreamWrite>
+    }
+
+    fn as_stream_write(&self) -> &dyn ConnectionStreamWrite {
+      self
+    }
+  }
+
+  impl Write for DeadlineStreamOuter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      ConnectionStreamWrite::write(self, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      ConnectionStreamWrite::flush(self)
+    }
+  }
+
+  impl ConnectionStream for DeadlineStreamOuter {
+    fn new_ref(&self) -> Box<dyn ConnectionStream> {
+      Box::new(self.clone()) as Box<dyn ConnectionStream>
+    }
+
+    fn peer_addr(&self) -> io::Result<String> {
+      self.0.stream.peer_addr()
+    }
+
+    fn local_addr(&self) -> io::Result<String> {
+      self.0.stream.local_addr()
+    }
+
+    fn sni_hostname(&self) -> Option<String> {
+      self.0.stream.sni_hostname()
+    }
+
+    fn is_disconnected(&self) -> bool {
+      self.0.stream.is_disconnected()
+    }
+
+    fn set_cork(&self, cork: bool) -> io::Result<()> {
+      self.0.stream.set_cork(cork)
+    }
+
+    fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+      self.0.stream.set_linger(linger)
+    }
+
+    fn peer_unix_credentials(&self) -> Option<super::UnixCredentials> {
+      self.0.stream.peer_unix_credentials()
+    }
+  }
+}
+
+/// ConnectionStreamWrite that collects everything written to it in memory.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BufferedStreamWrite(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl BufferedStreamWrite {
+  /// Takes all bytes written so far out of the buffer.
+  pub(crate) fn take(&self) -> io::Result<Vec<u8>> {
+    Ok(std::mem::take(crate::util::unwrap_poison(self.0.lock())?.as_mut()))
+  }
+}
+
+impl ConnectionStreamWrite for BufferedStreamWrite {
+  fn write(&self, buf: &[u8]) -> io::Result<usize> {
+    crate::util::unwrap_poison(self.0.lock())?.extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+    crate::util::unwrap_poison(self.0.lock())?.extend_from_slice(buf);
+    Ok(())
+  }
+
+  fn flush(&self) -> io::Result<()> {
+    Ok(())
+  }
+
+  fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+    Ok(())
+  }
+
+  fn get_write_timeout(&self) -> io::Result<Option<Duration>> {
+    Ok(None)
+  }
+
+  fn new_ref_write(&self) -> Box<dyn Write + Send + Sync> {
+    Box::new(self.clone()) as Box<dyn Write + Send + Sync>
+  }
+
+  fn new_ref_stream_write(&self) -> Box<dyn ConnectionStreamWrite> {
+    Box::new(self.clone()) as Box<dyn ConnectionStreamWrite>
+  }
+
+  fn as_stream_write(&self) -> &dyn ConnectionStreamWrite {
+    self
+  }
+}
+
+impl Write for BufferedStreamWrite {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ConnectionStreamWrite::write(self, buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    ConnectionStreamWrite::flush(self)
+  }
+}
+
+/// ConnectionStreamWrite that collects writes into a buffer of a fixed size before passing them
+/// to another ConnectionStreamWrite. Writes that do not fit into the buffer go through directly.
+/// Bytes written through references obtained from `new_ref_write` or `new_ref_stream_write` are not buffered.
+#[derive(Debug)]
+pub(crate) struct SizedBufferStreamWrite<'a> {
+  inner: &'a dyn ConnectionStreamWrite,
+  buffer: std::sync::Mutex<Vec<u8>>,
+  capacity: usize,
+}
+
+impl<'a> SizedBufferStreamWrite<'a> {
+  pub(crate) fn new(inner: &'a dyn ConnectionStreamWrite, capacity: usize) -> Self {
+    Self { inner, buffer: std::sync::Mutex::new(Vec::with_capacity(capacity)), capacity }
+  }
+}
+
+impl ConnectionStreamWrite for SizedBufferStreamWrite<'_> {
+  fn write(&self, buf: &[u8]) -> io::Result<usize> {
+    self.write_all(buf)?;
+    Ok(buf.len())
+  }
+
+  fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+    let mut buffer = crate::util::unwrap_poison(self.buffer.lock())?;
+    if buffer.len() + buf.len() > self.capacity && !buffer.is_empty() {
+      self.inner.write_all(buffer.as_slice())?;
+      buffer.clear();
+    }
+
+    if buf.len() >= self.capacity {
+      return self.inner.write_all(buf);
+    }
+
+    buffer.extend_from_slice(buf);
+    Ok(())
+  }
+
+  fn flush(&self) -> io::Result<()> {
+    let mut buffer = crate::util::unwrap_poison(self.buffer.lock())?;
+    if !buffer.is_empty() {
+      self.inner.write_all(buffer.as_slice())?;
+      buffer.clear();
+    }
+    self.inner.flush()
+  }
+
+  fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+    self.inner.set_write_timeout(dur)
+  }
+
+  fn get_write_timeout(&self) -> io::Result<Option<Duration>> {
+    self.inner.get_write_timeout()
+  }
+
+  fn new_ref_write(&self) -> Box<dyn Write + Send + Sync> {
+    self.inner.new_ref_write()
+  }
+
+  fn new_ref_stream_write(&self) -> Box<dyn ConnectionStreamWrite> {
+    self.inner.new_ref_stream_write()
+  }
+
+  fn as_stream_write(&self) -> &dyn ConnectionStreamWrite {
+    self
+  }
+}
+
+impl Write for SizedBufferStreamWrite<'_> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ConnectionStreamWrite::write(self, buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    ConnectionStreamWrite::flush(self)
+  }
+}
+
+/// ConnectionStreamWrite that counts the bytes written through it to another ConnectionStreamWrite.
+/// Bytes written through references obtained from `new_ref_write` or `new_ref_stream_write` are not counted.
+#[derive(Debug)]
+pub(crate) struct CountingStreamWrite<'a>(
+  &'a dyn ConnectionStreamWrite,
+  std::sync::atomic::AtomicU64,
+);
+
+impl<'a> CountingStreamWrite<'a> {
+  pub(crate) fn new(inner: &'a dyn ConnectionStreamWrite) -> Self {
+    Self(inner, std::sync::atomic::AtomicU64::new(0))
+  }
+
+  /// Returns the number of bytes written so far.
+  pub(crate) fn count(&self) -> u64 {
+    self.1.load(std::sync::atomic::Ordering::Relaxed)
+  }
+}
+
+impl ConnectionStreamWrite for CountingStreamWrite<'_> {
+  fn write(&self, buf: &[u8]) -> io::Result<usize> {
+    let written = self.0.write(buf)?;
+    self.1.fetch_add(written as u64, std::sync::atomic::Ordering::Relaxed);
+    Ok(written)
+  }
+
+  fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+    self.0.write_all(buf)?;
+    self.1.fetch_add(buf.len() as u64, std::sync::atomic::Ordering::Relaxed);
+    Ok(())
+  }
+
+  fn flush(&self) -> io::Result<()> {
+    self.0.flush()
+  }
+
+  fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+    self.0.set_write_timeout(dur)
+  }
+
+  fn get_write_timeout(&self) -> io::Result<Option<Duration>> {
+    self.0.get_write_timeout()
+  }
+
+  fn new_ref_write(&self) -> Box<dyn Write + Send + Sync> {
+    self.0.new_ref_write()
+  }
+
+  fn new_ref_stream_write(&self) -> Box<dyn ConnectionStreamWrite> {
+    self.0.new_ref_stream_write()
+  }
+
+  fn as_stream_write(&self) -> &dyn ConnectionStreamWrite {
+    self
+  }
+}
+
+impl Write for CountingStreamWrite<'_> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ConnectionStreamWrite::write(self, buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    ConnectionStreamWrite::flush(self)
   }
 }
@@ -6,25 +6,23 @@ use std::sync::Arc;
 use std::time::Duration;
 
 /// Represents the Tii app.
+#[derive(Default)]
 pub struct TiiBuilder {
-  routers: Vec<Box<dyn Router>>,
-  error_handler: ErrorHandler,
-  not_found_handler: NotFoundHandler,
-  max_head_buffer_size: usize,
-  connection_timeout: Option<Duration>,
-  read_timeout: Option<Duration>,
-  keep_alive_timeout: Option<Duration>,
-  request_body_io_timeout: Option<Duration>,
-  write_timeout: Option<Duration>,
+  config: ServerConfig,
 }
 
-use crate::default_functions::{default_error_handler, default_fallback_not_found_handler};
+use crate::default_functions::{
+  default_error_handler, default_fallback_not_found_handler, negotiated_error_body,
+};
 pub use crate::functional_traits::*;
+use crate::http::compression::ResponseCompression;
+use crate::http::mime::MimeType;
 use crate::http::request_context::RequestContext;
+use crate::http::RequestHead;
 use crate::tii_error::{TiiError, TiiResult, UserError};
 use crate::tii_router::Routeable;
 use crate::tii_router_builder::TiiRouterBuilder;
-use crate::tii_server::TiiServer;
+use crate::tii_server::{RequestCompleted, ServerConfig, TiiServer, UnexpectedBodyPolicy};
 
 /// Represents a function able to handle an error.
 /// The first parameter of type `Option<Request>` will be `Some` if the request could be parsed.
@@ -41,8 +39,7 @@ pub type NotRouteableHandler = fn(&mut RequestContext, &[Routeable]) -> TiiResul
 /// Fallback handler if no router handled the request.
 pub type NotFoundHandler = fn(&mut RequestContext) -> TiiResult<Response>;
 
-impl Default for TiiBuilder {
-  /// Initialises a new Tii app.
+impl Default for ServerConfig {
   fn default() -> Self {
     Self {
       routers: Vec::new(),
@@ -54,6 +51,29 @@ impl Default for TiiBuilder {
       read_timeout: None,
       request_body_io_timeout: None,
       write_timeout: None,
+      continue_threshold: 0,
+      max_body_chunks: None,
+      max_body_size: None,
+      trusted_proxies: Vec::new(),
+      merge_slashes: false,
+      version_not_supported_response: false,
+      unexpected_body_policy: UnexpectedBodyPolicy::default(),
+      panic_observer: None,
+      wire_filter: None,
+      size_stats: false,
+      reject_trace: false,
+      connect_not_implemented: true,
+      error_body_renderer: None,
+      clock: Arc::new(SystemClock),
+      latin1_header_values: false,
+      html_transformer: None,
+      request_deadline: None,
+      max_path_length: None,
+      response_compression: None,
+      write_buffer_size: None,
+      tcp_cork: false,
+      request_observer: None,
+      date_header: false,
     }
   }
 }
@@ -75,17 +95,7 @@ impl TiiBuilder {
 
   /// This method creates the HttpServer from the builder.
   pub fn build(self) -> TiiServer {
-    TiiServer::new(
-      self.routers,
-      self.error_handler,
-      self.not_found_handler,
-      self.max_head_buffer_size,
-      self.connection_timeout,
-      self.read_timeout,
-      self.keep_alive_timeout,
-      self.request_body_io_timeout,
-      self.write_timeout,
-    )
+    TiiServer::new(self.config)
   }
 
   /// This method is equivalent to calling `Arc::new(builder.build())`
@@ -100,7 +110,7 @@ impl TiiBuilder {
   /// This function will panic if the host is equal to `*`, since this is the default host.
   /// If you want to add a route to every host, simply add it directly to the main app.
   pub fn add_router<T: Router + 'static>(mut self, handler: T) -> Self {
-    self.routers.push(Box::new(handler));
+    self.config.routers.push(Box::new(handler));
     self
   }
 
@@ -114,13 +124,13 @@ impl TiiBuilder {
 
   /// Sets the error handler for the server.
   pub fn with_error_handler(mut self, handler: ErrorHandler) -> TiiResult<Self> {
-    self.error_handler = handler;
+    self.config.error_handler = handler;
     Ok(self)
   }
 
   /// Sets the not found handler for the server.
   pub fn with_not_found_handler(mut self, handler: NotFoundHandler) -> TiiResult<Self> {
-    self.not_found_handler = handler;
+    self.config.not_found_handler = handler;
     Ok(self)
   }
 
@@ -132,13 +142,15 @@ impl TiiBuilder {
   /// This value includes protocol overhead such as the ": " separator between header name/value pairs
   /// as well as the HTTP Method and protocol version and the CRLF trailer of each line.
   ///
+  /// The trailer header lines of a chunked request body must fit into this size as a whole.
+  ///
   /// Setting this value to below a minimum of 0x100/256 is prevented and will cause this fn to return Err.
   ///
   pub fn with_max_head_buffer_size(mut self, size: usize) -> TiiResult<Self> {
     if size < 0x100 {
       return Err(UserError::RequestHeadBufferTooSmall(size).into());
     }
-    self.max_head_buffer_size = size;
+    self.config.max_head_buffer_size = size;
     Ok(self)
   }
 
@@ -146,7 +158,7 @@ impl TiiBuilder {
   /// the amount of time before tii will close the connection if it sends no data to tii.
   /// If this value is not set then Tii will use the read_timeout for this purpose
   pub fn with_connection_timeout(mut self, timeout: Option<Duration>) -> TiiResult<Self> {
-    self.connection_timeout = timeout;
+    self.config.connection_timeout = timeout;
     Ok(self)
   }
 
@@ -155,7 +167,7 @@ impl TiiBuilder {
   /// Different timeouts might overwrite this value for certain aspects.
   /// Default is None = Infinite timeout.
   pub fn with_read_timeout(mut self, timeout: Option<Duration>) -> TiiResult<Self> {
-    self.read_timeout = timeout;
+    self.config.read_timeout = timeout;
     Ok(self)
   }
 
@@ -163,7 +175,7 @@ impl TiiBuilder {
   /// the amount of time before tii will time out a connection when writing data to the underlying connection at any point.
   /// Default is None = Infinite timeout.
   pub fn with_write_timeout(mut self, timeout: Option<Duration>) -> TiiResult<Self> {
-    self.write_timeout = timeout;
+    self.config.write_timeout = timeout;
     Ok(self)
   }
 
@@ -173,8 +185,10 @@ impl TiiBuilder {
   /// that keep-alives are not supported by setting "Connection: Close" on every HTTP1/1 response.
   ///
   /// Otherwise, tii will wait this amount of time for the client to send at least 1 byte of the next request.
+  /// Once the first byte has arrived the read timeout applies again, so a short keep alive timeout
+  /// closes idle connections quickly without cutting off clients that are slowly sending a request.
   pub fn with_keep_alive_timeout(mut self, timeout: Option<Duration>) -> TiiResult<Self> {
-    self.keep_alive_timeout = timeout;
+    self.config.keep_alive_timeout = timeout;
     Ok(self)
   }
 
@@ -182,10 +196,280 @@ impl TiiBuilder {
   /// body before returning the `TimedOut` error.
   /// A value of None will cause the read timeout to be used.
   pub fn with_request_body_timeout(mut self, timeout: Option<Duration>) -> TiiResult<Self> {
-    self.request_body_io_timeout = timeout;
+    self.config.request_body_io_timeout = timeout;
+    Ok(self)
+  }
+
+  /// Sets the total amount of time a client has to send a request, measured from the first byte of the
+  /// request head until the request body has been read. The per read timeouts only limit the time
+  /// between two reads, so a client that sends a few bytes just before each of them expires can stretch
+  /// a request indefinitely. Once the deadline is exceeded every further read fails with `TimedOut`
+  /// and the connection is closed. The deadline keeps running while filters and the endpoint run,
+  /// so an endpoint that does slow work before it reads the body leaves the client less time to send it.
+  /// Default is None = No deadline.
+  pub fn with_request_deadline(mut self, deadline: Option<Duration>) -> TiiResult<Self> {
+    self.config.request_deadline = deadline;
+    Ok(self)
+  }
+
+  /// Sets the threshold for sending the interim `100 Continue` response.
+  /// If a HTTP/1.1 request carries `Expect: 100-continue` then tii will only send the interim response
+  /// if the declared Content-Length exceeds this threshold. Chunked request bodies always get the interim response.
+  /// Small bodies are just read directly, saving a round trip for clients that do not wait for the interim response.
+  /// The interim response is sent when the body is read for the first time. If the request is answered
+  /// without reading the body then no interim response is sent and the connection is closed afterward.
+  /// Default is 0 = The interim response is sent for every request with a body.
+  pub fn with_continue_threshold(mut self, threshold: usize) -> TiiResult<Self> {
+    self.config.continue_threshold = threshold;
+    Ok(self)
+  }
+
+  /// Sets the maximum amount of chunks a chunked request body may consist of.
+  /// A client that sends a huge amount of tiny chunks causes a lot of parsing overhead for very little data.
+  /// Reading the body fails once the limit is exceeded, the default error handler responds with `413 Content Too Large`.
+  /// Default is None = Unlimited.
+  pub fn with_max_body_chunks(mut self, max_chunks: Option<u64>) -> TiiResult<Self> {
+    self.config.max_body_chunks = max_chunks;
+    Ok(self)
+  }
+
+  /// Sets the maximum size of a request body in bytes.
+  /// A request whose Content-Length exceeds the limit is answered with `413 Content Too Large` right after
+  /// the request head was parsed, without reading any of the body, and the connection is closed.
+  /// Chunked request bodies fail to read once their chunks exceed the limit, the default error handler
+  /// responds with `413 Content Too Large` in that case.
+  /// Endpoints can still enforce smaller limits, for example with `RequestContext::raw_body_with_limit`.
+  /// Single routes can override the limit with `TiiRouteBuilder::max_body_size`.
+  /// Default is None = Unlimited.
+  pub fn with_max_body_size(mut self, max_size: Option<u64>) -> TiiResult<Self> {
+    self.config.max_body_size = max_size;
+    Ok(self)
+  }
+
+  /// Sets the maximum length in bytes of the request path after percent decoding, the query string is not included.
+  /// This is independent of `with_max_head_buffer_size` which limits the raw request line.
+  /// A request whose decoded path is longer is answered with `414 URI Too Long` and the connection is closed.
+  /// Default is None = Unlimited.
+  pub fn with_max_path_length(mut self, max_length: Option<usize>) -> TiiResult<Self> {
+    self.config.max_path_length = max_length;
+    Ok(self)
+  }
+
+  /// Sets the clock used to measure timeouts that are not enforced by socket timeouts,
+  /// like the idle timeout of `WsBroadcastBuilder` or the buckets of `ConnectionRateLimiter`.
+  /// Tests can supply a clock that is advanced manually to trigger these timeouts without sleeping.
+  /// Default is `SystemClock`.
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> TiiResult<Self> {
+    self.config.clock = clock;
+    Ok(self)
+  }
+
+  /// Adds a `Date` header to every response that does not already have one.
+  /// The time is taken from `Clock::system_time` of the clock set with `with_clock`,
+  /// so tests can inject a fixed time to get deterministic responses.
+  /// Default is false.
+  pub fn with_date_header(mut self, date_header: bool) -> TiiResult<Self> {
+    self.config.date_header = date_header;
     Ok(self)
   }
 
+  /// Accept header lines that are not valid UTF-8 by decoding them as ISO-8859-1.
+  /// Some older clients send Latin-1 in header values, for example in `Content-Disposition` filenames.
+  /// Default is false, the connection is closed when such a request head is read.
+  pub fn with_latin1_header_values(mut self, accept: bool) -> TiiResult<Self> {
+    self.config.latin1_header_values = accept;
+    Ok(self)
+  }
+
+  /// Adds a trusted reverse proxy.
+  /// Forwarding headers like `X-Forwarded-Proto` and `X-Forwarded-Host` are only honored
+  /// if the peer of the connection is a trusted proxy.
+  ///
+  /// The value is compared to the peer address without the port.
+  /// For example "127.0.0.1" or "::1" for tcp connections or "unix" for connections made via a unix socket.
+  pub fn with_trusted_proxy(mut self, address: impl ToString) -> TiiResult<Self> {
+    self.config.trusted_proxies.push(address.to_string());
+    Ok(self)
+  }
+
+  /// Adds several trusted reverse proxies, see `with_trusted_proxy`.
+  /// Requests from a trusted proxy also expose the address of the client the proxy forwarded,
+  /// see `RequestContext::client_address`.
+  pub fn with_trusted_proxies(
+    mut self,
+    addresses: impl IntoIterator<Item = impl ToString>,
+  ) -> TiiResult<Self> {
+    self.config.trusted_proxies.extend(addresses.into_iter().map(|address| address.to_string()));
+    Ok(self)
+  }
+
+  /// Enables or disables collapsing consecutive slashes in the request path before routing.
+  /// If enabled a request to `//foo///bar` is routed as `/foo/bar`.
+  /// Endpoints, including the path traversal guard of `serve_dir`, only ever see the merged path.
+  /// Default is false = The path is routed exactly as sent by the client.
+  pub fn with_merge_slashes(mut self, merge_slashes: bool) -> TiiResult<Self> {
+    self.config.merge_slashes = merge_slashes;
+    Ok(self)
+  }
+
+  /// Enables or disables responding with `505 HTTP Version Not Supported` if the request line
+  /// names an HTTP version tii does not support, for example `GET / HTTP/2.5`.
+  /// The response lists the supported versions and the connection is closed afterward.
+  /// Default is false = The connection is closed without a response.
+  pub fn with_version_not_supported_response(mut self, respond: bool) -> TiiResult<Self> {
+    self.config.version_not_supported_response = respond;
+    Ok(self)
+  }
+
+  /// Sets what happens to a request body sent with `GET`, `HEAD`, `DELETE` or `TRACE`.
+  /// See `UnexpectedBodyPolicy` for the available policies.
+  /// Default is `UnexpectedBodyPolicy::Discard` = The body is drained and endpoints never see it.
+  pub fn with_unexpected_body_policy(mut self, policy: UnexpectedBodyPolicy) -> TiiResult<Self> {
+    self.config.unexpected_body_policy = policy;
+    Ok(self)
+  }
+
+  /// Sets an observer that is called after the response to a request has been written completely,
+  /// with the method, path, status and duration of the request, see `RequestCompleted`.
+  /// This is intended for access logs and latency metrics without instrumenting every endpoint.
+  /// The observer runs on the thread of the connection before the next request is read, so it should be cheap.
+  /// Requests that switch to the web socket protocol are not reported.
+  pub fn with_request_observer<T: Fn(&RequestCompleted) + Send + Sync + 'static>(
+    mut self,
+    observer: T,
+  ) -> TiiResult<Self> {
+    self.config.request_observer = Some(Box::new(observer));
+    Ok(self)
+  }
+
+  /// Sets an observer that is called with the panic message whenever handling a connection panics,
+  /// for example because an endpoint or filter panicked.
+  /// This is intended for alerting or metrics and is called in addition to any logging.
+  /// The panic is resumed after the observer returns, so connectors still see and log it.
+  /// If the observer panics itself then that panic is swallowed and logged.
+  pub fn with_panic_observer<T: Fn(&str) + Send + Sync + 'static>(
+    mut self,
+    observer: T,
+  ) -> TiiResult<Self> {
+    self.config.panic_observer = Some(Box::new(observer));
+    Ok(self)
+  }
+
+  /// Sets a late hook that sees the fully serialized response (head and body) just before it is written to the socket.
+  /// The filter may modify the bytes in any way, for example to add a header that is computed from the final body.
+  /// The filter is not called for websocket handshakes.
+  ///
+  /// # Performance
+  /// With a wire filter set every response is serialized into memory before it is written,
+  /// this includes streamed and chunked bodies. Only use this if you really need the final bytes.
+  pub fn with_wire_filter<T: Fn(&RequestHead, &mut Vec<u8>) + Send + Sync + 'static>(
+    mut self,
+    filter: T,
+  ) -> TiiResult<Self> {
+    self.config.wire_filter = Some(Box::new(filter));
+    Ok(self)
+  }
+
+  /// Enables aggregating the sizes of request heads, request bodies and responses into histograms.
+  /// The histograms are available from `TiiServer::size_stats` and can be scraped periodically,
+  /// for example to tune `with_max_head_buffer_size` or to notice unusually large uploads.
+  /// Recording is cheap but not free, it is done with a few atomic increments per request.
+  /// Default is false = no sizes are recorded.
+  pub fn with_size_stats(mut self, enabled: bool) -> TiiResult<Self> {
+    self.config.size_stats = enabled;
+    Ok(self)
+  }
+
+  /// If true every `TRACE` request is answered with `405 Method Not Allowed` without being routed,
+  /// even if a route registers `TRACE`. This guards against cross-site tracing in apps whose routes
+  /// are registered by code you do not control.
+  /// Default is false = `TRACE` is only served by routes that register it explicitly, `route_any` does not.
+  /// All other `TRACE` requests are answered with `405 Method Not Allowed` or `404 Not Found` by the router.
+  pub fn with_trace_rejected(mut self, reject: bool) -> TiiResult<Self> {
+    self.config.reject_trace = reject;
+    Ok(self)
+  }
+
+  /// If true every `CONNECT` request is answered with `501 Not Implemented` without being routed.
+  /// Set this to false to route `CONNECT` requests, for example in a proxy that establishes tunnels.
+  /// The path of a `CONNECT` request is its authority, for example `example.com:443`.
+  /// Routes only match paths starting with `/`, so a pre routing filter has to rewrite the path first.
+  /// Default is true = tunneling is not supported.
+  pub fn with_connect_not_implemented(mut self, not_implemented: bool) -> TiiResult<Self> {
+    self.config.connect_not_implemented = not_implemented;
+    Ok(self)
+  }
+
+  /// Sets a hook that fills in the body of error responses (status 400 and above) which have no body yet,
+  /// regardless of whether they were produced by an endpoint, an error handler or a fallback handler.
+  /// The hook typically looks at the `Accept` header of the request to pick the format of the body.
+  /// It is not called for `HEAD` requests, nor for the responses tii writes before routing
+  /// like `417 Expectation Failed`.
+  /// Default is None = Error responses are sent as they are.
+  pub fn with_error_body_renderer<T: Fn(&RequestHead, &mut Response) + Send + Sync + 'static>(
+    mut self,
+    renderer: T,
+  ) -> TiiResult<Self> {
+    self.config.error_body_renderer = Some(Box::new(renderer));
+    Ok(self)
+  }
+
+  /// Compresses response bodies held in memory that are at least `threshold` bytes large and have one of the
+  /// given content types, if the client accepts a supported content coding in its `Accept-Encoding` header.
+  /// The codings are enabled by crate features, currently `br` with the `brotli` feature.
+  /// Without any of them responses are never compressed.
+  ///
+  /// Compressed responses carry `Content-Encoding` and every response that qualifies carries `Vary: Accept-Encoding`.
+  /// Streamed and file bodies as well as bodies that already have a `Content-Encoding` are sent as they are.
+  /// Default is None = No compression.
+  pub fn with_response_compression(
+    mut self,
+    threshold: usize,
+    content_types: impl IntoIterator<Item = MimeType>,
+  ) -> TiiResult<Self> {
+    self.config.response_compression =
+      Some(ResponseCompression { threshold, content_types: content_types.into_iter().collect() });
+    Ok(self)
+  }
+
+  /// Sets the size of the buffer responses are serialized into before they are written to the connection.
+  /// Larger buffers need fewer writes for large responses, writes that do not fit into the buffer go through directly.
+  /// Default is None = Responses are written to the buffer of the connection itself.
+  pub fn with_write_buffer_size(mut self, size: Option<usize>) -> TiiResult<Self> {
+    self.config.write_buffer_size = size;
+    Ok(self)
+  }
+
+  /// Corks tcp connections while a response is written and uncorks them afterward,
+  /// so the kernel coalesces the response head and body into as few segments as possible.
+  /// This is `TCP_CORK`, it requires linux and the `extras` feature and does nothing otherwise.
+  /// Default is false.
+  pub fn with_tcp_cork(mut self, cork: bool) -> TiiResult<Self> {
+    self.config.tcp_cork = cork;
+    Ok(self)
+  }
+
+  /// Sets a hook that post-processes the body of every `text/html` response before it is written,
+  /// for example to inject a CSP nonce into script tags or to rewrite asset URLs for cache busting.
+  /// Only bodies held in memory are transformed, streamed and file bodies as well as bodies with a
+  /// `Content-Encoding` are sent as they are. The Content-Length is computed after the transformation.
+  /// Default is None = Bodies are sent as they are.
+  pub fn with_html_transformer<T: Fn(&mut String, &RequestContext) + Send + Sync + 'static>(
+    mut self,
+    transformer: T,
+  ) -> TiiResult<Self> {
+    self.config.html_transformer = Some(Box::new(transformer));
+    Ok(self)
+  }
+
+  /// Fills in the body of error responses without a body according to the `Accept` header of the request.
+  /// Clients preferring `text/html` receive a small HTML page, clients preferring `application/json` receive
+  /// `{"error":"Not Found","status":404}`. Other clients receive the response as it is.
+  /// This is a shorthand for `with_error_body_renderer` with the built-in renderer.
+  pub fn with_negotiated_error_bodies(self) -> TiiResult<Self> {
+    self.with_error_body_renderer(negotiated_error_body)
+  }
+
   /// Helper fn to make builder code look a bit cleaner
   pub fn ok(self) -> TiiResult<Self> {
     Ok(self)
@@ -6,6 +6,7 @@ use crate::http::headers::HeaderName;
 use crate::http::method::Method;
 use crate::http::request::HttpVersion;
 use crate::http::Response;
+use crate::websocket::message::CloseCode;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
@@ -26,6 +27,14 @@ impl From<TiiError> for TiiResult<Response> {
   }
 }
 
+/// Errors that can occur while parsing the head of a request.
+///
+/// Every variant maps to an `io::ErrorKind` via `kind()`:
+/// - `UnexpectedEof` if the connection ended in the middle of the request head. (`HeadTruncated`)
+/// - `InvalidData` for everything else, i.e. malformed or unsupported syntax.
+///
+/// The `Display` output of each variant is stable and equals its name,
+/// followed by the offending value in parentheses for variants that carry one.
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
 #[non_exhaustive]
 pub enum RequestHeadParsingError {
@@ -46,19 +55,36 @@ pub enum RequestHeadParsingError {
   TransferEncodingNotSupported(String),
   InvalidContentLength(String),
   InvalidQueryString(String),
+  /// The connection ended before the request head was complete.
+  HeadTruncated,
+  /// The percent decoded path exceeds the configured maximum length. Contains the decoded length.
+  PathTooLong(usize),
   /// An error occurred during the WebSocket handshake.
   MissingSecWebSocketKeyHeader,
-  /// The web socket frame opcode was invalid.
-  InvalidWebSocketOpcode,
-  UnexpectedWebSocketOpcode,
-  WebSocketClosedDuringPendingMessage,
-  WebSocketTextMessageIsNotUtf8(Vec<u8>),
+}
+
+impl RequestHeadParsingError {
+  /// The io::ErrorKind this error maps to.
+  pub fn kind(&self) -> ErrorKind {
+    match self {
+      RequestHeadParsingError::HeadTruncated => ErrorKind::UnexpectedEof,
+      _ => ErrorKind::InvalidData,
+    }
+  }
 }
 
 impl Display for RequestHeadParsingError {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    //TODO make this not shit
-    Debug::fmt(self, f)
+    match self {
+      RequestHeadParsingError::StatusLineTooLong(data) => {
+        write!(f, "StatusLineTooLong({} bytes)", data.len())
+      }
+      RequestHeadParsingError::HeaderLineTooLong(data) => {
+        write!(f, "HeaderLineTooLong({} bytes)", data.len())
+      }
+      // The Debug output of all other variants is stable and equal to the documented format.
+      other => Debug::fmt(other, f),
+    }
   }
 }
 impl Error for RequestHeadParsingError {}
@@ -74,6 +100,8 @@ pub enum UserError {
   ImmutableRequestHeaderRemoved(HeaderName),
   ImmutableResponseHeaderModified(HeaderName),
   RequestHeadBufferTooSmall(usize),
+  InvalidWarningCode(u16),
+  CorsCredentialsWithAnyOrigin,
 }
 
 impl Display for UserError {
@@ -90,6 +118,7 @@ pub enum InvalidPathError {
   MorePartsAfterWildcard(String),
   RegexSyntaxError(String, String, String),
   RegexTooBig(String, String, usize),
+  OptionalVariableNotLast(String),
 }
 impl Display for InvalidPathError {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -99,12 +128,185 @@ impl Display for InvalidPathError {
 }
 impl Error for InvalidPathError {}
 
+/// Errors that can occur when parsing a request body into a typed value.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BodyParsingError {
+  /// The request has no body.
+  MissingBody,
+  /// The content type of the request is not supported for the requested type. Contains the content type if any.
+  UnsupportedMediaType(Option<String>),
+  /// The charset of the request is not supported.
+  UnsupportedCharset(String),
+  /// The body could not be deserialized. Contains the message of the deserializer.
+  Malformed(String),
+  /// The chunked body consists of more chunks than permitted. Contains the permitted amount.
+  TooManyChunks(u64),
+  /// The body is larger than permitted. Contains the permitted amount of bytes.
+  TooLarge(u64),
+  /// The trailer headers of the chunked body are larger than permitted. Contains the permitted amount of bytes.
+  TrailerTooLarge(usize),
+  /// The size line of a chunk of the chunked body is not a hexadecimal number. Contains the size line.
+  /// This is a body error and not a `RequestHeadParsingError` because the chunk framing is only read
+  /// while the endpoint reads the body, long after the head was parsed and routed.
+  /// It surfaces as an `InvalidData` io error from the read of the body like the other chunked body errors.
+  InvalidChunkSize(String),
+}
+
+impl Display for BodyParsingError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BodyParsingError::MissingBody => f.write_str("request has no body"),
+      BodyParsingError::UnsupportedMediaType(Some(mime)) => {
+        write!(f, "unsupported content type {mime}")
+      }
+      BodyParsingError::UnsupportedMediaType(None) => f.write_str("request has no content type"),
+      BodyParsingError::UnsupportedCharset(charset) => write!(f, "unsupported charset {charset}"),
+      BodyParsingError::Malformed(msg) => write!(f, "malformed request body: {msg}"),
+      BodyParsingError::TooManyChunks(max) => {
+        write!(f, "chunked request body exceeds the maximum of {max} chunks")
+      }
+      BodyParsingError::TooLarge(max) => {
+        write!(f, "request body exceeds the maximum of {max} bytes")
+      }
+      BodyParsingError::TrailerTooLarge(max) => {
+        write!(f, "request trailer exceeds the maximum of {max} bytes")
+      }
+      BodyParsingError::InvalidChunkSize(line) => write!(f, "invalid chunk size {line:?}"),
+    }
+  }
+}
+
+impl Error for BodyParsingError {}
+
+/// Errors that can occur when parsing a path parameter into a typed value.
+/// The default error handler responds to these with `400 Bad Request`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum PathParamError {
+  /// The route has no path parameter with this name. Contains the name.
+  Missing(String),
+  /// The value could not be parsed. Contains the name, the value and the message of the parser.
+  Malformed(String, String, String),
+}
+
+impl Display for PathParamError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PathParamError::Missing(name) => write!(f, "missing path parameter {name}"),
+      PathParamError::Malformed(name, value, msg) => {
+        write!(f, "malformed path parameter {name}={value:?}: {msg}")
+      }
+    }
+  }
+}
+
+impl Error for PathParamError {}
+
+/// Errors that can occur when parsing a `Range` header.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum RangeError {
+  /// The range is not in the `bytes` unit. Contains the unit.
+  UnsupportedUnit(String),
+  /// The header is not a valid range specifier. Contains the header value.
+  Malformed(String),
+  /// None of the ranges overlap the resource. The response should be `416 Range Not Satisfiable`.
+  Unsatisfiable,
+}
+
+impl Display for RangeError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RangeError::UnsupportedUnit(unit) => write!(f, "unsupported range unit {unit}"),
+      RangeError::Malformed(value) => write!(f, "malformed range {value}"),
+      RangeError::Unsatisfiable => f.write_str("range not satisfiable"),
+    }
+  }
+}
+
+impl Error for RangeError {}
+
+/// Errors that can occur on an established web socket connection.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WebsocketError {
+  /// The peer violated the web socket protocol. The reason describes the violation.
+  ProtocolViolation(String),
+  /// The peer sent a message that exceeds the maximum permitted message size.
+  MessageTooBig(u64),
+  /// The peer sent a text message (or close reason) that is not valid utf-8.
+  InvalidUtf8(Vec<u8>),
+  /// The underlying connection failed.
+  Io(io::Error),
+  /// The web socket is closed. Code and reason are those of the close frame sent by the peer if any.
+  Closed {
+    /// The status code of the close frame.
+    code: Option<u16>,
+    /// The reason of the close frame, may be empty.
+    reason: String,
+  },
+}
+
+impl WebsocketError {
+  /// Returns the status code of the close frame sent by the peer, if the web socket was closed with one.
+  pub fn close_code(&self) -> Option<CloseCode> {
+    match self {
+      WebsocketError::Closed { code, .. } => code.map(CloseCode::from),
+      _ => None,
+    }
+  }
+
+  pub fn kind(&self) -> ErrorKind {
+    match self {
+      WebsocketError::Io(io) => io.kind(),
+      WebsocketError::Closed { .. } => ErrorKind::ConnectionReset,
+      _ => ErrorKind::InvalidData,
+    }
+  }
+}
+
+impl Display for WebsocketError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      WebsocketError::ProtocolViolation(reason) => {
+        f.write_fmt(format_args!("web socket protocol violation: {}", reason))
+      }
+      WebsocketError::MessageTooBig(size) => {
+        f.write_fmt(format_args!("web socket message of at least {} bytes is too big", size))
+      }
+      WebsocketError::InvalidUtf8(_) => f.write_str("web socket text is not valid utf-8"),
+      WebsocketError::Io(err) => Display::fmt(err, f),
+      WebsocketError::Closed { code: Some(code), reason } => {
+        f.write_fmt(format_args!("web socket closed with code {} reason '{}'", code, reason))
+      }
+      WebsocketError::Closed { code: None, .. } => f.write_str("web socket closed"),
+    }
+  }
+}
+
+impl Error for WebsocketError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      WebsocketError::Io(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for WebsocketError {
+  fn from(value: io::Error) -> Self {
+    WebsocketError::Io(value)
+  }
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum TiiError {
   RequestHeadParsing(RequestHeadParsingError),
   UserError(UserError),
   InvalidPathError(InvalidPathError),
+  WebsocketError(WebsocketError),
   IO(io::Error),
   Other(Box<dyn Error + Send + Sync>),
 }
@@ -121,7 +323,8 @@ impl TiiError {
   pub fn kind(&self) -> ErrorKind {
     match self {
       TiiError::IO(io) => io.kind(),
-      TiiError::RequestHeadParsing(_) => ErrorKind::InvalidData,
+      TiiError::RequestHeadParsing(err) => err.kind(),
+      TiiError::WebsocketError(err) => err.kind(),
       _ => ErrorKind::Other,
     }
   }
@@ -131,6 +334,7 @@ impl TiiError {
       TiiError::RequestHeadParsing(err) => (err as &mut dyn Error).downcast_mut::<T>(),
       TiiError::UserError(err) => (err as &mut dyn Error).downcast_mut::<T>(),
       TiiError::InvalidPathError(err) => (err as &mut dyn Error).downcast_mut::<T>(),
+      TiiError::WebsocketError(err) => (err as &mut dyn Error).downcast_mut::<T>(),
       TiiError::Other(other) => other.downcast_mut::<T>(),
     }
   }
@@ -141,6 +345,7 @@ impl TiiError {
       TiiError::RequestHeadParsing(err) => (err as &dyn Error).downcast_ref::<T>(),
       TiiError::UserError(err) => (err as &dyn Error).downcast_ref::<T>(),
       TiiError::InvalidPathError(err) => (err as &dyn Error).downcast_ref::<T>(),
+      TiiError::WebsocketError(err) => (err as &dyn Error).downcast_ref::<T>(),
       TiiError::Other(other) => other.downcast_ref::<T>(),
     }
   }
@@ -150,6 +355,7 @@ impl TiiError {
       TiiError::RequestHeadParsing(err) => Box::new(err) as Box<dyn Error + Send + Sync>,
       TiiError::UserError(err) => Box::new(err) as Box<dyn Error + Send + Sync>,
       TiiError::InvalidPathError(err) => Box::new(err) as Box<dyn Error + Send + Sync>,
+      TiiError::WebsocketError(err) => Box::new(err) as Box<dyn Error + Send + Sync>,
       TiiError::Other(other) => other,
     }
   }
@@ -162,6 +368,7 @@ impl Display for TiiError {
       TiiError::RequestHeadParsing(err) => Display::fmt(err, f),
       TiiError::UserError(err) => Display::fmt(err, f),
       TiiError::InvalidPathError(err) => Display::fmt(err, f),
+      TiiError::WebsocketError(err) => Display::fmt(err, f),
       TiiError::Other(err) => Display::fmt(err, f),
     }
   }
@@ -181,6 +388,10 @@ where
       Ok(err) => return TiiError::RequestHeadParsing(*err),
       Err(err) => err,
     };
+    dyn_box = match dyn_box.downcast::<WebsocketError>() {
+      Ok(err) => return TiiError::WebsocketError(*err),
+      Err(err) => err,
+    };
 
     TiiError::Other(dyn_box)
   }
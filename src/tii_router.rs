@@ -4,16 +4,20 @@ use crate::functional_traits::{
   HttpEndpoint, RequestFilter, ResponseFilter, Router, RouterFilter,
   RouterWebSocketServingResponse, WebsocketEndpoint,
 };
+use crate::http::cors::Cors;
 use crate::http::headers::HeaderName;
 use crate::http::method::Method;
-use crate::http::mime::{AcceptMimeType, QValue};
+use crate::http::mime::{AcceptMimeType, MimeType, QValue};
 use crate::http::request::HttpVersion;
 use crate::http::request_context::RequestContext;
+use crate::http::response::{ResponseKind, ResponseMeta};
 use crate::http::{Response, StatusCode};
 use crate::stream::ConnectionStream;
 use crate::tii_builder::{ErrorHandler, NotRouteableHandler};
 use crate::tii_error::{InvalidPathError, RequestHeadParsingError, TiiError, TiiResult};
 use crate::util::unwrap_some;
+use crate::websocket::message::CloseCode;
+use crate::websocket::metrics::WebsocketMetrics;
 use crate::{trace_log, util};
 use base64::Engine;
 use regex::{Error, Regex};
@@ -21,12 +25,15 @@ use sha1::{Digest, Sha1};
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 enum PathPart {
   Literal(String),
   Variable(String),
+  OptionalVariable(String),
   Wildcard,
   RegexVariable(String, Regex),
   RegexTailVariable(String, Regex),
@@ -70,6 +77,14 @@ impl PathPart {
 
       if part.starts_with("{") && part.ends_with("}") {
         let variable = &part[1..part.len() - 1];
+        if let Some(name) = variable.strip_suffix("?") {
+          if !path.is_empty() && path != "/" {
+            return Err(InvalidPathError::OptionalVariableNotLast(full_path.to_string()).into());
+          }
+          parts.push(PathPart::OptionalVariable(name.to_string()));
+          return Ok(parts);
+        }
+
         if let Some((name, regex)) = variable.split_once(":") {
           let reg = Regex::new(regex).map_err(|e| match e {
             Error::Syntax(syntax) => {
@@ -101,6 +116,32 @@ impl PathPart {
     }
   }
 
+  /// How specific the part is when several routes match a path, higher is more specific.
+  const fn specificity(&self) -> u8 {
+    match self {
+      PathPart::Literal(_) => 5,
+      PathPart::RegexVariable(_, _) => 4,
+      PathPart::Variable(_) => 3,
+      PathPart::RegexTailVariable(_, _) => 2,
+      PathPart::OptionalVariable(_) => 1,
+      PathPart::Wildcard => 0,
+    }
+  }
+
+  /// Specificity of a whole path, compared part by part.
+  /// Paths that can not match further parts end with a marker that beats every part,
+  /// so `/api` is more specific than `/api/*` for the request `/api`.
+  fn path_specificity(parts: &[PathPart]) -> Vec<u8> {
+    let mut specificity = parts.iter().map(PathPart::specificity).collect::<Vec<_>>();
+    if !parts
+      .last()
+      .is_some_and(|part| part.is_tail() || matches!(part, PathPart::OptionalVariable(_)))
+    {
+      specificity.push(u8::MAX);
+    }
+    specificity
+  }
+
   const fn is_tail(&self) -> bool {
     matches!(self, PathPart::Wildcard | PathPart::RegexTailVariable(_, _))
   }
@@ -120,6 +161,18 @@ impl PathPart {
         unwrap_some(variables.as_mut()).insert(var_name.to_string(), part.to_string());
         true
       }
+      PathPart::OptionalVariable(var_name) => {
+        if part.is_empty() {
+          return true;
+        }
+
+        if variables.is_none() {
+          variables.replace(HashMap::new());
+        }
+
+        unwrap_some(variables.as_mut()).insert(var_name.to_string(), part.to_string());
+        true
+      }
       PathPart::Wildcard => true,
       PathPart::RegexVariable(var_name, regex) => {
         if regex.is_match(part) {
@@ -149,12 +202,23 @@ impl PathPart {
 
 #[derive(Debug, Clone)]
 /// Encapsulates a route and its handler.
+///
+/// If several routes match a request, the most specific path wins regardless of the order the routes were added in.
+/// Paths are compared part by part, from most to least specific:
+/// literal, `{name:regex}`, `{name}`, trailing `{name:regex}`, `{name?}` and `*`.
+/// A path that ends is more specific than one that continues with a trailing part,
+/// for example `/api/users` beats `/api/{name}` which beats `/api/*` which beats `/*`.
+/// Routes with equally specific paths are picked by the quality of the `Accept` header they satisfy,
+/// ties are broken by insertion order.
 pub struct Routeable {
   /// The route that this handler will match.
   path: String,
 
   parts: Vec<PathPart>,
 
+  /// See `PathPart::path_specificity`.
+  specificity: Vec<u8>,
+
   /// The method this route will handle
   method: Method,
 
@@ -162,14 +226,21 @@ pub struct Routeable {
   /// EMPTY SET means this route does not expect a request body.
   consumes: HashSet<AcceptMimeType>,
 
-  /// The mime types this route can produce
-  /// EMPTY SET means this route will produce a matching body type.
-  produces: HashSet<AcceptMimeType>,
+  /// The mime types this route can produce, in the order they were declared.
+  /// The first one accepted by the client wins content negotiation.
+  /// EMPTY means this route will produce a matching body type.
+  produces: Vec<AcceptMimeType>,
 }
 
 pub(crate) struct HttpRoute {
   pub(crate) routeable: Routeable,
 
+  /// Filters that only run for this route, after the routing filters of the router.
+  pub(crate) filters: Vec<Arc<dyn RequestFilter>>,
+
+  /// Overrides the maximum request body size of the server for this route.
+  pub(crate) max_body_size: Option<u64>,
+
   /// The handler to run when the route is matched.
   pub(crate) handler: Box<dyn HttpEndpoint>,
 }
@@ -177,6 +248,9 @@ pub(crate) struct HttpRoute {
 pub(crate) struct WebSocketRoute {
   pub(crate) routeable: Routeable,
 
+  /// Filters that only run for this route, after the routing filters of the router.
+  pub(crate) filters: Vec<Arc<dyn RequestFilter>>,
+
   /// The handler to run when the route is matched.
   pub(crate) handler: Box<dyn WebsocketEndpoint>,
 }
@@ -186,11 +260,13 @@ impl HttpRoute {
     path: impl ToString,
     method: impl Into<Method>,
     consumes: HashSet<AcceptMimeType>,
-    produces: HashSet<AcceptMimeType>,
+    produces: Vec<AcceptMimeType>,
     route: impl HttpEndpoint + 'static,
   ) -> TiiResult<Self> {
     Ok(HttpRoute {
       routeable: Routeable::new(path, method, consumes, produces)?,
+      filters: Vec::new(),
+      max_body_size: None,
       handler: Box::new(route) as Box<dyn HttpEndpoint>,
     })
   }
@@ -201,11 +277,12 @@ impl WebSocketRoute {
     path: impl ToString,
     method: impl Into<Method>,
     consumes: HashSet<AcceptMimeType>,
-    produces: HashSet<AcceptMimeType>,
+    produces: Vec<AcceptMimeType>,
     route: impl WebsocketEndpoint + 'static,
   ) -> TiiResult<Self> {
     Ok(WebSocketRoute {
       routeable: Routeable::new(path, method, consumes, produces)?,
+      filters: Vec::new(),
       handler: Box::new(route) as Box<dyn WebsocketEndpoint>,
     })
   }
@@ -280,11 +357,13 @@ impl Routeable {
     path: impl ToString,
     method: impl Into<Method>,
     consumes: HashSet<AcceptMimeType>,
-    produces: HashSet<AcceptMimeType>,
+    produces: Vec<AcceptMimeType>,
   ) -> TiiResult<Routeable> {
     let path = path.to_string();
+    let parts = PathPart::parse(path.as_str())?;
     Ok(Routeable {
-      parts: PathPart::parse(path.as_str())?,
+      specificity: PathPart::path_specificity(&parts),
+      parts,
       path,
       method: method.into(),
       consumes,
@@ -307,11 +386,42 @@ impl Routeable {
     &self.consumes
   }
 
-  /// The mime types this route can produce
-  pub fn produces(&self) -> &HashSet<AcceptMimeType> {
+  /// The mime types this route can produce, in the order they were declared
+  pub fn produces(&self) -> &[AcceptMimeType] {
     &self.produces
   }
 
+  /// Returns the content type this route will respond with for the request,
+  /// which is the produced type that permits the most preferred entry of the `Accept` header.
+  /// Yields None if the route produces nothing or neither side names a specific type.
+  pub fn negotiate_content_type(&self, route: &RequestContext) -> Option<MimeType> {
+    let mut best: Option<(QValue, Option<MimeType>)> = None;
+    for accept in route.request_head().get_accept() {
+      let qvalue = accept.qvalue();
+      if best.as_ref().map(|(best_q, _)| best_q >= &qvalue).unwrap_or_default() {
+        continue;
+      }
+
+      for mime in &self.produces {
+        if !accept.get_type().permits(mime) {
+          continue;
+        }
+
+        let negotiated = match (mime, accept.get_type()) {
+          (AcceptMimeType::Specific(mime), _) | (_, AcceptMimeType::Specific(mime)) => {
+            Some(mime.clone())
+          }
+          _ => None,
+        };
+
+        best = Some((qvalue, negotiated));
+        break;
+      }
+    }
+
+    best.and_then(|(_, negotiated)| negotiated)
+  }
+
   fn matches_path(
     &self,
     route: &RequestContext,
@@ -480,15 +590,45 @@ pub struct TiiRouter {
   /// Called when no route has been found in the router.
   not_found_handler: NotRouteableHandler,
 
+  /// Called when no WebSocket route has been found in the router for an upgrade request.
+  websocket_not_found_handler: NotRouteableHandler,
+
   /// Called when no acceptable route has been found
   not_acceptable_handler: NotRouteableHandler,
   /// Called when no route with a handled method has been found.
   method_not_allowed_handler: NotRouteableHandler,
+  /// Called when no route of the router handles the method of the request, regardless of path.
+  method_not_implemented_handler: NotRouteableHandler,
   /// Called when no route with a given media type has been found.
   unsupported_media_type_handler: NotRouteableHandler,
 
   /// Called when an error in any of the above occurs.
   error_handler: ErrorHandler,
+
+  /// Upper bound of concurrently served WebSocket connections, None = unbounded.
+  max_websocket_connections: Option<usize>,
+
+  /// Upper bound of the size of a received WebSocket message, None = the default of the receiver.
+  max_websocket_message_size: Option<u64>,
+
+  /// Amount of WebSocket connections currently being served.
+  websocket_connections: Arc<AtomicUsize>,
+
+  /// Counters of all WebSocket connections served by this router.
+  websocket_metrics: Arc<WebsocketMetrics>,
+
+  /// Cross-origin requests allowed to the routes of this router, None = no CORS headers.
+  cors: Option<Cors>,
+}
+
+/// Held while a WebSocket endpoint serves a connection, dropping it frees up capacity.
+struct WebsocketConnectionPermit(Arc<AtomicUsize>, Arc<WebsocketMetrics>);
+
+impl Drop for WebsocketConnectionPermit {
+  fn drop(&mut self) {
+    self.0.fetch_sub(1, SeqCst);
+    self.1.connection_closed();
+  }
 }
 
 impl Debug for TiiRouter {
@@ -503,6 +643,40 @@ impl Debug for TiiRouter {
   }
 }
 
+/// Picks the route for the request, see `Routeable` for the precedence of matching routes.
+/// If no route matches, the decision is the mismatch that got closest to matching.
+fn best_route<'a, T>(
+  routes: &'a [T],
+  routeable: impl Fn(&T) -> &Routeable,
+  request: &RequestContext,
+) -> (RoutingDecision, Option<&'a T>) {
+  let mut best_decision = RoutingDecision::PathMismatch;
+  let mut best_route: Option<&'a T> = None;
+
+  for route in routes {
+    let decision = routeable(route).matches(request);
+    let better = match (&decision, &best_decision, best_route) {
+      (RoutingDecision::Match(qv, _), RoutingDecision::Match(best_qv, _), Some(best)) => {
+        let specificity = &routeable(route).specificity;
+        let best_specificity = &routeable(best).specificity;
+        specificity > best_specificity || (specificity == best_specificity && qv > best_qv)
+      }
+      _ => decision > best_decision,
+    };
+
+    if !better {
+      continue;
+    }
+
+    if matches!(decision, RoutingDecision::Match(_, _)) {
+      best_route = Some(route);
+    }
+    best_decision = decision;
+  }
+
+  (best_decision, best_route)
+}
+
 /// Performs the WebSocket handshake.
 fn websocket_handshake(request: &RequestContext) -> TiiResult<Response> {
   const HANDSHAKE_KEY_CONSTANT: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
@@ -521,11 +695,22 @@ fn websocket_handshake(request: &RequestContext) -> TiiResult<Response> {
   //let sec_websocket_accept = sha1.encode();
 
   // Serialise the handshake response
-  let response = Response::new(StatusCode::SwitchingProtocols)
+  #[cfg_attr(not(feature = "ws_deflate"), expect(unused_mut))]
+  let mut response = Response::new(StatusCode::SwitchingProtocols)
     .with_header(HeaderName::Upgrade, "websocket")?
     .with_header(HeaderName::Connection, "Upgrade")?
     .with_header("Sec-WebSocket-Accept", sec_websocket_accept)?;
 
+  #[cfg(feature = "ws_deflate")]
+  if request
+    .request_head()
+    .get_header("Sec-WebSocket-Extensions")
+    .is_some_and(crate::websocket::deflate::accepts_offer)
+  {
+    response
+      .add_header("Sec-WebSocket-Extensions", crate::websocket::deflate::EXTENSION_RESPONSE)?;
+  }
+
   // Oddly enough I think you can establish a WS connection with a POST request that has data.
   // This will consume that data if it has not already been used by a filter.
   // Some beta versions of Web Sockets used the request body to convey the Sec-WebSocket-Key...
@@ -533,21 +718,125 @@ fn websocket_handshake(request: &RequestContext) -> TiiResult<Response> {
   Ok(response)
 }
 
+/// Turns a 200 response to a GET/HEAD request into a 304 if the validators (ETag/Last-Modified)
+/// set by the endpoint match the preconditions of the request.
+fn conditional_get(request: &RequestContext, mut response: Response) -> Response {
+  if response.status_code != StatusCode::OK {
+    return response;
+  }
+
+  let head = request.request_head();
+  if !matches!(head.method(), Method::Get | Method::Head) {
+    return response;
+  }
+
+  let not_modified = if let Some(if_none_match) = head.get_header(&HeaderName::IfNoneMatch) {
+    // If-None-Match takes precedence over If-Modified-Since. RFC 7232 section 6.
+    response
+      .get_header(&HeaderName::ETag)
+      .map(|etag| {
+        let etag = etag.trim_start_matches("W/");
+        if_none_match
+          .split(',')
+          .map(str::trim)
+          .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+      })
+      .unwrap_or_default()
+  } else if let Some(if_modified_since) = head.get_header(&HeaderName::IfModifiedSince) {
+    response
+      .get_header(&HeaderName::LastModified)
+      .map(|last_modified| {
+        match (util::parse_http_date(last_modified), util::parse_http_date(if_modified_since)) {
+          (Some(last_modified), Some(if_modified_since)) => last_modified <= if_modified_since,
+          // Clients usually echo the Last-Modified value as is.
+          _ => last_modified == if_modified_since,
+        }
+      })
+      .unwrap_or_default()
+  } else {
+    false
+  };
+
+  if not_modified {
+    trace_log!("ConditionalGet responding with 304 Not Modified");
+    response.status_code = StatusCode::NotModified;
+    response.body = None;
+    response.set_meta(ResponseMeta { kind: ResponseKind::NotModified, ..response.meta() });
+  }
+
+  response
+}
+
+/// Routes, filters and handlers of a `TiiRouter` as collected by `TiiRouterBuilder`.
+pub(crate) struct RouterConfig {
+  /// This filter/predicate will decide if the router should even serve the request at all
+  pub(crate) router_filter: Box<dyn RouterFilter>,
+
+  /// Filters that run before the route is matched.
+  /// These filters may modify the path of the request to affect routing decision.
+  pub(crate) pre_routing_filters: Vec<Box<dyn RequestFilter>>,
+  /// Filters that run once the routing decision has been made.
+  /// These filters only run if there is an actual endpoint.
+  pub(crate) routing_filters: Vec<Box<dyn RequestFilter>>,
+
+  /// These filters run on the response after the actual endpoint (or the error handler) has been called.
+  pub(crate) response_filters: Vec<Box<dyn ResponseFilter>>,
+
+  /// The routes to process requests for and their handlers.
+  pub(crate) routes: Vec<HttpRoute>,
+
+  /// The routes to process WebSocket requests for and their handlers.
+  pub(crate) websocket_routes: Vec<WebSocketRoute>,
+
+  /// Called when no route has been found in the router.
+  pub(crate) not_found_handler: NotRouteableHandler,
+
+  /// Called when no WebSocket route has been found in the router for an upgrade request.
+  pub(crate) websocket_not_found_handler: NotRouteableHandler,
+
+  pub(crate) not_acceptable_handler: NotRouteableHandler,
+  pub(crate) method_not_allowed_handler: NotRouteableHandler,
+  pub(crate) method_not_implemented_handler: NotRouteableHandler,
+  pub(crate) unsupported_media_type_handler: NotRouteableHandler,
+
+  /// Called when an error in any of the above occurs.
+  pub(crate) error_handler: ErrorHandler,
+
+  /// Upper bound of concurrently served WebSocket connections, None = unbounded.
+  pub(crate) max_websocket_connections: Option<usize>,
+
+  /// Upper bound of the size of a received WebSocket message, None = the default of the receiver.
+  pub(crate) max_websocket_message_size: Option<u64>,
+
+  /// Counters of all WebSocket connections served by the router.
+  pub(crate) websocket_metrics: Arc<WebsocketMetrics>,
+
+  /// Cross-origin requests allowed to the routes of the router.
+  pub(crate) cors: Option<Cors>,
+}
+
 impl TiiRouter {
-  #[expect(clippy::too_many_arguments)] //Only called by the builder.
-  pub(crate) fn new(
-    router_filter: Box<dyn RouterFilter>,
-    pre_routing_filters: Vec<Box<dyn RequestFilter>>,
-    routing_filters: Vec<Box<dyn RequestFilter>>,
-    response_filters: Vec<Box<dyn ResponseFilter>>,
-    routes: Vec<HttpRoute>,
-    websocket_routes: Vec<WebSocketRoute>,
-    not_found_handler: NotRouteableHandler,
-    not_acceptable_handler: NotRouteableHandler,
-    method_not_allowed_handler: NotRouteableHandler,
-    unsupported_media_type_handler: NotRouteableHandler,
-    error_handler: ErrorHandler,
-  ) -> Self {
+  pub(crate) fn new(config: RouterConfig) -> Self {
+    let RouterConfig {
+      router_filter,
+      pre_routing_filters,
+      routing_filters,
+      response_filters,
+      routes,
+      websocket_routes,
+      not_found_handler,
+      websocket_not_found_handler,
+      not_acceptable_handler,
+      method_not_allowed_handler,
+      method_not_implemented_handler,
+      unsupported_media_type_handler,
+      error_handler,
+      max_websocket_connections,
+      max_websocket_message_size,
+      websocket_metrics,
+      cors,
+    } = config;
+
     let mut routeables = Vec::new();
     for x in routes.iter() {
       routeables.push(x.routeable.clone());
@@ -565,13 +854,40 @@ impl TiiRouter {
       routes,
       websocket_routes,
       not_found_handler,
+      websocket_not_found_handler,
       not_acceptable_handler,
       method_not_allowed_handler,
+      method_not_implemented_handler,
       unsupported_media_type_handler,
       error_handler,
+      max_websocket_connections,
+      max_websocket_message_size,
+      websocket_connections: Arc::new(AtomicUsize::new(0)),
+      websocket_metrics,
+      cors,
     }
   }
 
+  /// Frames and bytes of all WebSocket connections served by this router and the amount of open connections.
+  pub fn websocket_metrics(&self) -> &Arc<WebsocketMetrics> {
+    &self.websocket_metrics
+  }
+
+  /// Reserves capacity for serving another WebSocket connection.
+  /// Returns None if the configured maximum amount of connections is already served.
+  fn acquire_websocket_connection(&self) -> Option<WebsocketConnectionPermit> {
+    let max = self.max_websocket_connections.unwrap_or(usize::MAX);
+    self
+      .websocket_connections
+      .fetch_update(SeqCst, SeqCst, |active| (active < max).then_some(active + 1))
+      .ok()?;
+    self.websocket_metrics.connection_opened();
+    Some(WebsocketConnectionPermit(
+      self.websocket_connections.clone(),
+      self.websocket_metrics.clone(),
+    ))
+  }
+
   fn serve_ws(
     &self,
     stream: &dyn ConnectionStream,
@@ -595,29 +911,15 @@ impl TiiRouter {
       return Ok(RouterWebSocketServingResponse::HandledWithoutProtocolSwitch(resp));
     }
 
-    let mut best_decision = RoutingDecision::PathMismatch;
-    let mut best_handler = None;
-
-    for handler in &self.websocket_routes {
-      let decision = handler.routeable.matches(request);
-      if best_decision >= decision {
-        continue;
-      }
-
-      best_decision = decision;
-      if let RoutingDecision::Match(qv, _) = &best_decision {
-        best_handler = Some(handler);
-        if qv == &QValue::MAX {
-          break;
-        }
-      }
-    }
+    let (best_decision, best_handler) =
+      best_route(&self.websocket_routes, |route| &route.routeable, request);
 
     if let Some(handler) = best_handler {
       request.set_routed_path(handler.routeable.path.as_str());
       self.handle_path_parameters(request, &best_decision);
 
-      for filter in self.routing_filters.iter() {
+      let routing_filters = self.routing_filters.iter().map(Box::as_ref);
+      for filter in routing_filters.chain(handler.filters.iter().map(Arc::as_ref)) {
         let resp = match filter.filter(request) {
           Ok(Some(res)) => res,
           Ok(None) => continue,
@@ -640,9 +942,27 @@ impl TiiRouter {
             return Ok(RouterWebSocketServingResponse::HandledWithoutProtocolSwitch(resp));
           }
 
+          // Response filters may have removed the extension header, in that case it is not negotiated.
+          let deflate = resp
+            .get_header("Sec-WebSocket-Extensions")
+            .is_some_and(|extensions| extensions.starts_with("permessage-deflate"));
           resp.write_to(HttpVersion::Http11, stream)?; //Errors here are fatal
 
-          let (sender, receiver) = crate::websocket::stream::new(stream);
+          let (sender, mut receiver) = crate::websocket::stream::new_negotiated(
+            stream,
+            request.clock().clone(),
+            deflate,
+            Some(self.websocket_metrics.clone()),
+          );
+          if let Some(max_message_size) = self.max_websocket_message_size {
+            receiver.set_max_message_size(max_message_size);
+          }
+          let Some(_permit) = self.acquire_websocket_connection() else {
+            trace_log!("WebsocketConnectionLimitReached closing with 1013");
+            sender.close_with_code(CloseCode::TryAgainLater, "Try Again Later")?;
+            return Ok(RouterWebSocketServingResponse::HandledWithProtocolSwitch);
+          };
+
           handler.handler.serve(request, receiver, sender)?;
           Ok(RouterWebSocketServingResponse::HandledWithProtocolSwitch)
         }
@@ -651,7 +971,12 @@ impl TiiRouter {
 
     trace_log!("WebsocketConnectionClosed Invoke fallback {}", &best_decision);
 
-    let fallback = self.invoke_appropriate_fallback_handler(request, &best_decision);
+    let fallback = match best_decision {
+      RoutingDecision::PathMismatch => {
+        (self.websocket_not_found_handler)(request, &self.routeables)
+      }
+      _ => self.invoke_appropriate_fallback_handler(request, &best_decision),
+    };
 
     let fallback_resp = match fallback {
       Ok(resp) => self.call_response_filters(request, resp)?,
@@ -676,6 +1001,27 @@ impl TiiRouter {
     }
   }
 
+  /// True if any route uses the method of the request. Routers without routes answer everything with 404.
+  /// GET and HEAD are always implemented, a general-purpose server must support them (RFC 9110 Section 9.1).
+  fn implements_method(&self, request: &RequestContext) -> bool {
+    let method = request.request_head().method();
+    matches!(method, Method::Get | Method::Head)
+      || self.routeables.is_empty()
+      || self.routeables.iter().any(|route| route.method() == method)
+  }
+
+  fn allowed_methods_for_path(&self, request: &RequestContext) -> Vec<Method> {
+    let mut methods = Vec::new();
+    for route in &self.routeables {
+      if route.matches_path(request, &mut None) && !methods.contains(route.method()) {
+        methods.push(route.method().clone());
+      }
+    }
+
+    methods.sort();
+    methods
+  }
+
   fn call_error_handler(
     &self,
     request: &mut RequestContext,
@@ -697,6 +1043,13 @@ impl TiiRouter {
     }
 
     let mut resp = self.serve_inner(request).or_else(|e| self.call_error_handler(request, e))?;
+    if let Some(cors) = self.cors.as_ref() {
+      cors.apply(request, &mut resp);
+    }
+    for cookie in request.cookies().take_pending()? {
+      resp = resp.with_cookie(cookie);
+    }
+    resp = conditional_get(request, resp);
     resp = self.call_response_filters(request, resp)?;
 
     Ok(Some(resp))
@@ -720,27 +1073,35 @@ impl TiiRouter {
       }
     }
 
-    let mut best_decision = RoutingDecision::PathMismatch;
-    let mut best_handler = None;
-
-    for handler in &self.routes {
-      let decision = handler.routeable.matches(request);
-      if best_decision >= decision {
-        continue;
-      }
-
-      best_decision = decision;
-      if let RoutingDecision::Match(qv, _) = &best_decision {
-        best_handler = Some(handler);
-        if qv == &QValue::MAX {
-          break;
-        }
+    if let Some(cors) = self.cors.as_ref() {
+      if let Some(resp) = cors.preflight(request, &self.allowed_methods_for_path(request)) {
+        trace_log!("RequestRespondedWith CORS preflight");
+        return Ok(resp);
       }
     }
 
+    let (best_decision, best_handler) = best_route(&self.routes, |route| &route.routeable, request);
+
     if let Some(handler) = best_handler {
       request.set_routed_path(handler.routeable.path.as_str());
       self.handle_path_parameters(request, &best_decision);
+      request.set_negotiated_content_type(handler.routeable.negotiate_content_type(request));
+      if request.request_head().method() == &Method::Options {
+        request.set_allowed_methods_for_path(self.allowed_methods_for_path(request));
+      }
+
+      if let Some(max_body_size) = handler.max_body_size {
+        request.set_max_body_size(Some(max_body_size))?;
+      }
+
+      if let (Some(max_body_size), Some(body)) = (request.max_body_size(), request.request_body()) {
+        if body.remaining()?.is_some_and(|content_length| content_length > max_body_size) {
+          trace_log!("RequestRespondedWith HTTP 413 for route {}", handler.routeable.path.as_str());
+          // The body is not read, so the connection can't be reused.
+          request.force_connection_close();
+          return Ok(Response::content_too_large_no_body());
+        }
+      }
 
       for filter in self.routing_filters.iter() {
         if let Some(resp) = filter.filter(request)? {
@@ -748,6 +1109,12 @@ impl TiiRouter {
         }
       }
 
+      for filter in handler.filters.iter() {
+        if let Some(resp) = filter.filter(request)? {
+          return Ok(resp);
+        }
+      }
+
       return handler.handler.serve(request);
     }
 
@@ -760,6 +1127,9 @@ impl TiiRouter {
     best_decision: &RoutingDecision,
   ) -> TiiResult<Response> {
     match best_decision {
+      RoutingDecision::PathMismatch if !self.implements_method(request) => {
+        (self.method_not_implemented_handler)(request, &self.routeables)
+      }
       RoutingDecision::PathMismatch => (self.not_found_handler)(request, &self.routeables),
       RoutingDecision::MethodMismatch => {
         (self.method_not_allowed_handler)(request, &self.routeables)
@@ -779,6 +1149,10 @@ impl Router for TiiRouter {
     self.serve_outer(request)
   }
 
+  fn max_route_body_size(&self) -> Option<u64> {
+    self.routes.iter().filter_map(|route| route.max_body_size).max()
+  }
+
   fn serve_websocket(
     &self,
     stream: &dyn ConnectionStream,
@@ -793,6 +1167,10 @@ impl Router for Arc<TiiRouter> {
     Arc::as_ref(self).serve(request)
   }
 
+  fn max_route_body_size(&self) -> Option<u64> {
+    Arc::as_ref(self).max_route_body_size()
+  }
+
   fn serve_websocket(
     &self,
     stream: &dyn ConnectionStream,
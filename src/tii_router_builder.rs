@@ -1,53 +1,29 @@
 //! Contains the builder for a router
 
 use crate::default_functions::{
-  default_error_handler, default_method_not_allowed_handler, default_not_acceptable_handler,
+  default_error_handler, default_method_not_allowed_handler,
+  default_method_not_implemented_handler, default_not_acceptable_handler,
   default_not_found_handler, default_pre_routing_filter, default_unsupported_media_type_handler,
+  method_override_filter,
 };
-use crate::functional_traits::{
-  HttpEndpoint, RequestFilter, ResponseFilter, RouterFilter, WebsocketEndpoint,
-};
+use crate::functional_traits::{HttpEndpoint, RequestFilter, ResponseFilter, WebsocketEndpoint};
+use crate::http::cors::Cors;
 use crate::http::method::Method;
 use crate::http::mime::AcceptMimeType;
 use crate::http::request_context::RequestContext;
 use crate::http::Response;
-use crate::tii_builder::{ErrorHandler, NotRouteableHandler};
-use crate::tii_error::TiiResult;
-use crate::tii_router::{HttpRoute, TiiRouter, WebSocketRoute};
+use crate::tii_builder::NotRouteableHandler;
+use crate::tii_error::{TiiResult, UserError};
+use crate::tii_router::{HttpRoute, RouterConfig, TiiRouter, WebSocketRoute};
+use crate::websocket::metrics::WebsocketMetrics;
 use crate::websocket::stream::{WebsocketReceiver, WebsocketSender};
 use std::collections::HashSet;
 use std::sync::Arc;
 
 /// Represents a sub-app to run for a specific host.
+#[derive(Default)]
 pub struct TiiRouterBuilder {
-  /// This filter/predicate will decide if the router should even serve the request at all
-  router_filter: Box<dyn RouterFilter>,
-
-  /// Filters that run before the route is matched.
-  /// These filters may modify the path of the request to affect routing decision.
-  pre_routing_filters: Vec<Box<dyn RequestFilter>>,
-  /// Filters that run once the routing decision has been made.
-  /// These filters only run if there is an actual endpoint.
-  routing_filters: Vec<Box<dyn RequestFilter>>,
-
-  /// These filters run on the response after the actual endpoint (or the error handler) has been called.
-  response_filters: Vec<Box<dyn ResponseFilter>>,
-
-  /// The routes to process requests for and their handlers.
-  routes: Vec<HttpRoute>,
-
-  /// The routes to process WebSocket requests for and their handlers.
-  websocket_routes: Vec<WebSocketRoute>,
-
-  /// Called when no route has been found in the router.
-  not_found_handler: NotRouteableHandler,
-
-  not_acceptable_handler: NotRouteableHandler,
-  method_not_allowed_handler: NotRouteableHandler,
-  unsupported_media_type_handler: NotRouteableHandler,
-
-  /// Called when an error in any of the above occurs.
-  error_handler: ErrorHandler,
+  config: RouterConfig,
 }
 
 /// For multi method routes!
@@ -91,7 +67,9 @@ pub struct TiiRouteBuilder {
   route: String,
   method: Method,
   consumes: HashSet<AcceptMimeType>,
-  produces: HashSet<AcceptMimeType>,
+  produces: Vec<AcceptMimeType>,
+  filters: Vec<Arc<dyn RequestFilter>>,
+  max_body_size: Option<u64>,
 }
 
 impl TiiRouteBuilder {
@@ -106,6 +84,8 @@ impl TiiRouteBuilder {
       method,
       consumes: Default::default(),
       produces: Default::default(),
+      filters: Vec::new(),
+      max_body_size: None,
     }
   }
 
@@ -116,27 +96,47 @@ impl TiiRouteBuilder {
   }
 
   /// Add a mime type which the endpoint may produce.
+  /// If several produced types are acceptable to the client, the one added first is used.
   pub fn produces(mut self, mime: impl Into<AcceptMimeType>) -> Self {
-    self.produces.insert(mime.into());
+    let mime = mime.into();
+    if !self.produces.contains(&mime) {
+      self.produces.push(mime);
+    }
+    self
+  }
+
+  /// Adds a filter that only runs for requests routed to this endpoint.
+  /// It runs after the routing filters of the router and the filters of enclosing `begin_filtered` sections.
+  pub fn with_filter<T>(mut self, filter: T) -> Self
+  where
+    T: RequestFilter + 'static,
+  {
+    self.filters.push(Arc::new(filter));
+    self
+  }
+
+  /// Overrides the maximum request body size of `TiiBuilder::with_max_body_size` for this route,
+  /// for example to allow large uploads on one route while all others keep a small limit.
+  /// Bodies whose Content-Length exceeds the limit are answered with a 413 before the filters of the route
+  /// and the endpoint run. Chunked bodies fail with a `BodyParsingError::TooLarge` once they exceed it.
+  pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+    self.max_body_size = Some(max_body_size);
     self
   }
 
   /// Finish building the route by proving the route.
   pub fn endpoint<T: HttpEndpoint + 'static>(mut self, handler: T) -> TiiResult<TiiRouterBuilder> {
-    self.inner.routes.push(HttpRoute::new(
-      self.route,
-      self.method,
-      self.consumes,
-      self.produces,
-      handler,
-    )?);
+    let mut route = HttpRoute::new(self.route, self.method, self.consumes, self.produces, handler)?;
+    route.filters = self.filters;
+    route.max_body_size = self.max_body_size;
+    self.inner.config.routes.push(route);
     Ok(self.inner)
   }
 }
 
-impl Default for TiiRouterBuilder {
+impl Default for RouterConfig {
   fn default() -> Self {
-    TiiRouterBuilder {
+    RouterConfig {
       router_filter: Box::new(default_pre_routing_filter),
       pre_routing_filters: Vec::default(),
       routing_filters: Vec::default(),
@@ -144,10 +144,16 @@ impl Default for TiiRouterBuilder {
       routes: Vec::new(),
       websocket_routes: Vec::new(),
       not_found_handler: default_not_found_handler,
+      websocket_not_found_handler: default_not_found_handler,
       not_acceptable_handler: default_not_acceptable_handler,
       method_not_allowed_handler: default_method_not_allowed_handler,
+      method_not_implemented_handler: default_method_not_implemented_handler,
       unsupported_media_type_handler: default_unsupported_media_type_handler,
       error_handler: default_error_handler,
+      max_websocket_connections: None,
+      max_websocket_message_size: None,
+      websocket_metrics: Arc::new(WebsocketMetrics::new()),
+      cors: None,
     }
   }
 }
@@ -165,10 +171,20 @@ impl TiiRouterBuilder {
   where
     T: RequestFilter + 'static,
   {
-    self.pre_routing_filters.push(Box::new(filter));
+    self.config.pre_routing_filters.push(Box::new(filter));
     Ok(self)
   }
 
+  /// Lets clients that can only send GET and POST, like html forms, reach PUT, PATCH and DELETE routes.
+  /// A POST carrying the `X-HTTP-Method-Override` header, or an `application/x-www-form-urlencoded` body
+  /// with a `_method` field, is routed as if it was sent with that method.
+  /// Other requests and overrides to any other method are left untouched.
+  ///
+  /// This adds a pre routing filter, it is called in the order in which the pre routing filters were added.
+  pub fn with_method_override(self) -> TiiResult<Self> {
+    self.with_pre_routing_request_filter(method_override_filter)
+  }
+
   /// Adds a routing filter. This filter gets called once routing is done.
   /// This filter is called directly before a handler is called.
   /// This filter is only called on requests that actually do have a handler.
@@ -176,7 +192,7 @@ impl TiiRouterBuilder {
   where
     T: RequestFilter + 'static,
   {
-    self.routing_filters.push(Box::new(filter));
+    self.config.routing_filters.push(Box::new(filter));
     Ok(self)
   }
 
@@ -197,7 +213,7 @@ impl TiiRouterBuilder {
   where
     T: ResponseFilter + 'static,
   {
-    self.response_filters.push(Box::new(filter));
+    self.config.response_filters.push(Box::new(filter));
     Ok(self)
   }
 
@@ -232,22 +248,40 @@ impl TiiRouterBuilder {
 
   /// Adds a route that will handle the given http method.
   /// The endpoint will be called for any media type.
+  ///
+  /// The last part of the route may be an optional variable like `/items/{id?}`,
+  /// which matches both `/items` and `/items/5`. The path param is absent if the part was omitted.
+  /// If multiple routes match a request equally well, the one that was added first is used.
   pub fn route_method<T: HttpEndpoint + 'static>(
     mut self,
     method: Method,
     route: &str,
     handler: T,
   ) -> TiiResult<Self> {
-    self.routes.push(HttpRoute::new(
+    self.config.routes.push(HttpRoute::new(
       route,
       method,
       HashSet::from([AcceptMimeType::Wildcard]),
-      HashSet::new(),
+      Vec::new(),
       handler,
     )?);
     Ok(self)
   }
 
+  /// Adds a route for each `(method, route, handler)` tuple, like calling `route_method` for each of them.
+  /// This is intended for routes from a table, for example generated or read from a manifest.
+  /// Handlers of different types can be mixed by boxing them,
+  /// i.e. as `Box<dyn Fn(&RequestContext) -> TiiResult<Response> + Send + Sync>`.
+  pub fn extend<T: HttpEndpoint + 'static, R: AsRef<str>>(
+    mut self,
+    routes: impl IntoIterator<Item = (Method, R, T)>,
+  ) -> TiiResult<Self> {
+    for (method, route, handler) in routes {
+      self = self.route_method(method, route.as_ref(), handler)?;
+    }
+    Ok(self)
+  }
+
   /// Adds a route that will handle the GET http method.
   /// The endpoint will be called for any media type.
   pub fn route_get<T: HttpEndpoint + 'static>(self, route: &str, handler: T) -> TiiResult<Self> {
@@ -295,6 +329,32 @@ impl TiiRouterBuilder {
     section(self)
   }
 
+  /// Calls the passed closure like `begin`, every route added by the closure
+  /// only serves requests that pass the given filter.
+  /// This can be used to, for example, require authentication for a whole section of routes.
+  ///
+  /// The filter runs after the routing filters of the router, and only if one of the routes of the section was chosen.
+  /// Filters of nested sections run from the outermost to the innermost section.
+  pub fn begin_filtered<F, T>(self, filter: F, section: T) -> TiiResult<Self>
+  where
+    F: RequestFilter + 'static,
+    T: FnOnce(Self) -> TiiResult<Self>,
+  {
+    let filter: Arc<dyn RequestFilter> = Arc::new(filter);
+    let first_route = self.config.routes.len();
+    let first_websocket_route = self.config.websocket_routes.len();
+
+    let mut this = section(self)?;
+    for route in this.config.routes.iter_mut().skip(first_route) {
+      route.filters.insert(0, filter.clone());
+    }
+    for route in this.config.websocket_routes.iter_mut().skip(first_websocket_route) {
+      route.filters.insert(0, filter.clone());
+    }
+
+    Ok(this)
+  }
+
   /// Build an endpoint with a GET http method.
   pub fn get(self, route: &str) -> TiiRouteBuilder {
     TiiRouteBuilder::new(self, Method::Get, route.to_string())
@@ -425,11 +485,11 @@ impl TiiRouterBuilder {
     route: &str,
     handler: T,
   ) -> TiiResult<Self> {
-    self.websocket_routes.push(WebSocketRoute::new(
+    self.config.websocket_routes.push(WebSocketRoute::new(
       route,
       method,
       HashSet::new(),
-      HashSet::new(),
+      Vec::new(),
       handler,
     )?);
     Ok(self)
@@ -507,21 +567,81 @@ impl TiiRouterBuilder {
     self.ws_route_method(Method::Delete, route, handler)
   }
 
+  /// Sets the handler that is called when a WebSocket upgrade request matches none of the WebSocket routes.
+  /// The response it returns is sent without switching protocols and the connection is closed afterward.
+  /// The default handler responds with 404 Not Found, a handler returning `501 Not Implemented`
+  /// may be used to tell clients that the server does not do WebSockets at all on that path.
+  pub fn with_websocket_not_found_handler(
+    mut self,
+    handler: NotRouteableHandler,
+  ) -> TiiResult<Self> {
+    self.config.websocket_not_found_handler = handler;
+    Ok(self)
+  }
+
+  /// Sets the handler that is called when the path of a request matches no route and no route of this
+  /// router uses the method of the request either. A path that matches a route with another method
+  /// still yields `405 Method Not Allowed`. GET and HEAD are never treated as unimplemented,
+  /// they yield `404 Not Found` instead.
+  /// The default handler responds with `501 Not Implemented` and an Allow header listing the
+  /// methods of all routes of this router.
+  pub fn with_method_not_implemented_handler(
+    mut self,
+    handler: NotRouteableHandler,
+  ) -> TiiResult<Self> {
+    self.config.method_not_implemented_handler = handler;
+    Ok(self)
+  }
+
+  /// Limits the amount of WebSocket connections this router serves at the same time, to protect
+  /// against running out of threads or file descriptors when flooded with connections.
+  /// Once the limit is reached further upgrade requests still complete the handshake, but the
+  /// endpoint is not called. Instead the connection is closed right away with close code
+  /// 1013 "Try Again Later".
+  /// Default is no limit.
+  pub fn with_max_websocket_connections(mut self, max_connections: usize) -> TiiResult<Self> {
+    self.config.max_websocket_connections = Some(max_connections);
+    Ok(self)
+  }
+
+  /// Limits the size of messages the WebSocket endpoints of this router receive,
+  /// see `WebsocketReceiver::set_max_message_size`.
+  /// A client exceeding the limit is sent a close frame with status code 1009 "Message Too Big",
+  /// and reading the message fails with `WebsocketError::MessageTooBig`.
+  /// Default is 64 MiB, which is also the largest permitted value.
+  pub fn with_max_websocket_message_size(mut self, max_message_size: u64) -> TiiResult<Self> {
+    self.config.max_websocket_message_size = Some(max_message_size);
+    Ok(self)
+  }
+
+  /// Counts the frames and bytes of the WebSocket connections of this router and the amount of open connections
+  /// in the given metrics, for example to share them with a monitoring endpoint or with other routers.
+  /// Default is a new instance for this router, see `TiiRouter::websocket_metrics`.
+  pub fn with_websocket_metrics(mut self, metrics: Arc<WebsocketMetrics>) -> TiiResult<Self> {
+    self.config.websocket_metrics = metrics;
+    Ok(self)
+  }
+
+  /// Allows cross-origin requests to the routes of this router as configured by `cors`.
+  /// Preflight requests, `OPTIONS` with `Origin` and `Access-Control-Request-Method` headers, for a method
+  /// that a route of the requested path handles are answered with `204 No Content` and the
+  /// `Access-Control-Allow-*` headers, without calling a filter or endpoint after the pre routing filters.
+  /// Other responses to requests from an allowed origin get the `Access-Control-Allow-Origin` header
+  /// before the response filters are called, unless the endpoint has already set it.
+  /// Default is no CORS headers at all.
+  ///
+  /// Returns `UserError::CorsCredentialsWithAnyOrigin` if `cors` allows credentials for any origin.
+  pub fn with_cors(mut self, cors: Cors) -> TiiResult<Self> {
+    if cors.is_credentials_with_any_origin() {
+      return Err(UserError::CorsCredentialsWithAnyOrigin.into());
+    }
+    self.config.cors = Some(cors);
+    Ok(self)
+  }
+
   /// Build the router
   pub fn build(self) -> TiiRouter {
-    TiiRouter::new(
-      self.router_filter,
-      self.pre_routing_filters,
-      self.routing_filters,
-      self.response_filters,
-      self.routes,
-      self.websocket_routes,
-      self.not_found_handler,
-      self.not_acceptable_handler,
-      self.method_not_allowed_handler,
-      self.unsupported_media_type_handler,
-      self.error_handler,
-    )
+    TiiRouter::new(self.config)
   }
 
   /// Equivalent of calling Arc::new(builder.build())
@@ -2,23 +2,33 @@
 //! It also handles http keep alive and rudimentary (fallback) error handling.
 //! If no router wants to handle the request it also has a 404 handler.
 
-use crate::functional_traits::Router;
+use crate::functional_traits::{Clock, Router};
+use crate::http::compression::{compress_response, ResponseCompression};
 use crate::http::headers::HeaderName;
+use crate::http::method::Method;
+use crate::http::mime::MimeType;
 use crate::http::request::HttpVersion;
-use crate::http::request_context::RequestContext;
-use crate::http::{Response, StatusCode};
-use crate::stream::{ConnectionStream, IntoConnectionStream};
+use crate::http::request_body::RequestBody;
+use crate::http::request_context::{RequestContext, RequestContextOptions};
+use crate::http::response_body::ResponseBody;
+use crate::http::{RequestHead, Response, StatusCode};
+use crate::stream::{
+  BufferedStreamWrite, ConnectionStream, ConnectionStreamWrite, CountingStreamWrite,
+  IntoConnectionStream, SizedBufferStreamWrite,
+};
 use crate::tii_builder::{ErrorHandler, NotFoundHandler, RouterWebSocketServingResponse};
-use crate::tii_error::{TiiError, TiiResult};
-use crate::{error_log, trace_log};
+use crate::tii_error::{RequestHeadParsingError, TiiError, TiiResult};
+use crate::{error_log, trace_log, util};
 use std::any::Any;
 use std::fmt::{Debug, Formatter};
 use std::io;
 use std::io::ErrorKind;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering::SeqCst;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
 /// Trait for metadata for streams. This could for example be an indicator of what type of stream this is
 /// if this is relevant for your application. For example an app may ingest connections from a plain and tls socket at the same time.
@@ -28,6 +38,12 @@ use std::time::Duration;
 pub trait ConnectionStreamMetadata: Any + Debug + Send + Sync {
   /// upcast to dyn Any. most likely just return "self".
   fn as_any(&self) -> &dyn Any;
+
+  /// Returns true if the connection is secure, for example because it uses TLS.
+  /// The default impl returns false.
+  fn is_secure(&self) -> bool {
+    false
+  }
 }
 
 #[derive(Debug)]
@@ -40,6 +56,55 @@ impl ConnectionStreamMetadata for PhantomStreamMetadata {
   }
 }
 
+/// Lets a connector end a connection once its current request is answered.
+/// See `Connector::shutdown_graceful`.
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionDrain(Mutex<DrainState>);
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+enum DrainState {
+  /// A request is being read or served.
+  #[default]
+  Busy,
+  /// The connection waits for the next request.
+  Idle,
+  /// The connection must end after the current request.
+  Draining,
+}
+
+impl ConnectionDrain {
+  /// Moves the connection into the given state, returns false if it is draining instead.
+  fn enter(&self, state: DrainState) -> bool {
+    let Ok(mut guard) = util::unwrap_poison(self.0.lock()) else {
+      return false;
+    };
+
+    if *guard == DrainState::Draining {
+      return false;
+    }
+
+    *guard = state;
+    true
+  }
+
+  fn is_draining(&self) -> bool {
+    util::unwrap_poison(self.0.lock()).map(|guard| *guard == DrainState::Draining).unwrap_or(true)
+  }
+
+  /// Marks the connection to end after its current request.
+  /// Returns true if the connection is waiting for a request and can be closed right away.
+  #[cfg(feature = "extras")]
+  pub(crate) fn drain(&self) -> bool {
+    let Ok(mut guard) = util::unwrap_poison(self.0.lock()) else {
+      return true;
+    };
+
+    let idle = *guard == DrainState::Idle;
+    *guard = DrainState::Draining;
+    idle
+  }
+}
+
 /// Struct that represents a built server capable of handling connections from some sources.
 /// It does NOT own any OS resources like server sockets / file descriptors.
 #[derive(Debug)]
@@ -48,13 +113,170 @@ pub struct TiiServer {
   routers: Vec<Box<dyn Router>>,
   error_handler: ErrorHandler,
   not_found_handler: NotFoundHandler,
-  max_head_buffer_size: usize,
+  request_options: RequestContextOptions,
   connection_timeout: Option<Duration>,
   read_timeout: Option<Duration>,
   keep_alive_timeout: Option<Duration>,
   request_body_io_timeout: Option<Duration>,
   write_timeout: Option<Duration>,
+  continue_threshold: usize,
+  /// Limit for the Content-Length checked before routing, routes may allow larger bodies than the server.
+  max_declared_body_size: Option<u64>,
+  trusted_proxies: Vec<String>,
+  merge_slashes: bool,
+  version_not_supported_response: bool,
+  unexpected_body_policy: UnexpectedBodyPolicy,
+  panic_observer: Callback<PanicObserver>,
+  wire_filter: Callback<WireFilter>,
+  error_body_renderer: Callback<ErrorBodyRenderer>,
+  html_transformer: Callback<HtmlTransformer>,
+  request_deadline: Option<Duration>,
+  size_stats: Option<SizeStats>,
+  reject_trace: bool,
+  connect_not_implemented: bool,
   shutdown_hooks: Hooks,
+  clock: Arc<dyn Clock>,
+  response_compression: Option<ResponseCompression>,
+  /// Size of the buffer responses are serialized into, None = the buffer of the connection.
+  write_buffer_size: Option<usize>,
+  /// Cork the connection while a response is written.
+  tcp_cork: bool,
+  request_observer: Callback<RequestObserver>,
+  /// Add a `Date` header taken from the clock to responses.
+  date_header: bool,
+}
+
+/// What to do with a request body sent along with a method whose body has no defined meaning.
+/// This applies to `GET`, `HEAD`, `DELETE` and `TRACE` requests.
+/// Such bodies are unusual and may be a sign of request smuggling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UnexpectedBodyPolicy {
+  /// The body is read and discarded before the request is routed, endpoints see a request without a body.
+  /// The connection can be kept alive afterward.
+  #[default]
+  Discard,
+  /// The request is answered with `400 Bad Request` without reading the body and the connection is closed.
+  Reject,
+  /// The body is handed to the endpoint like for any other method.
+  PassThrough,
+}
+
+/// Aggregated sizes of all requests served by a `TiiServer`, see `TiiBuilder::with_size_stats`.
+/// A request is counted once its response was written.
+#[derive(Debug, Default)]
+pub struct SizeStats {
+  request_heads: SizeHistogram,
+  request_bodies: SizeHistogram,
+  responses: SizeHistogram,
+}
+
+impl SizeStats {
+  /// Sizes of the request heads as parsed, this is the status line and the headers including line breaks.
+  /// Whitespace around header values the client may have sent is not counted.
+  pub fn request_head_sizes(&self) -> &SizeHistogram {
+    &self.request_heads
+  }
+
+  /// Sizes of the request bodies as read from the connection, including chunk framing and trailers.
+  /// Requests without a body are counted with a size of 0.
+  pub fn request_body_sizes(&self) -> &SizeHistogram {
+    &self.request_bodies
+  }
+
+  /// Sizes of the responses as written to the connection, including status line and headers.
+  pub fn response_sizes(&self) -> &SizeHistogram {
+    &self.responses
+  }
+}
+
+/// Number of buckets of a `SizeHistogram`, one for 0 and one for each power of two of an u64.
+const SIZE_HISTOGRAM_BUCKETS: usize = 65;
+
+/// Histogram of sizes in bytes with power of two buckets.
+/// Bucket 0 counts sizes of 0, bucket n counts sizes from `2^(n-1)` to `2^n - 1`.
+#[derive(Debug)]
+pub struct SizeHistogram {
+  buckets: [AtomicU64; SIZE_HISTOGRAM_BUCKETS],
+  sum: AtomicU64,
+}
+
+impl Default for SizeHistogram {
+  fn default() -> Self {
+    Self { buckets: std::array::from_fn(|_| AtomicU64::new(0)), sum: AtomicU64::new(0) }
+  }
+}
+
+impl SizeHistogram {
+  pub(crate) fn record(&self, size: u64) {
+    let bucket = (u64::BITS - size.leading_zeros()) as usize;
+    if let Some(bucket) = self.buckets.get(bucket) {
+      bucket.fetch_add(1, Relaxed);
+    }
+    self.sum.fetch_add(size, Relaxed);
+  }
+
+  /// Returns the number of recorded sizes.
+  pub fn count(&self) -> u64 {
+    self.buckets.iter().map(|bucket| bucket.load(Relaxed)).sum()
+  }
+
+  /// Returns the sum of all recorded sizes. Wraps around on overflow.
+  pub fn sum(&self) -> u64 {
+    self.sum.load(Relaxed)
+  }
+
+  /// Returns the inclusive upper bound and the count of every bucket that is not empty, ordered by size.
+  pub fn buckets(&self) -> Vec<(u64, u64)> {
+    self
+      .buckets
+      .iter()
+      .enumerate()
+      .map(|(index, bucket)| {
+        let upper_bound = if index == 0 { 0 } else { u64::MAX >> (u64::BITS as usize - index) };
+        (upper_bound, bucket.load(Relaxed))
+      })
+      .filter(|(_, count)| *count > 0)
+      .collect()
+  }
+}
+
+/// Callback that is invoked with the panic message whenever handling a connection panics.
+pub type PanicObserver = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Callback that is invoked after every response has been written, see `RequestCompleted`.
+pub type RequestObserver = Box<dyn Fn(&RequestCompleted) + Send + Sync>;
+
+/// Summary of a request whose response has been written completely.
+/// This is passed to the observer set with `TiiBuilder::with_request_observer`,
+/// for example to write access logs or to record latency histograms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestCompleted {
+  /// The method of the request.
+  pub method: Method,
+  /// The path of the request as it was routed, without the query string.
+  pub path: String,
+  /// The status code of the response.
+  pub status: StatusCode,
+  /// Time from starting to parse the request head until the last byte of the response was written,
+  /// measured with the clock of the server.
+  pub duration: Duration,
+}
+
+/// Hook that fills in the body of error responses (status 400 and above) that have no body.
+pub type ErrorBodyRenderer = Box<dyn Fn(&RequestHead, &mut Response) + Send + Sync>;
+
+/// Hook that post-processes the body of `text/html` responses before they are serialized.
+pub type HtmlTransformer = Box<dyn Fn(&mut String, &RequestContext) + Send + Sync>;
+
+/// Late hook that can observe and modify the serialized bytes of a response before they are written.
+pub type WireFilter = Box<dyn Fn(&RequestHead, &mut Vec<u8>) + Send + Sync>;
+
+struct Callback<T>(Option<T>);
+
+impl<T> Debug for Callback<T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str("Callback")
+  }
 }
 
 struct Hooks(Mutex<Vec<Box<dyn FnMut() + Send + Sync>>>);
@@ -71,37 +293,127 @@ impl Default for Hooks {
   }
 }
 
+/// Settings of a `TiiServer` as collected by `TiiBuilder`.
+pub(crate) struct ServerConfig {
+  pub(crate) routers: Vec<Box<dyn Router>>,
+  pub(crate) error_handler: ErrorHandler,
+  pub(crate) not_found_handler: NotFoundHandler,
+  pub(crate) max_head_buffer_size: usize,
+  pub(crate) connection_timeout: Option<Duration>,
+  pub(crate) read_timeout: Option<Duration>,
+  pub(crate) keep_alive_timeout: Option<Duration>,
+  pub(crate) request_body_io_timeout: Option<Duration>,
+  pub(crate) write_timeout: Option<Duration>,
+  pub(crate) continue_threshold: usize,
+  pub(crate) max_body_chunks: Option<u64>,
+  pub(crate) max_body_size: Option<u64>,
+  pub(crate) trusted_proxies: Vec<String>,
+  pub(crate) merge_slashes: bool,
+  pub(crate) version_not_supported_response: bool,
+  pub(crate) unexpected_body_policy: UnexpectedBodyPolicy,
+  pub(crate) panic_observer: Option<PanicObserver>,
+  pub(crate) wire_filter: Option<WireFilter>,
+  pub(crate) size_stats: bool,
+  pub(crate) reject_trace: bool,
+  pub(crate) connect_not_implemented: bool,
+  pub(crate) error_body_renderer: Option<ErrorBodyRenderer>,
+  pub(crate) clock: Arc<dyn Clock>,
+  pub(crate) latin1_header_values: bool,
+  pub(crate) html_transformer: Option<HtmlTransformer>,
+  pub(crate) request_deadline: Option<Duration>,
+  pub(crate) max_path_length: Option<usize>,
+  pub(crate) response_compression: Option<ResponseCompression>,
+  pub(crate) write_buffer_size: Option<usize>,
+  pub(crate) tcp_cork: bool,
+  pub(crate) request_observer: Option<RequestObserver>,
+  pub(crate) date_header: bool,
+}
+
 impl TiiServer {
-  #[expect(clippy::too_many_arguments)] //Builder
-  pub(crate) fn new(
-    routers: Vec<Box<dyn Router>>,
-    error_handler: ErrorHandler,
-    not_found_handler: NotFoundHandler,
-    max_head_buffer_size: usize,
-    connection_timeout: Option<Duration>,
-    read_timeout: Option<Duration>,
-    keep_alive_timeout: Option<Duration>,
-    request_body_io_timeout: Option<Duration>,
-    write_timeout: Option<Duration>,
-  ) -> Self {
+  pub(crate) fn new(config: ServerConfig) -> Self {
+    let ServerConfig {
+      routers,
+      error_handler,
+      not_found_handler,
+      max_head_buffer_size,
+      connection_timeout,
+      read_timeout,
+      keep_alive_timeout,
+      request_body_io_timeout,
+      write_timeout,
+      continue_threshold,
+      max_body_chunks,
+      max_body_size,
+      trusted_proxies,
+      merge_slashes,
+      version_not_supported_response,
+      unexpected_body_policy,
+      panic_observer,
+      wire_filter,
+      size_stats,
+      reject_trace,
+      connect_not_implemented,
+      error_body_renderer,
+      clock,
+      latin1_header_values,
+      html_transformer,
+      request_deadline,
+      max_path_length,
+      response_compression,
+      write_buffer_size,
+      tcp_cork,
+      request_observer,
+      date_header,
+    } = config;
+
+    let max_declared_body_size = max_body_size.map(|max| {
+      routers.iter().filter_map(|router| router.max_route_body_size()).fold(max, u64::max)
+    });
+
     TiiServer {
       shutdown: AtomicBool::new(false),
       routers,
       error_handler,
       not_found_handler,
-      max_head_buffer_size,
       read_timeout,
       connection_timeout: connection_timeout.or(read_timeout),
       keep_alive_timeout: keep_alive_timeout.or(read_timeout),
       request_body_io_timeout: request_body_io_timeout.or(read_timeout),
       write_timeout,
+      continue_threshold,
+      max_declared_body_size,
+      trusted_proxies,
+      merge_slashes,
+      version_not_supported_response,
+      unexpected_body_policy,
+      panic_observer: Callback(panic_observer),
+      wire_filter: Callback(wire_filter),
+      error_body_renderer: Callback(error_body_renderer),
+      html_transformer: Callback(html_transformer),
+      request_deadline,
+      size_stats: size_stats.then(SizeStats::default),
+      reject_trace,
+      connect_not_implemented,
       shutdown_hooks: Hooks::default(),
+      request_options: RequestContextOptions::default()
+        .with_max_head_buffer_size(max_head_buffer_size)
+        .with_max_body_chunks(max_body_chunks)
+        .with_max_body_size(max_body_size)
+        .with_clock(clock.clone())
+        .with_latin1_header_values(latin1_header_values)
+        .with_max_path_length(max_path_length),
+      clock,
+      response_compression,
+      write_buffer_size,
+      tcp_cork,
+      request_observer: Callback(request_observer),
+      date_header,
     }
   }
 
   /// Handles a connection without any metadata
   pub fn handle_connection<S: IntoConnectionStream>(&self, stream: S) -> TiiResult<()> {
-    self.handle_connection_inner::<S, PhantomStreamMetadata>(stream, None)
+    self.handle_connection_inner::<S, PhantomStreamMetadata>(stream, None, None)
   }
 
   /// Handles a connection with arbitrary metadata
@@ -110,7 +422,18 @@ impl TiiServer {
     stream: S,
     meta: M,
   ) -> TiiResult<()> {
-    self.handle_connection_inner(stream, Some(meta))
+    self.handle_connection_inner(stream, Some(meta), None)
+  }
+
+  /// Handles a connection of a connector, the connector may end it early using `drain`.
+  #[cfg(feature = "extras")]
+  pub(crate) fn handle_connection_draining<S: IntoConnectionStream, M: ConnectionStreamMetadata>(
+    &self,
+    stream: S,
+    meta: M,
+    drain: Option<&ConnectionDrain>,
+  ) -> TiiResult<()> {
+    self.handle_connection_inner(stream, Some(meta), drain)
   }
 
   /// Will mark this tii server as shutdown.
@@ -131,6 +454,16 @@ impl TiiServer {
     }
   }
 
+  /// Returns the aggregated request and response sizes, if enabled with `TiiBuilder::with_size_stats`.
+  pub fn size_stats(&self) -> Option<&SizeStats> {
+    self.size_stats.as_ref()
+  }
+
+  /// Returns the clock configured with `TiiBuilder::with_clock`.
+  pub fn clock(&self) -> &Arc<dyn Clock> {
+    &self.clock
+  }
+
   /// Returns true if this TiiServer is marked for shutdown.
   pub fn is_shutdown(&self) -> bool {
     self.shutdown.load(SeqCst)
@@ -158,16 +491,64 @@ impl TiiServer {
     &self,
     stream: S,
     meta: Option<M>,
+    drain: Option<&ConnectionDrain>,
+  ) -> TiiResult<()> {
+    let Some(observer) = self.panic_observer.0.as_ref() else {
+      return self.handle_connection_unobserved(stream, meta, drain);
+    };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+      self.handle_connection_unobserved(stream, meta, drain)
+    })) {
+      Ok(result) => result,
+      Err(payload) => {
+        let observed = panic::catch_unwind(AssertUnwindSafe(|| {
+          util::panic_msg_ref(payload.as_ref(), |msg| observer(msg))
+        }));
+        if observed.is_err() {
+          error_log!("tii: panic observer panicked itself");
+        }
+        panic::resume_unwind(payload)
+      }
+    }
+  }
+
+  fn handle_connection_unobserved<S: IntoConnectionStream, M: ConnectionStreamMetadata>(
+    &self,
+    stream: S,
+    meta: Option<M>,
+    drain: Option<&ConnectionDrain>,
   ) -> TiiResult<()> {
     if self.shutdown.load(SeqCst) {
       return Err(TiiError::from_io_kind(ErrorKind::ConnectionAborted));
     }
 
     let stream = stream.into_connection_stream();
+    let (stream, deadline) = match self.request_deadline {
+      Some(_) => {
+        let (stream, deadline) = crate::stream::deadline::new(stream, self.clock.clone());
+        (stream, Some(deadline))
+      }
+      None => (stream, None),
+    };
 
     stream.set_read_timeout(self.connection_timeout)?;
     stream.set_write_timeout(self.write_timeout)?;
-    if !stream.ensure_readable()? {
+
+    let no_drain = ConnectionDrain::default();
+    let drain = drain.unwrap_or(&no_drain);
+    if !drain.enter(DrainState::Idle) {
+      trace_log!("ConnectionDrained");
+      return Ok(());
+    }
+
+    let readable = stream.ensure_readable();
+    if !drain.enter(DrainState::Busy) {
+      trace_log!("ConnectionDrained");
+      return Ok(());
+    }
+
+    if !readable? {
       return Err(TiiError::from_io_kind(ErrorKind::UnexpectedEof));
     }
 
@@ -176,16 +557,48 @@ impl TiiServer {
     let mut count = 0u64;
 
     loop {
-      if count > 0 && !self.handle_keep_alive(stream.as_ref())? {
-        break;
+      if count > 0 {
+        if !drain.enter(DrainState::Idle) {
+          trace_log!("Keep-alive connection drained...");
+          break;
+        }
+
+        let next_request = self.handle_keep_alive(stream.as_ref())?;
+        if !drain.enter(DrainState::Busy) {
+          trace_log!("Keep-alive connection drained...");
+          break;
+        }
+
+        if !next_request {
+          break;
+        }
       }
 
       stream.set_read_timeout(self.read_timeout)?;
+      let started = self.clock.now();
+      if let (Some(deadline), Some(timeout)) = (deadline.as_ref(), self.request_deadline) {
+        deadline.start(timeout)?;
+      }
 
       let mut context =
-        RequestContext::new(stream.as_ref(), meta.as_ref().cloned(), self.max_head_buffer_size)?;
+        RequestContext::new(stream.as_ref(), meta.as_ref().cloned(), &self.request_options)
+          .inspect_err(|err| self.handle_request_head_error(stream.as_ref(), err))?;
       count += 1;
 
+      if !self.trusted_proxies.is_empty() {
+        let peer_host = util::address_host(context.peer_address());
+        let trusted = self.trusted_proxies.iter().any(|proxy| proxy == peer_host);
+        context.set_trusted_proxy(trusted);
+        if trusted {
+          context.resolve_client_address(&self.trusted_proxies);
+        }
+      }
+
+      if self.merge_slashes && context.request_head().path().contains("//") {
+        let merged = util::merge_slashes(context.request_head().path());
+        context.request_head_mut().set_path(merged);
+      }
+
       stream.set_read_timeout(self.request_body_io_timeout)?;
 
       // If the request is valid an is a WebSocket request, call the corresponding handler
@@ -195,6 +608,9 @@ impl TiiServer {
         //Http 1.0 or 0.9 does not have web sockets
 
         trace_log!("WebsocketConnectionRequested");
+        if let Some(deadline) = deadline.as_ref() {
+          deadline.clear()?;
+        }
 
         for router in self.routers.iter() {
           //Note, it's not a good idea to further handle errors form web socket router as
@@ -203,7 +619,7 @@ impl TiiServer {
           match router.serve_websocket(stream.as_ref(), &mut context)? {
             RouterWebSocketServingResponse::HandledWithProtocolSwitch => return Ok(()),
             RouterWebSocketServingResponse::HandledWithoutProtocolSwitch(response) => {
-              self.write_response(stream.as_ref(), context, false, response)?;
+              self.write_response(stream.as_ref(), context, started, false, response)?;
               return Ok(());
             }
             RouterWebSocketServingResponse::NotHandled => (), // Next router please
@@ -217,7 +633,7 @@ impl TiiServer {
             .unwrap_or_else(|e| self.fallback_error_handler(&mut context, e)),
         };
 
-        self.write_response(stream.as_ref(), context, false, response)?;
+        self.write_response(stream.as_ref(), context, started, false, response)?;
         return Ok(());
       }
 
@@ -234,16 +650,33 @@ impl TiiServer {
             .map(|e| e.eq_ignore_ascii_case("keep-alive"))
             .unwrap_or_default();
 
-      let mut response = None;
-      for router in self.routers.iter() {
-        response = Some(match router.serve(&mut context) {
-          Ok(Some(resp)) => resp,
-          Ok(None) => continue,
-          Err(error) => (self.error_handler)(&mut context, error)
-            .unwrap_or_else(|e| self.fallback_error_handler(&mut context, e)),
-        });
+      if !self.handle_declared_body_size(stream.as_ref(), &context)? {
+        trace_log!("DeclaredBodyTooLarge");
+        return Ok(());
+      }
 
-        break;
+      if !self.handle_unexpected_body(stream.as_ref(), &mut context)? {
+        trace_log!("UnexpectedBodyRejected");
+        return Ok(());
+      }
+
+      if !self.handle_expect(stream.as_ref(), &context)? {
+        trace_log!("ExpectationFailed");
+        return Ok(());
+      }
+
+      let mut response = self.handle_unrouted_method(&context);
+      if response.is_none() {
+        for router in self.routers.iter() {
+          response = Some(match router.serve(&mut context) {
+            Ok(Some(resp)) => resp,
+            Ok(None) => continue,
+            Err(error) => (self.error_handler)(&mut context, error)
+              .unwrap_or_else(|e| self.fallback_error_handler(&mut context, e)),
+          });
+
+          break;
+        }
       }
 
       let response = response.unwrap_or_else(|| match (self.not_found_handler)(&mut context) {
@@ -252,9 +685,23 @@ impl TiiServer {
           .unwrap_or_else(|e| self.fallback_error_handler(&mut context, e)),
       });
 
+      if let Some(deadline) = deadline.as_ref() {
+        deadline.clear()?;
+      }
+
+      if let Some(body) = context.request_body() {
+        if body.cancel_pending_continue()? {
+          trace_log!("ExpectContinue body was not read, no interim response sent");
+          keep_alive = false;
+        }
+      }
+
+      keep_alive &= !drain.is_draining();
       keep_alive &= !context.is_connection_close_forced();
+      keep_alive &= !response.is_connection_close();
+      keep_alive &= !response.body().map(ResponseBody::is_close_delimited).unwrap_or_default();
 
-      self.write_response(stream.as_ref(), context, keep_alive, response)?;
+      self.write_response(stream.as_ref(), context, started, keep_alive, response)?;
 
       // Can we do keep alive?
       if !keep_alive {
@@ -310,13 +757,194 @@ impl TiiServer {
     }
   }
 
+  /// Returns false if the declared Content-Length exceeds the maximum body size.
+  /// The 413 response has already been written in that case and the connection must be closed,
+  /// not a single byte of the body has been read.
+  fn handle_declared_body_size(
+    &self,
+    stream: &dyn ConnectionStream,
+    context: &RequestContext,
+  ) -> TiiResult<bool> {
+    let (Some(max_body_size), Some(body)) = (self.max_declared_body_size, context.request_body())
+    else {
+      return Ok(true);
+    };
+
+    match body.remaining()? {
+      Some(content_length) if content_length > max_body_size => {
+        trace_log!("RequestRespondedWith HTTP 413 for Content-Length {}", content_length);
+        let mut response = Response::content_too_large_no_body();
+        if context.request_head().version() == HttpVersion::Http11 {
+          response.headers.set(HeaderName::Connection, "Close");
+        }
+        response.write_to(context.request_head().version(), stream.as_stream_write())?;
+        Ok(false)
+      }
+      _ => Ok(true),
+    }
+  }
+
+  /// Applies the `UnexpectedBodyPolicy` to requests with a body whose method gives the body no meaning.
+  /// Returns false if the request was rejected.
+  /// The 400 response has already been written in that case and the connection must be closed.
+  fn handle_unexpected_body(
+    &self,
+    stream: &dyn ConnectionStream,
+    context: &mut RequestContext,
+  ) -> TiiResult<bool> {
+    if context.request_body().is_none()
+      || !matches!(
+        context.request_head().method(),
+        Method::Get | Method::Head | Method::Delete | Method::Trace
+      )
+    {
+      return Ok(true);
+    }
+
+    match self.unexpected_body_policy {
+      UnexpectedBodyPolicy::PassThrough => Ok(true),
+      UnexpectedBodyPolicy::Discard => {
+        trace_log!("UnexpectedBody discarding body of {}", context.request_head().method());
+        // The client may wait for a 100 Continue before it sends a body we are going to discard anyway.
+        if !self.handle_expect(stream, context)? {
+          return Ok(false);
+        }
+        context.set_body_consume_old(None)?;
+        Ok(true)
+      }
+      UnexpectedBodyPolicy::Reject => {
+        trace_log!("RequestRespondedWith HTTP 400 for body of {}", context.request_head().method());
+        let mut response = Response::bad_request_no_body();
+        if context.request_head().version() == HttpVersion::Http11 {
+          response.headers.set(HeaderName::Connection, "Close");
+        }
+        // The body is not consumed, the connection is closed instead.
+        response.write_to(context.request_head().version(), stream.as_stream_write())?;
+        Ok(false)
+      }
+    }
+  }
+
+  /// Returns the response for methods that are answered without routing, see `TiiBuilder::with_trace_rejected`
+  /// and `TiiBuilder::with_connect_not_implemented`.
+  fn handle_unrouted_method(&self, context: &RequestContext) -> Option<Response> {
+    match context.request_head().method() {
+      Method::Trace if self.reject_trace => {
+        trace_log!("RequestRespondedWith HTTP 405 for TRACE");
+        Some(Response::method_not_allowed(&[]))
+      }
+      Method::Connect if self.connect_not_implemented => {
+        trace_log!("RequestRespondedWith HTTP 501 for CONNECT");
+        Some(Response::new(StatusCode::NotImplemented))
+      }
+      _ => None,
+    }
+  }
+
+  /// Returns false if the request has an expectation we cannot meet.
+  /// The 417 response has already been written in that case and the connection must be closed.
+  fn handle_expect(
+    &self,
+    stream: &dyn ConnectionStream,
+    context: &RequestContext,
+  ) -> TiiResult<bool> {
+    if context.request_head().version() != HttpVersion::Http11 {
+      return Ok(true);
+    }
+
+    let Some(expect) = context.request_head().get_header(&HeaderName::Expect) else {
+      return Ok(true);
+    };
+
+    if !expect.trim().eq_ignore_ascii_case("100-continue") {
+      trace_log!("RequestRespondedWith HTTP 417 for expectation {}", expect);
+      let mut response = Response::expectation_failed_no_body();
+      response.headers.set(HeaderName::Connection, "Close");
+      // The client may still be waiting for a 100 Continue, so the body is not consumed.
+      response.write_to(HttpVersion::Http11, stream.as_stream_write())?;
+      return Ok(false);
+    }
+
+    let Some(body) = context.request_body() else {
+      return Ok(true);
+    };
+
+    if let Some(content_length) = body.remaining()? {
+      if content_length <= self.continue_threshold as u64 {
+        trace_log!("ExpectContinue body of {} bytes is below threshold", content_length);
+        return Ok(true);
+      }
+    }
+
+    trace_log!("ExpectContinue interim response is sent once the body is read");
+    body.send_continue_on_first_read(stream.new_ref_write())?;
+    Ok(true)
+  }
+
+  /// Serializes the response to the stream and returns the amount of bytes written.
+  fn write_response_to_stream(
+    &self,
+    stream: &dyn ConnectionStream,
+    context: &RequestContext,
+    response: Response,
+  ) -> TiiResult<u64> {
+    if let Some(wire_filter) = self.wire_filter.0.as_ref() {
+      let buffer = BufferedStreamWrite::default();
+      response.write_to(context.request_head().version(), &buffer)?;
+      let mut wire = buffer.take()?;
+      wire_filter(context.request_head(), &mut wire);
+      stream.write_all(wire.as_slice())?;
+      stream.flush()?;
+      return Ok(wire.len() as u64);
+    }
+
+    let buffered = self
+      .write_buffer_size
+      .map(|size| SizedBufferStreamWrite::new(stream.as_stream_write(), size));
+    let destination = buffered
+      .as_ref()
+      .map_or(stream.as_stream_write(), |buffered| buffered as &dyn ConnectionStreamWrite);
+    let counting = CountingStreamWrite::new(destination);
+    response.write_to(context.request_head().version(), &counting).inspect_err(|e| {
+      trace_log!("response.write_to {}", e);
+    })?;
+    // Not every response ends with a flush (HTTP/0.9 doesn't), anything still buffered goes out here.
+    if let Some(buffered) = buffered.as_ref() {
+      buffered.flush()?;
+    }
+    Ok(counting.count())
+  }
+
   fn write_response(
     &self,
     stream: &dyn ConnectionStream,
     context: RequestContext,
+    started: Instant,
     keep_alive: bool,
     mut response: Response,
   ) -> TiiResult<()> {
+    if let Some(renderer) = self.error_body_renderer.0.as_ref() {
+      if response.status_code.code() >= 400
+        && response.body.is_none()
+        && context.request_head().method() != &Method::Head
+      {
+        renderer(context.request_head(), &mut response);
+      }
+    }
+
+    if let Some(transformer) = self.html_transformer.0.as_ref() {
+      transform_html(transformer, &context, &mut response);
+    }
+
+    if let Some(compression) = self.response_compression.as_ref() {
+      compress_response(compression, &context, &mut response)?;
+    }
+
+    if self.date_header && response.headers.get(HeaderName::Date).is_none() {
+      let now = self.clock.system_time().duration_since(UNIX_EPOCH).unwrap_or_default();
+      response.headers.set(HeaderName::Date, util::http_date(now.as_secs()));
+    }
+
     if context.request_head().version() == HttpVersion::Http11 {
       let previous_headers = if keep_alive {
         response.headers.replace_all(HeaderName::Connection, "Keep-Alive")
@@ -334,19 +962,72 @@ impl TiiServer {
     }
 
     trace_log!("RequestRespondedWith HTTP {}", response.status_code.code());
+    let status = response.status_code.clone();
 
-    response.write_to(context.request_head().version(), stream.as_stream_write()).inspect_err(
-      |e| {
-        trace_log!("response.write_to {}", e);
-      },
-    )?;
+    if self.tcp_cork {
+      stream.set_cork(true)?;
+    }
+
+    let written = self.write_response_to_stream(stream, &context, response);
+    // Uncork even if writing failed, otherwise whatever was written stays held back by the kernel.
+    let uncorked = if self.tcp_cork { stream.set_cork(false) } else { Ok(()) };
+    let response_size = written?;
+    uncorked?;
 
     trace_log!("RequestServedSuccess");
 
     context.consume_request_body()?;
+
+    if let Some(size_stats) = self.size_stats.as_ref() {
+      let head = context.request_head();
+      let head_size = head.raw_status_line().len()
+        + head
+          .get_all_headers()
+          .map(|hdr| hdr.name.to_str().len() + hdr.value.len() + 4)
+          .sum::<usize>()
+        + 4;
+      size_stats.request_heads.record(head_size as u64);
+      size_stats
+        .request_bodies
+        .record(context.request_body().map(RequestBody::wire_len).unwrap_or(0));
+      size_stats.responses.record(response_size);
+    }
+
+    if let Some(observer) = self.request_observer.0.as_ref() {
+      observer(&RequestCompleted {
+        method: context.request_head().method().clone(),
+        path: context.request_head().path().to_string(),
+        status,
+        duration: self.clock.now().saturating_duration_since(started),
+      });
+    }
+
     Ok(())
   }
 
+  fn handle_request_head_error(&self, stream: &dyn ConnectionStream, error: &TiiError) {
+    let mut response = match error.downcast_ref::<RequestHeadParsingError>() {
+      Some(RequestHeadParsingError::HttpVersionNotSupported(version))
+        if self.version_not_supported_response =>
+      {
+        trace_log!("RequestRespondedWith HTTP 505 for version {}", version);
+        Response::version_not_supported(
+          "Supported versions: HTTP/1.1, HTTP/1.0, HTTP/0.9",
+          MimeType::TextPlain,
+        )
+      }
+      Some(RequestHeadParsingError::PathTooLong(length)) => {
+        trace_log!("RequestRespondedWith HTTP 414 for decoded path of {} bytes", length);
+        Response::new(StatusCode::RequestURITooLong)
+      }
+      _ => return,
+    };
+    response.headers.set(HeaderName::Connection, "Close");
+
+    // The connection is closed anyway, there is nothing more we can do if this fails.
+    _ = response.write_to(HttpVersion::Http11, stream.as_stream_write());
+  }
+
   fn fallback_error_handler(&self, request: &mut RequestContext, error: TiiError) -> Response {
     request.force_connection_close();
 
@@ -367,3 +1048,36 @@ impl Drop for TiiServer {
     trace_log!("TiiServer::drop");
   }
 }
+
+/// Passes the body of a `text/html` response through the transformer.
+/// Streamed, file and already encoded bodies as well as bodies that are not utf-8 are left alone.
+fn transform_html(
+  transformer: &HtmlTransformer,
+  context: &RequestContext,
+  response: &mut Response,
+) {
+  let is_html = response.get_header(HeaderName::ContentType).is_some_and(|content_type| {
+    content_type.split(';').next().is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/html"))
+  });
+  if !is_html || response.get_header(HeaderName::ContentEncoding).is_some() {
+    return;
+  }
+
+  let mut html = match response.body.take() {
+    Some(ResponseBody::FixedSizeTextData(html)) => html,
+    Some(ResponseBody::FixedSizeBinaryData(data)) => match String::from_utf8(data) {
+      Ok(html) => html,
+      Err(err) => {
+        response.body = Some(ResponseBody::FixedSizeBinaryData(err.into_bytes()));
+        return;
+      }
+    },
+    other => {
+      response.body = other;
+      return;
+    }
+  };
+
+  transformer(&mut html, context);
+  response.body = Some(ResponseBody::FixedSizeTextData(html));
+}
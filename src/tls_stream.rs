@@ -3,12 +3,12 @@ use crate::stream::{ConnectionStream, ConnectionStreamRead, ConnectionStreamWrit
 use crate::util::unwrap_poison;
 use rust_tls_duplex_stream::RustTlsDuplexStream;
 use rustls::server::ServerConnectionData;
-use rustls::ServerConnection;
+use rustls::{ConnectionCommon, ServerConnection};
 use std::fmt::Debug;
 use std::io;
 use std::io::{Read, Write};
-use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 use unowned_buf::{UnownedReadBuffer, UnownedWriteBuffer};
 
@@ -129,6 +129,38 @@ impl<T: TlsCapableStream + ?Sized> Write for StreamWrapper<T> {
   }
 }
 
+/// The rustls connection is owned by the duplex stream and can't be queried afterward.
+/// This wrapper remembers the SNI server name as soon as rustls has processed the ClientHello.
+#[derive(Debug)]
+struct SniRecordingConnection {
+  connection: ServerConnection,
+  sni: Arc<OnceLock<Option<String>>>,
+}
+
+impl SniRecordingConnection {
+  fn record_sni(&self) {
+    if self.sni.get().is_none() && !self.connection.is_handshaking() {
+      _ = self.sni.set(self.connection.server_name().map(ToString::to_string));
+    }
+  }
+}
+
+impl Deref for SniRecordingConnection {
+  type Target = ConnectionCommon<ServerConnectionData>;
+
+  fn deref(&self) -> &Self::Target {
+    self.record_sni();
+    &self.connection
+  }
+}
+
+impl DerefMut for SniRecordingConnection {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    self.record_sni();
+    &mut self.connection
+  }
+}
+
 /// Wrapper struct that wraps a TLS Engine from RustTLS together with a read and write buffers.
 #[derive(Debug, Clone)]
 pub struct TiiTlsStream(Arc<TiiTlsWrapperInner>);
@@ -154,6 +186,8 @@ impl TiiTlsStream {
     let peer = stream.peer_addr()?.to_string();
     let local = stream.local_addr()?.to_string();
     let stream_wrapper = StreamWrapper(Arc::new(stream));
+    let sni = Arc::new(OnceLock::new());
+    let tls = SniRecordingConnection { connection: tls, sni: sni.clone() };
     let tls =
       RustTlsDuplexStream::new(tls, stream_wrapper.clone(), stream_wrapper.clone(), move |task| {
         spawner.spawn(task)?;
@@ -167,6 +201,7 @@ impl TiiTlsStream {
       write: Mutex::new(UnownedWriteBuffer::new()),
       peer,
       local,
+      sni,
     }))) as Box<dyn ConnectionStream>)
   }
 }
@@ -174,11 +209,12 @@ impl TiiTlsStream {
 #[derive(Debug)]
 struct TiiTlsWrapperInner {
   stream_ref: Arc<dyn TlsCapableStream>,
-  tls: RustTlsDuplexStream<ServerConnection, ServerConnectionData>,
+  tls: RustTlsDuplexStream<SniRecordingConnection, ServerConnectionData>,
   read: Mutex<UnownedReadBuffer<0x4000>>,
   write: Mutex<UnownedWriteBuffer<0x4000>>,
   peer: String,
   local: String,
+  sni: Arc<OnceLock<Option<String>>>,
 }
 
 impl Drop for TiiTlsWrapperInner {
@@ -291,4 +327,8 @@ impl ConnectionStream for TiiTlsStream {
   fn local_addr(&self) -> io::Result<String> {
     Ok(self.0.local.clone())
   }
+
+  fn sni_hostname(&self) -> Option<String> {
+    self.0.sni.get().cloned().flatten()
+  }
 }
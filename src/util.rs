@@ -18,6 +18,14 @@ fn do_abort() -> ! {
 pub fn panic_msg<X>(
   panic_message: Box<dyn std::any::Any + Send + 'static>,
   handler: impl FnOnce(&str) -> X,
+) -> X {
+  panic_msg_ref(panic_message.as_ref(), handler)
+}
+
+/// Same as `panic_msg` but borrows the panic message so it can be resumed afterward.
+pub fn panic_msg_ref<X>(
+  panic_message: &(dyn std::any::Any + Send + 'static),
+  handler: impl FnOnce(&str) -> X,
 ) -> X {
   if let Some(msg) = panic_message.downcast_ref::<&'static str>() {
     handler(msg)
@@ -41,16 +49,104 @@ pub fn unwrap_some<T>(some: Option<T>) -> T {
   do_abort();
 }
 
-pub fn unwrap_ok<T, E>(some: Result<T, E>) -> T {
-  if let Ok(t) = some {
-    return t;
+pub fn unwrap_poison<T>(result: LockResult<T>) -> io::Result<T> {
+  result.map_err(|_| io::Error::other("Poisoned Mutex"))
+}
+
+/// Strips the port from an address like "127.0.0.1:8080" or "[::1]:8080".
+/// Addresses without a port (for example "unix") are returned as is.
+pub fn address_host(address: &str) -> &str {
+  if let Some(rest) = address.strip_prefix('[') {
+    return rest.split_once(']').map(|(host, _)| host).unwrap_or(address);
   }
 
-  do_abort();
+  match address.rsplit_once(':') {
+    Some((host, _)) if !host.contains(':') => host,
+    _ => address,
+  }
 }
 
-pub fn unwrap_poison<T>(result: LockResult<T>) -> io::Result<T> {
-  result.map_err(|_| io::Error::new(io::ErrorKind::Other, "Poisoned Mutex"))
+/// Collapses runs of consecutive slashes in a path into a single slash, for example "//foo///bar" becomes "/foo/bar".
+pub fn merge_slashes(path: &str) -> String {
+  let mut merged = String::with_capacity(path.len());
+  for char in path.chars() {
+    if char == '/' && merged.ends_with('/') {
+      continue;
+    }
+    merged.push(char);
+  }
+  merged
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] =
+  ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Formats seconds since the unix epoch as IMF-fixdate, for example "Sun, 06 Nov 1994 08:49:37 GMT".
+/// See [RFC 9110 Section 5.6.7](https://datatracker.ietf.org/doc/html/rfc9110#section-5.6.7).
+pub fn http_date(unix_seconds: u64) -> String {
+  let days = unix_seconds / 86400;
+  let seconds_of_day = unix_seconds % 86400;
+
+  // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+  let z = days + 719468;
+  let era = z / 146097;
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + u64::from(month <= 2);
+
+  format!(
+    "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+    WEEKDAYS.get((days % 7) as usize).unwrap_or(&"Thu"),
+    day,
+    MONTHS.get(month as usize - 1).unwrap_or(&"Jan"),
+    year,
+    seconds_of_day / 3600,
+    seconds_of_day % 3600 / 60,
+    seconds_of_day % 60
+  )
+}
+
+/// Parses an IMF-fixdate into seconds since the unix epoch.
+/// The obsolete date formats of HTTP are not supported and yield None.
+pub fn parse_http_date(date: &str) -> Option<u64> {
+  let (_weekday, date) = date.split_once(", ")?;
+  let mut parts = date.split(' ');
+  let day: u64 = parts.next()?.parse().ok()?;
+  let month = parts.next()?;
+  let month = MONTHS.iter().position(|name| *name == month)? as u64 + 1;
+  let year: u64 = parts.next()?.parse().ok()?;
+  let mut time = parts.next()?.split(':');
+  let hour: u64 = time.next()?.parse().ok()?;
+  let minute: u64 = time.next()?.parse().ok()?;
+  let second: u64 = time.next()?.parse().ok()?;
+  if parts.next()? != "GMT" || parts.next().is_some() || time.next().is_some() {
+    return None;
+  }
+
+  if !(1970..=9999).contains(&year)
+    || !(1..=31).contains(&day)
+    || hour > 23
+    || minute > 59
+    || second > 60
+  {
+    return None;
+  }
+
+  // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year / 400;
+  let yoe = year - era * 400;
+  let mp = if month > 2 { month - 3 } else { month + 9 };
+  let doy = (153 * mp + 2) / 5 + day - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  let days = (era * 146097 + doe).checked_sub(719468)?;
+
+  Some(days * 86400 + hour * 3600 + minute * 60 + second)
 }
 
 pub const fn three_digit_to_utf(num: u16) -> [u8; 3] {
@@ -0,0 +1,166 @@
+//! Implements the `permessage-deflate` extension as specified in [RFC 7692](https://datatracker.ietf.org/doc/html/rfc7692).
+//!
+//! Outgoing messages are compressed without context takeover, every message is compressed on its own.
+//! Incoming messages are inflated with the context of the previous messages unless the client resets it.
+
+use crate::tii_error::{TiiResult, WebsocketError};
+use crate::util::unwrap_poison;
+use miniz_oxide::deflate::core::CompressorOxide;
+use miniz_oxide::inflate::stream::InflateState;
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::sync::Mutex;
+
+/// Value of the `Sec-WebSocket-Extensions` response header if the extension is negotiated.
+pub(crate) const EXTENSION_RESPONSE: &str = "permessage-deflate; server_no_context_takeover";
+
+/// Trailer of a sync flush that is removed from compressed messages, see RFC 7692 Section 7.2.1.
+const SYNC_TRAILER: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+
+const CHUNK_SIZE: usize = 0x4000;
+
+/// Returns true if one of the offers in the `Sec-WebSocket-Extensions` request header is
+/// a `permessage-deflate` offer that can be accepted with `EXTENSION_RESPONSE`.
+pub(crate) fn accepts_offer(extensions: &str) -> bool {
+  extensions.split(',').any(|offer| {
+    let mut params = offer.split(';').map(str::trim);
+    if params.next() != Some("permessage-deflate") {
+      return false;
+    }
+
+    params.all(|param| {
+      let (name, value) = param.split_once('=').map_or((param, None), |(n, v)| (n.trim(), Some(v)));
+      match (name, value) {
+        ("server_no_context_takeover", None) | ("client_no_context_takeover", None) => true,
+        // We inflate with the full window, so any window the client compresses with is fine.
+        ("client_max_window_bits", _) => true,
+        // The compressor always uses a window of 2^15 bytes.
+        ("server_max_window_bits", Some(bits)) => bits.trim().trim_matches('"') == "15",
+        _ => false,
+      }
+    })
+  })
+}
+
+/// Compresses outgoing messages.
+pub(crate) struct Deflater(Mutex<Box<CompressorOxide>>);
+
+impl Debug for Deflater {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str("Deflater")
+  }
+}
+
+impl Deflater {
+  pub(crate) fn new() -> Self {
+    let mut compressor = Box::<CompressorOxide>::default();
+    compressor.set_format_and_level(DataFormat::Raw, 6);
+    Self(Mutex::new(compressor))
+  }
+
+  /// Compresses the payload of a message, the RSV1 bit must be set on the first frame of the message.
+  pub(crate) fn compress(&self, payload: &[u8]) -> TiiResult<Vec<u8>> {
+    let mut compressor = unwrap_poison(self.0.lock())?;
+    compress(compressor.as_mut(), payload)
+  }
+}
+
+/// Inflates incoming messages.
+pub(crate) struct Inflater(Box<InflateState>);
+
+impl Debug for Inflater {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str("Inflater")
+  }
+}
+
+impl Inflater {
+  pub(crate) fn new() -> Self {
+    Self(InflateState::new_boxed(DataFormat::Raw))
+  }
+
+  /// Inflates the payload of a message whose first frame had the RSV1 bit set.
+  /// Fails with `WebsocketError::MessageTooBig` once the inflated message exceeds `max_size`.
+  pub(crate) fn decompress(&mut self, payload: &[u8], max_size: u64) -> TiiResult<Vec<u8>> {
+    decompress(self.0.as_mut(), payload, max_size)
+  }
+}
+
+fn compress(compressor: &mut CompressorOxide, payload: &[u8]) -> TiiResult<Vec<u8>> {
+  compressor.reset();
+  let mut output = Vec::with_capacity(payload.len() / 2 + 64);
+  let mut input = payload;
+  loop {
+    let start = output.len();
+    output.resize(start + CHUNK_SIZE, 0);
+    let result = miniz_oxide::deflate::stream::deflate(
+      compressor,
+      input,
+      output.get_mut(start..).unwrap_or_default(),
+      MZFlush::Sync,
+    );
+    output.truncate(start + result.bytes_written);
+    result.status.map_err(|err| {
+      WebsocketError::Io(io::Error::other(format!("permessage-deflate compression failed {err:?}")))
+    })?;
+
+    input = input.get(result.bytes_consumed..).unwrap_or_default();
+    if input.is_empty() && result.bytes_written < CHUNK_SIZE {
+      break;
+    }
+  }
+
+  if output.ends_with(&SYNC_TRAILER) {
+    output.truncate(output.len() - SYNC_TRAILER.len());
+  }
+
+  Ok(output)
+}
+
+fn decompress(state: &mut InflateState, payload: &[u8], max_size: u64) -> TiiResult<Vec<u8>> {
+  let mut data = Vec::with_capacity(payload.len() + SYNC_TRAILER.len());
+  data.extend_from_slice(payload);
+  data.extend_from_slice(&SYNC_TRAILER);
+
+  let mut output = Vec::with_capacity(payload.len() * 4);
+  let mut input = data.as_slice();
+  loop {
+    let start = output.len();
+    output.resize(start + CHUNK_SIZE, 0);
+    let result = miniz_oxide::inflate::stream::inflate(
+      state,
+      input,
+      output.get_mut(start..).unwrap_or_default(),
+      MZFlush::Sync,
+    );
+    output.truncate(start + result.bytes_written);
+    input = input.get(result.bytes_consumed..).unwrap_or_default();
+
+    match result.status {
+      Ok(MZStatus::StreamEnd) => {
+        // The client ended the deflate stream, the next message starts a new one.
+        state.reset(DataFormat::Raw);
+        break;
+      }
+      Ok(_) | Err(MZError::Buf) => (),
+      Err(err) => {
+        return Err(
+          WebsocketError::ProtocolViolation(format!("invalid permessage-deflate data {err:?}"))
+            .into(),
+        )
+      }
+    }
+
+    if output.len() as u64 > max_size {
+      return Err(WebsocketError::MessageTooBig(output.len() as u64).into());
+    }
+
+    let progress = result.bytes_consumed > 0 || result.bytes_written > 0;
+    if !progress || (input.is_empty() && result.bytes_written < CHUNK_SIZE) {
+      break;
+    }
+  }
+
+  Ok(output)
+}
@@ -1,8 +1,12 @@
 //! Provides an implementation of WebSocket frames as specified in [RFC 6455 Section 5](https://datatracker.ietf.org/doc/html/rfc6455#section-5).
 
 use crate::stream::{ConnectionStreamRead, ConnectionStreamWrite};
-use crate::tii_error::{RequestHeadParsingError, TiiResult};
+use crate::tii_error::{TiiResult, WebsocketError};
 use crate::util;
+use std::io;
+
+/// Maximum size of a single message (the sum of all frames of the message) tii will accept from the peer.
+pub(crate) const MAX_MESSAGE_SIZE: u64 = 0x400_0000;
 
 /// Represents a frame of WebSocket data.
 /// Follows [Section 5.2 of RFC 6455](https://datatracker.ietf.org/doc/html/rfc6455#section-5.2)
@@ -75,45 +79,63 @@ impl Frame {
       payload: Vec::new(),
     };
 
-    tmp_frame.write_to_no_flush(write)?;
-    write.write_all(payload)?;
-    write.flush()?;
+    tmp_frame
+      .write_to_no_flush(write)
+      .and_then(|_| write.write_all(payload))
+      .and_then(|_| write.flush())
+      .map_err(WebsocketError::Io)?;
     Ok(())
   }
 
   /// Attempts to read a frame from the given stream, blocking until the frame is read.
-  pub fn from_stream<T: ConnectionStreamRead + ?Sized>(stream: &T) -> TiiResult<Self> {
+  /// Frames whose declared length exceeds `max_length` are rejected with `WebsocketError::MessageTooBig`
+  /// before their payload is allocated or read.
+  pub fn from_stream<T: ConnectionStreamRead + ?Sized>(
+    stream: &T,
+    max_length: u64,
+  ) -> TiiResult<Self> {
     let mut header: [u8; 2] = [0; 2];
-    stream.read_exact(&mut header)?;
+    stream.read_exact(&mut header).map_err(WebsocketError::Io)?;
 
     // Parse header information
     let fin = header[0] & 0x80 != 0;
     let rsv = [header[0] & 0x40 != 0, header[0] & 0x20 != 0, header[0] & 0x10 != 0];
-    let opcode =
-      Opcode::parse(header[0] & 0xF).ok_or(RequestHeadParsingError::InvalidWebSocketOpcode)?;
+    let opcode = Opcode::parse(header[0] & 0xF).ok_or_else(|| {
+      WebsocketError::ProtocolViolation(format!("invalid opcode {:X}", header[0] & 0xF))
+    })?;
     let mask = header[1] & 0x80 != 0;
 
     let mut length: u64 = (header[1] & 0x7F) as u64;
     if length == 126 {
-      stream.read_exact(&mut header)?;
+      stream.read_exact(&mut header).map_err(WebsocketError::Io)?;
       length = u16::from_be_bytes(header) as u64;
     } else if length == 127 {
       let mut buf: [u8; 8] = [0; 8];
-      stream.read_exact(&mut buf)?;
+      stream.read_exact(&mut buf).map_err(WebsocketError::Io)?;
       length = u64::from_be_bytes(buf);
+      if length & 0x8000_0000_0000_0000 != 0 {
+        return Err(
+          WebsocketError::ProtocolViolation("most significant bit of length is set".to_string())
+            .into(),
+        );
+      }
+    }
+
+    if length > max_length {
+      return Err(WebsocketError::MessageTooBig(length).into());
     }
 
     let masking_key = {
       let mut buf: [u8; 4] = [0; 4];
       if mask {
-        stream.read_exact(&mut buf)?;
+        stream.read_exact(&mut buf).map_err(WebsocketError::Io)?;
       }
       buf
     };
 
     // Read the payload
     let mut payload: Vec<u8> = vec![0; length as usize];
-    stream.read_exact(&mut payload)?;
+    stream.read_exact(&mut payload).map_err(WebsocketError::Io)?;
 
     // Unmask the payload
     payload
@@ -125,13 +147,27 @@ impl Frame {
     Ok(Self { fin, rsv, opcode, mask, length, masking_key, payload })
   }
 
+  /// Size of the frame on the wire, header and payload.
+  pub(crate) fn wire_len(&self) -> u64 {
+    Self::header_len(self.length, self.mask) + self.length
+  }
+
+  /// Size of the header of a frame with the given payload length.
+  pub(crate) fn header_len(length: u64, mask: bool) -> u64 {
+    let extended_length = match length {
+      0..126 => 0,
+      126..65536 => 2,
+      _ => 8,
+    };
+    2 + extended_length + if mask { 4 } else { 0 }
+  }
+
   pub fn write_to<T: ConnectionStreamWrite + ?Sized>(self, write: &T) -> TiiResult<()> {
-    self.write_to_no_flush(write)?;
-    write.flush()?;
+    self.write_to_no_flush(write).and_then(|_| write.flush()).map_err(WebsocketError::Io)?;
     Ok(())
   }
 
-  fn write_to_no_flush<T: ConnectionStreamWrite + ?Sized>(self, write: &T) -> TiiResult<()> {
+  fn write_to_no_flush<T: ConnectionStreamWrite + ?Sized>(self, write: &T) -> io::Result<()> {
     let mut buf = [0, 0];
 
     // Set the header bits
@@ -178,7 +214,8 @@ mod test {
   #![allow(dead_code)]
 
   use crate::stream::{ConnectionStream, IntoConnectionStream};
-  use crate::websocket::frame::{Frame, Opcode};
+  use crate::tii_error::WebsocketError;
+  use crate::websocket::frame::{Frame, Opcode, MAX_MESSAGE_SIZE};
   use std::collections::VecDeque;
   use std::io::{Read, Write};
   use std::sync::{Arc, Mutex};
@@ -291,7 +328,8 @@ mod test {
     bytes.extend(FRAME_2_BYTES);
 
     let stream = MockStream::with_data(bytes);
-    let frame = Frame::from_stream(stream.into_connection_stream().as_ref()).unwrap();
+    let frame =
+      Frame::from_stream(stream.into_connection_stream().as_ref(), MAX_MESSAGE_SIZE).unwrap();
 
     let expected_frame = Frame {
       fin: false,
@@ -309,7 +347,8 @@ mod test {
   #[test]
   fn test_continuation_frame() {
     let stream = MockStream::with_data(FRAME_2_BYTES.to_vec());
-    let frame = Frame::from_stream(stream.into_connection_stream().as_ref()).unwrap();
+    let frame =
+      Frame::from_stream(stream.into_connection_stream().as_ref(), MAX_MESSAGE_SIZE).unwrap();
 
     let expected_frame = Frame {
       fin: true,
@@ -327,7 +366,8 @@ mod test {
   #[test]
   fn test_standalone_frame() {
     let stream = MockStream::with_data(STANDALONE_FRAME_BYTES.to_vec());
-    let frame = Frame::from_stream(stream.into_connection_stream().as_ref()).unwrap();
+    let frame =
+      Frame::from_stream(stream.into_connection_stream().as_ref(), MAX_MESSAGE_SIZE).unwrap();
 
     let expected_frame = Frame {
       fin: true,
@@ -349,7 +389,8 @@ mod test {
     bytes.extend(vec![b'x' ^ 0x69; 256]);
 
     let stream = MockStream::with_data(bytes);
-    let frame = Frame::from_stream(stream.into_connection_stream().as_ref()).unwrap();
+    let frame =
+      Frame::from_stream(stream.into_connection_stream().as_ref(), MAX_MESSAGE_SIZE).unwrap();
 
     let expected_frame = Frame {
       fin: true,
@@ -372,7 +413,8 @@ mod test {
 
     let stream = MockStream::with_data(bytes);
 
-    let frame = Frame::from_stream(stream.into_connection_stream().as_ref()).unwrap();
+    let frame =
+      Frame::from_stream(stream.into_connection_stream().as_ref(), MAX_MESSAGE_SIZE).unwrap();
 
     let expected_frame = Frame {
       fin: true,
@@ -387,6 +429,34 @@ mod test {
     assert_eq!(frame, expected_frame);
   }
 
+  #[test]
+  fn test_too_big_frame() {
+    let mut bytes = LONG_FRAME_BYTES.to_vec();
+    bytes[2] = 0x01; // extended payload length of 2^56 + 65536
+
+    let stream = MockStream::with_data(bytes);
+    let err =
+      Frame::from_stream(stream.into_connection_stream().as_ref(), MAX_MESSAGE_SIZE).unwrap_err();
+    assert!(matches!(
+      err.downcast_ref::<WebsocketError>(),
+      Some(WebsocketError::MessageTooBig(0x0100_0000_0001_0000))
+    ));
+  }
+
+  #[test]
+  fn test_invalid_opcode() {
+    let mut bytes = STANDALONE_FRAME_BYTES.to_vec();
+    bytes[0] = 0b1000_0011; // fin, reserved opcode 3
+
+    let stream = MockStream::with_data(bytes);
+    let err =
+      Frame::from_stream(stream.into_connection_stream().as_ref(), MAX_MESSAGE_SIZE).unwrap_err();
+    assert!(matches!(
+      err.downcast_ref::<WebsocketError>(),
+      Some(WebsocketError::ProtocolViolation(_))
+    ));
+  }
+
   #[test]
   fn test_write() {
     let frame = Frame {
@@ -7,10 +7,10 @@ pub enum WebsocketMessage {
   Text(String),
   /// Binary data message
   Binary(Vec<u8>),
-  /// Ping message
-  Ping,
-  /// Pong message
-  Pong,
+  /// Ping message with its application data
+  Ping(Vec<u8>),
+  /// Pong message with its application data
+  Pong(Vec<u8>),
 }
 
 impl WebsocketMessage {
@@ -46,8 +46,94 @@ impl WebsocketMessage {
     match self {
       WebsocketMessage::Text(txt) => Some(txt.as_bytes()),
       WebsocketMessage::Binary(bin) => Some(bin.as_slice()),
-      WebsocketMessage::Ping => None,
-      WebsocketMessage::Pong => None,
+      WebsocketMessage::Ping(_) => None,
+      WebsocketMessage::Pong(_) => None,
     }
   }
 }
+
+/// Status code of a close frame, see [RFC 6455 Section 7.4](https://datatracker.ietf.org/doc/html/rfc6455#section-7.4).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CloseCode {
+  /// 1000 The purpose for which the connection was established has been fulfilled.
+  Normal,
+  /// 1001 The endpoint is going away, like a server shutting down or a browser leaving the page.
+  GoingAway,
+  /// 1002 The endpoint terminates the connection due to a protocol error.
+  ProtocolError,
+  /// 1003 The endpoint received a type of data it cannot accept, like binary data when it only understands text.
+  UnsupportedData,
+  /// 1005 No status code was present in the close frame. Must not be sent in a close frame.
+  NoStatusReceived,
+  /// 1006 The connection was closed without a close frame. Must not be sent in a close frame.
+  Abnormal,
+  /// 1007 The endpoint received data within a message that is not consistent with the type of the message.
+  InvalidPayload,
+  /// 1008 The endpoint received a message that violates its policy.
+  PolicyViolation,
+  /// 1009 The endpoint received a message that is too big to process.
+  MessageTooBig,
+  /// 1010 The client expected the server to negotiate an extension which it didn't.
+  MandatoryExtension,
+  /// 1011 The server encountered an unexpected condition that prevented it from fulfilling the request.
+  InternalError,
+  /// 1012 The server is restarting.
+  ServiceRestart,
+  /// 1013 The server is overloaded, the client should try again later.
+  TryAgainLater,
+  /// 1014 The server acting as a gateway received an invalid response from upstream.
+  BadGateway,
+  /// Any other code, like the application defined codes 4000-4999.
+  Other(u16),
+}
+
+impl CloseCode {
+  /// Returns the numeric status code.
+  pub const fn code(&self) -> u16 {
+    match self {
+      CloseCode::Normal => 1000,
+      CloseCode::GoingAway => 1001,
+      CloseCode::ProtocolError => 1002,
+      CloseCode::UnsupportedData => 1003,
+      CloseCode::NoStatusReceived => 1005,
+      CloseCode::Abnormal => 1006,
+      CloseCode::InvalidPayload => 1007,
+      CloseCode::PolicyViolation => 1008,
+      CloseCode::MessageTooBig => 1009,
+      CloseCode::MandatoryExtension => 1010,
+      CloseCode::InternalError => 1011,
+      CloseCode::ServiceRestart => 1012,
+      CloseCode::TryAgainLater => 1013,
+      CloseCode::BadGateway => 1014,
+      CloseCode::Other(code) => *code,
+    }
+  }
+}
+
+impl From<u16> for CloseCode {
+  fn from(code: u16) -> Self {
+    match code {
+      1000 => CloseCode::Normal,
+      1001 => CloseCode::GoingAway,
+      1002 => CloseCode::ProtocolError,
+      1003 => CloseCode::UnsupportedData,
+      1005 => CloseCode::NoStatusReceived,
+      1006 => CloseCode::Abnormal,
+      1007 => CloseCode::InvalidPayload,
+      1008 => CloseCode::PolicyViolation,
+      1009 => CloseCode::MessageTooBig,
+      1010 => CloseCode::MandatoryExtension,
+      1011 => CloseCode::InternalError,
+      1012 => CloseCode::ServiceRestart,
+      1013 => CloseCode::TryAgainLater,
+      1014 => CloseCode::BadGateway,
+      other => CloseCode::Other(other),
+    }
+  }
+}
+
+impl From<CloseCode> for u16 {
+  fn from(code: CloseCode) -> Self {
+    code.code()
+  }
+}
@@ -0,0 +1,138 @@
+//! Counters of the frames and bytes transferred over web sockets.
+
+use crate::websocket::frame::{Frame, Opcode};
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Snapshot of the frames transferred in one direction, counted by opcode.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FrameCounts {
+  /// Continuation frames of fragmented messages.
+  pub continuation: u64,
+  /// Text frames, the first frame of a fragmented text message.
+  pub text: u64,
+  /// Binary frames, the first frame of a fragmented binary message.
+  pub binary: u64,
+  /// Close frames.
+  pub close: u64,
+  /// Ping frames.
+  pub ping: u64,
+  /// Pong frames.
+  pub pong: u64,
+  /// Bytes on the wire including the frame headers.
+  pub bytes: u64,
+}
+
+impl FrameCounts {
+  /// Total amount of frames of all opcodes.
+  pub fn frames(&self) -> u64 {
+    self.continuation + self.text + self.binary + self.close + self.ping + self.pong
+  }
+}
+
+#[derive(Debug, Default)]
+struct AtomicFrameCounts {
+  continuation: AtomicU64,
+  text: AtomicU64,
+  binary: AtomicU64,
+  close: AtomicU64,
+  ping: AtomicU64,
+  pong: AtomicU64,
+  bytes: AtomicU64,
+}
+
+impl AtomicFrameCounts {
+  fn record(&self, opcode: Opcode, bytes: u64) {
+    let counter = match opcode {
+      Opcode::Continuation => &self.continuation,
+      Opcode::Text => &self.text,
+      Opcode::Binary => &self.binary,
+      Opcode::Close => &self.close,
+      Opcode::Ping => &self.ping,
+      Opcode::Pong => &self.pong,
+    };
+    counter.fetch_add(1, Relaxed);
+    self.bytes.fetch_add(bytes, Relaxed);
+  }
+
+  fn snapshot(&self) -> FrameCounts {
+    FrameCounts {
+      continuation: self.continuation.load(Relaxed),
+      text: self.text.load(Relaxed),
+      binary: self.binary.load(Relaxed),
+      close: self.close.load(Relaxed),
+      ping: self.ping.load(Relaxed),
+      pong: self.pong.load(Relaxed),
+      bytes: self.bytes.load(Relaxed),
+    }
+  }
+}
+
+/// Frames and bytes sent and received over a single web socket connection.
+#[derive(Debug, Default)]
+pub struct WebsocketCounters {
+  sent: AtomicFrameCounts,
+  received: AtomicFrameCounts,
+}
+
+impl WebsocketCounters {
+  /// Frames written to the client.
+  pub fn sent(&self) -> FrameCounts {
+    self.sent.snapshot()
+  }
+
+  /// Frames read from the client.
+  pub fn received(&self) -> FrameCounts {
+    self.received.snapshot()
+  }
+
+  pub(crate) fn frame_sent(&self, opcode: Opcode, bytes: u64) {
+    self.sent.record(opcode, bytes);
+  }
+
+  pub(crate) fn frame_received(&self, frame: &Frame) {
+    self.received.record(frame.opcode, frame.wire_len());
+  }
+}
+
+/// Frames and bytes of all web socket connections served by a router and the amount of open connections.
+/// Share an instance between routers with `TiiRouterBuilder::with_websocket_metrics` to aggregate them.
+#[derive(Debug, Default)]
+pub struct WebsocketMetrics {
+  counters: WebsocketCounters,
+  open_connections: AtomicU64,
+}
+
+impl WebsocketMetrics {
+  /// Creates metrics with all counters at zero.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Frames written to all clients.
+  pub fn sent(&self) -> FrameCounts {
+    self.counters.sent()
+  }
+
+  /// Frames read from all clients.
+  pub fn received(&self) -> FrameCounts {
+    self.counters.received()
+  }
+
+  /// Web socket connections whose handler is currently running.
+  pub fn open_connections(&self) -> u64 {
+    self.open_connections.load(Relaxed)
+  }
+
+  pub(crate) fn counters(&self) -> &WebsocketCounters {
+    &self.counters
+  }
+
+  pub(crate) fn connection_opened(&self) {
+    self.open_connections.fetch_add(1, Relaxed);
+  }
+
+  pub(crate) fn connection_closed(&self) {
+    self.open_connections.fetch_sub(1, Relaxed);
+  }
+}
@@ -5,6 +5,9 @@
 #![warn(missing_docs)]
 
 pub mod message;
+pub mod metrics;
 pub mod stream;
 
+#[cfg(feature = "ws_deflate")]
+pub(crate) mod deflate;
 mod frame;
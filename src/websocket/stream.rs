@@ -1,25 +1,153 @@
 //! Provides functionality for working with a WebSocket stream.
 
-use crate::websocket::frame::{Frame, Opcode};
-use crate::websocket::message::WebsocketMessage;
+use crate::websocket::frame::{Frame, Opcode, MAX_MESSAGE_SIZE};
+use crate::websocket::message::{CloseCode, WebsocketMessage};
+use crate::websocket::metrics::{WebsocketCounters, WebsocketMetrics};
 use std::collections::VecDeque;
 use std::{io, mem};
 
+use crate::functional_traits::{Clock, SystemClock};
 use crate::stream::ConnectionStream;
-use crate::tii_error::{RequestHeadParsingError, TiiError, TiiResult};
+use crate::tii_error::{TiiError, TiiResult, WebsocketError};
 use crate::util::{unwrap_poison, unwrap_some};
+#[cfg(feature = "ws_deflate")]
+use crate::websocket::deflate::{Deflater, Inflater};
 use crate::{error_log, trace_log, warn_log};
 use std::io::{Cursor, ErrorKind, Read, Write};
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::SeqCst;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 struct WebSocketGuard {
   closed: AtomicBool,
+  /// Code and reason of the close frame sent by the peer.
+  peer_close: Mutex<(Option<u16>, String)>,
   write_mutex: Mutex<()>,
   stream: Box<dyn ConnectionStream>,
+  clock: Arc<dyn Clock>,
+  /// Compresses text and binary messages if permessage-deflate was negotiated.
+  #[cfg(feature = "ws_deflate")]
+  deflater: Option<Deflater>,
+  /// Reference point for the timestamps carried in the payload of timed pings.
+  epoch: Instant,
+  /// Time the last pong was received and round trip time of the last timed ping that was answered.
+  pong_timing: Mutex<(Option<Instant>, Option<Duration>)>,
+  /// Frames and bytes of this connection.
+  counters: WebsocketCounters,
+  /// Metrics of the router that served this connection, they are updated together with `counters`.
+  metrics: Option<Arc<WebsocketMetrics>>,
+}
+
+impl WebSocketGuard {
+  fn closed_error(&self) -> TiiError {
+    let (code, reason) = self.peer_close.lock().map(|g| g.clone()).unwrap_or_default();
+    WebsocketError::Closed { code, reason }.into()
+  }
+
+  /// Creates the frame of a text or binary message, the payload is compressed if permessage-deflate was negotiated.
+  fn data_frame(&self, opcode: Opcode, payload: Vec<u8>) -> TiiResult<Frame> {
+    #[cfg(feature = "ws_deflate")]
+    if let Some(deflater) = self.deflater.as_ref() {
+      let mut frame = Frame::new(opcode, deflater.compress(payload.as_slice())?);
+      frame.rsv[0] = true;
+      return Ok(frame);
+    }
+
+    Ok(Frame::new(opcode, payload))
+  }
+
+  fn close_with_code(&self, code: u16, reason: &str) -> TiiResult<()> {
+    let _g = unwrap_poison(self.write_mutex.lock())?;
+
+    if self.closed.swap(true, SeqCst) {
+      return Ok(()); //ALREADY CLOSED!
+    }
+
+    let mut payload = code.to_be_bytes().to_vec();
+    let mut reason_len = reason.len().min(123);
+    while !reason.is_char_boundary(reason_len) {
+      reason_len -= 1;
+    }
+    payload.extend_from_slice(reason.get(..reason_len).unwrap_or_default().as_bytes());
+    self.write_frame_unchecked(Frame::new(Opcode::Close, payload))
+  }
+
+  fn write_frame(&self, frame: Frame) -> TiiResult<()> {
+    let _g = unwrap_poison(self.write_mutex.lock())?;
+    if self.closed.load(SeqCst) {
+      return Err(self.closed_error());
+    }
+
+    self.write_frame_unchecked(frame)
+  }
+
+  /// Writes the frame without checking if the web socket is closed, the caller holds the write mutex.
+  fn write_frame_unchecked(&self, frame: Frame) -> TiiResult<()> {
+    let (opcode, bytes) = (frame.opcode, frame.wire_len());
+    frame.write_to(self.stream.as_stream_write())?;
+    self.frame_sent(opcode, bytes);
+    Ok(())
+  }
+
+  fn frame_sent(&self, opcode: Opcode, bytes: u64) {
+    self.counters.frame_sent(opcode, bytes);
+    if let Some(metrics) = self.metrics.as_ref() {
+      metrics.counters().frame_sent(opcode, bytes);
+    }
+  }
+
+  fn frame_received(&self, frame: &Frame) {
+    self.counters.frame_received(frame);
+    if let Some(metrics) = self.metrics.as_ref() {
+      metrics.counters().frame_received(frame);
+    }
+  }
+
+  /// Records a received pong. If the payload is the timestamp of a timed ping then the round trip time is updated.
+  fn pong_received(&self, payload: &[u8]) -> TiiResult<()> {
+    let now = self.clock.now();
+    let rtt = <[u8; 8]>::try_from(payload)
+      .ok()
+      .map(|nanos| Duration::from_nanos(u64::from_be_bytes(nanos)))
+      .and_then(|sent| now.checked_duration_since(self.epoch)?.checked_sub(sent));
+
+    let mut timing = unwrap_poison(self.pong_timing.lock())?;
+    timing.0 = Some(now);
+    if rtt.is_some() {
+      timing.1 = rtt;
+    }
+    Ok(())
+  }
+
+  fn last_pong_age(&self) -> Option<Duration> {
+    let timing = self.pong_timing.lock().ok()?;
+    timing.0.map(|last_pong| self.clock.now().saturating_duration_since(last_pong))
+  }
+
+  fn last_rtt(&self) -> Option<Duration> {
+    self.pong_timing.lock().ok()?.1
+  }
+}
+
+/// Parses the payload of a close frame into status code and reason.
+fn parse_close_payload(payload: &[u8]) -> TiiResult<(Option<u16>, String)> {
+  match payload {
+    [] => Ok((None, String::new())),
+    [high, low, reason @ ..] => {
+      let reason = std::str::from_utf8(reason)
+        .map_err(|_| WebsocketError::InvalidUtf8(reason.to_vec()))?
+        .to_string();
+      Ok((Some(u16::from_be_bytes([*high, *low])), reason))
+    }
+    _ => Err(WebsocketError::ProtocolViolation("close frame payload of 1 byte".to_string()).into()),
+  }
+}
+
+/// Truncates application data to the 125 bytes that fit into a control frame.
+fn control_payload(payload: &[u8]) -> Vec<u8> {
+  payload.get(..125).unwrap_or(payload).to_vec()
 }
 
 /// Sending side of a web socket
@@ -29,10 +157,41 @@ pub struct WebsocketSender(Arc<WebSocketGuard>);
 
 /// Creates a new WebSocket receiver sender pair.
 pub fn new(connection: &dyn ConnectionStream) -> (WebsocketSender, WebsocketReceiver) {
+  new_with_clock(connection, Arc::new(SystemClock))
+}
+
+/// Creates a new WebSocket receiver sender pair that measures pong timings with the given clock.
+pub fn new_with_clock(
+  connection: &dyn ConnectionStream,
+  clock: Arc<dyn Clock>,
+) -> (WebsocketSender, WebsocketReceiver) {
+  new_negotiated(connection, clock, false, None)
+}
+
+/// Creates a new WebSocket receiver sender pair, `deflate` is true if the handshake negotiated permessage-deflate.
+/// Without the "ws_deflate" feature the extension is never negotiated and `deflate` is ignored.
+/// Transferred frames are also counted in `metrics` if given.
+pub(crate) fn new_negotiated(
+  connection: &dyn ConnectionStream,
+  clock: Arc<dyn Clock>,
+  deflate: bool,
+  metrics: Option<Arc<WebsocketMetrics>>,
+) -> (WebsocketSender, WebsocketReceiver) {
+  #[cfg(not(feature = "ws_deflate"))]
+  let _ = deflate;
+
   let guard = Arc::new(WebSocketGuard {
     closed: AtomicBool::new(false),
+    peer_close: Mutex::new((None, String::new())),
     write_mutex: Mutex::new(()),
     stream: connection.new_ref(),
+    epoch: clock.now(),
+    clock,
+    #[cfg(feature = "ws_deflate")]
+    deflater: deflate.then(Deflater::new),
+    pong_timing: Mutex::new((None, None)),
+    counters: WebsocketCounters::default(),
+    metrics,
   });
 
   let sender = WebsocketSender(guard.clone());
@@ -42,6 +201,10 @@ pub fn new(connection: &dyn ConnectionStream) -> (WebsocketSender, WebsocketRece
     state: Vec::new(),
     cursor: Default::default(),
     unhandled_messages: Default::default(),
+    auto_pong: true,
+    max_message_size: MAX_MESSAGE_SIZE,
+    #[cfg(feature = "ws_deflate")]
+    inflater: deflate.then(Inflater::new),
   };
 
   (sender, receiver)
@@ -59,8 +222,10 @@ impl WebsocketSender {
     match message {
       WebsocketMessage::Text(txt) => self.text(txt),
       WebsocketMessage::Binary(bin) => self.binary(bin),
-      WebsocketMessage::Ping => self.ping(),
-      WebsocketMessage::Pong => self.pong(),
+      WebsocketMessage::Ping(payload) => {
+        self.0.write_frame(Frame::new(Opcode::Ping, control_payload(&payload)))
+      }
+      WebsocketMessage::Pong(payload) => self.pong_with_payload(&payload),
     }
   }
 
@@ -72,38 +237,100 @@ impl WebsocketSender {
       return Ok(()); //ALREADY CLOSED!
     }
 
-    Frame::new(Opcode::Close, Vec::new()).write_to(self.0.stream.as_stream_write())
+    self.0.write_frame_unchecked(Frame::new(Opcode::Close, Vec::new()))
+  }
+
+  /// Closes the Websocket sending a close frame with the given status code and reason,
+  /// see [RFC 6455 Section 7.4](https://datatracker.ietf.org/doc/html/rfc6455#section-7.4) for the codes.
+  /// The reason is truncated to the 123 bytes that fit into a close frame.
+  /// Returns an `InvalidInput` error for the codes 1005, 1006 and 1015 which must not be sent in a close frame.
+  pub fn close_with_code(&self, code: impl Into<CloseCode>, reason: &str) -> TiiResult<()> {
+    let code = code.into();
+    if matches!(code, CloseCode::NoStatusReceived | CloseCode::Abnormal | CloseCode::Other(1015)) {
+      return Err(TiiError::new_io(
+        ErrorKind::InvalidInput,
+        format!("close code {} must not be sent in a close frame", code.code()),
+      ));
+    }
+
+    self.0.close_with_code(code.code(), reason)
   }
 
   /// Sends a binary message to the client
+  /// Returns `WebsocketError::Closed` if the web socket is already closed.
   pub fn binary(&self, message: impl Into<Vec<u8>>) -> TiiResult<()> {
-    let _g = unwrap_poison(self.0.write_mutex.lock())?;
-    Frame::new(Opcode::Binary, message.into()).write_to(self.0.stream.as_stream_write())
+    self.0.write_frame(self.0.data_frame(Opcode::Binary, message.into())?)
   }
 
   /// Sends a text message to the client
+  /// Returns `WebsocketError::Closed` if the web socket is already closed.
   pub fn text(&self, message: impl ToString) -> TiiResult<()> {
-    let _g = unwrap_poison(self.0.write_mutex.lock())?;
-    Frame::new(Opcode::Text, message.to_string().into_bytes())
-      .write_to(self.0.stream.as_stream_write())
+    self.0.write_frame(self.0.data_frame(Opcode::Text, message.to_string().into_bytes())?)
   }
 
   /// Sends a ping to the client.
+  /// Returns `WebsocketError::Closed` if the web socket is already closed.
   pub fn ping(&self) -> TiiResult<()> {
-    let _g = unwrap_poison(self.0.write_mutex.lock())?;
-    Frame::new(Opcode::Ping, Vec::new()).write_to(self.0.stream.as_stream_write())
+    self.0.write_frame(Frame::new(Opcode::Ping, Vec::new()))
   }
 
   /// Sends a pong message to the client.
+  /// Returns `WebsocketError::Closed` if the web socket is already closed.
   pub fn pong(&self) -> TiiResult<()> {
-    let _g = unwrap_poison(self.0.write_mutex.lock())?;
-    Frame::new(Opcode::Ping, Vec::new()).write_to(self.0.stream.as_stream_write())
+    self.0.write_frame(Frame::new(Opcode::Pong, Vec::new()))
+  }
+
+  /// Sends a pong message carrying the given application data to the client.
+  /// A pong answering a ping must echo the payload of that ping, see
+  /// [RFC 6455 Section 5.5.3](https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.3).
+  /// The payload is truncated to the 125 bytes that fit into a control frame.
+  /// Returns `WebsocketError::Closed` if the web socket is already closed.
+  pub fn pong_with_payload(&self, payload: &[u8]) -> TiiResult<()> {
+    self.0.write_frame(Frame::new(Opcode::Pong, control_payload(payload)))
+  }
+
+  /// Sends a ping to the client whose payload is the current timestamp.
+  /// Clients echo the payload of a ping in their pong as required by
+  /// [RFC 6455 Section 5.5.3](https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.3).
+  /// Once the `WebsocketReceiver` reads that pong the round trip time is available from `last_rtt`.
+  /// Pongs that do not carry a timestamp only update `last_pong_age`.
+  ///
+  /// Returns `WebsocketError::Closed` if the web socket is already closed.
+  pub fn ping_rtt(&self) -> TiiResult<()> {
+    let nanos =
+      u64::try_from(self.0.clock.now().saturating_duration_since(self.0.epoch).as_nanos())
+        .unwrap_or(u64::MAX);
+    self.0.write_frame(Frame::new(Opcode::Ping, nanos.to_be_bytes().to_vec()))
+  }
+
+  /// Round trip time measured by the most recent pong answering a ping sent with `ping_rtt`.
+  /// Returns None if no such pong has been received yet.
+  #[must_use]
+  pub fn last_rtt(&self) -> Option<Duration> {
+    self.0.last_rtt()
+  }
+
+  /// Time elapsed since the `WebsocketReceiver` read the last pong from the client.
+  /// Returns None if no pong has been received yet.
+  #[must_use]
+  pub fn last_pong_age(&self) -> Option<Duration> {
+    self.0.last_pong_age()
+  }
+
+  /// Returns the clock this web socket measures time with.
+  pub fn clock(&self) -> &Arc<dyn Clock> {
+    &self.0.clock
   }
 
   /// Attempts to get the peer address of this stream.
   pub fn peer_addr(&self) -> TiiResult<String> {
     Ok(self.0.stream.peer_addr()?)
   }
+
+  /// Frames and bytes sent and received over this web socket.
+  pub fn counters(&self) -> &WebsocketCounters {
+    &self.0.counters
+  }
 }
 
 /// Receiving side of a web socket
@@ -113,6 +340,11 @@ pub struct WebsocketReceiver {
   state: Vec<Frame>,
   cursor: Cursor<Vec<u8>>,
   unhandled_messages: VecDeque<WebsocketMessage>,
+  auto_pong: bool,
+  max_message_size: u64,
+  /// Inflates compressed messages if permessage-deflate was negotiated.
+  #[cfg(feature = "ws_deflate")]
+  inflater: Option<Inflater>,
 }
 
 /// Return enum for the fn WebsocketReceiver::read_message_timeout
@@ -135,7 +367,66 @@ impl WebsocketReceiver {
       return Ok(()); //ALREADY CLOSED!
     }
 
-    Frame::new(Opcode::Close, Vec::new()).write_to(self.guard.stream.as_stream_write())
+    self.guard.write_frame_unchecked(Frame::new(Opcode::Close, Vec::new()))
+  }
+
+  /// Round trip time measured by the most recent pong answering a ping sent with `WebsocketSender::ping_rtt`.
+  /// Returns None if no such pong has been received yet.
+  #[must_use]
+  pub fn last_rtt(&self) -> Option<Duration> {
+    self.guard.last_rtt()
+  }
+
+  /// Time elapsed since the last pong was read from the client.
+  /// Returns None if no pong has been received yet.
+  #[must_use]
+  pub fn last_pong_age(&self) -> Option<Duration> {
+    self.guard.last_pong_age()
+  }
+
+  /// Returns the clock this web socket measures time with.
+  pub fn clock(&self) -> &Arc<dyn Clock> {
+    &self.guard.clock
+  }
+
+  /// Frames and bytes sent and received over this web socket.
+  pub fn counters(&self) -> &WebsocketCounters {
+    &self.guard.counters
+  }
+
+  /// Returns true if pings are answered automatically, this is the default.
+  #[must_use]
+  pub fn is_auto_pong(&self) -> bool {
+    self.auto_pong
+  }
+
+  /// Controls whether pings received from the client are answered automatically.
+  ///
+  /// If enabled (the default) every ping is answered with a pong echoing its payload
+  /// and is not returned by `read_message`, `read_message_timeout` or the "io::Read" trait.
+  ///
+  /// If disabled every ping is returned as `WebsocketMessage::Ping` and no pong is sent,
+  /// the handler answers it with `WebsocketSender::pong_with_payload`.
+  /// The caller decides if and when to respond, for example with `WebsocketSender::pong`.
+  pub fn set_auto_pong(&mut self, auto_pong: bool) {
+    self.auto_pong = auto_pong;
+  }
+
+  /// Returns the maximum size of a message in bytes, see `set_max_message_size`.
+  #[must_use]
+  pub fn max_message_size(&self) -> u64 {
+    self.max_message_size
+  }
+
+  /// Limits the size of a message received from the client, including messages made of many frames
+  /// and compressed messages after they are inflated. Frames that would exceed the limit are rejected
+  /// based on their declared length before they are read.
+  ///
+  /// Once the limit is exceeded a close frame with status code 1009 "Message Too Big" is sent,
+  /// and `WebsocketError::MessageTooBig` is returned.
+  /// The default is 64 MiB, larger values are capped to that.
+  pub fn set_max_message_size(&mut self, max_message_size: u64) {
+    self.max_message_size = max_message_size.min(MAX_MESSAGE_SIZE);
   }
 
   /// If the WebsocketReceiver is used with the "io::Read" trait then
@@ -152,7 +443,12 @@ impl WebsocketReceiver {
       return Ok(Some(message));
     }
 
-    self.read_next_frame()
+    loop {
+      match self.read_next_frame()? {
+        Some(WebsocketMessage::Ping(_)) if self.auto_pong => continue,
+        other => return Ok(other),
+      }
+    }
   }
 
   /// This fn waits until timeout expires before the first byte of the next Message is received.
@@ -168,6 +464,8 @@ impl WebsocketReceiver {
   /// Passing None for timeout means Infinite timeout until either the client closes the connection
   /// sends a byte or the OS reset the connection;
   ///
+  /// Pings that are answered automatically restart the timeout.
+  ///
   pub fn read_message_timeout(
     &mut self,
     timeout: Option<Duration>,
@@ -176,6 +474,18 @@ impl WebsocketReceiver {
       return Ok(ReadMessageTimeoutResult::Message(message));
     }
 
+    loop {
+      match self.read_next_message_timeout(timeout)? {
+        ReadMessageTimeoutResult::Message(WebsocketMessage::Ping(_)) if self.auto_pong => continue,
+        other => return Ok(other),
+      }
+    }
+  }
+
+  fn read_next_message_timeout(
+    &mut self,
+    timeout: Option<Duration>,
+  ) -> TiiResult<ReadMessageTimeoutResult> {
     if self.guard.stream.available() == 0 {
       if self.guard.closed.load(SeqCst) {
         return Ok(ReadMessageTimeoutResult::Closed);
@@ -213,9 +523,31 @@ impl WebsocketReceiver {
     }
   }
 
+  /// Closes the web socket with status code 1009 and returns the error for a message that exceeds the limit.
+  fn message_too_big(&self, size: u64) -> TiiError {
+    warn_log!(
+      "WebsocketReceiver message of at least {} bytes exceeds the limit of {}",
+      size,
+      self.max_message_size
+    );
+    if let Err(err) = self.guard.close_with_code(CloseCode::MessageTooBig.code(), "Message Too Big")
+    {
+      error_log!("WebsocketReceiver failed to send close frame for too big message: {}", err);
+    }
+    WebsocketError::MessageTooBig(size).into()
+  }
+
+  fn is_compression_negotiated(&self) -> bool {
+    #[cfg(feature = "ws_deflate")]
+    return self.inflater.is_some();
+    #[cfg(not(feature = "ws_deflate"))]
+    return false;
+  }
+
   /// Attempts to read a message from the given stream.
   ///
-  /// Silently responds to pings with pongs, as specified in [RFC 6455 Section 5.5.2](https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.2).
+  /// In auto pong mode pings are answered with pongs, as specified in [RFC 6455 Section 5.5.2](https://datatracker.ietf.org/doc/html/rfc6455#section-5.5.2).
+  /// The ping is still returned so the caller can skip it.
   fn read_next_frame(&mut self) -> TiiResult<Option<WebsocketMessage>> {
     if self.guard.closed.load(SeqCst) {
       return Ok(None);
@@ -224,28 +556,55 @@ impl WebsocketReceiver {
     let as_read = self.guard.stream.as_stream_read();
     // Keep reading frames until we get the finish frame
     while self.state.last().map(|f| !f.fin).unwrap_or(true) {
-      let frame = Frame::from_stream(as_read).inspect_err(|e| {
+      let pending: u64 = self.state.iter().map(|f| f.length).sum();
+      let limit = self.max_message_size.saturating_sub(pending);
+      let frame = match Frame::from_stream(as_read, limit) {
+        Ok(frame) => frame,
+        Err(TiiError::WebsocketError(WebsocketError::MessageTooBig(length))) => {
+          return Err(self.message_too_big(pending + length));
+        }
+        Err(e) => {
+          self.guard.closed.store(true, SeqCst);
+          error_log!("WebsocketReceiver::read_next_frame Frame::from_stream error: {}", &e);
+          return Err(e);
+        }
+      };
+      self.guard.frame_received(&frame);
+
+      // RSV1 marks compressed messages, it is only permitted on the first frame of a message.
+      if frame.rsv[0]
+        && (!self.is_compression_negotiated()
+          || !matches!(frame.opcode, Opcode::Text | Opcode::Binary))
+      {
         self.guard.closed.store(true, SeqCst);
-        error_log!("WebsocketReceiver::read_next_frame Frame::from_stream error: {}", e);
-      })?;
+        return Err(
+          WebsocketError::ProtocolViolation(format!("RSV1 set on {:?} frame", frame.opcode)).into(),
+        );
+      }
 
       if frame.opcode == Opcode::Ping {
-        return Ok(Some(WebsocketMessage::Ping));
+        if self.auto_pong {
+          self.guard.write_frame(Frame::new(Opcode::Pong, frame.payload.clone()))?;
+        }
+        return Ok(Some(WebsocketMessage::Ping(frame.payload)));
       }
 
       if frame.opcode == Opcode::Pong {
-        return Ok(Some(WebsocketMessage::Pong));
+        self.guard.pong_received(frame.payload.as_slice())?;
+        return Ok(Some(WebsocketMessage::Pong(frame.payload)));
       }
 
       if frame.opcode == Opcode::Close {
         self.guard.closed.store(true, SeqCst);
+        let peer_close = parse_close_payload(frame.payload.as_slice())?;
+        *unwrap_poison(self.guard.peer_close.lock())? = peer_close;
         if self.state.is_empty() {
           return Ok(None);
         }
 
-        return Err(TiiError::RequestHeadParsing(
-          RequestHeadParsingError::WebSocketClosedDuringPendingMessage,
-        ));
+        return Err(
+          WebsocketError::ProtocolViolation("closed during pending message".to_string()).into(),
+        );
       }
 
       self.state.push(frame);
@@ -253,34 +612,56 @@ impl WebsocketReceiver {
 
     let frames = mem::take(&mut self.state);
     let frame_type = unwrap_some(frames.first()).opcode;
+    #[cfg(feature = "ws_deflate")]
+    let compressed = unwrap_some(frames.first()).rsv[0];
 
     let size = frames.iter().map(|f| f.payload.len()).sum();
     let mut payload = Vec::with_capacity(size);
 
     for (idx, frame) in frames.into_iter().enumerate() {
       if idx != 0 && frame.opcode != Opcode::Continuation {
-        return Err(TiiError::RequestHeadParsing(
-          RequestHeadParsingError::UnexpectedWebSocketOpcode,
-        ));
+        self.guard.closed.store(true, SeqCst);
+        return Err(
+          WebsocketError::ProtocolViolation(format!(
+            "expected continuation frame got {:?}",
+            frame.opcode
+          ))
+          .into(),
+        );
       }
       payload.extend_from_slice(frame.payload.as_slice());
     }
 
+    #[cfg(feature = "ws_deflate")]
+    if let (true, Some(inflater)) = (compressed, self.inflater.as_mut()) {
+      payload = match inflater.decompress(payload.as_slice(), self.max_message_size) {
+        Ok(payload) => payload,
+        Err(TiiError::WebsocketError(WebsocketError::MessageTooBig(size))) => {
+          return Err(self.message_too_big(size));
+        }
+        Err(err) => {
+          self.guard.closed.store(true, SeqCst);
+          return Err(err);
+        }
+      };
+    }
+
     match frame_type {
       Opcode::Text => {
         let payload = String::from_utf8(payload).map_err(|e| {
           self.guard.closed.store(true, SeqCst);
-          TiiError::RequestHeadParsing(RequestHeadParsingError::WebSocketTextMessageIsNotUtf8(
-            e.into_bytes(),
-          ))
+          TiiError::from(WebsocketError::InvalidUtf8(e.into_bytes()))
         })?;
 
         Ok(Some(WebsocketMessage::Text(payload)))
       }
       Opcode::Binary => Ok(Some(WebsocketMessage::Binary(payload))),
-      _ => {
+      other => {
         self.guard.closed.store(true, SeqCst);
-        Err(TiiError::RequestHeadParsing(RequestHeadParsingError::UnexpectedWebSocketOpcode))
+        Err(
+          WebsocketError::ProtocolViolation(format!("message starts with {:?} frame", other))
+            .into(),
+        )
       }
     }
   }
@@ -306,7 +687,9 @@ impl Read for WebsocketReceiver {
             }
           }
           None => {
-            self.unhandled_messages.push_back(message);
+            if !(self.auto_pong && matches!(message, WebsocketMessage::Ping(_))) {
+              self.unhandled_messages.push_back(message);
+            }
             continue;
           }
         },
@@ -322,13 +705,22 @@ impl Read for WebsocketReceiver {
 impl Write for WebsocketSender {
   fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
     if self.0.closed.load(SeqCst) {
-      return Err(io::Error::from(ErrorKind::ConnectionReset));
+      return Err(self.0.closed_error().into());
     }
+
+    #[cfg(feature = "ws_deflate")]
+    if self.0.deflater.is_some() {
+      self.0.write_frame(self.0.data_frame(Opcode::Binary, buf.to_vec())?)?;
+      return Ok(buf.len());
+    }
+
     Frame::write_unowned_payload_frame(self.0.stream.as_stream_write(), Opcode::Binary, buf)
       .inspect_err(|e| {
         self.0.closed.store(true, SeqCst);
         error_log!("WebsocketSender::write error: {}", e);
       })?;
+    let length = buf.len() as u64;
+    self.0.frame_sent(Opcode::Binary, Frame::header_len(length, false) + length);
     Ok(buf.len())
   }
 
@@ -346,8 +738,7 @@ impl Drop for WebSocketGuard {
     }
 
     trace_log!("WebsocketReceiver::drop closing...");
-    if let Err(err) = Frame::new(Opcode::Close, Vec::new()).write_to(self.stream.as_stream_write())
-    {
+    if let Err(err) = self.write_frame_unchecked(Frame::new(Opcode::Close, Vec::new())) {
       warn_log!("WebsocketSender::drop error: {}", err);
     }
     trace_log!("WebsocketReceiver::drop closed.");
@@ -0,0 +1,74 @@
+#[cfg(all(feature = "extras", unix))]
+mod mock_stream;
+
+#[cfg(all(feature = "extras", unix))]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use std::process::Command;
+  use std::time::{Duration, Instant};
+  use tii::extras::spawn_response;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_server::TiiServer;
+
+  fn echo(_: &RequestContext) -> Response {
+    let mut command = Command::new("echo");
+    command.arg("hello");
+    spawn_response(command, Some(Duration::from_secs(10)))
+  }
+
+  fn fail(_: &RequestContext) -> Response {
+    let mut command = Command::new("sh");
+    command.args(["-c", "echo oops >&2; exit 3"]);
+    spawn_response(command, Some(Duration::from_secs(10)))
+  }
+
+  fn hang(_: &RequestContext) -> Response {
+    let mut command = Command::new("sleep");
+    command.arg("10");
+    spawn_response(command, Some(Duration::from_millis(100)))
+  }
+
+  fn missing(_: &RequestContext) -> Response {
+    spawn_response(Command::new("/does/not/exist"), None)
+  }
+
+  fn get(server: &TiiServer, path: &str) -> String {
+    let stream = MockStream::with_str(format!("GET {path} HTTP/1.1\r\n\r\n").as_str());
+    server.handle_connection(stream.to_stream()).expect("ERROR");
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn run() {
+    let server = TiiBuilder::default()
+      .router(|rt| {
+        rt.route_get("/echo", echo)?
+          .route_get("/fail", fail)?
+          .route_get("/hang", hang)?
+          .route_get("/missing", missing)
+      })
+      .expect("ERROR")
+      .build();
+
+    assert_eq!(
+      get(&server, "/echo"),
+      "HTTP/1.1 200 OK\r\nConnection: Close\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nhello\n\r\n0\r\n\r\n"
+    );
+
+    let error =
+      "HTTP/1.1 500 Internal Server Error\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n";
+    assert_eq!(get(&server, "/fail"), error);
+    assert_eq!(get(&server, "/missing"), error);
+
+    let start = Instant::now();
+    assert_eq!(get(&server, "/hang"), error);
+    assert!(start.elapsed() < Duration::from_secs(5));
+  }
+}
+
+#[cfg(all(feature = "extras", unix))]
+#[test]
+fn spawn_response() {
+  inner::run();
+}
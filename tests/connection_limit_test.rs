@@ -0,0 +1,186 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{Read, Write};
+  use std::net::{SocketAddr, TcpListener, TcpStream};
+  use std::sync::{mpsc, Arc, Mutex};
+  use std::thread;
+  use std::time::{Duration, Instant};
+  use tii::extras;
+  use tii::extras::{ConnectionLimit, ConnectionLimitPolicy, Connector, TcpConnectorOptions};
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::{Clock, SystemClock, TiiBuilder};
+  use tii::tii_error::TiiResult;
+
+  const OK: &str =
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello";
+  const UNAVAILABLE: &str =
+    "HTTP/1.1 503 Service Unavailable\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n";
+
+  /// Clock that only moves when it is advanced.
+  #[derive(Debug)]
+  struct FakeClock(Mutex<Instant>);
+
+  impl FakeClock {
+    fn advance(&self, duration: Duration) {
+      *self.0.lock().unwrap() += duration;
+    }
+  }
+
+  impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+      *self.0.lock().unwrap()
+    }
+  }
+
+  struct Occupied {
+    connector: extras::TcpConnector,
+    release: mpsc::Sender<()>,
+    first: thread::JoinHandle<TiiResult<String>>,
+    queued: thread::JoinHandle<TiiResult<String>>,
+  }
+
+  /// Starts a connector that handles one connection at a time and queues the others for an hour.
+  /// Returns once a first connection occupies the limit until it is released and a second connection is queued.
+  fn occupied(clock: Arc<dyn Clock>) -> TiiResult<Occupied> {
+    let (started, handler_running) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    let started = Arc::new(Mutex::new(started));
+    let released = Arc::new(Mutex::new(released));
+    let tii_server = TiiBuilder::builder_arc(move |builder| {
+      builder.with_clock(clock)?.router(move |router| {
+        router.route_any("/*", move |_: &RequestContext| {
+          started.lock().unwrap().send(()).unwrap();
+          _ = released.lock().unwrap().recv();
+          Response::ok("Hello", MimeType::TextPlain)
+        })
+      })
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let policy = ConnectionLimitPolicy::Queue(Duration::from_secs(3600));
+    let options =
+      TcpConnectorOptions::default().with_connection_limit(ConnectionLimit::new(1, policy));
+    let connector =
+      extras::TcpConnector::from_listener_with_options_unpooled(listener, tii_server, options)?;
+
+    let first = thread::spawn(move || request(addr));
+    handler_running.recv_timeout(Duration::from_secs(10))?;
+    let queued = thread::spawn(move || request(addr));
+    // Give the connector the time to accept the second connection and start waiting for the limit.
+    thread::sleep(Duration::from_millis(200));
+    Ok(Occupied { connector, release, first, queued })
+  }
+
+  fn slow(_: &RequestContext) -> TiiResult<Response> {
+    thread::sleep(Duration::from_millis(200));
+    Ok(Response::ok("Hello", MimeType::TextPlain))
+  }
+
+  fn request(addr: SocketAddr) -> TiiResult<String> {
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    // A rejected connection may be closed before the request is written or fully read.
+    _ = stream.write_all("GET / HTTP/1.1\r\n\r\n".as_bytes());
+    let mut response = Vec::new();
+    _ = stream.read_to_end(&mut response);
+    Ok(String::from_utf8_lossy(response.as_slice()).to_string())
+  }
+
+  fn burst(policy: ConnectionLimitPolicy) -> TiiResult<Vec<String>> {
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| router.route_any("/*", slow))?.ok()
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    let options =
+      TcpConnectorOptions::default().with_connection_limit(ConnectionLimit::new(1, policy));
+    let connector =
+      extras::TcpConnector::from_listener_with_options_unpooled(listener, tii_server, options)?;
+
+    let clients = (0..3).map(|_| thread::spawn(move || request(addr))).collect::<Vec<_>>();
+    let mut responses = Vec::new();
+    for client in clients {
+      responses.push(client.join().expect("client panicked")?);
+    }
+
+    assert!(connector.shutdown_and_join(None));
+    Ok(responses)
+  }
+
+  pub(crate) fn queue() -> TiiResult<()> {
+    let responses = burst(ConnectionLimitPolicy::Queue(Duration::from_secs(5)))?;
+    for response in responses {
+      assert_eq!(response, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello");
+    }
+    Ok(())
+  }
+
+  pub(crate) fn queue_timeout_uses_server_clock() -> TiiResult<()> {
+    let clock = Arc::new(FakeClock(Mutex::new(Instant::now())));
+    let occupied = occupied(clock.clone())?;
+
+    let start = Instant::now();
+    clock.advance(Duration::from_secs(3601));
+    assert_eq!(occupied.queued.join().expect("client panicked")?, UNAVAILABLE);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    occupied.release.send(()).unwrap();
+    assert_eq!(occupied.first.join().expect("client panicked")?, OK);
+    assert!(occupied.connector.shutdown_and_join(None));
+    Ok(())
+  }
+
+  pub(crate) fn shutdown_rejects_queued() -> TiiResult<()> {
+    let occupied = occupied(Arc::new(SystemClock))?;
+
+    let start = Instant::now();
+    occupied.connector.shutdown();
+    assert!(start.elapsed() < Duration::from_secs(4));
+    assert_eq!(occupied.queued.join().expect("client panicked")?, UNAVAILABLE);
+
+    occupied.release.send(()).unwrap();
+    assert_eq!(occupied.first.join().expect("client panicked")?, OK);
+    assert!(occupied.connector.join(Some(Duration::from_secs(10))));
+    Ok(())
+  }
+
+  pub(crate) fn reject() -> TiiResult<()> {
+    let responses = burst(ConnectionLimitPolicy::Reject)?;
+    let rejected = responses
+      .iter()
+      .filter(|response| response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"))
+      .count();
+    assert_eq!(rejected, 2);
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn queue() {
+  inner::queue().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn reject() {
+  inner::reject().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn queue_timeout_uses_server_clock() {
+  inner::queue_timeout_uses_server_clock().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn shutdown_rejects_queued() {
+  inner::shutdown_rejects_queued().expect("ERROR");
+}
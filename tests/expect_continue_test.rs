@@ -0,0 +1,45 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn echo_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut data = Vec::new();
+  ctx.request_body().expect("ERR").read_to_end(&mut data)?;
+  Ok(Response::ok(data, MimeType::TextPlain))
+}
+
+fn send(server: &TiiServer, request: &str) -> String {
+  let stream = MockStream::with_str(request);
+  server.handle_connection(stream.to_stream()).expect("ERR");
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn interim_response_precedes_final_response() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/echo", echo_route)).expect("ERR").build();
+
+  let response =
+    send(&server, "POST /echo HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\nhello");
+  assert!(response.starts_with("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n"), "{response}");
+  assert!(response.ends_with("\r\n\r\nhello"), "{response}");
+}
+
+#[test]
+pub fn no_interim_response_without_expectation() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/echo", echo_route)).expect("ERR").build();
+
+  let response = send(&server, "POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello");
+  assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+
+  let response =
+    send(&server, "POST /echo HTTP/1.0\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\nhello");
+  assert!(response.starts_with("HTTP/1.0 200 OK\r\n"), "{response}");
+}
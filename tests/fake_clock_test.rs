@@ -0,0 +1,96 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{BufRead, BufReader, Read, Write};
+  use std::net::{TcpListener, TcpStream};
+  use std::sync::{mpsc, Arc, Mutex};
+  use std::thread;
+  use std::time::{Duration, Instant};
+  use tii::extras;
+  use tii::extras::{ws_link_hook, Connector, WsBroadcastBuilder, WsHandle};
+  use tii::tii_builder::{Clock, TiiBuilder};
+  use tii::tii_error::TiiResult;
+  use tii::websocket::message::WebsocketMessage;
+
+  /// Clock that only moves when it is advanced.
+  #[derive(Debug)]
+  struct FakeClock(Mutex<Instant>);
+
+  impl FakeClock {
+    fn advance(&self, duration: Duration) {
+      *self.0.lock().unwrap() += duration;
+    }
+  }
+
+  impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+      *self.0.lock().unwrap()
+    }
+  }
+
+  pub(crate) fn run() -> TiiResult<()> {
+    let clock = Arc::new(FakeClock(Mutex::new(Instant::now())));
+    let (disconnected, disconnects) = mpsc::channel();
+    let linker = WsBroadcastBuilder::default()
+      .with_heartbeat(Duration::from_millis(100))
+      .with_idle_timeout(Duration::from_secs(3600))
+      .with_message_handler(|_: WsHandle, _: WebsocketMessage| {})
+      .with_disconnect_handler(move |handle: WsHandle| {
+        disconnected.send(handle.peer_addr()).unwrap();
+      });
+    let server_clock = clock.clone();
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder
+        .with_clock(server_clock)?
+        .router(|router| router.ws_route_any("/ws", ws_link_hook(linker.connect_hook())))?
+        .ok()
+    })?;
+    thread::spawn(move || linker.finalize().run());
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let connector = extras::TcpConnector::from_listener_unpooled(listener, tii_server)?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.write_all(b"GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n")?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    assert_eq!(line, "HTTP/1.1 101 Switching Protocols\r\n");
+    while line != "\r\n" {
+      line.clear();
+      reader.read_line(&mut line)?;
+    }
+
+    // Heartbeats keep coming but the connection is not idle until the clock moves.
+    thread::sleep(Duration::from_millis(300));
+    assert!(disconnects.try_recv().is_err());
+
+    let advanced_at = Instant::now();
+    clock.advance(Duration::from_secs(7200));
+
+    let close_payload = loop {
+      let mut head = [0u8; 2];
+      reader.read_exact(&mut head)?;
+      let mut payload = vec![0u8; (head[1] & 0x7F) as usize];
+      reader.read_exact(payload.as_mut_slice())?;
+      if head[0] & 0x0F == 0x8 {
+        break payload;
+      }
+      // Heartbeat pings are ignored
+    };
+
+    assert!(advanced_at.elapsed() < Duration::from_secs(5));
+    assert_eq!(close_payload.get(..2), Some(1000u16.to_be_bytes().as_slice()));
+    disconnects.recv_timeout(Duration::from_secs(10))?;
+
+    assert!(connector.shutdown_and_join(None));
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn fake_clock_triggers_ws_idle_timeout() {
+  inner::run().unwrap();
+}
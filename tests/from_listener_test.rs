@@ -0,0 +1,53 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{Read, Write};
+  use std::net::{TcpListener, TcpStream};
+  use std::time::Duration;
+  use tii::extras;
+  use tii::extras::Connector;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+
+  fn hello(_: &RequestContext) -> TiiResult<Response> {
+    Ok(Response::ok("<html><body><h1>Hello</h1></body></html>", MimeType::TextHtml))
+  }
+
+  pub(crate) fn work() -> TiiResult<()> {
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder
+        .router(|router| router.route_any("/*", hello))?
+        .with_connection_timeout(Some(Duration::from_secs(5)))?
+        .ok()
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    let connector = extras::TcpConnector::from_listener_unpooled(listener, tii_server)?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    stream.write_all("GET / HTTP/1.1\r\n\r\n".as_bytes())?;
+    stream.flush()?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    assert_eq!(std::str::from_utf8(response.as_slice())?, "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: Close\r\nContent-Length: 40\r\n\r\n<html><body><h1>Hello</h1></body></html>");
+
+    assert!(connector.shutdown_and_join(None));
+    drop(connector);
+
+    // With the connector having finished shutdown()
+    let _listen = TcpListener::bind(addr)?;
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn run() {
+  inner::work().expect("ERROR");
+}
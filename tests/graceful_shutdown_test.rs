@@ -0,0 +1,151 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{Read, Write};
+  use std::net::{TcpListener, TcpStream};
+  use std::sync::mpsc;
+  use std::sync::{Arc, Mutex};
+  use std::thread;
+  use std::time::{Duration, Instant};
+  use tii::extras;
+  use tii::extras::Connector;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+
+  /// Starts a connector whose handler sleeps 2 seconds and sends a request with the given `Connection` header to it.
+  /// Returns once the handler is running together with the thread that reads the response.
+  fn start_slow_request(
+    connection: &str,
+  ) -> TiiResult<(extras::TcpConnector, thread::JoinHandle<String>)> {
+    let (started, handler_running) = mpsc::channel();
+    let started = Arc::new(Mutex::new(started));
+    let tii_server = TiiBuilder::builder_arc(move |builder| {
+      builder.router(move |router| {
+        let started = started.clone();
+        router.route_get("/slow", move |_: &RequestContext| {
+          started.lock().unwrap().send(()).unwrap();
+          thread::sleep(Duration::from_secs(2));
+          Response::ok("slow done", MimeType::TextPlain)
+        })
+      })
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let connector = extras::TcpConnector::from_listener_unpooled(listener, tii_server)?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.write_all(
+      format!("GET /slow HTTP/1.1\r\nConnection: {connection}\r\nContent-Length: 0\r\n\r\n")
+        .as_bytes(),
+    )?;
+    let client = thread::spawn(move || {
+      let mut response = Vec::new();
+      _ = stream.read_to_end(&mut response);
+      String::from_utf8(response).unwrap()
+    });
+
+    handler_running.recv_timeout(Duration::from_secs(10))?;
+    Ok((connector, client))
+  }
+
+  pub(crate) fn completes_in_flight_request() -> TiiResult<()> {
+    let (connector, client) = start_slow_request("close")?;
+
+    let start = Instant::now();
+    assert!(connector.shutdown_graceful(Duration::from_secs(5)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(connector.is_shutdown());
+
+    let response = client.join().unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.ends_with("\r\n\r\nslow done"), "{response}");
+    Ok(())
+  }
+
+  pub(crate) fn ends_in_flight_keep_alive() -> TiiResult<()> {
+    let (connector, client) = start_slow_request("keep-alive")?;
+
+    let start = Instant::now();
+    assert!(connector.shutdown_graceful(Duration::from_secs(5)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let response = client.join().unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.contains("\r\nConnection: Close\r\n"), "{response}");
+    assert!(response.ends_with("\r\n\r\nslow done"), "{response}");
+    Ok(())
+  }
+
+  pub(crate) fn closes_idle_keep_alive() -> TiiResult<()> {
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| {
+        router.route_get("/fast", |_: &RequestContext| Response::ok("fast", MimeType::TextPlain))
+      })
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let connector = extras::TcpConnector::from_listener_unpooled(listener, tii_server)?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream
+      .write_all(b"GET /fast HTTP/1.1\r\nConnection: keep-alive\r\nContent-Length: 0\r\n\r\n")?;
+
+    let mut response = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !response.ends_with(b"fast") {
+      let read = stream.read(&mut buffer)?;
+      assert_ne!(read, 0, "{}", String::from_utf8_lossy(&response));
+      response.extend_from_slice(&buffer[..read]);
+    }
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.contains("\r\nConnection: Keep-Alive\r\n"), "{response}");
+
+    let start = Instant::now();
+    assert!(connector.shutdown_graceful(Duration::from_secs(5)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(connector.is_shutdown());
+
+    let mut rest = Vec::new();
+    stream.read_to_end(&mut rest)?;
+    assert!(rest.is_empty());
+    Ok(())
+  }
+
+  pub(crate) fn closes_at_deadline() -> TiiResult<()> {
+    let (connector, client) = start_slow_request("close")?;
+
+    assert!(!connector.shutdown_graceful(Duration::from_millis(200)));
+    assert_eq!(client.join().unwrap(), "");
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn graceful_shutdown_completes_in_flight_request() {
+  inner::completes_in_flight_request().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn graceful_shutdown_closes_at_deadline() {
+  inner::closes_at_deadline().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn graceful_shutdown_ends_in_flight_keep_alive() {
+  inner::ends_in_flight_keep_alive().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn graceful_shutdown_closes_idle_keep_alive() {
+  inner::closes_idle_keep_alive().expect("ERROR");
+}
@@ -0,0 +1,56 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{Read, Write};
+  use std::net::{TcpListener, TcpStream};
+  use std::time::Duration;
+  use tii::extras;
+  use tii::extras::{Connector, TcpConnectorOptions};
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::stream::IntoConnectionStream;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+
+  fn hello(_: &RequestContext) -> TiiResult<Response> {
+    Ok(Response::ok("Hello", MimeType::TextPlain))
+  }
+
+  pub(crate) fn work() -> TiiResult<()> {
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| router.route_any("/*", hello))?.ok()
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let options = TcpConnectorOptions::default().with_linger(Duration::from_secs(1));
+    let connector =
+      extras::TcpConnector::from_listener_with_options_unpooled(listener, tii_server, options)?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.try_clone()?.into_connection_stream().set_linger(Some(Duration::ZERO))?;
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.write_all("GET / HTTP/1.1\r\n\r\n".as_bytes())?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    assert_eq!(
+      std::str::from_utf8(response.as_slice())?,
+      "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello"
+    );
+    drop(stream);
+
+    assert!(connector.shutdown_and_join(None));
+    drop(connector);
+
+    // Rebinding right away works, no need to wait for the sockets to leave TIME_WAIT.
+    let _listen = TcpListener::bind(addr)?;
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn linger() {
+  inner::work().expect("ERROR");
+}
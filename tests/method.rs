@@ -41,3 +41,12 @@ fn test_well_custom() {
   assert_eq!("SADNESS", n.to_string().as_str());
   assert_eq!("SADNESS", format!("{}", n).as_str());
 }
+
+#[test]
+fn test_patch() {
+  assert_eq!(Method::from("PATCH"), Method::Patch);
+  assert_eq!(Method::Patch.as_str(), "PATCH");
+  assert_eq!(Method::Patch.to_string(), "PATCH");
+  assert!(Method::Patch.is_well_known());
+  assert!(Method::well_known().contains(&Method::Patch));
+}
@@ -0,0 +1,129 @@
+#[cfg(feature = "serde")]
+mod mock_stream;
+
+#[cfg(feature = "serde")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use serde::Deserialize;
+  use std::sync::Mutex;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+  use tii::tii_server::TiiServer;
+
+  #[derive(Debug, Deserialize, PartialEq, Eq)]
+  struct Person {
+    name: String,
+    age: u32,
+  }
+
+  static PARSED: Mutex<Vec<Person>> = Mutex::new(Vec::new());
+
+  fn parse_route(ctx: &RequestContext) -> TiiResult<Response> {
+    let person: Person = ctx.parse_body()?;
+    PARSED.lock().unwrap().push(person);
+    Ok(Response::ok("Okay!", MimeType::TextPlain))
+  }
+
+  fn reread_route(ctx: &RequestContext) -> TiiResult<Response> {
+    let person: Person = ctx.parse_body_with_limit(64)?;
+    let mut body = Vec::new();
+    ctx.request_body().unwrap().read_to_end(&mut body)?;
+    Ok(Response::ok(
+      format!("{} {}", person.name, String::from_utf8_lossy(&body)),
+      MimeType::TextPlain,
+    ))
+  }
+
+  fn limited_route(ctx: &RequestContext) -> TiiResult<Response> {
+    let _: Person = ctx.parse_body_with_limit(8)?;
+    unreachable!()
+  }
+
+  fn server() -> TiiServer {
+    TiiBuilder::default()
+      .router(|rt| {
+        rt.route_any("/dummy", parse_route)?
+          .route_any("/reread", reread_route)?
+          .route_any("/limited", limited_route)
+      })
+      .expect("ERR")
+      .build()
+  }
+
+  fn send(server: &TiiServer, content_type: &str, body: &str) -> String {
+    send_to(server, "/dummy", content_type, body)
+  }
+
+  fn send_to(server: &TiiServer, path: &str, content_type: &str, body: &str) -> String {
+    let stream = MockStream::with_str(
+      format!(
+        "POST {path} HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+      )
+      .as_str(),
+    );
+    server.handle_connection(stream.to_stream()).unwrap();
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn json_and_form() {
+    let server = server();
+    let ok = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nOkay!";
+
+    assert_eq!(send(&server, "application/json", r#"{"name":"Tii Tester","age":42}"#), ok);
+    assert_eq!(
+      send(&server, "application/x-www-form-urlencoded; charset=UTF-8", "name=Tii+Tester&age=42"),
+      ok
+    );
+
+    let parsed = std::mem::take(&mut *PARSED.lock().unwrap());
+    let expected = Person { name: "Tii Tester".to_string(), age: 42 };
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0], expected);
+    assert_eq!(parsed[1], expected);
+
+    assert_eq!(
+      send(&server, "text/plain", "name=Tii+Tester&age=42"),
+      "HTTP/1.1 415 Unsupported Media Type\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+    assert_eq!(
+      send(&server, "application/json; charset=iso-8859-1", r#"{"name":"Tii Tester","age":42}"#),
+      "HTTP/1.1 415 Unsupported Media Type\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+    assert_eq!(
+      send(&server, "application/json", r#"{"name":"Tii Tester"}"#),
+      "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+  }
+
+  pub fn limit_and_reread() {
+    let server = server();
+    let form = "name=Tii&age=42";
+
+    let reread = send_to(&server, "/reread", "application/x-www-form-urlencoded", form);
+    assert!(reread.ends_with(&format!("\r\n\r\nTii {form}")), "{reread}");
+    let json = r#"{"name":"Tii","age":42}"#;
+    let reread = send_to(&server, "/reread", "application/json", json);
+    assert!(reread.ends_with(&format!("\r\n\r\nTii {json}")), "{reread}");
+
+    assert!(send_to(&server, "/limited", "application/x-www-form-urlencoded", form)
+      .starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+    assert!(send_to(&server, "/limited", "application/json", json)
+      .starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+  }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn parse_body() {
+  inner::json_and_form();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn parse_body_with_limit() {
+  inner::limit_and_reread();
+}
@@ -0,0 +1,66 @@
+use tii::http::range;
+use tii::http::range::ByteRange;
+use tii::tii_error::RangeError;
+
+fn r(start: u64, end: u64) -> ByteRange {
+  ByteRange { start, end }
+}
+
+#[test]
+pub fn test_single() {
+  assert_eq!(range::parse("bytes=0-499", 1000).unwrap(), vec![r(0, 499)]);
+  assert_eq!(range::parse("bytes=500-999", 1000).unwrap(), vec![r(500, 999)]);
+  assert_eq!(range::parse("bytes=0-0", 1000).unwrap()[0].len(), 1);
+}
+
+#[test]
+pub fn test_multiple() {
+  assert_eq!(
+    range::parse("bytes=0-49, 100-149,200-", 1000).unwrap(),
+    vec![r(0, 49), r(100, 149), r(200, 999)]
+  );
+}
+
+#[test]
+pub fn test_suffix() {
+  assert_eq!(range::parse("bytes=-500", 1000).unwrap(), vec![r(500, 999)]);
+  assert_eq!(range::parse("bytes=-5000", 1000).unwrap(), vec![r(0, 999)]);
+}
+
+#[test]
+pub fn test_open_ended() {
+  assert_eq!(range::parse("bytes=500-", 1000).unwrap(), vec![r(500, 999)]);
+  assert_eq!(range::parse("bytes=999-", 1000).unwrap(), vec![r(999, 999)]);
+}
+
+#[test]
+pub fn test_clamped() {
+  assert_eq!(range::parse("bytes=900-1999", 1000).unwrap(), vec![r(900, 999)]);
+  assert_eq!(r(900, 999).content_range(1000), "bytes 900-999/1000");
+}
+
+#[test]
+pub fn test_out_of_bounds() {
+  assert_eq!(range::parse("bytes=1000-1999", 1000), Err(RangeError::Unsatisfiable));
+  assert_eq!(range::parse("bytes=1000-", 1000), Err(RangeError::Unsatisfiable));
+  assert_eq!(range::parse("bytes=-0", 1000), Err(RangeError::Unsatisfiable));
+  assert_eq!(range::parse("bytes=-10", 0), Err(RangeError::Unsatisfiable));
+  // Unsatisfiable ranges are dropped if another one is satisfiable.
+  assert_eq!(range::parse("bytes=2000-2999, 0-9", 1000).unwrap(), vec![r(0, 9)]);
+}
+
+#[test]
+pub fn test_invalid() {
+  assert_eq!(
+    range::parse("items=0-9", 1000),
+    Err(RangeError::UnsupportedUnit("items".to_string()))
+  );
+  for header in ["bytes", "bytes=", "bytes=9-0", "bytes=a-9", "bytes=0-9-", "bytes=+1-9", "bytes=-"]
+  {
+    assert_eq!(
+      range::parse(header, 1000),
+      Err(RangeError::Malformed(header.to_string())),
+      "{header}"
+    );
+  }
+}
@@ -0,0 +1,82 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{Read, Write};
+  use std::net::{SocketAddr, TcpListener, TcpStream};
+  use std::time::Duration;
+  use tii::extras;
+  use tii::extras::{ConnectionRateLimiter, Connector};
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+
+  fn hello(_: &RequestContext) -> TiiResult<Response> {
+    Ok(Response::ok("Hello", MimeType::TextPlain))
+  }
+
+  fn request(addr: &SocketAddr) -> TiiResult<Vec<u8>> {
+    let mut stream = TcpStream::connect_timeout(addr, Duration::from_secs(30))?;
+    stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    // A dropped connection may already be closed by the time we write.
+    _ = stream.write_all("GET / HTTP/1.1\r\n\r\n".as_bytes());
+    let mut response = Vec::new();
+    _ = stream.read_to_end(&mut response);
+    Ok(response)
+  }
+
+  pub(crate) fn work() -> TiiResult<()> {
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| router.route_any("/*", hello))?.ok()
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    let connector = extras::TcpConnector::from_listener_with_rate_limit_unpooled(
+      listener,
+      tii_server,
+      ConnectionRateLimiter::new(1, 3),
+    )?;
+
+    let mut served = 0;
+    for _ in 0..6 {
+      if !request(&addr)?.is_empty() {
+        served += 1;
+      }
+    }
+    assert_eq!(served, 3);
+
+    assert!(connector.shutdown_and_join(None));
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn run() {
+  inner::work().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn per_ip() {
+  use std::net::{IpAddr, Ipv4Addr};
+  use std::time::Duration;
+  use tii::extras::ConnectionRateLimiter;
+
+  let first = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+  let second = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+  let limiter = ConnectionRateLimiter::with_idle_timeout(1, 2, Duration::from_millis(200));
+  assert!(limiter.check(first));
+  assert!(limiter.check(first));
+  assert!(!limiter.check(first));
+  assert!(limiter.check(second));
+  assert_eq!(limiter.tracked_addresses(), 2);
+
+  std::thread::sleep(Duration::from_millis(300));
+  assert!(limiter.check(second));
+  assert_eq!(limiter.tracked_addresses(), 1);
+}
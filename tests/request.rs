@@ -2,6 +2,7 @@ mod mock_stream;
 
 use crate::mock_stream::MockStream;
 use tii::http::cookie::Cookie;
+use tii::http::encoding::{ContentEncoding, TransferEncoding};
 use tii::http::headers::{Header, HeaderName};
 use tii::http::method::Method;
 use tii::http::RequestHead;
@@ -50,6 +51,20 @@ fn test_cookie_request() {
   assert_eq!(request.get_cookie("sus"), None);
 }
 
+#[test]
+fn test_cookie_request_quoted_values() {
+  let test_data =
+    b"GET / HTTP/1.1\r\nHost: localhost\r\nCookie: a=\"b=c\"; d=e ; broken; =x\r\n\r\n";
+  let stream = MockStream::with_data(VecDeque::from_iter(test_data.iter().cloned()));
+  let raw_stream = stream.clone().into_connection_stream();
+  let request = RequestHead::new(raw_stream.as_ref(), 8096).unwrap();
+
+  assert_eq!(request.get_cookies(), vec![Cookie::new("a", "b=c"), Cookie::new("d", "e")]);
+  assert_eq!(request.get_cookie("a"), Some(Cookie::new("a", "b=c")));
+  assert_eq!(request.get_cookie("d"), Some(Cookie::new("d", "e")));
+  assert_eq!(request.get_cookie_header().as_deref(), Some("a=\"b=c\"; d=e ; broken; =x"));
+}
+
 #[test]
 fn test_proxied_request_from_stream() {
   let test_data =
@@ -72,3 +87,20 @@ fn test_proxied_request_from_stream() {
 
   assert_eq!(collected, expected_headers);
 }
+
+#[test]
+fn test_request_encodings() {
+  let test_data = b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, Chunked\r\nContent-Encoding: br\r\nContent-Encoding: x-gzip;level=9\r\n\r\n";
+  let stream = MockStream::with_data(VecDeque::from_iter(test_data.iter().cloned()));
+  let raw_stream = stream.clone().into_connection_stream();
+
+  let request = RequestHead::new(raw_stream.as_ref(), 8096).unwrap();
+  assert_eq!(request.transfer_encodings(), vec![TransferEncoding::Gzip, TransferEncoding::Chunked]);
+  assert_eq!(request.content_encodings(), vec![ContentEncoding::Brotli, ContentEncoding::Gzip]);
+
+  assert_eq!(
+    TransferEncoding::parse_list("chunked, , foo"),
+    vec![TransferEncoding::Chunked, TransferEncoding::Custom("foo".to_string())]
+  );
+  assert_eq!(ContentEncoding::from(ContentEncoding::Brotli.as_str()), ContentEncoding::Brotli);
+}
@@ -0,0 +1,87 @@
+use crate::mock_stream::MockStream;
+use std::io;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn upload_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut data = Vec::new();
+  if let Some(body) = ctx.request_body() {
+    body.read_to_end(&mut data)?;
+  }
+  Ok(Response::ok(format!("got {}", data.len()), MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| rt.route_any("/upload", upload_route))
+    .expect("ERR")
+    .with_read_timeout(Some(Duration::from_millis(300)))
+    .expect("ERR")
+    .with_request_deadline(Some(Duration::from_secs(1)))
+    .expect("ERR")
+    .build()
+}
+
+/// Sends the prefix at once and then the rest one byte every 100ms,
+/// each read finishes well within the read timeout.
+fn dribble(prefix: &'static str, slow: String) -> TiiResult<(TiiResult<()>, Duration)> {
+  let listener = TcpListener::bind("127.0.0.1:0")?;
+  let addr = listener.local_addr()?;
+  let client = thread::spawn(move || -> io::Result<()> {
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.write_all(prefix.as_bytes())?;
+    for byte in slow.as_bytes() {
+      stream.write_all(&[*byte])?;
+      thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+  });
+
+  let (connection, _) = listener.accept()?;
+  let start = Instant::now();
+  let result = server().handle_connection(connection);
+  let elapsed = start.elapsed();
+  // The client notices the closed connection with its next writes.
+  assert!(client.join().unwrap().is_err());
+  Ok((result, elapsed))
+}
+
+#[test]
+pub fn request_deadline_slow_head() {
+  let slow = format!("X-Padding: {}\r\n\r\n", "a".repeat(100));
+  let (result, elapsed) = dribble("GET /upload HTTP/1.1\r\n", slow).unwrap();
+  assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+  assert!(elapsed >= Duration::from_millis(900), "{elapsed:?}");
+  assert!(elapsed < Duration::from_secs(3), "{elapsed:?}");
+}
+
+#[test]
+pub fn request_deadline_slow_body() {
+  let prefix = "POST /upload HTTP/1.1\r\nContent-Length: 100\r\n\r\n";
+  let (result, elapsed) = dribble(prefix, "a".repeat(100)).unwrap();
+  // The endpoint fails with TimedOut, discarding the rest of the body then fails as well.
+  assert!(result.is_err());
+  assert!(elapsed >= Duration::from_millis(900), "{elapsed:?}");
+  assert!(elapsed < Duration::from_secs(3), "{elapsed:?}");
+}
+
+#[test]
+pub fn request_deadline_restarts_per_request() {
+  let stream = MockStream::with_str(
+    "POST /upload HTTP/1.1\r\nConnection: keep-alive\r\nContent-Length: 5\r\n\r\nhelloGET /upload HTTP/1.1\r\n\r\n",
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  let written = stream.copy_written_data_to_string();
+  assert!(written.contains("\r\n\r\ngot 5HTTP/1.1 200 OK\r\n"), "{written}");
+  assert!(written.ends_with("\r\n\r\ngot 0"), "{written}");
+}
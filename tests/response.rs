@@ -2,6 +2,7 @@ mod mock_stream;
 
 use mock_stream::MockStream;
 use tii::http::cookie::{SameSite, SetCookie};
+use tii::http::encoding::ContentEncoding;
 use tii::http::headers::HeaderName;
 use tii::http::response::Response;
 use tii::http::status::StatusCode;
@@ -73,6 +74,32 @@ fn test_chunked_response() {
   );
 }
 
+#[test]
+fn test_channel_response() {
+  let (sender, receiver) = std::sync::mpsc::channel::<Vec<u8>>();
+  let producer = std::thread::spawn(move || {
+    sender.send(b"Hello".to_vec()).unwrap();
+    sender.send(b"World".to_vec()).unwrap();
+    sender.send(b"in chunks".to_vec()).unwrap();
+  });
+
+  let response = Response::new(StatusCode::OK).with_body(ResponseBody::from_channel(receiver));
+
+  let expected_bytes: Vec<u8> = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n5\r\nWorld\r\n9\r\nin chunks\r\n0\r\n\r\n".to_vec();
+  let stream = MockStream::without_data();
+  let raw_stream = stream.clone().into_connection_stream();
+
+  response.write_to(HttpVersion::Http11, raw_stream.as_stream_write()).expect("err");
+  producer.join().unwrap();
+  assert_eq!(
+    stream.copy_written_data(),
+    expected_bytes,
+    "{} != {}",
+    String::from_utf8_lossy(&expected_bytes),
+    String::from_utf8_lossy(&stream.copy_written_data())
+  );
+}
+
 #[test]
 fn test_cookie_response() {
   let response = Response::new(StatusCode::OK)
@@ -118,6 +145,56 @@ fn test_cookie_response() {
   );
 }
 
+#[test]
+fn test_response_cookie_same_site_none_partitioned() {
+  let response = Response::new(StatusCode::OK)
+    .with_cookie(
+      SetCookie::new("X-Embedded", "embedded-value")
+        .with_path("/")
+        .with_same_site(SameSite::None)
+        .with_partitioned(true),
+    )
+    .with_cookie(SetCookie::new("X-Cross-Site", "cross-site").with_same_site(SameSite::None))
+    .with_cookie(SetCookie::new("X-Lax", "lax").with_same_site(SameSite::Lax));
+
+  assert_eq!(
+    response.get_headers(&HeaderName::SetCookie),
+    vec![
+      "X-Embedded=embedded-value; Path=/; SameSite=None; Secure; Partitioned",
+      "X-Cross-Site=cross-site; SameSite=None; Secure",
+      "X-Lax=lax; SameSite=Lax"
+    ]
+  );
+}
+
+#[test]
+fn test_response_delete_cookie() {
+  let response = Response::new(StatusCode::OK)
+    .delete_cookie("session", Some("/app"), Some("example.com"))
+    .delete_cookie("theme", None, None)
+    .with_cookie(SetCookie::delete("token").with_path("/api"));
+
+  assert_eq!(
+    response.get_headers(&HeaderName::SetCookie),
+    vec![
+      "session=; Max-Age=0; Domain=example.com; Path=/app",
+      "theme=; Max-Age=0",
+      "token=; Max-Age=0; Path=/api"
+    ]
+  );
+}
+
+#[test]
+fn test_response_content_encoding() {
+  let response = Response::new(StatusCode::OK)
+    .with_content_encoding(ContentEncoding::Gzip)
+    .with_content_encoding(ContentEncoding::Brotli);
+
+  assert_eq!(response.get_headers(HeaderName::ContentEncoding), vec!["br"]);
+  assert_eq!(response.content_encodings(), vec![ContentEncoding::Brotli]);
+  assert_eq!(Response::new(StatusCode::OK).content_encodings(), vec![]);
+}
+
 // #[test]
 //This fn only tests for test codes sake. the Response from Stream is not useful for a server.
 // fn test_response_from_stream() {
@@ -137,3 +214,45 @@ fn test_cookie_response() {
 //   expected_headers.add(HeaderType::ContentLength, "51");
 //   assert_eq!(response.headers, expected_headers);
 // }
+
+#[test]
+fn test_warning_response() {
+  let response = Response::new(StatusCode::OK)
+    .with_warning(110, "tii", "Response is \"Stale\"")
+    .unwrap()
+    .with_warning_date(299, "", "Deprecated", "Thu, 1 Jan 1970 00:00:00 GMT")
+    .unwrap();
+
+  assert_eq!(
+    response.get_headers(HeaderName::Warning),
+    vec![
+      "110 tii \"Response is \\\"Stale\\\"\"",
+      "299 - \"Deprecated\" \"Thu, 1 Jan 1970 00:00:00 GMT\""
+    ]
+  );
+
+  assert!(Response::new(StatusCode::OK).with_warning(42, "tii", "Too short").is_err());
+}
+
+#[test]
+fn test_age_response() {
+  let response = Response::new(StatusCode::OK)
+    .with_age(Duration::from_secs(60))
+    .with_age(Duration::from_millis(120_500));
+
+  assert_eq!(response.get_headers(HeaderName::Age), vec!["120"]);
+}
+
+#[test]
+fn test_see_other_response() {
+  let response = Response::see_other_no_body("/orders/42");
+  assert_eq!(response.status_code, StatusCode::SeeOther);
+
+  let stream = MockStream::without_data();
+  let raw_stream = stream.clone().into_connection_stream();
+  response.write_to(HttpVersion::Http11, raw_stream.as_stream_write()).expect("err");
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 303 See Other\r\nLocation: /orders/42\r\nContent-Length: 0\r\n\r\n"
+  );
+}
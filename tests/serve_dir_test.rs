@@ -0,0 +1,235 @@
+#[cfg(feature = "extras")]
+mod mock_stream;
+
+#[cfg(feature = "extras")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use std::path::PathBuf;
+  use tii::extras::builtin_endpoints;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_server::TiiServer;
+
+  fn static_dir() -> &'static str {
+    let dir: PathBuf = std::env::temp_dir().join(format!("tii_serve_dir_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("index.html"), "root").unwrap();
+    std::fs::write(dir.join("sub").join("index.html"), "sub").unwrap();
+    Box::leak(dir.to_str().unwrap().to_string().into_boxed_str())
+  }
+
+  fn get(server: &TiiServer, path: &str) -> String {
+    let stream = MockStream::with_str(format!("GET {path} HTTP/1.1\r\n\r\n").as_str());
+    server.handle_connection(stream.to_stream()).unwrap();
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn run() {
+    let dir = static_dir();
+    let server = TiiBuilder::default()
+      .router(|rt| {
+        rt.route_any("/static/*", builtin_endpoints::serve_dir(dir))?
+          .route_any("/*", builtin_endpoints::serve_dir(dir))
+      })
+      .expect("ERR")
+      .build();
+
+    assert!(get(&server, "/").ends_with("\r\n\r\nroot"));
+    assert!(get(&server, "/sub/").ends_with("\r\n\r\nsub"));
+    assert!(get(&server, "/static/").ends_with("\r\n\r\nroot"));
+    assert_eq!(
+      get(&server, "/static"),
+      "HTTP/1.1 301 Moved Permanently\r\nLocation: /static/\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  fn get_accept(server: &TiiServer, path: &str, accept: &str) -> String {
+    let stream =
+      MockStream::with_str(format!("GET {path} HTTP/1.1\r\nAccept: {accept}\r\n\r\n").as_str());
+    server.handle_connection(stream.to_stream()).unwrap();
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn run_listing() {
+    let dir: PathBuf =
+      std::env::temp_dir().join(format!("tii_serve_dir_listing_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    std::fs::write(dir.join("a.txt"), "hello").unwrap();
+    let dir: &'static str = Box::leak(dir.to_str().unwrap().to_string().into_boxed_str());
+
+    let server = TiiBuilder::default()
+      .router(|rt| rt.route_any("/files/*", builtin_endpoints::serve_dir_with_listing(dir)))
+      .expect("ERR")
+      .build();
+
+    let response = get_accept(&server, "/files/", "application/json");
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n"));
+    assert!(body.starts_with("[{\"name\":\"a.txt\",\"size\":5,\"is_dir\":false,\"modified\":"));
+    assert!(body.contains("},{\"name\":\"nested\",\"size\":"));
+    assert!(body.contains(",\"is_dir\":true,\"modified\":"));
+    assert!(body.ends_with("}]"));
+
+    let not_found = "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n";
+    assert_eq!(get_accept(&server, "/files/", "text/html,application/json;q=0.9"), not_found);
+    assert_eq!(get_accept(&server, "/files/", "*/*"), not_found);
+    assert_eq!(get(&server, "/files/missing/"), not_found);
+    assert!(get_accept(&server, "/files/nested/", "application/json").ends_with("\r\n\r\n[]"));
+    assert!(get_accept(&server, "/files/a.txt", "application/json").ends_with("\r\n\r\nhello"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  fn header<'a>(response: &'a str, name: &str) -> &'a str {
+    let prefix = format!("\r\n{name}: ");
+    let start = response.find(prefix.as_str()).unwrap() + prefix.len();
+    let len = response[start..].find("\r\n").unwrap();
+    &response[start..start + len]
+  }
+
+  fn get_with(server: &TiiServer, path: &str, header: &str) -> String {
+    let stream = MockStream::with_str(format!("GET {path} HTTP/1.1\r\n{header}\r\n\r\n").as_str());
+    server.handle_connection(stream.to_stream()).unwrap();
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn run_etag() {
+    let dir: PathBuf =
+      std::env::temp_dir().join(format!("tii_serve_dir_etag_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("app.js"), "let x = 1;").unwrap();
+    let dir: &'static str = Box::leak(dir.to_str().unwrap().to_string().into_boxed_str());
+
+    let server = TiiBuilder::default()
+      .router(|rt| {
+        rt.route_any("/weak/*", builtin_endpoints::serve_dir(dir))?.route_any(
+          "/strong/*",
+          builtin_endpoints::serve_dir_with_etag(dir, builtin_endpoints::ETagStrength::Strong),
+        )
+      })
+      .expect("ERR")
+      .build();
+
+    let response = get(&server, "/weak/app.js");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nlet x = 1;"));
+    let etag = header(&response, "ETag").to_string();
+    let last_modified = header(&response, "Last-Modified").to_string();
+    assert!(etag.starts_with("W/\""));
+    assert!(last_modified.ends_with(" GMT"));
+
+    let not_modified = get_with(&server, "/weak/app.js", format!("If-None-Match: {etag}").as_str());
+    assert!(not_modified.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    assert!(not_modified.ends_with("\r\n\r\n"));
+    assert_eq!(header(&not_modified, "ETag"), etag);
+
+    let not_modified =
+      get_with(&server, "/weak/app.js", "If-Modified-Since: Fri, 01 Jan 2100 00:00:00 GMT");
+    assert!(not_modified.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    let modified =
+      get_with(&server, "/weak/app.js", "If-Modified-Since: Thu, 01 Jan 1970 00:00:00 GMT");
+    assert!(modified.ends_with("\r\n\r\nlet x = 1;"));
+    let modified = get_with(
+      &server,
+      "/weak/app.js",
+      "If-Modified-Since: Thu, 01 Jan 99999999999999 00:00:00 GMT",
+    );
+    assert!(modified.ends_with("\r\n\r\nlet x = 1;"));
+    let modified = get_with(&server, "/weak/app.js", "If-None-Match: \"other\"");
+    assert!(modified.ends_with("\r\n\r\nlet x = 1;"));
+
+    let strong = get(&server, "/strong/app.js");
+    assert_eq!(header(&strong, "ETag"), etag.strip_prefix("W/").unwrap());
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  pub fn run_chain() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("tii_serve_dirs_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("override")).unwrap();
+    std::fs::create_dir_all(dir.join("default")).unwrap();
+    std::fs::write(dir.join("override").join("style.css"), "override").unwrap();
+    std::fs::write(dir.join("default").join("style.css"), "default").unwrap();
+    std::fs::write(dir.join("default").join("index.html"), "index").unwrap();
+    let dirs: &'static [&'static str] = Box::leak(Box::new([
+      &*Box::leak(dir.join("override").to_str().unwrap().to_string().into_boxed_str()),
+      &*Box::leak(dir.join("default").to_str().unwrap().to_string().into_boxed_str()),
+    ]));
+
+    let server = TiiBuilder::default()
+      .router(|rt| rt.route_any("/*", builtin_endpoints::serve_dirs(dirs)))
+      .expect("ERR")
+      .build();
+
+    assert!(get(&server, "/style.css").ends_with("\r\n\r\noverride"));
+    assert!(get(&server, "/").ends_with("\r\n\r\nindex"));
+    assert!(get(&server, "/missing.css").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(get(&server, "/../default/style.css").starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    std::fs::remove_file(dir.join("override").join("style.css")).unwrap();
+    assert!(get(&server, "/style.css").ends_with("\r\n\r\ndefault"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  pub fn run_localized() {
+    let dir: PathBuf =
+      std::env::temp_dir().join(format!("tii_serve_dir_localized_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("page.html"), "hello").unwrap();
+    std::fs::write(dir.join("page.fr.html"), "bonjour").unwrap();
+    std::fs::write(dir.join("page.de-CH.html"), "grüezi").unwrap();
+    let dir: &'static str = Box::leak(dir.to_str().unwrap().to_string().into_boxed_str());
+
+    let server = TiiBuilder::default()
+      .router(|rt| rt.route_any("/*", builtin_endpoints::serve_dir_localized(dir)))
+      .expect("ERR")
+      .build();
+
+    let french = get_with(&server, "/page.html", "Accept-Language: fr-CA,fr;q=0.9,en;q=0.8");
+    assert!(french.ends_with("\r\n\r\nbonjour"));
+    assert_eq!(header(&french, "Vary"), "Accept-Language");
+    assert!(get_with(&server, "/page.html", "Accept-Language: en;q=0.9,fr;q=0.5")
+      .ends_with("\r\n\r\nbonjour"));
+    assert!(get_with(&server, "/page.html", "Accept-Language: de-CH").ends_with("\r\n\r\ngrüezi"));
+    assert!(get_with(&server, "/page.html", "Accept-Language: it").ends_with("\r\n\r\nhello"));
+    assert!(
+      get_with(&server, "/page.html", "Accept-Language: ../page.fr").ends_with("\r\n\r\nhello")
+    );
+    assert!(get(&server, "/page.html").ends_with("\r\n\r\nhello"));
+    assert!(get(&server, "/page.fr.html").ends_with("\r\n\r\nbonjour"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn serve_dir() {
+  inner::run();
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn serve_dir_with_listing() {
+  inner::run_listing();
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn serve_dirs() {
+  inner::run_chain();
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn serve_dir_etag() {
+  inner::run_etag();
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn serve_dir_localized() {
+  inner::run_localized();
+}
@@ -0,0 +1,98 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use std::thread;
+  use std::time::{Duration, Instant};
+  use tii::extras::{SseBroadcaster, SseEvent};
+  use tii::http::request::HttpVersion;
+  use tii::tii_error::TiiResult;
+
+  fn wait_for(stream: &MockStream, text: &str) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !stream.copy_written_data_to_string().contains(text) {
+      assert!(Instant::now() < deadline, "timed out waiting for {text}");
+      thread::sleep(Duration::from_millis(5));
+    }
+  }
+
+  pub(crate) fn work() -> TiiResult<()> {
+    let broadcaster = SseBroadcaster::new(2);
+
+    // The slow subscriber never gets its response written.
+    let slow = broadcaster.subscribe()?;
+
+    let mut fast = Vec::new();
+    for _ in 0..2 {
+      let stream = MockStream::without_data();
+      let con = stream.to_stream();
+      let handle = broadcaster.clone();
+      let writer = thread::spawn(move || {
+        let response = handle.subscribe()?;
+        drop(handle);
+        response.write_to(HttpVersion::Http11, con.as_stream_write())
+      });
+      fast.push((stream, writer));
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while broadcaster.subscribers() < 3 {
+      assert!(Instant::now() < deadline, "subscribers did not subscribe");
+      thread::sleep(Duration::from_millis(5));
+    }
+
+    // A subscriber whose client went away is cleaned up by the next broadcast.
+    drop(broadcaster.subscribe()?);
+
+    for data in ["one", "two", "three"] {
+      broadcaster.broadcast(&SseEvent::new(data).with_event("score"))?;
+      for (stream, _) in fast.iter() {
+        wait_for(stream, format!("event: score\ndata: {data}\n\n").as_str());
+      }
+    }
+
+    assert_eq!(broadcaster.subscribers(), 2);
+
+    drop(broadcaster);
+    for (stream, writer) in fast {
+      writer.join().expect("writer panicked")?;
+      let data = stream.copy_written_data_to_string();
+      assert!(data.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n"), "{data}");
+      assert!(data.contains("Cache-Control: no-cache\r\n"), "{data}");
+      assert!(data.ends_with("0\r\n\r\n"), "{data}");
+    }
+
+    // The slow subscriber was dropped when its buffer of 2 events was full, it only sees those.
+    let stream = MockStream::without_data();
+    let con = stream.to_stream();
+    slow.write_to(HttpVersion::Http11, con.as_stream_write())?;
+    let data = stream.copy_written_data_to_string();
+    assert!(data.contains("data: one\n\n"), "{data}");
+    assert!(data.contains("data: two\n\n"), "{data}");
+    assert!(!data.contains("data: three\n\n"), "{data}");
+    assert!(data.ends_with("0\r\n\r\n"), "{data}");
+    Ok(())
+  }
+
+  pub(crate) fn line_breaks() {
+    let event = SseEvent::new("a\r\nb\rc\nd").with_event("sco\rre").with_id("1\r\ndata: x\n");
+    assert_eq!(
+      String::from_utf8(event.to_bytes()).unwrap(),
+      "event: score\nid: 1data: x\ndata: a\ndata: b\ndata: c\ndata: d\n\n"
+    );
+  }
+}
+
+#[cfg(feature = "extras")]
+mod mock_stream;
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn sse_broadcast() {
+  inner::work().unwrap();
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn sse_event_line_breaks() {
+  inner::line_breaks();
+}
@@ -0,0 +1,64 @@
+use crate::mock_stream::MockStream;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::{Clock, TiiBuilder};
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+/// Clock that always reports the same wall clock time.
+#[derive(Debug)]
+struct FixedClock(SystemTime);
+
+impl Clock for FixedClock {
+  fn now(&self) -> Instant {
+    Instant::now()
+  }
+
+  fn system_time(&self) -> SystemTime {
+    self.0
+  }
+}
+
+fn get(server: &TiiServer, path: &str) -> String {
+  let stream = MockStream::with_str(&format!("GET {path} HTTP/1.1\r\n\r\n"));
+  server.handle_connection(stream.to_stream()).expect("ERR");
+  stream.copy_written_data_to_string()
+}
+
+fn date_server(date_header: bool) -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/", |_: &RequestContext| Response::ok("okay", MimeType::TextPlain))?.route_get(
+        "/own",
+        |_: &RequestContext| {
+          Response::ok("okay", MimeType::TextPlain)
+            .with_header("Date", "Mon, 01 Jan 2024 00:00:00 GMT")
+        },
+      )
+    })
+    .expect("ERR")
+    .with_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_secs(784111777))))
+    .expect("ERR")
+    .with_date_header(date_header)
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc100() {
+  let server = date_server(true);
+  assert_eq!(
+    get(&server, "/"),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nDate: Sun, 06 Nov 1994 08:49:37 GMT\r\nConnection: Close\r\nContent-Length: 4\r\n\r\nokay"
+  );
+
+  let own = get(&server, "/own");
+  assert!(own.contains("Date: Mon, 01 Jan 2024 00:00:00 GMT\r\n"), "{own}");
+  assert!(!own.contains("1994"), "{own}");
+
+  assert!(!get(&date_server(false), "/").contains("Date:"));
+}
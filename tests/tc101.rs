@@ -0,0 +1,103 @@
+use crate::mock_stream::MockStream;
+use std::time::Duration;
+use tii::http::cors::Cors;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::{TiiResult, UserError};
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn handler(_: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok("okay", MimeType::TextPlain))
+}
+
+fn server(cors: Cors) -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.with_cors(cors)?
+        .route_get("/items", handler)?
+        .route_post("/items", handler)?
+        .route_delete("/orders/{id}", handler)
+    })
+    .expect("ERR")
+    .build()
+}
+
+fn send(server: &TiiServer, request: &str) -> String {
+  let stream = MockStream::with_str(request);
+  server.handle_connection(stream.to_stream()).expect("ERR");
+  stream.copy_written_data_to_string()
+}
+
+const PREFLIGHT: &str = "OPTIONS /items HTTP/1.1\r\nOrigin: https://app.example\r\nAccess-Control-Request-Method: POST\r\nAccess-Control-Request-Headers: content-type\r\n\r\n";
+
+#[test]
+pub fn tc101_preflight() {
+  let server = server(
+    Cors::origins(["https://app.example"])
+      .with_allowed_header("Content-Type")
+      .with_max_age(Duration::from_secs(600)),
+  );
+
+  let response = send(&server, PREFLIGHT);
+  assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"), "{response}");
+  assert!(response.contains("Access-Control-Allow-Origin: https://app.example\r\n"), "{response}");
+  assert!(response.contains("Access-Control-Allow-Methods: GET, POST\r\n"), "{response}");
+  assert!(response.contains("Access-Control-Allow-Headers: Content-Type\r\n"), "{response}");
+  assert!(response.contains("Access-Control-Max-Age: 600\r\n"), "{response}");
+  assert!(response.contains("Vary: Origin\r\n"), "{response}");
+
+  // No route of the path handles the method, so this is routed like any other OPTIONS request.
+  let response = send(
+    &server,
+    "OPTIONS /items HTTP/1.1\r\nOrigin: https://app.example\r\nAccess-Control-Request-Method: PUT\r\n\r\n",
+  );
+  assert!(!response.contains("Access-Control-Allow-Methods"), "{response}");
+
+  let response = send(&server, &PREFLIGHT.replace("https://app.example", "https://evil.example"));
+  assert!(!response.contains("Access-Control-Allow"), "{response}");
+
+  let response = send(
+    &server,
+    "OPTIONS /orders/7 HTTP/1.1\r\nOrigin: https://app.example\r\nAccess-Control-Request-Method: DELETE\r\n\r\n",
+  );
+  assert!(response.contains("Access-Control-Allow-Methods: DELETE\r\n"), "{response}");
+}
+
+#[test]
+pub fn tc101_actual_request() {
+  let get = "GET /items HTTP/1.1\r\nOrigin: https://app.example\r\n\r\n";
+
+  let response = send(&server(Cors::any_origin().with_exposed_header("X-Total")), get);
+  assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+  assert!(response.contains("Access-Control-Allow-Origin: *\r\n"), "{response}");
+  assert!(response.contains("Access-Control-Expose-Headers: X-Total\r\n"), "{response}");
+  assert!(!response.contains("Vary"), "{response}");
+
+  let response = send(&server(Cors::origins(["https://app.example"]).with_credentials(true)), get);
+  assert!(response.contains("Access-Control-Allow-Origin: https://app.example\r\n"), "{response}");
+  assert!(response.contains("Access-Control-Allow-Credentials: true\r\n"), "{response}");
+  assert!(response.contains("Vary: Origin\r\n"), "{response}");
+
+  // Caches must not hand the response without Access-Control-Allow-Origin to an allowed origin.
+  let response = send(&server(Cors::origins(["https://other.example"])), get);
+  assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+  assert!(!response.contains("Access-Control"), "{response}");
+  assert!(response.contains("Vary: Origin\r\n"), "{response}");
+
+  let response = send(&server(Cors::any_origin()), "GET /items HTTP/1.1\r\n\r\n");
+  assert!(!response.contains("Access-Control"), "{response}");
+}
+
+#[test]
+pub fn tc101_credentials_require_origins() {
+  let Err(err) =
+    TiiBuilder::default().router(|rt| rt.with_cors(Cors::any_origin().with_credentials(true)))
+  else {
+    panic!("credentials for any origin were accepted");
+  };
+  assert!(matches!(err.downcast_ref::<UserError>(), Some(UserError::CorsCredentialsWithAnyOrigin)));
+}
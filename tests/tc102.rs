@@ -0,0 +1,65 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::response_body::ResponseBody;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+
+#[test]
+pub fn tc102_channel_body_http10_is_streamed() {
+  let (producer_sender, producer_receiver) = mpsc::channel();
+  let server = TiiBuilder::default()
+    .router(move |rt| {
+      rt.route_get("/events", move |_: &RequestContext| {
+        let (sender, receiver) = mpsc::channel();
+        producer_sender.send(sender).expect("ERR");
+        Response::ok(ResponseBody::from_channel(receiver), MimeType::TextPlain)
+      })
+    })
+    .expect("ERR")
+    .build();
+
+  let listener = TcpListener::bind("127.0.0.1:0").expect("ERR");
+  let addr = listener.local_addr().expect("ERR");
+  let handle = thread::spawn(move || {
+    let (stream, _) = listener.accept().expect("ERR");
+    server.handle_connection(stream).expect("ERR");
+  });
+
+  let mut client = TcpStream::connect(addr).expect("ERR");
+  client.set_read_timeout(Some(Duration::from_secs(10))).expect("ERR");
+  client.write_all(b"GET /events HTTP/1.0\r\n\r\n").expect("ERR");
+  let mut client = BufReader::new(client);
+
+  let mut head = String::new();
+  let sender: mpsc::Sender<Vec<u8>> = producer_receiver.recv().expect("ERR");
+  sender.send(b"data: first\n".to_vec()).expect("ERR");
+  loop {
+    let mut line = String::new();
+    client.read_line(&mut line).expect("ERR");
+    head.push_str(&line);
+    if line == "\r\n" {
+      break;
+    }
+  }
+  assert!(head.starts_with("HTTP/1.0 200 OK\r\n"), "{head}");
+  assert!(head.contains("Connection: Close\r\n"), "{head}");
+  assert!(!head.contains("Content-Length"), "{head}");
+  assert!(!head.contains("Transfer-Encoding"), "{head}");
+
+  // The first event arrives while the channel is still open, the body is not buffered.
+  let mut line = String::new();
+  client.read_line(&mut line).expect("ERR");
+  assert_eq!(line, "data: first\n");
+
+  sender.send(b"data: second\n".to_vec()).expect("ERR");
+  drop(sender);
+  let mut rest = String::new();
+  client.read_to_string(&mut rest).expect("ERR");
+  assert_eq!(rest, "data: second\n");
+  handle.join().expect("ERR");
+}
@@ -6,6 +6,7 @@ use tii::http::response_body::ResponseBody;
 use tii::http::{Response, StatusCode};
 use tii::tii_builder::TiiBuilder;
 use tii::tii_error::TiiResult;
+use tii::tii_server::UnexpectedBodyPolicy;
 
 mod mock_stream;
 
@@ -24,8 +25,12 @@ fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
 
 #[test]
 pub fn tc17() {
-  let server =
-    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+  let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .build();
 
   let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nContent-Length: 5\r\n\r\n12345");
   let con = stream.to_stream();
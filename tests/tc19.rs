@@ -3,6 +3,7 @@ use tii::http::request_context::RequestContext;
 use tii::http::{Response, StatusCode};
 use tii::tii_builder::TiiBuilder;
 use tii::tii_error::TiiResult;
+use tii::tii_server::UnexpectedBodyPolicy;
 
 mod mock_stream;
 
@@ -15,8 +16,12 @@ fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
 
 #[test]
 pub fn tc19() {
-  let server =
-    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+  let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .build();
 
   let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n12345\r\n10\r\n1234567890123456\r\n0\r\n\r\n");
   let con = stream.to_stream();
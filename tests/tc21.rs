@@ -6,6 +6,7 @@ use tii::http::request_context::RequestContext;
 use tii::http::Response;
 use tii::tii_builder::TiiBuilder;
 use tii::tii_error::TiiResult;
+use tii::tii_server::UnexpectedBodyPolicy;
 
 mod mock_stream;
 
@@ -32,6 +33,8 @@ fn dummy_route_eof(ctx: &RequestContext) -> TiiResult<Response> {
 #[test]
 pub fn tc21a() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERR")
     .build();
@@ -46,6 +49,8 @@ pub fn tc21a() {
 #[test]
 pub fn tc21b() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERR")
     .build();
@@ -60,6 +65,8 @@ pub fn tc21b() {
 #[test]
 pub fn tc21c() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERR")
     .build();
@@ -74,6 +81,8 @@ pub fn tc21c() {
 #[test]
 pub fn tc21d() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERR")
     .build();
@@ -88,6 +97,8 @@ pub fn tc21d() {
 #[test]
 pub fn tc21e() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERROR")
     .build();
@@ -102,6 +113,8 @@ pub fn tc21e() {
 #[test]
 pub fn tc21f() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERROR")
     .build();
@@ -116,6 +129,8 @@ pub fn tc21f() {
 #[test]
 pub fn tc21g() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERROR")
     .build();
@@ -130,6 +145,8 @@ pub fn tc21g() {
 #[test]
 pub fn tc21h() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_eof))
     .expect("ERROR")
     .build();
@@ -144,6 +161,8 @@ pub fn tc21h() {
 #[test]
 pub fn tc21i() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERROR")
     .build();
@@ -158,6 +177,8 @@ pub fn tc21i() {
 #[test]
 pub fn tc21j() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERROR")
     .build();
@@ -172,6 +193,8 @@ pub fn tc21j() {
 #[test]
 pub fn tc21k() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_eof))
     .expect("ERROR")
     .build();
@@ -188,6 +211,8 @@ pub fn tc21k() {
 #[test]
 pub fn tc21l() {
   let server = TiiBuilder::default()
+    .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)
+    .expect("ERR")
     .router(|rt| rt.route_any("/dummy", dummy_route_invalid_data))
     .expect("ERROR")
     .build();
@@ -7,6 +7,7 @@ use tii::http::request_context::RequestContext;
 use tii::http::Response;
 use tii::tii_builder::TiiBuilder;
 use tii::tii_error::TiiResult;
+use tii::tii_server::UnexpectedBodyPolicy;
 
 mod mock_stream;
 
@@ -40,6 +41,7 @@ pub fn tc33() {
           .with_pre_routing_request_filter(filter_set_accept)
       })?
       .with_max_head_buffer_size(512)?
+      .with_unexpected_body_policy(UnexpectedBodyPolicy::PassThrough)?
       .ok()
   })
   .expect("ERROR");
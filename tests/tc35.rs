@@ -33,15 +33,13 @@ pub fn tc35_1() {
 #[test]
 pub fn tc35_2() {
   let server =
-    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route2)).expect("ERR").build();
 
   let stream = MockStream::with_str("GET /dummy?&b HTTP/1.1\r\nHdr: test\r\n\r\n");
   let con = stream.to_stream();
-  let err = server.handle_connection(con).unwrap_err();
-  assert_eq!(err.kind(), io::ErrorKind::InvalidData);
-  assert_eq!(err.to_string(), "InvalidQueryString(\"&b\")");
+  server.handle_connection(con).unwrap();
   let data = stream.copy_written_data_to_string();
-  assert_eq!(data, "");
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 11\r\n\r\n[(\"b\", \"\")]");
 }
 
 #[test]
@@ -97,3 +95,26 @@ pub fn tc35_6() {
   let data = stream.copy_written_data_to_string();
   assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 27\r\n\r\n[(\"a!\", \"!\"), (\"b!\", \"a!\")]");
 }
+
+fn search_route(ctx: &RequestContext) -> Response {
+  let head = ctx.request_head();
+  assert_eq!(head.get_query_param("q"), Some("hello world"));
+  assert_eq!(head.get_query_params("tag"), vec!["a", "b"]);
+  assert_eq!(head.get_query_param("flag"), Some(""));
+  assert_eq!(head.get_query_param("path"), Some("/a b+c"));
+  Response::ok(format!("{:?}", head.query()), MimeType::TextPlain)
+}
+
+#[test]
+pub fn tc35_7() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/search", search_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str(
+    "GET /search?q=hello+world&tag=a&tag=b&flag&path=%2Fa%20b%2Bc HTTP/1.1\r\nHdr: test\r\n\r\n",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 84\r\n\r\n[(\"q\", \"hello world\"), (\"tag\", \"a\"), (\"tag\", \"b\"), (\"flag\", \"\"), (\"path\", \"/a b+c\")]");
+}
@@ -0,0 +1,68 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut data = Vec::new();
+  ctx.request_body().unwrap().read_to_end(&mut data)?;
+  Ok(Response::ok(data, MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc36_small_body_no_interim() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_continue_threshold(16)
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(
+    "POST /dummy HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\r\nhello",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nhello");
+}
+
+#[test]
+pub fn tc36_large_body_interim() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_continue_threshold(16)
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(
+    "POST /dummy HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 20\r\n\r\nhellohellohellohello",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 20\r\n\r\nhellohellohellohello");
+}
+
+#[test]
+pub fn tc36_chunked_body_interim() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_continue_threshold(16)
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(
+    "POST /dummy HTTP/1.1\r\nExpect: 100-continue\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nhello");
+}
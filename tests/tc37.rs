@@ -0,0 +1,79 @@
+use crate::mock_stream::MockStream;
+use std::sync::Mutex;
+use tii::http::headers::HeaderName;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::response::ResponseKind;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Response::ok("Okay!", MimeType::TextPlain).with_header(HeaderName::ETag, "\"v1\"")
+}
+
+#[test]
+pub fn tc37_matching_etag() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nIf-None-Match: \"v0\", \"v1\"\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 304 Not Modified\r\nContent-Type: text/plain\r\nETag: \"v1\"\r\nConnection: Close\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc37_mismatching_etag() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nIf-None-Match: \"v0\"\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nETag: \"v1\"\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nOkay!");
+}
+
+#[test]
+pub fn tc37_post_is_not_conditional() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str("POST /dummy HTTP/1.1\r\nIf-None-Match: \"v1\"\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nETag: \"v1\"\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nOkay!");
+}
+
+static SEEN_KIND: Mutex<Option<ResponseKind>> = Mutex::new(None);
+
+fn meta_filter(_ctx: &mut RequestContext, response: Response) -> TiiResult<Response> {
+  *SEEN_KIND.lock().unwrap() = Some(response.meta().kind);
+  Ok(response)
+}
+
+#[test]
+pub fn tc37_response_meta() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route)?.with_response_filter(meta_filter))
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nIf-None-Match: \"v1\"\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  assert_eq!(*SEEN_KIND.lock().unwrap(), Some(ResponseKind::NotModified));
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nIf-None-Match: \"v0\"\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  assert_eq!(*SEEN_KIND.lock().unwrap(), Some(ResponseKind::Full));
+}
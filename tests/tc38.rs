@@ -0,0 +1,105 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok(ctx.absolute_url("/path"), MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc38_trusted_proxy() {
+  // MockStream reports its peer address as "Box".
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_trusted_proxy("Box")
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nHost: 10.0.0.1:8080\r\nX-Forwarded-Proto: https\r\nX-Forwarded-Host: public.example.com\r\nX-Forwarded-Port: 443\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 31\r\n\r\nhttps://public.example.com/path");
+}
+
+#[test]
+pub fn tc38_untrusted_proxy() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_trusted_proxy("127.0.0.1")
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nHost: 10.0.0.1:8080\r\nX-Forwarded-Proto: https\r\nX-Forwarded-Host: public.example.com\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 25\r\n\r\nhttp://10.0.0.1:8080/path");
+}
+
+fn client_route(ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok(ctx.client_address(), MimeType::TextPlain))
+}
+
+fn client_address(trusted: &[&str], headers: &str) -> String {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/client", client_route))
+    .expect("ERR")
+    .with_trusted_proxies(trusted)
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(format!("GET /client HTTP/1.1\r\n{headers}\r\n").as_str());
+  server.handle_connection(stream.to_stream()).unwrap();
+  let data = stream.copy_written_data_to_string();
+  data.split_once("\r\n\r\n").unwrap().1.to_string()
+}
+
+#[test]
+pub fn tc38_client_address_untrusted_peer() {
+  // The forwarding headers of an untrusted peer are spoofed.
+  assert_eq!(client_address(&["127.0.0.1"], "X-Forwarded-For: 203.0.113.7\r\n"), "Box");
+  assert_eq!(client_address(&[], "Forwarded: for=203.0.113.7\r\n"), "Box");
+}
+
+#[test]
+pub fn tc38_client_address_trusted_proxy() {
+  assert_eq!(client_address(&["Box"], "X-Forwarded-For: 203.0.113.7\r\n"), "203.0.113.7");
+  assert_eq!(client_address(&["Box"], ""), "Box");
+
+  // The client prepended a spoofed entry, the rightmost entry that is not a trusted proxy wins.
+  assert_eq!(
+    client_address(&["Box", "10.0.0.2"], "X-Forwarded-For: 1.1.1.1, 203.0.113.7, 10.0.0.2\r\n"),
+    "203.0.113.7"
+  );
+  assert_eq!(
+    client_address(&["Box"], "X-Forwarded-For: 1.1.1.1\r\nX-Forwarded-For: 203.0.113.7\r\n"),
+    "203.0.113.7"
+  );
+
+  // Forwarded takes precedence over X-Forwarded-For.
+  assert_eq!(
+    client_address(
+      &["Box"],
+      "Forwarded: for=\"[2001:db8::1]:4711\";proto=https\r\nX-Forwarded-For: 203.0.113.7\r\n"
+    ),
+    "2001:db8::1"
+  );
+  assert_eq!(
+    client_address(
+      &["Box", "10.0.0.2"],
+      "Forwarded: for=1.1.1.1, for=203.0.113.7:80, for=10.0.0.2\r\n"
+    ),
+    "203.0.113.7"
+  );
+
+  // Obfuscated identifiers are no addresses, the proxy is the best we know.
+  assert_eq!(client_address(&["Box"], "Forwarded: for=unknown\r\n"), "Box");
+}
@@ -0,0 +1,70 @@
+use crate::mock_stream::MockStream;
+use std::sync::Mutex;
+use tii::http::mime::MimeType;
+use tii::http::request_body::RequestBodyTee;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+static AUDIT_LOG: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+fn tee_filter(ctx: &mut RequestContext) -> TiiResult<Option<Response>> {
+  if let Some(body) = ctx.request_body() {
+    let tee = body.tee(1024)?;
+    ctx.set_property("tee", tee);
+  }
+  Ok(None)
+}
+
+fn audit_filter(ctx: &mut RequestContext, response: Response) -> TiiResult<Response> {
+  if let Some(tee) = ctx.get_property::<RequestBodyTee, _>("tee") {
+    *AUDIT_LOG.lock().unwrap() = tee.captured()?;
+  }
+  Ok(response)
+}
+
+fn streaming_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let body = ctx.request_body().unwrap();
+  let mut total = 0usize;
+  let mut buf = [0u8; 100];
+  loop {
+    let read = body.read(&mut buf)?;
+    if read == 0 {
+      break;
+    }
+    total += read;
+  }
+
+  Ok(Response::ok(total.to_string(), MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc39() {
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.route_any("/dummy", streaming_route)?
+        .with_pre_routing_request_filter(tee_filter)?
+        .with_response_filter(audit_filter)
+    })
+    .expect("ERR")
+    .build();
+
+  let payload: String = (0..3000).map(|i| char::from(b'a' + (i % 26) as u8)).collect();
+  let stream = MockStream::with_str(
+    format!("POST /dummy HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", payload.len(), payload)
+      .as_str(),
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 4\r\n\r\n3000"
+  );
+
+  let captured = AUDIT_LOG.lock().unwrap().clone();
+  assert_eq!(captured.as_slice(), &payload.as_bytes()[..1024]);
+}
@@ -0,0 +1,45 @@
+use crate::mock_stream::MockStream;
+use tii::http::headers::HeaderName;
+use tii::http::method::Method;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::{Response, StatusCode};
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok("Okay!", MimeType::TextPlain))
+}
+
+fn options_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let allow =
+    ctx.allowed_methods_for_path().iter().map(Method::as_str).collect::<Vec<_>>().join(", ");
+
+  Response::new(StatusCode::NoContent)
+    .with_header(HeaderName::Allow, allow)?
+    .with_header("X-Custom", "custom")
+}
+
+#[test]
+pub fn tc40() {
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/dummy", dummy_route)?
+        .route_post("/dummy", dummy_route)?
+        .route_options("/dummy", options_route)?
+        .route_get("/other", dummy_route)
+    })
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("OPTIONS /dummy HTTP/1.1\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 204 No Content\r\nAllow: GET, POST, OPTIONS\r\nX-Custom: custom\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
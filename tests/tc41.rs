@@ -0,0 +1,32 @@
+use crate::mock_stream::MockStream;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn panic_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  panic!("handler exploded");
+}
+
+#[test]
+pub fn tc41() {
+  let observed = Arc::new(Mutex::new(Vec::<String>::new()));
+  let observed_clone = observed.clone();
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", panic_route))
+    .expect("ERR")
+    .with_panic_observer(move |msg| observed_clone.lock().unwrap().push(msg.to_string()))
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\n\r\n");
+  let con = stream.to_stream();
+  let result = panic::catch_unwind(AssertUnwindSafe(|| server.handle_connection(con)));
+  assert!(result.is_err());
+  assert_eq!(observed.lock().unwrap().as_slice(), &["handler exploded".to_string()]);
+}
@@ -0,0 +1,29 @@
+use crate::mock_stream::MockStream;
+use tii::http::cookie::SetCookie;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let session = ctx.cookies().get("session").map(|c| c.value.clone()).unwrap_or_default();
+  ctx.cookies().remove("session")?;
+  ctx.cookies().set(SetCookie::new("token", "abc").with_path("/").with_http_only(true))?;
+  Ok(Response::ok(session, MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc42() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+
+  let stream =
+    MockStream::with_str("GET /dummy HTTP/1.1\r\nCookie: theme=dark; session=s3cr3t\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nSet-Cookie: session=; Max-Age=0\r\nSet-Cookie: token=abc; Path=/; HttpOnly\r\nConnection: Close\r\nContent-Length: 6\r\n\r\ns3cr3t");
+}
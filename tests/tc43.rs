@@ -0,0 +1,40 @@
+use crate::mock_stream::MockStream;
+use sha1::{Digest, Sha1};
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::{RequestHead, Response};
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok("Okay!", MimeType::TextPlain))
+}
+
+fn sha1_filter(_head: &RequestHead, wire: &mut Vec<u8>) {
+  let Some(head_len) = wire.windows(4).position(|w| w == b"\r\n\r\n") else {
+    return;
+  };
+
+  let digest = Sha1::digest(&wire[head_len + 4..]);
+  let hex = digest.iter().map(|b| format!("{b:02x}")).collect::<String>();
+  let header = format!("\r\nX-Body-SHA1: {hex}");
+  wire.splice(head_len..head_len, header.into_bytes());
+}
+
+#[test]
+pub fn tc43() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_wire_filter(sha1_filter)
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\nX-Body-SHA1: 512623b285c56ee64c83c43569e845f17e13b65a\r\n\r\nOkay!");
+}
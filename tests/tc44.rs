@@ -0,0 +1,65 @@
+use crate::mock_stream::MockStream;
+use std::io;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::{RequestHeadParsingError, TiiResult};
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  unreachable!();
+}
+
+fn parse_error(request: &str) -> (io::ErrorKind, String, bool) {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERROR").build();
+
+  let stream = MockStream::with_str(request);
+  let err = server.handle_connection(stream.to_stream()).unwrap_err();
+  assert_eq!(stream.copy_written_data_to_string(), "");
+  let is_parse_error = err.downcast_ref::<RequestHeadParsingError>().is_some();
+  (err.kind(), err.to_string(), is_parse_error)
+}
+
+#[test]
+pub fn tc44_truncated_status_line() {
+  let (kind, msg, parse) = parse_error("GET /dummy HTTP/1.1");
+  assert_eq!(kind, io::ErrorKind::UnexpectedEof);
+  assert_eq!(msg, "HeadTruncated");
+  assert!(parse);
+}
+
+#[test]
+pub fn tc44_truncated_header() {
+  let (kind, msg, parse) = parse_error("GET /dummy HTTP/1.1\r\nHdr: te");
+  assert_eq!(kind, io::ErrorKind::UnexpectedEof);
+  assert_eq!(msg, "HeadTruncated");
+  assert!(parse);
+}
+
+#[test]
+pub fn tc44_bad_header_line() {
+  let (kind, msg, parse) = parse_error("GET /dummy HTTP/1.1\r\nHdr:test\r\n\r\n");
+  assert_eq!(kind, io::ErrorKind::InvalidData);
+  assert_eq!(msg, "HeaderValueMissing");
+  assert!(parse);
+}
+
+#[test]
+pub fn tc44_too_many_whitespaces() {
+  let (kind, msg, parse) = parse_error("GET /dummy HTTP/1.1 x\r\n\r\n");
+  assert_eq!(kind, io::ErrorKind::InvalidData);
+  assert_eq!(msg, "StatusLineTooManyWhitespaces");
+  assert!(parse);
+}
+
+#[test]
+pub fn tc44_header_line_too_long() {
+  let long = "a".repeat(9000);
+  let (kind, msg, parse) =
+    parse_error(format!("GET /dummy HTTP/1.1\r\nHdr: {long}\r\n\r\n").as_str());
+  assert_eq!(kind, io::ErrorKind::InvalidData);
+  assert_eq!(msg, "HeaderLineTooLong(8192 bytes)");
+  assert!(parse);
+}
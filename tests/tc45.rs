@@ -0,0 +1,40 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok(ctx.request_head().path(), MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc45_merge_slashes() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/foo/bar", dummy_route))
+    .expect("ERR")
+    .with_merge_slashes(true)
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET //foo//bar HTTP/1.1\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 8\r\n\r\n/foo/bar");
+}
+
+#[test]
+pub fn tc45_merge_slashes_disabled() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/foo/bar", dummy_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str("GET //foo//bar HTTP/1.1\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert!(data.starts_with("HTTP/1.1 404 Not Found\r\n"), "{}", data);
+}
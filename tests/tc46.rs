@@ -0,0 +1,67 @@
+use crate::mock_stream::MockStream;
+use std::io::ErrorKind;
+use tii::http::request_context::RequestContext;
+use tii::http::{Response, StatusCode};
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let body = ctx.request_body().unwrap();
+  let mut data = vec![];
+  body.read_to_end(&mut data)?;
+  Response::new(StatusCode::OK).with_body(data).into()
+}
+
+fn tiny_chunks(count: usize) -> String {
+  let mut request = "POST /dummy HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n".to_string();
+  for _ in 0..count {
+    request.push_str("1\r\na\r\n");
+  }
+  request.push_str("0\r\n\r\n");
+  request
+}
+
+#[test]
+pub fn tc46_too_many_chunks() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_max_body_chunks(Some(100))
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(tiny_chunks(5000).as_str());
+  let con = stream.to_stream();
+  // The response is written, then draining the rest of the failed body fails the connection.
+  let err = server.handle_connection(con).unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 413 Content Too Large\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc46_chunks_within_limit() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_max_body_chunks(Some(100))
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(tiny_chunks(100).as_str());
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    format!(
+      "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 100\r\n\r\n{}",
+      "a".repeat(100)
+    )
+  );
+}
@@ -0,0 +1,91 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  match ctx.negotiated_content_type() {
+    Some(MimeType::TextHtml) => Ok(Response::ok("<p>Hello</p>", MimeType::TextHtml)),
+    Some(MimeType::TextPlain) => Ok(Response::ok("Hello", MimeType::TextPlain)),
+    other => Ok(Response::ok(format!("{:?}", other), MimeType::TextPlain)),
+  }
+}
+
+fn server() -> tii::tii_server::TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.begin_get("/dummy", |route| {
+        route.produces(MimeType::TextHtml).produces(MimeType::TextPlain).endpoint(dummy_route)
+      })?
+      .begin_get("/reversed", |route| {
+        route.produces(MimeType::TextPlain).produces(MimeType::TextHtml).endpoint(dummy_route)
+      })
+    })
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc47_negotiated_plain() {
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nAccept: text/plain\r\n\r\n");
+  let con = stream.to_stream();
+  server().handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello"
+  );
+}
+
+#[test]
+pub fn tc47_negotiated_html() {
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nAccept: text/html\r\n\r\n");
+  let con = stream.to_stream();
+  server().handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: Close\r\nContent-Length: 12\r\n\r\n<p>Hello</p>"
+  );
+}
+
+#[test]
+pub fn tc47_negotiated_by_quality() {
+  let stream = MockStream::with_str(
+    "GET /dummy HTTP/1.1\r\nAccept: text/html;q=0.5, text/plain;q=0.8\r\n\r\n",
+  );
+  let con = stream.to_stream();
+  server().handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello"
+  );
+}
+
+#[test]
+pub fn tc47_wildcard_uses_declaration_order() {
+  for _ in 0..16 {
+    let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\nAccept: */*\r\n\r\n");
+    let con = stream.to_stream();
+    server().handle_connection(con).unwrap();
+    let data = stream.copy_written_data_to_string();
+    assert_eq!(
+      data,
+      "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: Close\r\nContent-Length: 12\r\n\r\n<p>Hello</p>"
+    );
+
+    let stream = MockStream::with_str("GET /reversed HTTP/1.1\r\nAccept: */*\r\n\r\n");
+    let con = stream.to_stream();
+    server().handle_connection(con).unwrap();
+    let data = stream.copy_written_data_to_string();
+    assert_eq!(
+      data,
+      "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello"
+    );
+  }
+}
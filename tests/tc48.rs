@@ -0,0 +1,31 @@
+use crate::mock_stream::MockStream;
+use std::io;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  unreachable!()
+}
+
+#[test]
+pub fn tc48() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/", dummy_route))
+    .expect("ERR")
+    .with_version_not_supported_response(true)
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET / HTTP/2.5\r\nHdr: test\r\n\r\n");
+  let con = stream.to_stream();
+  let err = server.handle_connection(con).unwrap_err();
+  assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  assert_eq!(err.to_string(), "HttpVersionNotSupported(\"HTTP/2.5\")");
+
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 505 HTTP Version Not Supported\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 48\r\n\r\nSupported versions: HTTP/1.1, HTTP/1.0, HTTP/0.9");
+}
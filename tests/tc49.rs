@@ -0,0 +1,61 @@
+use crate::mock_stream::MockStream;
+use std::path::PathBuf;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn upload_dir() -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("tii_tc49_{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+fn upload(ctx: &RequestContext) -> TiiResult<Response> {
+  let path = upload_dir().join(ctx.get_path_param("name").unwrap());
+  let written = ctx.body_to_file_with_limit(path, 16)?;
+  Ok(Response::ok(written.to_string(), MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default().router(|rt| rt.route_any("/upload/{name}", upload)).expect("ERR").build()
+}
+
+#[test]
+pub fn tc49_body_to_file() {
+  let stream = MockStream::with_str(
+    "POST /upload/tc49_body_to_file HTTP/1.1\r\nContent-Length: 12\r\n\r\nHello World!",
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 2\r\n\r\n12"
+  );
+
+  let content = std::fs::read_to_string(upload_dir().join("tc49_body_to_file")).unwrap();
+  assert_eq!(content, "Hello World!");
+}
+
+#[test]
+pub fn tc49_body_to_file_too_large() {
+  let stream = MockStream::with_str("POST /upload/tc49_body_to_file_too_large HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nA\r\n0123456789\r\nA\r\n0123456789\r\n0\r\n\r\n");
+  server().handle_connection(stream.to_stream()).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 413 Content Too Large\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+
+  // Neither the target nor the temporary file are left behind.
+  let leftovers = std::fs::read_dir(upload_dir())
+    .unwrap()
+    .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+    .filter(|name| name.contains("too_large"))
+    .collect::<Vec<_>>();
+  assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
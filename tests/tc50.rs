@@ -0,0 +1,65 @@
+use crate::mock_stream::MockStream;
+use tii::http::request_context::RequestContext;
+use tii::http::{Response, StatusCode};
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_router::Routeable;
+use tii::websocket::stream::{WebsocketReceiver, WebsocketSender};
+
+mod mock_stream;
+
+fn dummy_ws(_ctx: &RequestContext, _rx: WebsocketReceiver, _tx: WebsocketSender) {
+  unreachable!()
+}
+
+fn not_implemented(_ctx: &mut RequestContext, _routes: &[Routeable]) -> TiiResult<Response> {
+  Ok(Response::new(StatusCode::NotImplemented))
+}
+
+#[test]
+pub fn tc50_unmatched_upgrade() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.ws_route_any("/ws", dummy_ws)).expect("ERR").build();
+
+  let stream = MockStream::with_str("GET /nope HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERR");
+
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n");
+}
+
+#[test]
+pub fn tc50_unmatched_upgrade_custom_handler() {
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.ws_route_any("/ws", dummy_ws)?.with_websocket_not_found_handler(not_implemented)
+    })
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET /nope HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERR");
+
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 501 Not Implemented\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc50_bad_handshake() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.ws_route_any("/ws", dummy_ws)).expect("ERR").build();
+
+  let stream = MockStream::with_str(
+    "GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\r\n",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERR");
+
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n");
+}
@@ -0,0 +1,44 @@
+use crate::mock_stream::MockStream;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  unreachable!()
+}
+
+#[test]
+pub fn tc51_unknown_expectation() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str(
+    "POST /dummy HTTP/1.1\r\nExpect: 200-ok\r\nConnection: keep-alive\r\nContent-Length: 5\r\n\r\nhello",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 417 Expectation Failed\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc51_continue_is_case_insensitive() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", |_: &RequestContext| Ok(Response::no_content())))
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(
+    "POST /dummy HTTP/1.1\r\nExpect: 100-Continue\r\nContent-Length: 0\r\n\r\n",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 204 No Content\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n");
+}
@@ -0,0 +1,26 @@
+use crate::mock_stream::MockStream;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Response::no_content().with_header("x-custom", "v")?.with_header("CONTENT-type", "t")
+}
+
+#[test]
+pub fn tc52_header_name_casing() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 204 No Content\r\nx-custom: v\r\nContent-Type: t\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
@@ -0,0 +1,57 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::{InvalidPathError, TiiResult};
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn items(ctx: &RequestContext) -> TiiResult<Response> {
+  let body = match ctx.get_path_param("id") {
+    Some(id) => format!("item {id}"),
+    None => "all items".to_string(),
+  };
+  Ok(Response::ok(body, MimeType::TextPlain))
+}
+
+fn new_item(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok("new item", MimeType::TextPlain))
+}
+
+fn get(server: &TiiServer, path: &str) -> String {
+  let stream = MockStream::with_str(format!("GET {path} HTTP/1.1\r\n\r\n").as_str());
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERROR");
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc53_optional_variable() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_get("/items/new", new_item)?.route_get("/items/{id?}", items))
+    .expect("ERROR")
+    .build();
+
+  assert_eq!(get(&server, "/items"), "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 9\r\n\r\nall items");
+  assert_eq!(get(&server, "/items/"), "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 9\r\n\r\nall items");
+  assert_eq!(get(&server, "/items/5"), "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 6\r\n\r\nitem 5");
+  assert_eq!(get(&server, "/items/new"), "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 8\r\n\r\nnew item");
+  assert_eq!(
+    get(&server, "/items/5/parts"),
+    "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc53_optional_variable_not_last() {
+  let Err(err) = TiiBuilder::default().router(|rt| rt.route_get("/items/{id?}/parts", items))
+  else {
+    panic!("optional variable in the middle of the path was accepted");
+  };
+  assert_eq!(
+    err.downcast_ref::<InvalidPathError>(),
+    Some(&InvalidPathError::OptionalVariableNotLast("/items/{id?}/parts".to_string()))
+  );
+}
@@ -0,0 +1,48 @@
+use crate::mock_stream::MockStream;
+use std::io::Cursor;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::response_body::ResponseBody;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn streamed(_ctx: &RequestContext) -> TiiResult<Response> {
+  let body = ResponseBody::from_reader_close_delimited(Cursor::new("hello world"));
+  Ok(Response::ok(body, MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc54_http10() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", streamed)).expect("ERR").build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.0\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\n\r\nhello world"
+  );
+}
+
+#[test]
+pub fn tc54_http11_closes_connection() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", streamed)).expect("ERR").build();
+
+  // The second request is never served, the connection is closed to end the body of the first.
+  let stream = MockStream::with_str(
+    "GET /dummy HTTP/1.1\r\nConnection: keep-alive\r\n\r\nGET /dummy HTTP/1.1\r\nConnection: keep-alive\r\n\r\n",
+  );
+  let con = stream.to_stream();
+  server.handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(
+    data,
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\n\r\nhello world"
+  );
+}
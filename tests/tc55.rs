@@ -0,0 +1,63 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn hello(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok("hello", MimeType::TextPlain))
+}
+
+fn auth(ctx: &mut RequestContext) -> Option<Response> {
+  match ctx.request_head().get_header("Authorization") {
+    Some("secret") => None,
+    _ => Some(Response::unauthorized()),
+  }
+}
+
+fn audit(ctx: &mut RequestContext) {
+  ctx.set_property("audit", true);
+}
+
+fn audited(ctx: &RequestContext) -> TiiResult<Response> {
+  assert_eq!(ctx.get_property::<bool, _>("audit"), Some(&true));
+  Ok(Response::ok("audited", MimeType::TextPlain))
+}
+
+fn get(server: &TiiServer, request: &str) -> String {
+  let stream = MockStream::with_str(request);
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERROR");
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc55_scoped_filters() {
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/public", hello)?
+        .begin_filtered(auth, |rt| {
+          rt.get("/admin/audit").with_filter(audit).endpoint(audited)?.route_get("/admin/*", hello)
+        })?
+        .route_get("/also-public", hello)
+    })
+    .expect("ERROR")
+    .build();
+
+  let ok = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nhello";
+  let unauthorized = "HTTP/1.1 401 Unauthorized\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n";
+
+  assert_eq!(get(&server, "GET /public HTTP/1.1\r\n\r\n"), ok);
+  assert_eq!(get(&server, "GET /also-public HTTP/1.1\r\n\r\n"), ok);
+  assert_eq!(get(&server, "GET /admin/x HTTP/1.1\r\n\r\n"), unauthorized);
+  assert_eq!(get(&server, "GET /admin/x HTTP/1.1\r\nAuthorization: secret\r\n\r\n"), ok);
+  assert_eq!(get(&server, "GET /admin/audit HTTP/1.1\r\n\r\n"), unauthorized);
+  assert_eq!(
+    get(&server, "GET /admin/audit HTTP/1.1\r\nAuthorization: secret\r\n\r\n"),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 7\r\n\r\naudited"
+  );
+}
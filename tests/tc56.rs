@@ -0,0 +1,62 @@
+use crate::mock_stream::MockStream;
+use std::io::ErrorKind;
+use tii::http::request_context::RequestContext;
+use tii::http::{Response, StatusCode};
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let body = ctx.request_body().unwrap();
+  let mut data = vec![];
+  body.read_to_end(&mut data)?;
+  Response::new(StatusCode::OK).with_body(data).into()
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .with_max_head_buffer_size(256)
+    .expect("ERR")
+    .build()
+}
+
+fn with_trailer(trailer: &str) -> String {
+  format!(
+    "POST /dummy HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n{trailer}\r\n"
+  )
+}
+
+#[test]
+pub fn tc56_trailer_within_limit() {
+  let stream = MockStream::with_str(with_trailer("Expires: never\r\nX-Checksum: abc\r\n").as_str());
+  let con = stream.to_stream();
+  server().handle_connection(con).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nhello");
+}
+
+#[test]
+pub fn tc56_oversized_trailer_line() {
+  let trailer = format!("X-Big: {}\r\n", "a".repeat(300));
+  let stream = MockStream::with_str(with_trailer(trailer.as_str()).as_str());
+  let con = stream.to_stream();
+  let err = server().handle_connection(con).unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n");
+}
+
+#[test]
+pub fn tc56_too_many_trailers() {
+  let trailer = "X-A: b\r\n".repeat(100);
+  let stream = MockStream::with_str(with_trailer(trailer.as_str()).as_str());
+  let con = stream.to_stream();
+  let err = server().handle_connection(con).unwrap_err();
+  assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n");
+}
@@ -0,0 +1,87 @@
+#[cfg(feature = "serde")]
+mod mock_stream;
+
+#[cfg(feature = "serde")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use serde::Deserialize;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+  use tii::tii_server::TiiServer;
+
+  const BODY: &str = "{ \"event\" :\t\"paid\",\r\n  \"amount\": 42 }\n";
+
+  #[derive(Debug, Deserialize, PartialEq, Eq)]
+  struct Event {
+    event: String,
+    amount: u32,
+  }
+
+  fn webhook(ctx: &RequestContext) -> TiiResult<Response> {
+    let raw = ctx.raw_body()?.to_vec();
+    assert_eq!(raw.as_slice(), BODY.as_bytes());
+
+    let event: Event = ctx.parse_body()?;
+    assert_eq!(event, Event { event: "paid".to_string(), amount: 42 });
+
+    // Still the same bytes after the body has been parsed.
+    assert_eq!(ctx.raw_body()?, raw.as_slice());
+    Ok(Response::ok(raw, MimeType::TextPlain))
+  }
+
+  fn limited(ctx: &RequestContext) -> TiiResult<Response> {
+    ctx.raw_body_with_limit(8)?;
+    unreachable!()
+  }
+
+  fn server() -> TiiServer {
+    TiiBuilder::default()
+      .router(|rt| rt.route_any("/webhook", webhook)?.route_any("/limited", limited))
+      .expect("ERR")
+      .build()
+  }
+
+  fn send(server: &TiiServer, request: &str) -> String {
+    let stream = MockStream::with_str(request);
+    _ = server.handle_connection(stream.to_stream());
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn work() {
+    let server = server();
+    let expected = format!(
+      "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: {}\r\n\r\n{BODY}",
+      BODY.len()
+    );
+
+    let request = format!(
+      "POST /webhook HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{BODY}",
+      BODY.len()
+    );
+    assert_eq!(send(&server, request.as_str()), expected);
+
+    let request = format!(
+      "POST /webhook HTTP/1.1\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{BODY}\r\n0\r\n\r\n",
+      BODY.len()
+    );
+    assert_eq!(send(&server, request.as_str()), expected);
+
+    let request = format!(
+      "POST /limited HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{BODY}",
+      BODY.len()
+    );
+    assert_eq!(
+      send(&server, request.as_str()),
+      "HTTP/1.1 413 Content Too Large\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+  }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn raw_body() {
+  inner::work();
+}
@@ -0,0 +1,62 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use tii::stream::IntoConnectionStream;
+use tii::websocket::message::WebsocketMessage;
+use tii::websocket::stream;
+
+/// Reads the unmasked ping sent by the server and answers with a masked pong echoing its payload.
+fn echo_ping(mut client: TcpStream, delay: Duration) {
+  let mut header = [0u8; 2];
+  client.read_exact(&mut header).expect("ERROR");
+  assert_eq!(header, [0b1000_1001, 8]);
+  let mut payload = [0u8; 8];
+  client.read_exact(&mut payload).expect("ERROR");
+
+  thread::sleep(delay);
+
+  let key = [0x12, 0x34, 0x56, 0x78];
+  let mut pong = vec![0b1000_1010, 0b1000_0000 | 8];
+  pong.extend_from_slice(&key);
+  pong.extend(payload.iter().zip(key.iter().cycle()).map(|(b, k)| b ^ k));
+  client.write_all(pong.as_slice()).expect("ERROR");
+}
+
+#[test]
+pub fn tc58_ping_rtt() {
+  let listener = TcpListener::bind("127.0.0.1:0").expect("ERROR");
+  let client = TcpStream::connect(listener.local_addr().expect("ERROR")).expect("ERROR");
+  let (server, _) = listener.accept().expect("ERROR");
+  let con = server.into_connection_stream();
+  let (sender, mut receiver) = stream::new(con.as_ref());
+
+  assert_eq!(sender.last_rtt(), None);
+  assert_eq!(sender.last_pong_age(), None);
+
+  let delay = Duration::from_millis(50);
+  let client = thread::spawn(move || echo_ping(client, delay));
+  sender.ping_rtt().expect("ERROR");
+  assert!(matches!(receiver.read_message().expect("ERROR"), Some(WebsocketMessage::Pong(_))));
+  client.join().expect("ERROR");
+
+  let rtt = sender.last_rtt().expect("no rtt measured");
+  assert!(rtt >= delay, "{rtt:?}");
+  assert!(rtt < Duration::from_secs(10), "{rtt:?}");
+  assert_eq!(receiver.last_rtt(), Some(rtt));
+  assert!(sender.last_pong_age().expect("no pong received") < Duration::from_secs(10));
+}
+
+#[test]
+pub fn tc58_pong_without_timestamp() {
+  let listener = TcpListener::bind("127.0.0.1:0").expect("ERROR");
+  let mut client = TcpStream::connect(listener.local_addr().expect("ERROR")).expect("ERROR");
+  let (server, _) = listener.accept().expect("ERROR");
+  let con = server.into_connection_stream();
+  let (sender, mut receiver) = stream::new(con.as_ref());
+
+  client.write_all(&[0b1000_1010, 0b1000_0000, 1, 2, 3, 4]).expect("ERROR");
+  assert!(matches!(receiver.read_message().expect("ERROR"), Some(WebsocketMessage::Pong(_))));
+  assert_eq!(sender.last_rtt(), None);
+  assert!(sender.last_pong_age().is_some());
+}
@@ -0,0 +1,50 @@
+use crate::mock_stream::MockStream;
+use tii::websocket::message::WebsocketMessage;
+use tii::websocket::stream;
+
+mod mock_stream;
+
+#[rustfmt::skip]
+const PING_THEN_TEXT: [u8; 9] = [
+  0b1000_1001, // fin, opcode ping
+  0b0_0000010, // not mask, payload length 2
+  b'h', b'i',
+  0b1000_0001, // fin, opcode text
+  0b0_0000011, // not mask, payload length 3
+  b'a', b'b', b'c',
+];
+
+#[test]
+pub fn tc59_auto_pong() {
+  let mock = MockStream::with_slice(&PING_THEN_TEXT);
+  let con = mock.to_stream();
+  let (_sender, mut receiver) = stream::new(con.as_ref());
+  assert!(receiver.is_auto_pong());
+
+  match receiver.read_message().expect("ERROR") {
+    Some(WebsocketMessage::Text(text)) => assert_eq!(text, "abc"),
+    other => panic!("unexpected message {:?}", other),
+  }
+  assert_eq!(mock.copy_written_data(), vec![0b1000_1010, 0b0_0000010, b'h', b'i']);
+}
+
+#[test]
+pub fn tc59_manual_pong() {
+  let mock = MockStream::with_slice(&PING_THEN_TEXT);
+  let con = mock.to_stream();
+  let (sender, mut receiver) = stream::new(con.as_ref());
+  receiver.set_auto_pong(false);
+
+  let payload = match receiver.read_message().expect("ERROR") {
+    Some(WebsocketMessage::Ping(payload)) => payload,
+    other => panic!("unexpected message {:?}", other),
+  };
+  assert_eq!(payload, b"hi");
+  assert!(mock.copy_written_data().is_empty());
+  sender.pong_with_payload(&payload).expect("ERROR");
+  assert_eq!(mock.copy_written_data(), vec![0b1000_1010, 0b0_0000010, b'h', b'i']);
+  match receiver.read_message().expect("ERROR") {
+    Some(WebsocketMessage::Text(text)) => assert_eq!(text, "abc"),
+    other => panic!("unexpected message {:?}", other),
+  }
+}
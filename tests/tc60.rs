@@ -0,0 +1,64 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::{TiiServer, UnexpectedBodyPolicy};
+
+mod mock_stream;
+
+const REQUESTS: &str = "GET /body HTTP/1.1\r\nConnection: Keep-Alive\r\nContent-Length: 10\r\n\r\n0123456789GET /body HTTP/1.1\r\n\r\n";
+
+fn body(ctx: &RequestContext) -> TiiResult<Response> {
+  let body = match ctx.request_body() {
+    Some(body) => {
+      let mut data = Vec::new();
+      body.read_to_end(&mut data)?;
+      String::from_utf8(data)?
+    }
+    None => "none".to_string(),
+  };
+  Ok(Response::ok(body, MimeType::TextPlain))
+}
+
+fn server(policy: Option<UnexpectedBodyPolicy>) -> TiiServer {
+  let builder = TiiBuilder::default();
+  let builder = match policy {
+    Some(policy) => builder.with_unexpected_body_policy(policy).expect("ERROR"),
+    None => builder,
+  };
+  builder.router(|rt| rt.route_get("/body", body)).expect("ERROR").build()
+}
+
+fn handle(server: &TiiServer) -> String {
+  let stream = MockStream::with_str(REQUESTS);
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERROR");
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc60_discard() {
+  let expected = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Keep-Alive\r\nContent-Length: 4\r\n\r\nnone\
+HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 4\r\n\r\nnone";
+  assert_eq!(handle(&server(None)), expected);
+  assert_eq!(handle(&server(Some(UnexpectedBodyPolicy::Discard))), expected);
+}
+
+#[test]
+pub fn tc60_reject() {
+  assert_eq!(
+    handle(&server(Some(UnexpectedBodyPolicy::Reject))),
+    "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc60_pass_through() {
+  assert_eq!(
+    handle(&server(Some(UnexpectedBodyPolicy::PassThrough))),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Keep-Alive\r\nContent-Length: 10\r\n\r\n0123456789\
+HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 4\r\n\r\nnone"
+  );
+}
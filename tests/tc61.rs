@@ -0,0 +1,64 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn echo(ctx: &RequestContext) -> TiiResult<Response> {
+  let body = ctx.request_body().expect("no body");
+  let mut data = Vec::new();
+  body.read_to_end(&mut data)?;
+  let trailers = body
+    .trailers()
+    .expect("no trailers")
+    .iter()
+    .map(|trailer| format!("{}={}", trailer.name, trailer.value))
+    .collect::<Vec<_>>()
+    .join(",");
+  let head = ctx.request_head();
+  // Permitted trailers are merged into the headers, forbidden ones like Content-Type are not.
+  assert_eq!(head.get_header("X-Checksum"), trailers.contains("X-Checksum").then_some("abc"));
+  assert_eq!(head.get_headers("Content-Type"), Vec::<&str>::new());
+  assert_eq!(
+    head.get_all_headers().filter(|header| header.name.to_str() == "X-Count").count(),
+    usize::from(trailers.contains("X-Count"))
+  );
+  Ok(Response::ok(format!("{}|{}", String::from_utf8(data)?, trailers), MimeType::TextPlain))
+}
+
+fn post(server: &TiiServer, body: &str) -> String {
+  let stream = MockStream::with_str(
+    format!("POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n{body}").as_str(),
+  );
+  let con = stream.to_stream();
+  // A malformed body fails the connection after the response was written.
+  _ = server.handle_connection(con);
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc61_chunked_body() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_post("/echo", echo)).expect("ERROR").build();
+
+  assert_eq!(
+    post(&server, "5\r\nhello\r\n5\r\nworld\r\n0\r\n\r\n"),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 11\r\n\r\nhelloworld|"
+  );
+  assert_eq!(
+    post(&server, "5\r\nhello\r\n5\r\nworld\r\n0\r\nX-Checksum: abc\r\nX-Count:2\r\n\r\n"),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 35\r\n\r\nhelloworld|X-Checksum=abc,X-Count=2"
+  );
+  assert_eq!(
+    post(&server, "5\r\nhello\r\n0\r\nX-Checksum: abc\r\nContent-Type: text/evil\r\n\r\n"),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 43\r\n\r\nhello|X-Checksum=abc,Content-Type=text/evil"
+  );
+  assert_eq!(
+    post(&server, "5\r\nhello\r\nzz\r\nworld\r\n0\r\n\r\n"),
+    "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
@@ -0,0 +1,69 @@
+use crate::mock_stream::MockStream;
+use std::io::Cursor;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::response_body::ResponseBody;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn stream(_ctx: &RequestContext) -> TiiResult<Response> {
+  let reader = Cursor::new(b"hello world".to_vec());
+  Ok(Response::ok(ResponseBody::from_reader_chunked(reader), MimeType::TextPlain))
+}
+
+fn get(server: &TiiServer, request: &str) -> String {
+  let stream = MockStream::with_str(request);
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERROR");
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc62_chunked_reader() {
+  let server = TiiBuilder::default().router(|rt| rt.route_get("/", stream)).expect("ERROR").build();
+
+  let response = get(&server, "GET / HTTP/1.1\r\n\r\n");
+  assert_eq!(
+    response,
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nTransfer-Encoding: chunked\r\n\r\nB\r\nhello world\r\n0\r\n\r\n"
+  );
+  assert!(response.ends_with("0\r\n\r\n"));
+}
+
+#[test]
+pub fn tc62_chunked_reader_http10() {
+  let server = TiiBuilder::default().router(|rt| rt.route_get("/", stream)).expect("ERROR").build();
+
+  assert_eq!(
+    get(&server, "GET / HTTP/1.0\r\n\r\n"),
+    "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 11\r\n\r\nhello world"
+  );
+}
+
+#[test]
+pub fn tc62_large_chunked_reader_http10() {
+  let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+  let body = data.clone();
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/", move |_: &RequestContext| {
+        let reader = Cursor::new(body.clone());
+        Response::ok(ResponseBody::from_reader_chunked(reader), MimeType::ApplicationOctetStream)
+      })
+    })
+    .expect("ERROR")
+    .build();
+
+  // Too large to buffer, so it is sent close delimited instead.
+  let stream = MockStream::with_str("GET / HTTP/1.0\r\n\r\n");
+  server.handle_connection(stream.to_stream()).expect("ERROR");
+  let written = stream.copy_written_data();
+  let head =
+    b"HTTP/1.0 200 OK\r\nContent-Type: application/octet-stream\r\nConnection: Close\r\n\r\n";
+  assert!(written.starts_with(head), "{}", String::from_utf8_lossy(&written[..200]));
+  assert!(written[head.len()..] == data[..]);
+}
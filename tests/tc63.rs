@@ -0,0 +1,25 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn hello(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok("hello", MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc63_h2c_upgrade_is_ignored() {
+  let server = TiiBuilder::default().router(|rt| rt.route_get("/", hello)).expect("ERROR").build();
+
+  let stream = MockStream::with_str("GET / HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABkAARAAAAAAAIAAAAA\r\n\r\n");
+  let con = stream.to_stream();
+  server.handle_connection(con).expect("ERROR");
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nhello"
+  );
+}
@@ -0,0 +1,70 @@
+use crate::mock_stream::MockStream;
+use std::io::Read;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut body = Vec::new();
+  if let Some(req_body) = ctx.request_body() {
+    req_body.as_read().read_to_end(&mut body)?;
+  }
+  Ok(Response::ok(format!("got {}", body.len()), MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc64_disabled_by_default() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_any("/dummy", dummy_route)).expect("ERR").build();
+
+  let stream = MockStream::with_str("GET /dummy HTTP/1.1\r\n\r\n");
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert!(server.size_stats().is_none());
+}
+
+#[test]
+pub fn tc64_sizes_are_recorded() {
+  let server = TiiBuilder::default()
+    .with_size_stats(true)
+    .expect("ERR")
+    .router(|rt| rt.route_any("/dummy", dummy_route))
+    .expect("ERR")
+    .build();
+
+  let requests = [
+    "GET /dummy HTTP/1.1\r\nHdr: test\r\n\r\n",
+    "POST /dummy HTTP/1.1\r\nContent-Length: 5\r\n\r\n12345",
+    "POST /dummy HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n12345\r\n0\r\n\r\n",
+  ];
+
+  let mut written = 0u64;
+  for request in requests {
+    let stream = MockStream::with_str(request);
+    server.handle_connection(stream.to_stream()).unwrap();
+    written += stream.copy_written_data().len() as u64;
+  }
+
+  let stats = server.size_stats().unwrap();
+
+  let heads = stats.request_head_sizes();
+  assert_eq!(heads.count(), 3);
+  // "GET /dummy HTTP/1.1\r\nHdr: test\r\n\r\n"
+  // "POST /dummy HTTP/1.1\r\nContent-Length: 5\r\n\r\n"
+  // "POST /dummy HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"
+  assert_eq!(heads.sum(), 34 + 43 + 52);
+  assert_eq!(heads.buckets(), vec![(63, 3)]);
+
+  let bodies = stats.request_body_sizes();
+  assert_eq!(bodies.count(), 3);
+  assert_eq!(bodies.sum(), 5 + 15);
+  assert_eq!(bodies.buckets(), vec![(0, 1), (7, 1), (15, 1)]);
+
+  let responses = stats.response_sizes();
+  assert_eq!(responses.count(), 3);
+  assert_eq!(responses.sum(), written);
+  assert!(responses.buckets().iter().all(|(upper_bound, _)| *upper_bound == 127));
+}
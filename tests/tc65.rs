@@ -0,0 +1,78 @@
+use crate::mock_stream::MockStream;
+use std::io;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::response_body::ResponseBody;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn aborting(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok(
+    ResponseBody::chunked(|sink| {
+      sink.write_all(b"hello")?;
+      Err(sink.abort("database read failed"))
+    }),
+    MimeType::TextPlain,
+  ))
+}
+
+#[test]
+pub fn tc65_abort_chunked() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_get("/", aborting)).expect("ERROR").build();
+
+  let stream = MockStream::with_str(
+    "GET / HTTP/1.1\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\nGET / HTTP/1.1\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\n",
+  );
+  let err = server.handle_connection(stream.to_stream()).unwrap_err();
+  assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+  assert_eq!(err.to_string(), "response body aborted: database read failed");
+
+  // The second request is never served because the connection is closed after the aborted body.
+  let data = stream.copy_written_data_to_string();
+  assert_eq!(data, "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Keep-Alive\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n");
+}
+
+#[test]
+pub fn tc65_abort_http10() {
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_get("/", aborting)).expect("ERROR").build();
+
+  // The body is sent close delimited, the client can only tell it is incomplete if it knows the content.
+  let stream = MockStream::with_str("GET / HTTP/1.0\r\n\r\n");
+  let err = server.handle_connection(stream.to_stream()).unwrap_err();
+  assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\n\r\nhello"
+  );
+}
+
+struct FailingReader;
+
+impl io::Read for FailingReader {
+  fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+    Err(io::Error::new(io::ErrorKind::ConnectionAborted, "database read failed"))
+  }
+}
+
+#[test]
+pub fn tc65_failing_reader_http10() {
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/", |_: &RequestContext| {
+        Response::ok(ResponseBody::from_reader_chunked(FailingReader), MimeType::TextPlain)
+      })
+    })
+    .expect("ERROR")
+    .build();
+
+  // Readers are buffered for HTTP/1.0 clients, so nothing is sent if reading fails.
+  let stream = MockStream::with_str("GET / HTTP/1.0\r\n\r\n");
+  let err = server.handle_connection(stream.to_stream()).unwrap_err();
+  assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+  assert_eq!(stream.copy_written_data_to_string(), "");
+}
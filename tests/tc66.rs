@@ -0,0 +1,68 @@
+use crate::mock_stream::MockStream;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tii::http::request_context::RequestContext;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_server::TiiServer;
+use tii::websocket::stream::{WebsocketReceiver, WebsocketSender};
+
+mod mock_stream;
+
+const UPGRADE: &str = "GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+
+const SWITCHING: &str = "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\nContent-Length: 0\r\n\r\n";
+
+fn server(entered: Sender<()>, release: Receiver<()>) -> Arc<TiiServer> {
+  let release = Mutex::new(release);
+  TiiBuilder::builder_arc(|builder| {
+    builder.router(|rt| {
+      rt.with_max_websocket_connections(2)?.ws_route_get(
+        "/ws",
+        move |_: &RequestContext, _: WebsocketReceiver, _: WebsocketSender| {
+          entered.send(()).unwrap();
+          release.lock().unwrap().recv().unwrap();
+        },
+      )
+    })
+  })
+  .unwrap()
+}
+
+#[test]
+pub fn tc66_websocket_connection_limit() {
+  let (entered_tx, entered_rx) = channel();
+  let (release_tx, release_rx) = channel();
+  let server = server(entered_tx, release_rx);
+
+  let mut connected = Vec::new();
+  for _ in 0..2 {
+    let server = server.clone();
+    connected.push(thread::spawn(move || {
+      let stream = MockStream::with_str(UPGRADE);
+      server.handle_connection(stream.to_stream()).unwrap();
+    }));
+    entered_rx.recv().unwrap();
+  }
+
+  let refused = MockStream::with_str(UPGRADE);
+  server.handle_connection(refused.to_stream()).unwrap();
+  let mut expected = SWITCHING.as_bytes().to_vec();
+  expected.extend_from_slice(&[0b1000_1000, 17, 0x03, 0xF5]);
+  expected.extend_from_slice(b"Try Again Later");
+  assert_eq!(refused.copy_written_data(), expected);
+  assert!(entered_rx.try_recv().is_err());
+
+  // The first two connections were never disturbed, once they end there is capacity again.
+  release_tx.send(()).unwrap();
+  release_tx.send(()).unwrap();
+  for handle in connected {
+    handle.join().unwrap();
+  }
+
+  let accepted = MockStream::with_str(UPGRADE);
+  release_tx.send(()).unwrap();
+  server.handle_connection(accepted.to_stream()).unwrap();
+  entered_rx.recv().unwrap();
+  assert!(accepted.copy_written_data_to_string().starts_with(SWITCHING));
+}
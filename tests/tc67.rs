@@ -0,0 +1,48 @@
+use crate::mock_stream::MockStream;
+use tii::http::headers::HeaderName;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn html_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Response::ok("<p>hi</p>", MimeType::TextHtml).with_header("Server", "tii")
+}
+
+fn text_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Response::ok("hi", MimeType::TextPlain).with_header("Server", "tii")
+}
+
+fn header_filter(_ctx: &mut RequestContext, mut response: Response) -> TiiResult<Response> {
+  if response.get_header(HeaderName::ContentType) == Some("text/html") {
+    response.add_header("X-Frame-Options", "DENY")?;
+  }
+  response.remove_header(HeaderName::Server);
+  assert!(response.get_all_headers().all(|hdr| hdr.name != HeaderName::Server));
+  Ok(response)
+}
+
+fn get(server: &TiiServer, path: &str) -> String {
+  let stream = MockStream::with_str(format!("GET {path} HTTP/1.1\r\n\r\n").as_str());
+  server.handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc67_response_filter_reads_headers() {
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/html", html_route)?
+        .route_get("/text", text_route)?
+        .with_response_filter(header_filter)
+    })
+    .expect("ERR")
+    .build();
+
+  assert_eq!(get(&server, "/html"), "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nX-Frame-Options: DENY\r\nConnection: Close\r\nContent-Length: 9\r\n\r\n<p>hi</p>");
+  assert_eq!(get(&server, "/text"), "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 2\r\n\r\nhi");
+}
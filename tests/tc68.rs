@@ -0,0 +1,95 @@
+use crate::mock_stream::MockStream;
+use tii::http::method::Method;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn dummy_route(ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok(
+    format!("{} {}", ctx.request_head().method(), ctx.request_head().path()),
+    MimeType::TextPlain,
+  ))
+}
+
+fn send(server: &TiiServer, request: &str) -> String {
+  let stream = MockStream::with_str(request);
+  server.handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc68_trace_to_get_route() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_get("/dummy", dummy_route)?.route_any("/any", dummy_route))
+    .expect("ERR")
+    .build();
+
+  let not_allowed = send(&server, "TRACE /dummy HTTP/1.1\r\n\r\n");
+  assert!(not_allowed.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{not_allowed}");
+  let not_allowed = send(&server, "TRACE /any HTTP/1.1\r\n\r\n");
+  assert!(not_allowed.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{not_allowed}");
+}
+
+#[test]
+pub fn tc68_trace_registered() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_method(Method::Trace, "/dummy", dummy_route))
+    .expect("ERR")
+    .build();
+
+  assert!(send(&server, "TRACE /dummy HTTP/1.1\r\n\r\n").ends_with("\r\n\r\nTRACE /dummy"));
+
+  let server = TiiBuilder::default()
+    .with_trace_rejected(true)
+    .expect("ERR")
+    .router(|rt| rt.route_method(Method::Trace, "/dummy", dummy_route))
+    .expect("ERR")
+    .build();
+
+  assert_eq!(
+    send(&server, "TRACE /dummy HTTP/1.1\r\n\r\n"),
+    "HTTP/1.1 405 Method Not Allowed\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc68_connect() {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_method(Method::Connect, "/tunnel/*", dummy_route))
+    .expect("ERR")
+    .build();
+
+  assert_eq!(
+    send(&server, "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n"),
+    "HTTP/1.1 501 Not Implemented\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+
+  let server = TiiBuilder::default()
+    .with_connect_not_implemented(false)
+    .expect("ERR")
+    .router(|rt| {
+      rt.with_pre_routing_request_filter(tunnel_filter)?.route_method(
+        Method::Connect,
+        "/tunnel/*",
+        dummy_route,
+      )
+    })
+    .expect("ERR")
+    .build();
+
+  assert!(send(&server, "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n")
+    .ends_with("\r\n\r\nCONNECT /tunnel/example.com:443"));
+}
+
+fn tunnel_filter(ctx: &mut RequestContext) -> TiiResult<Option<Response>> {
+  if ctx.request_head().method() == &Method::Connect {
+    let path = format!("/tunnel/{}", ctx.request_head().path());
+    ctx.request_head_mut().set_path(path);
+  }
+  Ok(None)
+}
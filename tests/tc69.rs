@@ -0,0 +1,57 @@
+use crate::mock_stream::MockStream;
+use std::io::Read;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn upload_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut body = Vec::new();
+  ctx.request_body().unwrap().as_read().read_to_end(&mut body)?;
+  Ok(Response::ok(format!("got {}", body.len()), MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .with_max_body_size(Some(1024 * 1024))
+    .expect("ERR")
+    .router(|rt| rt.route_post("/upload", upload_route))
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc69_declared_too_large() {
+  let server = server();
+  // The body is never sent, reading any of it would fail with an unexpected eof.
+  let stream = MockStream::with_str(
+    "POST /upload HTTP/1.1\r\nContent-Length: 100000000\r\nExpect: 100-continue\r\n\r\n",
+  );
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 413 Content Too Large\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc69_within_limit() {
+  let server = server();
+  let stream = MockStream::with_str("POST /upload HTTP/1.1\r\nContent-Length: 5\r\n\r\n12345");
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert!(stream.copy_written_data_to_string().ends_with("\r\n\r\ngot 5"));
+}
+
+#[test]
+pub fn tc69_chunked_too_large() {
+  let server = server();
+  let stream = MockStream::with_str(
+    "POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n12345\r\n100000\r\n12345",
+  );
+  _ = server.handle_connection(stream.to_stream());
+  assert!(stream.copy_written_data_to_string().starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+}
@@ -0,0 +1,75 @@
+use crate::mock_stream::MockStream;
+use tii::http::request_context::RequestContext;
+use tii::http::{Response, StatusCode};
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn dummy_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::new(StatusCode::InternalServerError))
+}
+
+fn text_route(_ctx: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::new(StatusCode::BadRequest).with_body("own body".to_string()))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .with_negotiated_error_bodies()
+    .expect("ERR")
+    .router(|rt| rt.route_any("/error", dummy_route)?.route_any("/text", text_route))
+    .expect("ERR")
+    .build()
+}
+
+fn get(server: &TiiServer, path: &str, accept: &str) -> String {
+  let stream =
+    MockStream::with_str(format!("GET {path} HTTP/1.1\r\nAccept: {accept}\r\n\r\n").as_str());
+  server.handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc70_browser_gets_html() {
+  let server = server();
+  let response =
+    get(&server, "/missing", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8");
+  assert_eq!(response, "HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\nConnection: Close\r\nContent-Length: 104\r\n\r\n<!DOCTYPE html><html><head><title>404 Not Found</title></head><body><h1>404 Not Found</h1></body></html>");
+}
+
+#[test]
+pub fn tc70_api_gets_json() {
+  let server = server();
+  assert_eq!(get(&server, "/missing", "application/json"), "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nConnection: Close\r\nContent-Length: 34\r\n\r\n{\"error\":\"Not Found\",\"status\":404}");
+  assert!(get(&server, "/error", "application/json")
+    .ends_with("\r\n\r\n{\"error\":\"Internal Server Error\",\"status\":500}"));
+}
+
+#[test]
+pub fn tc70_unchanged() {
+  let server = server();
+  assert_eq!(
+    get(&server, "/missing", "*/*"),
+    "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+  assert!(get(&server, "/text", "application/json").ends_with("\r\n\r\nown body"));
+
+  let plain = TiiBuilder::default().build();
+  assert_eq!(
+    get(&plain, "/missing", "application/json"),
+    "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc70_custom_renderer() {
+  let server = TiiBuilder::default()
+    .with_error_body_renderer(|_, response| {
+      *response = Response::new(response.status_code.clone()).with_body("oops".to_string());
+    })
+    .expect("ERR")
+    .build();
+  assert!(get(&server, "/missing", "*/*").ends_with("\r\n\r\noops"));
+}
@@ -0,0 +1,147 @@
+use crate::mock_stream::MockStream;
+use std::io::Read;
+use tii::http::mime::MimeType;
+use tii::http::multipart::Multipart;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+const FILE: &[u8] = b"\x00\xFF\r\n--bound\r\n--boundar\x89PNG\r\n";
+
+fn upload_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut multipart = Multipart::new(ctx)?;
+
+  let mut field = multipart.next_part()?.expect("field");
+  assert_eq!(field.name(), "comment");
+  assert_eq!(field.filename(), None);
+  let mut comment = String::new();
+  field.read_to_string(&mut comment)?;
+  assert_eq!(comment, "hello world");
+
+  let mut file = multipart.next_part()?.expect("file");
+  assert_eq!(file.name(), "upload");
+  assert_eq!(file.filename(), Some("a \"b\".png"));
+  assert_eq!(file.get_header("content-type"), Some("image/png"));
+  assert_eq!(file.headers().len(), 2);
+  let mut data = Vec::new();
+  file.read_to_end(&mut data)?;
+  assert_eq!(data.as_slice(), FILE);
+
+  assert!(multipart.next_part()?.is_none());
+  assert!(multipart.next_part()?.is_none());
+  Ok(Response::ok(format!("{comment} {}", data.len()), MimeType::TextPlain))
+}
+
+fn count_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut multipart = Multipart::new(ctx)?;
+  let mut names = Vec::new();
+  while let Some(part) = multipart.next_part()? {
+    names.push(part.name().to_string());
+  }
+  Ok(Response::ok(names.join(","), MimeType::TextPlain))
+}
+
+fn retry_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut multipart = Multipart::new(ctx)?;
+  assert!(multipart.next_part().is_err());
+  assert!(multipart.next_part()?.is_none());
+  Ok(Response::ok("finished", MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .with_max_body_size(Some(1024))
+    .expect("ERR")
+    .router(|rt| {
+      rt.route_post("/upload", upload_route)?
+        .route_post("/count", count_route)?
+        .route_post("/retry", retry_route)
+    })
+    .expect("ERR")
+    .build()
+}
+
+fn multipart_body() -> Vec<u8> {
+  let mut body = Vec::new();
+  body.extend_from_slice(b"preamble\r\n--boundary\r\n");
+  body
+    .extend_from_slice(b"Content-Disposition: form-data; name=\"comment\"\r\n\r\nhello world\r\n");
+  body.extend_from_slice(b"--boundary\r\n");
+  body.extend_from_slice(
+    b"Content-Disposition: form-data; name=\"upload\"; filename=\"a \\\"b\\\".png\"\r\n",
+  );
+  body.extend_from_slice(b"Content-Type: image/png\r\n\r\n");
+  body.extend_from_slice(FILE);
+  body.extend_from_slice(b"\r\n--boundary--\r\nepilogue");
+  body
+}
+
+fn request(path: &str, content_type: &str, body: &[u8]) -> MockStream {
+  let mut data = format!(
+    "POST {path} HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+    body.len()
+  )
+  .into_bytes();
+  data.extend_from_slice(body);
+  MockStream::with_slice(data.as_slice())
+}
+
+#[test]
+pub fn tc71_text_field_and_file() {
+  let server = server();
+  let stream = request("/upload", "multipart/form-data; boundary=boundary", &multipart_body());
+  server.handle_connection(stream.to_stream()).unwrap();
+  let data = stream.copy_written_data_to_string();
+  assert!(data.starts_with("HTTP/1.1 200 OK\r\n"), "{data}");
+  assert!(data.ends_with("\r\n\r\nhello world 28"), "{data}");
+}
+
+#[test]
+pub fn tc71_unread_parts_are_skipped() {
+  let server = server();
+  let stream = request("/count", "multipart/form-data; boundary=\"boundary\"", &multipart_body());
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert!(stream.copy_written_data_to_string().ends_with("\r\n\r\ncomment,upload"));
+}
+
+#[test]
+pub fn tc71_missing_closing_boundary() {
+  let server = server();
+  let body = b"--boundary\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nabc";
+  let stream = request("/count", "multipart/form-data; boundary=boundary", body);
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert!(stream.copy_written_data_to_string().starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
+#[test]
+pub fn tc71_finished_after_error() {
+  let server = server();
+  let body = b"--boundary\r\nX-Part: no disposition\r\n\r\nab";
+  let stream = request("/retry", "multipart/form-data; boundary=boundary", body);
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert!(stream.copy_written_data_to_string().ends_with("\r\n\r\nfinished"));
+}
+
+#[test]
+pub fn tc71_not_multipart() {
+  let server = server();
+  let stream = request("/count", "text/plain", b"abc");
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert!(stream
+    .copy_written_data_to_string()
+    .starts_with("HTTP/1.1 415 Unsupported Media Type\r\n"));
+}
+
+#[test]
+pub fn tc71_too_large() {
+  let server = server();
+  let stream = MockStream::with_str(
+    "POST /count HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=boundary\r\nContent-Length: 2048\r\n\r\n",
+  );
+  server.handle_connection(stream.to_stream()).unwrap();
+  assert!(stream.copy_written_data_to_string().starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+}
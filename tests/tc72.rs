@@ -0,0 +1,56 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn form_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let params = ctx.form_params_with_limit(64)?;
+  let body = params.iter().map(|(key, value)| format!("{key}={value}")).collect::<Vec<_>>();
+  Ok(Response::ok(body.join("|"), MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default().router(|rt| rt.route_post("/form", form_route)).expect("ERR").build()
+}
+
+fn post(content_type: &str, body: &str) -> String {
+  let stream = MockStream::with_str(
+    format!(
+      "POST /form HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+      body.len()
+    )
+    .as_str(),
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc72_decoded_pairs() {
+  let data = post("application/x-www-form-urlencoded", "a=1&b=hello+world&c%26=%C3%A4%3D");
+  assert!(data.starts_with("HTTP/1.1 200 OK\r\n"), "{data}");
+  assert!(data.ends_with("\r\n\r\na=1|b=hello world|c&=ä="), "{data}");
+}
+
+#[test]
+pub fn tc72_wrong_content_type() {
+  let data = post("text/plain", "a=1");
+  assert!(data.starts_with("HTTP/1.1 415 Unsupported Media Type\r\n"), "{data}");
+}
+
+#[test]
+pub fn tc72_malformed() {
+  let data = post("application/x-www-form-urlencoded", "a=1=2");
+  assert!(data.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{data}");
+}
+
+#[test]
+pub fn tc72_too_large() {
+  let data = post("application/x-www-form-urlencoded", "a=".repeat(40).as_str());
+  assert!(data.starts_with("HTTP/1.1 413 Content Too Large\r\n"), "{data}");
+}
@@ -0,0 +1,69 @@
+use crate::mock_stream::MockStream;
+use std::io::Read;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn read_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut body = Vec::new();
+  ctx.request_body().unwrap().as_read().read_to_end(&mut body)?;
+  Ok(Response::ok(format!("got {}", body.len()), MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .with_max_body_size(Some(64))
+    .expect("ERR")
+    .router(|rt| {
+      rt.route_post("/small", read_route)?.post("/upload").max_body_size(1024).endpoint(read_route)
+    })
+    .expect("ERR")
+    .build()
+}
+
+fn post(path: &str, body_len: usize) -> String {
+  let stream = MockStream::with_str(
+    format!("POST {path} HTTP/1.1\r\nContent-Length: {body_len}\r\n\r\n{}", "a".repeat(body_len))
+      .as_str(),
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+fn post_chunked(path: &str, body_len: usize) -> String {
+  let stream = MockStream::with_str(
+    format!(
+      "POST {path} HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n{body_len:x}\r\n{}\r\n0\r\n\r\n",
+      "a".repeat(body_len)
+    )
+    .as_str(),
+  );
+  _ = server().handle_connection(stream.to_stream());
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc73_global_limit() {
+  assert!(post("/small", 64).ends_with("\r\n\r\ngot 64"));
+  assert_eq!(
+    post("/small", 100),
+    "HTTP/1.1 413 Content Too Large\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+  assert!(post_chunked("/small", 100).starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+}
+
+#[test]
+pub fn tc73_route_override() {
+  assert!(post("/upload", 500).ends_with("\r\n\r\ngot 500"));
+  assert!(post_chunked("/upload", 500).ends_with("\r\n\r\ngot 500"));
+  assert_eq!(
+    post("/upload", 2000),
+    "HTTP/1.1 413 Content Too Large\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+  assert!(post_chunked("/upload", 2000).starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+}
@@ -0,0 +1,57 @@
+use tii::http::headers::HeaderName;
+use tii::http::mime::MimeType;
+use tii::http::{Response, StatusCode};
+
+fn upstream() -> Response {
+  Response::found("http://backend:8080/login?next=%2F", "moved", MimeType::TextPlain)
+    .with_header("Set-Cookie", "session=abc; Domain=.backend; Path=/; HttpOnly")
+    .unwrap()
+    .with_header("Set-Cookie", "theme=dark; Path=/settings")
+    .unwrap()
+    .with_header("Connection", "keep-alive, X-Upstream-Secret")
+    .unwrap()
+    .with_header("Keep-Alive", "timeout=5")
+    .unwrap()
+    .with_header("X-Upstream-Secret", "hunter2")
+    .unwrap()
+    .with_header("Cache-Control", "no-store")
+    .unwrap()
+}
+
+#[test]
+pub fn tc74_relay_drops_hop_by_hop_headers() {
+  let response = Response::relay_from(upstream());
+  assert_eq!(response.status_code, StatusCode::Found);
+  assert_eq!(response.get_header(HeaderName::Connection), None);
+  assert_eq!(response.get_header("Keep-Alive"), None);
+  assert_eq!(response.get_header("X-Upstream-Secret"), None);
+  assert_eq!(response.get_header(HeaderName::CacheControl), Some("no-store"));
+  assert_eq!(response.get_header(HeaderName::Location), Some("http://backend:8080/login?next=%2F"));
+  assert_eq!(response.get_headers(HeaderName::SetCookie).len(), 2);
+  assert_eq!(response.body().and_then(|body| body.content_length()), Some(5));
+}
+
+#[test]
+pub fn tc74_relay_rewrites_location_and_cookies() {
+  let response = Response::relay_from(upstream())
+    .with_rewritten_origin("http://backend:8080", "https://example.com/app/");
+  assert_eq!(
+    response.get_header(HeaderName::Location),
+    Some("https://example.com/app/login?next=%2F")
+  );
+  assert_eq!(
+    response.get_headers(HeaderName::SetCookie),
+    vec!["session=abc; Domain=example.com; Path=/app; HttpOnly", "theme=dark; Path=/app/settings"]
+  );
+}
+
+#[test]
+pub fn tc74_relay_rewrites_relative_location() {
+  let response = Response::relay_from(Response::found_no_body("/login"))
+    .with_rewritten_origin("http://backend:8080", "https://example.com/app");
+  assert_eq!(response.get_header(HeaderName::Location), Some("/app/login"));
+
+  let response = Response::relay_from(Response::found_no_body("http://other/login"))
+    .with_rewritten_origin("http://backend:8080", "https://example.com/app");
+  assert_eq!(response.get_header(HeaderName::Location), Some("http://other/login"));
+}
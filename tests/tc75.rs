@@ -0,0 +1,46 @@
+use crate::mock_stream::MockStream;
+use tii::http::method::Method;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+type Handler = Box<dyn Fn(&RequestContext) -> TiiResult<Response> + Send + Sync>;
+
+fn item(ctx: &RequestContext) -> TiiResult<Response> {
+  let id = ctx.get_path_param("id").unwrap_or_default();
+  Ok(Response::ok(format!("item {id}"), MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  let routes: Vec<(Method, String, Handler)> = vec![
+    (Method::Get, "/list".to_string(), Box::new(|_| Ok(Response::ok("list", MimeType::TextPlain)))),
+    (Method::Get, "/items/{id}".to_string(), Box::new(item)),
+    (Method::Delete, "/items/{id}".to_string(), Box::new(|_| Ok(Response::no_content()))),
+  ];
+  TiiBuilder::default().router(|rt| rt.extend(routes)).expect("ERR").build()
+}
+
+fn request(method: &str, path: &str) -> String {
+  let stream = MockStream::with_str(format!("{method} {path} HTTP/1.1\r\n\r\n").as_str());
+  server().handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc75_routes_from_table() {
+  assert!(request("GET", "/list").ends_with("\r\n\r\nlist"));
+  assert!(request("GET", "/items/7").ends_with("\r\n\r\nitem 7"));
+  assert!(request("DELETE", "/items/7").starts_with("HTTP/1.1 204 No Content\r\n"));
+  assert!(request("POST", "/list").starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+}
+
+#[test]
+pub fn tc75_invalid_route_in_table() {
+  let routes = vec![(Method::Get, "/ok", item), (Method::Get, "/files/*/more", item)];
+  assert!(TiiBuilder::default().router(|rt| rt.extend(routes)).is_err());
+}
@@ -0,0 +1,51 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::{RequestHeadParsingError, TiiResult};
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn disposition(ctx: &RequestContext) -> TiiResult<Response> {
+  let value = ctx.request_head().get_header("Content-Disposition").unwrap_or_default();
+  Ok(Response::ok(value.to_string(), MimeType::TextPlain))
+}
+
+fn server(latin1: bool) -> TiiServer {
+  TiiBuilder::default()
+    .with_latin1_header_values(latin1)
+    .expect("ERR")
+    .router(|rt| rt.route_any("/", disposition))
+    .expect("ERR")
+    .build()
+}
+
+fn request(server: &TiiServer) -> (TiiResult<()>, String) {
+  // "café.txt" with the é encoded as the single ISO-8859-1 byte 0xE9
+  let stream = MockStream::with_slice(
+    b"GET / HTTP/1.1\r\nContent-Disposition: attachment; filename=\"caf\xE9.txt\"\r\nConnection: close\r\n\r\n",
+  );
+  let result = server.handle_connection(stream.to_stream());
+  (result, stream.copy_written_data_to_string())
+}
+
+#[test]
+pub fn tc76_latin1_rejected_by_default() {
+  let (result, data) = request(&server(false));
+  let err = result.unwrap_err();
+  assert!(matches!(
+    err.downcast_ref::<RequestHeadParsingError>(),
+    Some(RequestHeadParsingError::HeaderLineIsNotUsAscii)
+  ));
+  assert_eq!(data, "");
+}
+
+#[test]
+pub fn tc76_latin1_accepted_when_enabled() {
+  let (result, data) = request(&server(true));
+  result.unwrap();
+  assert!(data.starts_with("HTTP/1.1 200 OK\r\n"), "{data}");
+  assert!(data.ends_with("\r\n\r\nattachment; filename=\"café.txt\""), "{data}");
+}
@@ -0,0 +1,119 @@
+#[cfg(feature = "ws_deflate")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use tii::http::request_context::RequestContext;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_server::TiiServer;
+  use tii::websocket::stream::{WebsocketReceiver, WebsocketSender};
+
+  const UPGRADE: &str = "GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Extensions: permessage-deflate; client_max_window_bits, x-unknown\r\n\r\n";
+
+  const SWITCHING: &str = "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\nSec-WebSocket-Extensions: permessage-deflate; server_no_context_takeover\r\nContent-Length: 0\r\n\r\n";
+
+  fn server() -> TiiServer {
+    TiiBuilder::default()
+      .router(|rt| {
+        rt.ws_route_get(
+          "/ws",
+          |_: &RequestContext, mut receiver: WebsocketReceiver, sender: WebsocketSender| {
+            while let Some(message) = receiver.read_message().unwrap() {
+              sender.send(message).unwrap();
+            }
+          },
+        )
+      })
+      .expect("ERR")
+      .build()
+  }
+
+  /// A masked (with an all zero key) text frame, RSV1 is set if compressed.
+  fn client_frame(payload: &[u8], compressed: bool) -> Vec<u8> {
+    let mut frame = vec![if compressed { 0xC1 } else { 0x81 }];
+    match payload.len() {
+      len @ 0..=125 => frame.push(0x80 | len as u8),
+      len => {
+        frame.push(0x80 | 126);
+        frame.extend_from_slice(&(len as u16).to_be_bytes());
+      }
+    }
+    frame.extend_from_slice(&[0, 0, 0, 0]);
+    frame.extend_from_slice(payload);
+    frame
+  }
+
+  /// Reads an unmasked frame written by the server, returns the first header byte and the payload.
+  fn server_frame(data: &mut &[u8]) -> (u8, Vec<u8>) {
+    let (head, len, rest) = match data {
+      [head, 126, high, low, rest @ ..] => {
+        (*head, u16::from_be_bytes([*high, *low]) as usize, rest)
+      }
+      [head, len, rest @ ..] => (*head, *len as usize, rest),
+      _ => panic!("truncated frame"),
+    };
+    let (payload, rest) = rest.split_at(len);
+    *data = rest;
+    (head, payload.to_vec())
+  }
+
+  fn inflate(payload: &[u8]) -> String {
+    let mut data = payload.to_vec();
+    // Restore the removed sync flush trailer and end the stream with an empty final block.
+    data.extend_from_slice(&[0x00, 0x00, 0xFF, 0xFF, 0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    String::from_utf8(miniz_oxide::inflate::decompress_to_vec(&data).unwrap()).unwrap()
+  }
+
+  pub(crate) fn round_trip() {
+    let message = "{\"event\":\"tick\",\"value\":42} ".repeat(100);
+    let compressed = miniz_oxide::deflate::compress_to_vec(message.as_bytes(), 6);
+    assert!(compressed.len() < 125);
+
+    let mut request = UPGRADE.as_bytes().to_vec();
+    request.extend(client_frame(&compressed, true));
+    request.extend(client_frame(b"plain", false));
+    request.extend_from_slice(&[0x88, 0x80, 0, 0, 0, 0]);
+    let stream = MockStream::with_slice(&request);
+    server().handle_connection(stream.to_stream()).unwrap();
+
+    let written = stream.copy_written_data();
+    let (head, mut frames) = written.split_at(SWITCHING.len());
+    assert_eq!(String::from_utf8_lossy(head), SWITCHING);
+
+    let (header, payload) = server_frame(&mut frames);
+    assert_eq!(header, 0xC1);
+    assert!(payload.len() < message.len() / 10);
+    assert_eq!(inflate(&payload), message);
+
+    let (header, payload) = server_frame(&mut frames);
+    assert_eq!(header, 0xC1);
+    assert_eq!(inflate(&payload), "plain");
+  }
+
+  pub(crate) fn not_offered() {
+    let mut request =
+      UPGRADE.replace("permessage-deflate; client_max_window_bits, ", "").into_bytes();
+    request.extend(client_frame(b"plain", false));
+    request.extend_from_slice(&[0x88, 0x80, 0, 0, 0, 0]);
+    let stream = MockStream::with_slice(&request);
+    server().handle_connection(stream.to_stream()).unwrap();
+
+    let written = stream.copy_written_data();
+    assert!(written.ends_with(b"\r\n\r\n\x81\x05plain"), "{written:?}");
+    let written = String::from_utf8_lossy(&written);
+    assert!(!written.contains("Sec-WebSocket-Extensions"), "{written}");
+  }
+}
+
+#[cfg(feature = "ws_deflate")]
+mod mock_stream;
+
+#[cfg(feature = "ws_deflate")]
+#[test]
+pub fn tc77_permessage_deflate_round_trip() {
+  inner::round_trip();
+}
+
+#[cfg(feature = "ws_deflate")]
+#[test]
+pub fn tc77_permessage_deflate_not_offered() {
+  inner::not_offered();
+}
@@ -0,0 +1,65 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn dummy_route(_: &RequestContext) -> Response {
+  Response::ok("Okay!", MimeType::TextPlain)
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/items", dummy_route)?
+        .route_post("/items", dummy_route)?
+        .route_put("/other", dummy_route)
+    })
+    .expect("ERR")
+    .build()
+}
+
+fn request(method: &str, path: &str) -> String {
+  let stream =
+    MockStream::with_str(format!("{method} {path} HTTP/1.1\r\nConnection: close\r\n\r\n").as_str());
+  server().handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc78_method_used_nowhere() {
+  let expected = "HTTP/1.1 501 Not Implemented\r\nAllow: GET, POST, PUT\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n";
+  assert_eq!(request("DELETE", "/missing"), expected);
+  assert_eq!(request("BREW", "/nothing/here"), expected);
+}
+
+#[test]
+pub fn tc78_method_not_allowed_on_path() {
+  assert_eq!(
+    request("DELETE", "/items"),
+    "HTTP/1.1 405 Method Not Allowed\r\nAllow: GET, POST\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+  assert_eq!(
+    request("PUT", "/missing"),
+    "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc78_get_and_head_are_always_implemented() {
+  let not_found = "HTTP/1.1 404 Not Found\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n";
+  assert_eq!(request("HEAD", "/missing"), not_found);
+
+  let server =
+    TiiBuilder::default().router(|rt| rt.route_post("/items", dummy_route)).expect("ERR").build();
+  for method in ["GET", "HEAD"] {
+    let stream = MockStream::with_str(
+      format!("{method} /missing HTTP/1.1\r\nConnection: close\r\n\r\n").as_str(),
+    );
+    server.handle_connection(stream.to_stream()).unwrap();
+    assert_eq!(stream.copy_written_data_to_string(), not_found);
+  }
+}
@@ -0,0 +1,87 @@
+use crate::mock_stream::MockStream;
+use tii::http::request_context::RequestContext;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::{TiiResult, WebsocketError};
+use tii::tii_server::TiiServer;
+use tii::websocket::stream::{WebsocketReceiver, WebsocketSender};
+
+mod mock_stream;
+
+const UPGRADE: &str = "GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+
+const CLOSE_TOO_BIG: &[u8] = b"\x88\x11\x03\xF1Message Too Big";
+
+fn echo(
+  _: &RequestContext,
+  mut receiver: WebsocketReceiver,
+  sender: WebsocketSender,
+) -> TiiResult<()> {
+  while let Some(message) = receiver.read_message()? {
+    sender.send(message)?;
+  }
+  Ok(())
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| rt.with_max_websocket_message_size(16)?.ws_route_get("/ws", echo))
+    .expect("ERR")
+    .build()
+}
+
+/// A masked (with an all zero key) frame with the given first header byte.
+fn frame(head: u8, payload: &[u8]) -> Vec<u8> {
+  let mut frame = vec![head, 0x80 | payload.len() as u8, 0, 0, 0, 0];
+  frame.extend_from_slice(payload);
+  frame
+}
+
+fn connect(frames: &[u8]) -> (TiiResult<()>, Vec<u8>) {
+  let mut request = UPGRADE.as_bytes().to_vec();
+  request.extend_from_slice(frames);
+  let stream = MockStream::with_slice(&request);
+  let result = server().handle_connection(stream.to_stream());
+  let written = stream.copy_written_data();
+  let frames = written.get(written.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4..);
+  (result, frames.unwrap().to_vec())
+}
+
+fn assert_too_big(result: TiiResult<()>, size: u64) {
+  let err = result.unwrap_err();
+  assert!(
+    matches!(err.downcast_ref::<WebsocketError>(), Some(WebsocketError::MessageTooBig(s)) if *s == size),
+    "{err}"
+  );
+}
+
+#[test]
+pub fn tc79_within_limit() {
+  let mut frames = frame(0x81, b"0123456789abcdef");
+  frames.extend_from_slice(&[0x88, 0x80, 0, 0, 0, 0]);
+  let (result, written) = connect(&frames);
+  result.unwrap();
+  assert_eq!(written, b"\x81\x100123456789abcdef");
+}
+
+#[test]
+pub fn tc79_huge_declared_frame_length() {
+  // Declares a payload of 1 TiB, which must be rejected before allocating anything.
+  let mut frames = vec![0x82, 0x80 | 127];
+  frames.extend_from_slice(&(1u64 << 40).to_be_bytes());
+  frames.extend_from_slice(&[0, 0, 0, 0]);
+  let (result, written) = connect(&frames);
+  assert_too_big(result, 1 << 40);
+  assert_eq!(written, CLOSE_TOO_BIG);
+}
+
+#[test]
+pub fn tc79_fragmented_message_exceeds_limit() {
+  // Text frame without fin, followed by never ending continuation frames.
+  let mut frames = frame(0x01, b"0123456789");
+  for _ in 0..100 {
+    frames.extend(frame(0x00, b"0123456789"));
+  }
+  let (result, written) = connect(&frames);
+  assert_too_big(result, 20);
+  assert_eq!(written, CLOSE_TOO_BIG);
+}
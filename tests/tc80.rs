@@ -0,0 +1,54 @@
+use crate::mock_stream::MockStream;
+use tii::http::headers::HeaderName;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+const PAGE: &str = "<script nonce=\"{{nonce}}\">run()</script>";
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .with_html_transformer(|html: &mut String, request: &RequestContext| {
+      let nonce = request.request_head().get_header("X-Nonce").unwrap_or_default();
+      *html = html.replace("{{nonce}}", nonce);
+    })
+    .expect("ERR")
+    .router(|rt| {
+      rt.route_get("/page", |_: &RequestContext| Response::ok(PAGE, MimeType::TextHtml))?
+        .route_get("/text", |_: &RequestContext| Response::ok(PAGE, MimeType::TextPlain))?
+        .route_get("/encoded", |_: &RequestContext| {
+          Response::ok(PAGE, MimeType::TextHtml)
+            .with_header(HeaderName::ContentEncoding, "identity")
+        })
+    })
+    .expect("ERR")
+    .build()
+}
+
+fn get(path: &str) -> String {
+  let stream = MockStream::with_str(
+    format!("GET {path} HTTP/1.1\r\nX-Nonce: r4nd0m\r\nConnection: close\r\n\r\n").as_str(),
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  stream.copy_written_data_to_string()
+}
+
+#[test]
+pub fn tc80_html_transformed() {
+  assert_eq!(
+    get("/page"),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nConnection: Close\r\nContent-Length: 37\r\n\r\n<script nonce=\"r4nd0m\">run()</script>"
+  );
+}
+
+#[test]
+pub fn tc80_other_bodies_untouched() {
+  assert!(get("/text")
+    .ends_with("\r\nContent-Length: 40\r\n\r\n<script nonce=\"{{nonce}}\">run()</script>"));
+  assert!(get("/encoded")
+    .ends_with("\r\nContent-Length: 40\r\n\r\n<script nonce=\"{{nonce}}\">run()</script>"));
+}
@@ -0,0 +1,88 @@
+use crate::mock_stream::MockStream;
+use std::io::ErrorKind;
+use tii::http::request_context::RequestContext;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::{TiiResult, WebsocketError};
+use tii::tii_server::TiiServer;
+use tii::websocket::message::CloseCode;
+use tii::websocket::stream::{WebsocketReceiver, WebsocketSender};
+
+mod mock_stream;
+
+const UPGRADE: &str = "GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.ws_route_get(
+        "/restart",
+        |_: &RequestContext, _: WebsocketReceiver, sender: WebsocketSender| {
+          sender.close_with_code(CloseCode::GoingAway, "server restart")
+        },
+      )?
+      .ws_route_get(
+        "/reserved",
+        |_: &RequestContext, _: WebsocketReceiver, sender: WebsocketSender| {
+          for code in [1005, 1006, 1015] {
+            let err = sender.close_with_code(code, "reserved").unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+          }
+          sender.close_with_code(4001, "app")
+        },
+      )?
+      .ws_route_get(
+        "/echo",
+        |_: &RequestContext,
+         mut receiver: WebsocketReceiver,
+         sender: WebsocketSender|
+         -> TiiResult<()> {
+          while let Some(message) = receiver.read_message()? {
+            sender.send(message)?;
+          }
+          sender.text("too late")
+        },
+      )
+    })
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc81_close_with_code() {
+  let stream = MockStream::with_str(UPGRADE.replace("/ws", "/restart").as_str());
+  server().handle_connection(stream.to_stream()).unwrap();
+
+  let written = stream.copy_written_data();
+  assert!(written.ends_with(b"\r\n\r\n\x88\x10\x03\xE9server restart"), "{written:?}");
+}
+
+#[test]
+pub fn tc81_close_with_reserved_code() {
+  let stream = MockStream::with_str(UPGRADE.replace("/ws", "/reserved").as_str());
+  server().handle_connection(stream.to_stream()).unwrap();
+
+  let written = stream.copy_written_data();
+  assert!(written.ends_with(b"\r\n\r\n\x88\x05\x0F\xA1app"), "{written:?}");
+}
+
+#[test]
+pub fn tc81_peer_close_status() {
+  let mut request = UPGRADE.replace("/ws", "/echo").into_bytes();
+  // Masked (with an all zero key) close frame with code 1001 and reason "bye".
+  request.extend_from_slice(b"\x88\x85\x00\x00\x00\x00\x03\xE9bye");
+  let stream = MockStream::with_slice(&request);
+  let err = server().handle_connection(stream.to_stream()).unwrap_err();
+
+  let err = err.downcast_ref::<WebsocketError>().unwrap();
+  assert_eq!(err.close_code(), Some(CloseCode::GoingAway));
+  assert!(matches!(err, WebsocketError::Closed { code: Some(1001), reason } if reason == "bye"));
+}
+
+#[test]
+pub fn tc81_close_code_conversion() {
+  assert_eq!(CloseCode::from(1000), CloseCode::Normal);
+  assert_eq!(CloseCode::from(1009), CloseCode::MessageTooBig);
+  assert_eq!(CloseCode::from(4001), CloseCode::Other(4001));
+  assert_eq!(u16::from(CloseCode::ProtocolError), 1002);
+  assert_eq!(CloseCode::Other(4001).code(), 4001);
+}
@@ -0,0 +1,50 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn upload_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut data = Vec::new();
+  ctx.request_body().unwrap().read_to_end(&mut data)?;
+  Ok(Response::ok(data, MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.route_post("/upload", upload_route)?
+        .route_post("/denied", |_: &RequestContext| Response::forbidden_no_body())
+    })
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc82_continue_before_body_is_read() {
+  let stream = MockStream::with_str(
+    "POST /upload HTTP/1.1\r\nExpect: 100-continue\r\nConnection: keep-alive\r\nContent-Length: 5\r\n\r\nhello",
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Keep-Alive\r\nContent-Length: 5\r\n\r\nhello"
+  );
+}
+
+#[test]
+pub fn tc82_no_continue_if_body_is_not_read() {
+  // The client waits for the 100 Continue and never sends the body.
+  let stream = MockStream::with_str(
+    "POST /denied HTTP/1.1\r\nExpect: 100-continue\r\nConnection: keep-alive\r\nContent-Length: 5\r\n\r\n",
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 403 Forbidden\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
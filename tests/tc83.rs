@@ -0,0 +1,45 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/bye", |_: &RequestContext| {
+        Response::ok("bye", MimeType::TextPlain).with_connection_close()
+      })?
+      .route_get("/hello", |_: &RequestContext| Response::ok("hello", MimeType::TextPlain))
+    })
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc83_connection_close_ends_keep_alive() {
+  let stream = MockStream::with_str(
+    "GET /bye HTTP/1.1\r\nConnection: keep-alive\r\nContent-Length: 0\r\n\r\nGET /hello HTTP/1.1\r\nConnection: keep-alive\r\nContent-Length: 0\r\n\r\n",
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 3\r\n\r\nbye"
+  );
+}
+
+#[test]
+pub fn tc83_without_connection_close_keeps_alive() {
+  let stream = MockStream::with_str(
+    "GET /hello HTTP/1.1\r\nConnection: keep-alive\r\nContent-Length: 0\r\n\r\nGET /bye HTTP/1.1\r\nConnection: keep-alive\r\nContent-Length: 0\r\n\r\n",
+  );
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Keep-Alive\r\nContent-Length: 5\r\n\r\nhello\
+     HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 3\r\n\r\nbye"
+  );
+}
@@ -0,0 +1,60 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::{PathParamError, TiiResult};
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn user_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let id: u64 = ctx.path_param_parsed("id")?;
+  Ok(Response::ok(format!("user {}", id + 1), MimeType::TextPlain))
+}
+
+fn file_route(ctx: &RequestContext) -> TiiResult<Response> {
+  assert_eq!(
+    ctx.path_param_parsed::<u64>("missing"),
+    Err(PathParamError::Missing("missing".to_string()))
+  );
+  let path: String = ctx.path_param_parsed("path")?;
+  Ok(Response::ok(path, MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| rt.route_get("/user/{id}", user_route)?.route_get("/file/{path:.*}", file_route))
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc84_typed_path_param() {
+  let stream = MockStream::with_str("GET /user/42 HTTP/1.1\r\n\r\n");
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 7\r\n\r\nuser 43"
+  );
+}
+
+#[test]
+pub fn tc84_malformed_path_param() {
+  let stream = MockStream::with_str("GET /user/abc HTTP/1.1\r\n\r\n");
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc84_regex_path_param() {
+  let stream = MockStream::with_str("GET /file/a/b.txt HTTP/1.1\r\n\r\n");
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 7\r\n\r\na/b.txt"
+  );
+}
@@ -0,0 +1,85 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn method_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let mut body = Vec::new();
+  if let Some(request_body) = ctx.request_body() {
+    request_body.read_to_end(&mut body)?;
+  }
+  let body = String::from_utf8_lossy(&body);
+  Ok(Response::ok(format!("{} {body}", ctx.request_head().method()), MimeType::TextPlain))
+}
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.with_method_override()?
+        .route_post("/item", method_route)?
+        .route_delete("/item", method_route)?
+        .route_put("/item", method_route)
+    })
+    .expect("ERR")
+    .build()
+}
+
+fn request(request: &str) -> String {
+  let stream = MockStream::with_str(request);
+  server().handle_connection(stream.to_stream()).unwrap();
+  let written = stream.copy_written_data_to_string();
+  written.split_once("\r\n\r\n").unwrap().1.to_string()
+}
+
+#[test]
+pub fn tc85_override_header() {
+  assert_eq!(
+    request("POST /item HTTP/1.1\r\nX-HTTP-Method-Override: DELETE\r\nContent-Length: 0\r\n\r\n"),
+    "DELETE "
+  );
+  assert_eq!(
+    request("POST /item HTTP/1.1\r\nX-HTTP-Method-Override: put\r\nContent-Length: 0\r\n\r\n"),
+    "PUT "
+  );
+}
+
+#[test]
+pub fn tc85_override_form_field() {
+  assert_eq!(
+    request("POST /item HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: 22\r\n\r\n_method=PUT&name=tii+1"),
+    "PUT _method=PUT&name=tii+1"
+  );
+}
+
+#[test]
+pub fn tc85_override_only_allowlisted_from_post() {
+  assert_eq!(
+    request("POST /item HTTP/1.1\r\nX-HTTP-Method-Override: GET\r\nContent-Length: 0\r\n\r\n"),
+    "POST "
+  );
+
+  assert_eq!(
+    request("PUT /item HTTP/1.1\r\nX-HTTP-Method-Override: DELETE\r\nContent-Length: 0\r\n\r\n"),
+    "PUT "
+  );
+}
+
+#[test]
+pub fn tc85_large_or_malformed_form_is_not_overridden() {
+  let body = format!("_method=PUT&name={}", "x".repeat(10 * 1024));
+  let large = request(&format!(
+    "POST /item HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: {}\r\n\r\n{body}",
+    body.len()
+  ));
+  assert_eq!(large, format!("POST {body}"));
+
+  assert_eq!(
+    request("POST /item HTTP/1.1\r\nContent-Type: application/x-www-form-urlencoded\r\nContent-Length: 17\r\n\r\n_method=PUT&a=b=c"),
+    "POST _method=PUT&a=b=c"
+  );
+}
@@ -0,0 +1,61 @@
+use crate::mock_stream::MockStream;
+use std::sync::{Arc, Mutex};
+use tii::http::request_context::RequestContext;
+use tii::tii_builder::TiiBuilder;
+use tii::websocket::metrics::{FrameCounts, WebsocketMetrics};
+use tii::websocket::stream::{WebsocketReceiver, WebsocketSender};
+
+mod mock_stream;
+
+const UPGRADE: &str = "GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+
+/// A masked (with an all zero key) frame sent by the client.
+fn client_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+  let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8, 0, 0, 0, 0];
+  frame.extend_from_slice(payload);
+  frame
+}
+
+#[test]
+pub fn tc86_websocket_metrics() {
+  let metrics = Arc::new(WebsocketMetrics::new());
+  let seen = Arc::new(Mutex::new(None));
+  let seen_in_handler = seen.clone();
+  let metrics_in_handler = metrics.clone();
+  let metrics_in_router = metrics.clone();
+
+  let server = TiiBuilder::default()
+    .router(move |rt| {
+      rt.with_websocket_metrics(metrics_in_router)?.ws_route_get(
+        "/ws",
+        move |_: &RequestContext, mut receiver: WebsocketReceiver, sender: WebsocketSender| {
+          assert_eq!(metrics_in_handler.open_connections(), 1);
+          while let Some(message) = receiver.read_message().unwrap() {
+            sender.send(message).unwrap();
+          }
+          *seen_in_handler.lock().unwrap() =
+            Some((sender.counters().sent(), receiver.counters().received()));
+        },
+      )
+    })
+    .expect("ERR")
+    .build();
+
+  let mut request = UPGRADE.as_bytes().to_vec();
+  request.extend(client_frame(0x1, b"hello"));
+  request.extend(client_frame(0x9, b""));
+  request.extend(client_frame(0x1, b"hi"));
+  request.extend(client_frame(0x8, b""));
+  let stream = MockStream::with_slice(&request);
+  server.handle_connection(stream.to_stream()).unwrap();
+
+  let sent = FrameCounts { text: 2, pong: 1, bytes: 7 + 2 + 4, ..FrameCounts::default() };
+  let received =
+    FrameCounts { text: 2, ping: 1, close: 1, bytes: 11 + 6 + 8 + 6, ..FrameCounts::default() };
+  assert_eq!(received.frames(), 4);
+
+  assert_eq!(*seen.lock().unwrap(), Some((sent, received)));
+  assert_eq!(metrics.sent(), sent);
+  assert_eq!(metrics.received(), received);
+  assert_eq!(metrics.open_connections(), 0);
+}
@@ -0,0 +1,50 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::RequestHeadParsingError;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn server() -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/{path:.*}", |ctx: &RequestContext| {
+        Response::ok(ctx.request_head().path().to_string(), MimeType::TextPlain)
+      })
+    })
+    .expect("ERR")
+    .with_max_path_length(Some(16))
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc87_decoded_path_too_long() {
+  let stream = MockStream::with_str(
+    "GET /%E2%82%AC%E2%82%AC%E2%82%AC%E2%82%AC%E2%82%AC%E2%82%AC HTTP/1.1\r\n\r\n",
+  );
+  let err = server().handle_connection(stream.to_stream()).unwrap_err();
+  assert!(matches!(
+    err.downcast_ref::<RequestHeadParsingError>(),
+    Some(RequestHeadParsingError::PathTooLong(19))
+  ));
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 414 Request-URI Too Long\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+  );
+}
+
+#[test]
+pub fn tc87_decoded_path_within_limit() {
+  // The raw path is longer than the limit, only the decoded length counts. The query is not part of the path.
+  let stream =
+    MockStream::with_str("GET /%41%42%43%44%45%46%47%48?padding=xxxxxxxxxxxx HTTP/1.1\r\n\r\n");
+  server().handle_connection(stream.to_stream()).unwrap();
+  assert_eq!(
+    stream.copy_written_data_to_string(),
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 9\r\n\r\n/ABCDEFGH"
+  );
+}
@@ -0,0 +1,78 @@
+#[cfg(feature = "serde")]
+mod mock_stream;
+
+#[cfg(feature = "serde")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use serde::{Deserialize, Serialize};
+  use tii::http::request_context::RequestContext;
+  use tii::http::{Response, StatusCode};
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+  use tii::tii_server::TiiServer;
+
+  #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+  struct Person {
+    name: String,
+    age: u32,
+  }
+
+  fn birthday_route(ctx: &RequestContext) -> TiiResult<Response> {
+    let mut person: Person = ctx.body_json_with_limit(64)?;
+    person.age += 1;
+    Response::json(StatusCode::Created, &person)
+  }
+
+  fn server() -> TiiServer {
+    TiiBuilder::default()
+      .router(|rt| rt.route_post("/birthday", birthday_route))
+      .expect("ERR")
+      .build()
+  }
+
+  fn send(content_type: &str, body: &str) -> String {
+    let stream = MockStream::with_str(
+      format!(
+        "POST /birthday HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+      )
+      .as_str(),
+    );
+    server().handle_connection(stream.to_stream()).unwrap();
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn round_trip() {
+    let person = Person { name: "Tii Tester".to_string(), age: 42 };
+    let written = send("application/json", &serde_json::to_string(&person).unwrap());
+    let (head, body) = written.split_once("\r\n\r\n").unwrap();
+    assert_eq!(head, "HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nConnection: Close\r\nContent-Length: 30");
+    assert_eq!(serde_json::from_str::<Person>(body).unwrap(), Person { age: 43, ..person });
+  }
+
+  pub fn rejected() {
+    assert_eq!(
+      send("application/x-www-form-urlencoded", "name=Tii+Tester&age=42"),
+      "HTTP/1.1 415 Unsupported Media Type\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+    assert_eq!(
+      send("application/json", r#"{"name":"Tii Tester"}"#),
+      "HTTP/1.1 400 Bad Request\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n"
+    );
+    let long_name = "x".repeat(64);
+    assert!(send("application/json", &format!(r#"{{"name":"{long_name}","age":42}}"#))
+      .starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+  }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn tc88_json_round_trip() {
+  inner::round_trip();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn tc88_json_rejected() {
+  inner::rejected();
+}
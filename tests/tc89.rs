@@ -0,0 +1,112 @@
+mod mock_stream;
+
+#[cfg(feature = "brotli")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use std::io::Read;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_server::TiiServer;
+
+  fn text() -> String {
+    "Tii compresses this text with brotli. ".repeat(50)
+  }
+
+  fn server() -> TiiServer {
+    TiiBuilder::default()
+      .router(|rt| {
+        rt.route_get("/text", |_: &RequestContext| Response::ok(text(), MimeType::TextPlain))?
+          .route_get("/small", |_: &RequestContext| Response::ok("small", MimeType::TextPlain))?
+          .route_get("/binary", |_: &RequestContext| {
+            Response::ok(text(), MimeType::ApplicationOctetStream)
+          })
+      })
+      .expect("ERR")
+      .with_response_compression(256, [MimeType::TextPlain, MimeType::TextHtml])
+      .expect("ERR")
+      .build()
+  }
+
+  fn get(path: &str, accept_encoding: &str) -> (String, Vec<u8>) {
+    let stream = MockStream::with_str(&format!(
+      "GET {path} HTTP/1.1\r\nAccept-Encoding: {accept_encoding}\r\n\r\n"
+    ));
+    server().handle_connection(stream.to_stream()).unwrap();
+    let written = stream.copy_written_data();
+    let split = written.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    let (head, body) = written.split_at(split);
+    (String::from_utf8(head.to_vec()).unwrap(), body.to_vec())
+  }
+
+  pub fn brotli_preferred() {
+    let (head, body) = get("/text", "gzip;q=0.8, br, deflate");
+    assert!(head.contains("Content-Encoding: br\r\n"), "{head}");
+    assert!(head.contains("Vary: Accept-Encoding\r\n"), "{head}");
+    assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())), "{head}");
+    assert!(body.len() < text().len() / 10);
+
+    let mut decoded = String::new();
+    brotli::Decompressor::new(body.as_slice(), 4096).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, text());
+  }
+
+  pub fn not_compressed() {
+    let (head, body) = get("/text", "gzip, br;q=0");
+    assert!(!head.contains("Content-Encoding"), "{head}");
+    assert!(head.contains("Vary: Accept-Encoding\r\n"), "{head}");
+    assert_eq!(body, text().as_bytes());
+
+    let (head, _) = get("/small", "br");
+    assert_eq!(
+      head,
+      "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\n"
+    );
+
+    let (head, body) = get("/binary", "br");
+    assert!(!head.contains("Content-Encoding"), "{head}");
+    assert_eq!(body, text().as_bytes());
+  }
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+pub fn tc89_brotli_preferred() {
+  inner::brotli_preferred();
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+pub fn tc89_not_compressed() {
+  inner::not_compressed();
+}
+
+#[cfg(not(feature = "brotli"))]
+#[test]
+pub fn tc89_no_supported_coding() {
+  use crate::mock_stream::MockStream;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+
+  let text = "Tii can not compress this text without brotli. ".repeat(50);
+  let server = TiiBuilder::default()
+    .router(|rt| {
+      rt.route_get("/text", move |_: &RequestContext| {
+        Response::ok(text.clone(), MimeType::TextPlain)
+      })
+    })
+    .expect("ERR")
+    .with_response_compression(256, [MimeType::TextPlain])
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str("GET /text HTTP/1.1\r\nAccept-Encoding: br, gzip\r\n\r\n");
+  server.handle_connection(stream.to_stream()).unwrap();
+  let written = stream.copy_written_data_to_string();
+  assert!(written.starts_with("HTTP/1.1 200 OK\r\n"), "{written}");
+  assert!(!written.contains("Vary"), "{written}");
+  assert!(!written.contains("Content-Encoding"), "{written}");
+}
@@ -0,0 +1,53 @@
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tii::http::request_context::RequestContext;
+use tii::http::response::Response;
+use tii::http::StatusCode;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+#[test]
+pub fn tc90() {
+  let (started, started_rx) = mpsc::channel();
+  let (finished, finished_rx) = mpsc::channel();
+  let server = TiiBuilder::builder_arc(move |builder| {
+    builder.router(|router| {
+      router.route_any("/slow", move |ctx: &RequestContext| -> TiiResult<Response> {
+        started.send(ctx.is_disconnected()).unwrap();
+        let begin = Instant::now();
+        while !ctx.is_disconnected() {
+          if begin.elapsed() > Duration::from_secs(10) {
+            finished.send(false).unwrap();
+            return Ok(Response::new(StatusCode::OK));
+          }
+          thread::sleep(Duration::from_millis(10));
+        }
+        finished.send(true).unwrap();
+        Ok(Response::new(StatusCode::OK))
+      })
+    })
+  })
+  .unwrap();
+
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let addr = listener.local_addr().unwrap();
+  let handle = thread::spawn(move || {
+    let (stream, _) = listener.accept().unwrap();
+    _ = server.handle_connection(stream);
+  });
+
+  let mut client = TcpStream::connect(addr).unwrap();
+  client.write_all(b"GET /slow HTTP/1.1\r\nContent-Length: 0\r\n\r\n").unwrap();
+
+  let disconnected = started_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+  assert!(!disconnected, "connected client reported as disconnected");
+
+  drop(client);
+
+  let detected = finished_rx.recv_timeout(Duration::from_secs(15)).unwrap();
+  assert!(detected, "handler did not notice the disconnect");
+  handle.join().unwrap();
+}
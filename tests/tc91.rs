@@ -0,0 +1,71 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_router_builder::TiiRouterBuilder;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn endpoint(name: &'static str) -> impl Fn(&RequestContext) -> TiiResult<Response> + Send + Sync {
+  move |_: &RequestContext| Ok(Response::ok(name, MimeType::TextPlain))
+}
+
+fn serve(server: &TiiServer, path: &str) -> String {
+  let stream = MockStream::with_str(format!("GET {path} HTTP/1.1\r\n\r\n").as_str());
+  server.handle_connection(stream.to_stream()).expect("ERR");
+  let data = stream.copy_written_data_to_string();
+  data.split_once("\r\n\r\n").expect("ERR").1.to_string()
+}
+
+fn build(routes: &[(&'static str, &'static str)]) -> TiiServer {
+  TiiBuilder::default()
+    .router(|mut router: TiiRouterBuilder| {
+      for (path, name) in routes {
+        router = router.route_get(path, endpoint(name))?;
+      }
+      Ok(router)
+    })
+    .expect("ERR")
+    .build()
+}
+
+#[test]
+pub fn tc91_exact_beats_catch_all_in_any_order() {
+  for server in [
+    build(&[("/*", "static"), ("/api/users", "users")]),
+    build(&[("/api/users", "users"), ("/*", "static")]),
+  ] {
+    assert_eq!(serve(&server, "/api/users"), "users");
+    assert_eq!(serve(&server, "/api/other"), "static");
+    assert_eq!(serve(&server, "/index.html"), "static");
+  }
+}
+
+#[test]
+pub fn tc91_most_specific_part_wins() {
+  let routes = [
+    ("/*", "catch_all"),
+    ("/api/*", "api_wildcard"),
+    ("/api/{name}", "api_variable"),
+    ("/api/{name}/posts", "variable_posts"),
+    ("/api/{id:[0-9]+}/posts", "regex_posts"),
+    ("/api/users", "api_users"),
+    ("/api", "api"),
+  ];
+
+  let mut reversed = routes;
+  reversed.reverse();
+
+  for server in [build(&routes), build(&reversed)] {
+    assert_eq!(serve(&server, "/api/users"), "api_users");
+    assert_eq!(serve(&server, "/api/alice"), "api_variable");
+    assert_eq!(serve(&server, "/api/42/posts"), "regex_posts");
+    assert_eq!(serve(&server, "/api/alice/posts"), "variable_posts");
+    assert_eq!(serve(&server, "/api/alice/comments"), "api_wildcard");
+    assert_eq!(serve(&server, "/api"), "api");
+    assert_eq!(serve(&server, "/other"), "catch_all");
+  }
+}
@@ -0,0 +1,38 @@
+use crate::mock_stream::MockStream;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn languages_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let languages = ctx
+    .preferred_languages()
+    .into_iter()
+    .map(|(language, weight)| format!("{language}={weight}"))
+    .collect::<Vec<_>>()
+    .join(" ");
+  Ok(Response::ok(languages, MimeType::TextPlain))
+}
+
+fn languages(headers: &str) -> String {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/languages", languages_route))
+    .expect("ERR")
+    .build();
+  let stream = MockStream::with_str(format!("GET /languages HTTP/1.1\r\n{headers}\r\n").as_str());
+  server.handle_connection(stream.to_stream()).expect("ERR");
+  let data = stream.copy_written_data_to_string();
+  data.split_once("\r\n\r\n").expect("ERR").1.to_string()
+}
+
+#[test]
+pub fn tc92() {
+  assert_eq!(languages("Accept-Language: en-US,en;q=0.8,fr;q=0.5\r\n"), "en-US=1 en=0.8 fr=0.5");
+  assert_eq!(languages("Accept-Language: fr;q=0.5, de , en;q=0.8\r\n"), "de=1 en=0.8 fr=0.5");
+  assert_eq!(languages("Accept-Language: fr, de;q=0, *;q=0.1\r\n"), "fr=1 *=0.1");
+  assert_eq!(languages("Accept-Language: fr;q=0.5\r\nAccept-Language: it\r\n"), "it=1 fr=0.5");
+  assert_eq!(languages(""), "");
+}
@@ -0,0 +1,51 @@
+#[cfg(feature = "extras")]
+mod mock_stream;
+
+#[cfg(feature = "extras")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use std::sync::atomic::AtomicBool;
+  use std::sync::atomic::Ordering::SeqCst;
+  use std::sync::Arc;
+  use tii::extras::builtin_endpoints;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_server::TiiServer;
+
+  fn get(server: &TiiServer, path: &str) -> String {
+    let stream = MockStream::with_str(format!("GET {path} HTTP/1.1\r\n\r\n").as_str());
+    server.handle_connection(stream.to_stream()).unwrap();
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn run() {
+    let ready = Arc::new(AtomicBool::new(false));
+    let check = ready.clone();
+    let server = TiiBuilder::default()
+      .router(|rt| {
+        rt.route_get("/healthz", builtin_endpoints::health())?
+          .route_get("/readyz", builtin_endpoints::readiness(move || check.load(SeqCst)))
+      })
+      .unwrap()
+      .build();
+
+    let health = get(&server, "/healthz");
+    assert!(health.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(health.ends_with("\r\n\r\nOK"));
+
+    let not_ready = get(&server, "/readyz");
+    assert!(not_ready.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    assert!(not_ready.contains("\r\nContent-Type: text/plain\r\n"));
+    assert!(not_ready.ends_with("\r\n\r\nNOT READY"));
+
+    ready.store(true, SeqCst);
+    let ready = get(&server, "/readyz");
+    assert!(ready.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(ready.ends_with("\r\n\r\nREADY"));
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn tc93() {
+  inner::run();
+}
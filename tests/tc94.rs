@@ -0,0 +1,77 @@
+use crate::mock_stream::MockStream;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+use tii::tii_server::TiiServer;
+
+mod mock_stream;
+
+fn body_route(ctx: &RequestContext) -> TiiResult<Response> {
+  let len = ctx.request_head().path().len() * 1000;
+  Ok(Response::ok("x".repeat(len), MimeType::TextPlain))
+}
+
+fn server(buffer_size: Option<usize>, cork: bool) -> TiiServer {
+  TiiBuilder::default()
+    .router(|rt| rt.route_any("/*", body_route))
+    .expect("ERR")
+    .with_write_buffer_size(buffer_size)
+    .expect("ERR")
+    .with_tcp_cork(cork)
+    .expect("ERR")
+    .build()
+}
+
+const REQUESTS: &str =
+  "GET /a HTTP/1.1\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\nGET /abcdefghijklmnopqrstuvwxyz HTTP/1.1\r\n\r\n";
+
+fn mock_response(server: &TiiServer) -> String {
+  let stream = MockStream::with_str(REQUESTS);
+  server.handle_connection(stream.to_stream()).expect("ERR");
+  stream.copy_written_data_to_string()
+}
+
+fn tcp_response(server: TiiServer) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").expect("ERR");
+  let addr = listener.local_addr().expect("ERR");
+  let handle = thread::spawn(move || {
+    let (stream, _) = listener.accept().expect("ERR");
+    server.handle_connection(stream).expect("ERR");
+  });
+
+  let mut client = TcpStream::connect(addr).expect("ERR");
+  client.write_all(REQUESTS.as_bytes()).expect("ERR");
+  let mut response = String::new();
+  client.read_to_string(&mut response).expect("ERR");
+  handle.join().expect("ERR");
+  response
+}
+
+#[test]
+pub fn tc94() {
+  let expected = mock_response(&server(None, false));
+  assert!(expected.starts_with("HTTP/1.1 200 OK\r\n"));
+  assert!(expected.ends_with(&"x".repeat(27000)));
+
+  for buffer_size in [1, 7, 64, 1000, 0x10000] {
+    assert_eq!(mock_response(&server(Some(buffer_size), true)), expected);
+  }
+
+  assert_eq!(tcp_response(server(None, false)), expected);
+  assert_eq!(tcp_response(server(Some(64), true)), expected);
+  assert_eq!(tcp_response(server(Some(0x10000), true)), expected);
+}
+
+#[test]
+pub fn tc94_http09() {
+  for buffer_size in [None, Some(1), Some(0x10000)] {
+    let stream = MockStream::with_str("GET /a\r\n");
+    server(buffer_size, false).handle_connection(stream.to_stream()).expect("ERR");
+    assert_eq!(stream.copy_written_data_to_string(), "x".repeat(2000));
+  }
+}
@@ -0,0 +1,65 @@
+#[cfg(all(target_os = "linux", feature = "extras"))]
+mod inner {
+  use std::io::{Read, Write};
+  use std::os::unix::fs::MetadataExt;
+  use std::os::unix::net::{UnixListener, UnixStream};
+  use std::thread;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+
+  fn credentials_route(ctx: &RequestContext) -> TiiResult<Response> {
+    let body = match ctx.peer_unix_credentials() {
+      Some(credentials) => format!("{} {} {}", credentials.uid, credentials.gid, credentials.pid),
+      None => "none".to_string(),
+    };
+    Ok(Response::ok(body, MimeType::TextPlain))
+  }
+
+  pub fn run() {
+    let server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| router.route_any("/credentials", credentials_route))
+    })
+    .unwrap();
+
+    let path = std::env::temp_dir().join(format!("tii_tc95_{}.sock", std::process::id()));
+    _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    let unix_server = server.clone();
+    let handle = thread::spawn(move || {
+      let (stream, _) = listener.accept().unwrap();
+      unix_server.handle_connection(stream).unwrap();
+    });
+
+    let mut client = UnixStream::connect(&path).unwrap();
+    client.write_all(b"GET /credentials HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    client.read_to_string(&mut response).unwrap();
+    handle.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let process = std::fs::metadata("/proc/self").unwrap();
+    let expected = format!("{} {} {}", process.uid(), process.gid(), std::process::id());
+    assert!(response.ends_with(&format!("\r\n\r\n{expected}")), "{response}");
+
+    let (tcp_server, tcp_client) = {
+      let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+      let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+      (listener.accept().unwrap().0, client)
+    };
+    let mut tcp_client = tcp_client;
+    tcp_client.write_all(b"GET /credentials HTTP/1.1\r\n\r\n").unwrap();
+    server.handle_connection(tcp_server).unwrap();
+    let mut response = String::new();
+    tcp_client.read_to_string(&mut response).unwrap();
+    assert!(response.ends_with("\r\n\r\nnone"), "{response}");
+  }
+}
+
+#[cfg(all(target_os = "linux", feature = "extras"))]
+#[test]
+pub fn tc95() {
+  inner::run();
+}
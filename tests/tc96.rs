@@ -0,0 +1,50 @@
+#[cfg(feature = "extras")]
+mod mock_stream;
+
+#[cfg(feature = "extras")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use tii::extras::builtin_endpoints;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_server::TiiServer;
+
+  fn request(server: &TiiServer, request: &str) -> String {
+    let stream = MockStream::with_str(request);
+    server.handle_connection(stream.to_stream()).unwrap();
+    stream.copy_written_data_to_string()
+  }
+
+  pub fn run() {
+    let server = TiiBuilder::default()
+      .router(|rt| {
+        rt.route_any("/found", builtin_endpoints::redirect_found("/target"))?
+          .route_any("/see-other", builtin_endpoints::redirect_see_other("/result"))?
+          .route_any("/temporary", builtin_endpoints::redirect_temporary("/elsewhere"))?
+          .route_any("/permanent", builtin_endpoints::redirect_permanent("https://example.com/"))?
+          .route_any("/redirect", builtin_endpoints::redirect("/new"))
+      })
+      .unwrap()
+      .build();
+
+    for (path, status, location) in [
+      ("/found", "302 Found", "/target"),
+      ("/see-other", "303 See Other", "/result"),
+      ("/temporary", "307 Temporary Redirect", "/elsewhere"),
+      ("/permanent", "308 Permanent Redirect", "https://example.com/"),
+      ("/redirect", "308 Permanent Redirect", "/new"),
+    ] {
+      let response =
+        request(&server, format!("POST {path} HTTP/1.1\r\nContent-Length: 4\r\n\r\nform").as_str());
+      assert_eq!(
+        response,
+        format!("HTTP/1.1 {status}\r\nLocation: {location}\r\nConnection: Close\r\nContent-Length: 0\r\n\r\n")
+      );
+    }
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn tc96() {
+  inner::run();
+}
@@ -0,0 +1,47 @@
+use crate::mock_stream::MockStream;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tii::http::method::Method;
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::{Response, StatusCode};
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+mod mock_stream;
+
+fn slow_route(_: &RequestContext) -> TiiResult<Response> {
+  thread::sleep(Duration::from_millis(20));
+  Ok(Response::ok("slow", MimeType::TextPlain))
+}
+
+#[test]
+pub fn tc97() {
+  let (sender, receiver) = mpsc::channel();
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_get("/slow", slow_route))
+    .expect("ERR")
+    .with_request_observer(move |completed| sender.send(completed.clone()).expect("ERR"))
+    .expect("ERR")
+    .build();
+
+  let stream = MockStream::with_str(
+    "GET /slow?a=b HTTP/1.1\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\nGET /missing HTTP/1.1\r\nContent-Length: 0\r\n\r\n",
+  );
+  server.handle_connection(stream.to_stream()).expect("ERR");
+
+  let first = receiver.try_recv().expect("ERR");
+  assert_eq!(first.method, Method::Get);
+  assert_eq!(first.path, "/slow");
+  assert_eq!(first.status, StatusCode::OK);
+  assert!(first.duration >= Duration::from_millis(20), "{:?}", first.duration);
+  assert!(first.duration < Duration::from_secs(10), "{:?}", first.duration);
+
+  let second = receiver.try_recv().expect("ERR");
+  assert_eq!(second.method, Method::Get);
+  assert_eq!(second.path, "/missing");
+  assert_eq!(second.status, StatusCode::NotFound);
+
+  assert!(receiver.try_recv().is_err());
+}
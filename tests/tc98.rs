@@ -0,0 +1,82 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+use tii::http::mime::MimeType;
+use tii::http::request_context::RequestContext;
+use tii::http::Response;
+use tii::tii_builder::TiiBuilder;
+use tii::tii_error::TiiResult;
+
+fn route(_: &RequestContext) -> TiiResult<Response> {
+  Ok(Response::ok("okay", MimeType::TextPlain))
+}
+
+fn connect() -> (TcpStream, thread::JoinHandle<()>) {
+  let server = TiiBuilder::default()
+    .router(|rt| rt.route_any("/*", route))
+    .expect("ERR")
+    .with_read_timeout(Some(Duration::from_secs(10)))
+    .expect("ERR")
+    .with_keep_alive_timeout(Some(Duration::from_millis(200)))
+    .expect("ERR")
+    .build();
+
+  let listener = TcpListener::bind("127.0.0.1:0").expect("ERR");
+  let addr = listener.local_addr().expect("ERR");
+  let handle = thread::spawn(move || {
+    let (stream, _) = listener.accept().expect("ERR");
+    server.handle_connection(stream).expect("ERR");
+  });
+
+  (TcpStream::connect(addr).expect("ERR"), handle)
+}
+
+fn read_response(client: &mut TcpStream) -> String {
+  let mut response = Vec::new();
+  let mut buf = [0u8; 1024];
+  while !response.ends_with(b"okay") {
+    let n = client.read(&mut buf).expect("ERR");
+    assert_ne!(n, 0, "{}", String::from_utf8_lossy(&response));
+    response.extend_from_slice(buf.get(..n).expect("ERR"));
+  }
+  String::from_utf8(response).expect("ERR")
+}
+
+const KEEP_ALIVE_REQUEST: &str =
+  "GET /a HTTP/1.1\r\nConnection: Keep-Alive\r\nContent-Length: 0\r\n\r\n";
+
+#[test]
+pub fn tc98_idle_connection_is_closed() {
+  let (mut client, handle) = connect();
+  client.set_read_timeout(Some(Duration::from_secs(5))).expect("ERR");
+
+  client.write_all(KEEP_ALIVE_REQUEST.as_bytes()).expect("ERR");
+  let response = read_response(&mut client);
+  assert!(response.contains("Connection: Keep-Alive\r\n"), "{response}");
+
+  let idle = Instant::now();
+  let mut rest = Vec::new();
+  client.read_to_end(&mut rest).expect("ERR");
+  assert!(rest.is_empty());
+  assert!(idle.elapsed() < Duration::from_secs(5), "{:?}", idle.elapsed());
+  handle.join().expect("ERR");
+}
+
+#[test]
+pub fn tc98_slow_request_is_not_cut_off() {
+  let (mut client, handle) = connect();
+  client.set_read_timeout(Some(Duration::from_secs(5))).expect("ERR");
+
+  client.write_all(KEEP_ALIVE_REQUEST.as_bytes()).expect("ERR");
+  read_response(&mut client);
+
+  for part in ["GET /b HTTP/1.1\r\n", "Content-Length: 0\r\n", "\r\n"] {
+    client.write_all(part.as_bytes()).expect("ERR");
+    thread::sleep(Duration::from_millis(400));
+  }
+
+  let response = read_response(&mut client);
+  assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+  handle.join().expect("ERR");
+}
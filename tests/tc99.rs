@@ -0,0 +1,100 @@
+#[cfg(feature = "extras")]
+mod mock_stream;
+
+#[cfg(feature = "extras")]
+mod inner {
+  use crate::mock_stream::MockStream;
+  use std::fs::{File, OpenOptions};
+  use std::io::Write;
+  use std::path::PathBuf;
+  use tii::extras::builtin_endpoints;
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::response_body::ResponseBody;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+  use tii::tii_server::TiiServer;
+
+  const SIZE: usize = 3 * 1024 * 1024 + 17;
+
+  fn content() -> Vec<u8> {
+    (0..SIZE).map(|i| (i % 251) as u8).collect()
+  }
+
+  fn large_file(name: &str) -> &'static str {
+    let path: PathBuf =
+      std::env::temp_dir().join(format!("tii_tc99_{}_{name}.bin", std::process::id()));
+    std::fs::write(&path, content()).expect("ERR");
+    Box::leak(path.to_str().expect("ERR").to_string().into_boxed_str())
+  }
+
+  fn get(server: &TiiServer, path: &str) -> (String, Vec<u8>) {
+    let stream = MockStream::with_str(&format!("GET {path} HTTP/1.1\r\n\r\n"));
+    server.handle_connection(stream.to_stream()).expect("ERR");
+    let written = stream.copy_written_data();
+    let split = written.windows(4).position(|w| w == b"\r\n\r\n").expect("ERR") + 4;
+    let (head, body) = written.split_at(split);
+    (String::from_utf8(head.to_vec()).expect("ERR"), body.to_vec())
+  }
+
+  pub fn streamed() {
+    let path = large_file("streamed");
+    let server = TiiBuilder::default()
+      .router(|rt| {
+        rt.route_get("/file", builtin_endpoints::serve_file(path))?.route_get(
+          "/body",
+          move |ctx: &RequestContext| -> TiiResult<Response> {
+            let response = builtin_endpoints::serve_file(path)(ctx)?;
+            // The file is not read into memory, the body only holds the open file and its size.
+            assert_eq!(
+              format!("{:?}", response.body().expect("ERR")),
+              format!("ResponseBody::FixedSizeFile(file, {SIZE})")
+            );
+            Ok(response)
+          },
+        )
+      })
+      .expect("ERR")
+      .build();
+
+    for route in ["/file", "/body"] {
+      let (head, body) = get(&server, route);
+      assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{head}");
+      assert!(head.contains(&format!("Content-Length: {SIZE}\r\n")), "{head}");
+      assert!(body == content(), "body of {route} differs");
+    }
+    std::fs::remove_file(path).expect("ERR");
+  }
+
+  pub fn file_grows_while_sent() {
+    let path = large_file("grows");
+    let server = TiiBuilder::default()
+      .router(|rt| {
+        rt.route_get("/grows", move |_: &RequestContext| -> TiiResult<Response> {
+          let body = ResponseBody::from_file(File::open(path)?)?;
+          OpenOptions::new().append(true).open(path)?.write_all(b"appended later")?;
+          Ok(Response::ok(body, MimeType::ApplicationOctetStream))
+        })
+      })
+      .expect("ERR")
+      .build();
+
+    let (head, body) = get(&server, "/grows");
+    assert!(head.contains(&format!("Content-Length: {SIZE}\r\n")), "{head}");
+    assert!(body == content(), "only the announced length may be sent");
+    std::fs::remove_file(path).expect("ERR");
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn tc99_streamed() {
+  inner::streamed();
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn tc99_file_grows_while_sent() {
+  inner::file_grows_while_sent();
+}
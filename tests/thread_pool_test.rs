@@ -0,0 +1,206 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{Read, Write};
+  use std::net::{SocketAddr, TcpListener, TcpStream};
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::sync::{mpsc, Arc, Mutex};
+  use std::thread;
+  use std::time::{Duration, Instant};
+  use tii::extras;
+  use tii::extras::{Connector, PoolStats, ThreadPool};
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::ThreadAdapter;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+
+  static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+  static MAX_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+  fn slow(_: &RequestContext) -> TiiResult<Response> {
+    let active = ACTIVE.fetch_add(1, Ordering::SeqCst) + 1;
+    MAX_ACTIVE.fetch_max(active, Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(200));
+    ACTIVE.fetch_sub(1, Ordering::SeqCst);
+    Ok(Response::ok("Hello", MimeType::TextPlain))
+  }
+
+  fn request(addr: SocketAddr) -> TiiResult<String> {
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.write_all("GET / HTTP/1.1\r\n\r\n".as_bytes())?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response)
+  }
+
+  pub(crate) fn work() -> TiiResult<()> {
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| router.route_any("/*", slow))?.ok()
+    })?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let pool = ThreadPool::new(2, 8)?;
+    assert_eq!(pool.threads(), 2);
+    let connector = extras::TcpConnector::from_listener_pooled(listener, tii_server, pool)?;
+
+    let clients = (0..6).map(|_| thread::spawn(move || request(addr))).collect::<Vec<_>>();
+    for client in clients {
+      let response = client.join().expect("client panicked")?;
+      assert_eq!(
+        response,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 5\r\n\r\nHello"
+      );
+    }
+
+    assert_eq!(MAX_ACTIVE.load(Ordering::SeqCst), 2);
+    assert!(connector.shutdown_and_join(None));
+    Ok(())
+  }
+
+  pub(crate) fn stats() -> TiiResult<()> {
+    let pool = ThreadPool::new(2, 8)?;
+    assert_eq!(pool.stats(), PoolStats { queued: 0, busy: 0, executed: 0 });
+
+    let (release, released) = mpsc::channel::<()>();
+    let released = Arc::new(Mutex::new(released));
+    let mut handles = Vec::new();
+    for _ in 0..5 {
+      let released = released.clone();
+      handles.push(pool.spawn(Box::new(move || {
+        _ = released.lock().unwrap().recv();
+      }))?);
+    }
+
+    // One thread waits for the release while holding the lock, the other waits for the lock.
+    let start = Instant::now();
+    while pool.stats().busy < 2 {
+      assert!(start.elapsed() < Duration::from_secs(10), "{:?}", pool.stats());
+      thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(pool.stats(), PoolStats { queued: 3, busy: 2, executed: 0 });
+
+    for _ in 0..5 {
+      release.send(()).unwrap();
+    }
+    for handle in handles {
+      handle.join().unwrap();
+    }
+
+    let start = Instant::now();
+    while pool.stats().executed < 5 {
+      assert!(start.elapsed() < Duration::from_secs(10), "{:?}", pool.stats());
+      thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(pool.stats(), PoolStats { queued: 0, busy: 0, executed: 5 });
+    Ok(())
+  }
+
+  /// Spawns blocking tasks and returns the amount of them that run concurrently.
+  fn concurrent_tasks(pool: &ThreadPool, tasks: usize) -> TiiResult<usize> {
+    let (release, released) = mpsc::channel::<()>();
+    let released = Arc::new(Mutex::new(released));
+    let mut handles = Vec::new();
+    for _ in 0..tasks {
+      let released = released.clone();
+      handles.push(pool.spawn(Box::new(move || {
+        _ = released.lock().unwrap().recv();
+      }))?);
+    }
+
+    let start = Instant::now();
+    while pool.stats().busy < tasks.min(pool.threads()) {
+      assert!(start.elapsed() < Duration::from_secs(10), "{:?}", pool.stats());
+      thread::sleep(Duration::from_millis(10));
+    }
+    // Give surplus threads the chance to pick up tasks.
+    thread::sleep(Duration::from_millis(200));
+    let busy = pool.stats().busy;
+
+    for _ in 0..tasks {
+      release.send(()).unwrap();
+    }
+    for handle in handles {
+      handle.join().unwrap();
+    }
+    Ok(busy)
+  }
+
+  pub(crate) fn join_exited() -> TiiResult<()> {
+    let pool = ThreadPool::new(2, 8)?;
+    let (release, released) = mpsc::channel::<()>();
+    let released = Arc::new(Mutex::new(released));
+    let mut handles = Vec::new();
+    for _ in 0..2 {
+      let released = released.clone();
+      handles.push(pool.spawn(Box::new(move || {
+        _ = released.lock().unwrap().recv();
+      }))?);
+    }
+
+    // Both threads are busy, so neither can exit before its task is done.
+    pool.resize(1)?;
+    let start = Instant::now();
+    assert!(!pool.join_exited(Some(Duration::from_millis(200))));
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    for _ in 0..2 {
+      release.send(()).unwrap();
+    }
+    for handle in handles {
+      handle.join().unwrap();
+    }
+    assert!(pool.join_exited(Some(Duration::from_secs(10))));
+    Ok(())
+  }
+
+  pub(crate) fn resize() -> TiiResult<()> {
+    let pool = ThreadPool::new(2, 8)?;
+    assert_eq!(concurrent_tasks(&pool, 6)?, 2);
+
+    pool.resize(4)?;
+    assert_eq!(pool.threads(), 4);
+    assert_eq!(concurrent_tasks(&pool, 6)?, 4);
+
+    pool.resize(1)?;
+    assert_eq!(pool.threads(), 1);
+    assert!(pool.join_exited(Some(Duration::from_secs(10))));
+    assert_eq!(concurrent_tasks(&pool, 3)?, 1);
+
+    pool.resize(0)?;
+    assert_eq!(pool.threads(), 1);
+    assert!(pool.join_exited(None));
+    let start = Instant::now();
+    while pool.stats().executed < 15 {
+      assert!(start.elapsed() < Duration::from_secs(10), "{:?}", pool.stats());
+      thread::sleep(Duration::from_millis(10));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn thread_pool_bounds_concurrency() {
+  inner::work().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn thread_pool_stats() {
+  inner::stats().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn thread_pool_resize() {
+  inner::resize().expect("ERROR");
+}
+
+#[cfg(feature = "extras")]
+#[test]
+fn thread_pool_join_exited() {
+  inner::join_exited().expect("ERROR");
+}
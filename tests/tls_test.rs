@@ -0,0 +1,153 @@
+#[cfg(all(feature = "extras", feature = "tls"))]
+mod inner {
+  use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+  use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+  use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+  use rustls::{
+    ClientConfig, ClientConnection, DigitallySignedStruct, SignatureScheme, StreamOwned,
+  };
+  use rustls_pemfile::{certs, private_key};
+  use std::io::{BufReader, Cursor, Read, Write};
+  use std::net::TcpStream;
+  use std::sync::Arc;
+  use std::time::Duration;
+  use tii::extras::{Connector, ConnectorMeta, TlsTcpConnector};
+  use tii::http::mime::MimeType;
+  use tii::http::request_context::RequestContext;
+  use tii::http::Response;
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+
+  /// The example certificate is self-signed, the test only cares about the encrypted transport.
+  #[derive(Debug)]
+  struct AcceptAnyCert(Arc<CryptoProvider>);
+
+  impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+      &self,
+      _end_entity: &CertificateDer<'_>,
+      _intermediates: &[CertificateDer<'_>],
+      _server_name: &ServerName<'_>,
+      _ocsp_response: &[u8],
+      _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+      Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+      &self,
+      message: &[u8],
+      cert: &CertificateDer<'_>,
+      dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+      verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+      &self,
+      message: &[u8],
+      cert: &CertificateDer<'_>,
+      dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+      verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+      self.0.signature_verification_algorithms.supported_schemes()
+    }
+  }
+
+  fn load_certs() -> Vec<CertificateDer<'static>> {
+    let mut reader = BufReader::new(Cursor::new(include_bytes!("../examples/ssl/cert.pem")));
+    certs(&mut reader).map(|cert| cert.expect("invalid cert")).collect()
+  }
+
+  fn load_private_key() -> PrivateKeyDer<'static> {
+    let mut reader = BufReader::new(Cursor::new(include_bytes!("../examples/ssl/key.pem")));
+    private_key(&mut reader).expect("invalid key").expect("no key")
+  }
+
+  fn route(ctx: &RequestContext) -> Response {
+    match ctx.get_stream_meta::<ConnectorMeta>() {
+      Some(ConnectorMeta::TlsTcp) => Response::ok("Hello TLS", MimeType::TextPlain),
+      _ => Response::forbidden("not tls", MimeType::TextPlain),
+    }
+  }
+
+  fn sni_route(ctx: &RequestContext) -> Response {
+    Response::ok(ctx.sni_hostname().unwrap_or("none"), MimeType::TextPlain)
+  }
+
+  fn client(
+    addr: &str,
+    server_name: &'static str,
+  ) -> TiiResult<StreamOwned<ClientConnection, TcpStream>> {
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+      .with_safe_default_protocol_versions()?
+      .dangerous()
+      .with_custom_certificate_verifier(Arc::new(AcceptAnyCert(provider)))
+      .with_no_client_auth();
+    let connection = ClientConnection::new(Arc::new(config), ServerName::try_from(server_name)?)?;
+    let socket = TcpStream::connect(addr)?;
+    socket.set_read_timeout(Some(Duration::from_secs(10)))?;
+    Ok(StreamOwned::new(connection, socket))
+  }
+
+  pub(crate) fn work() -> TiiResult<()> {
+    let tii_server =
+      TiiBuilder::builder_arc(|builder| builder.router(|rt| rt.route_get("/", route)))?;
+    let connector = TlsTcpConnector::start_unpooled_with_cert(
+      "127.0.0.1:28443",
+      load_certs(),
+      load_private_key(),
+      tii_server,
+    )?;
+
+    let mut stream = client("127.0.0.1:28443", "localhost")?;
+
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n")?;
+    let mut response = String::new();
+    // The server closes the connection without close_notify once the response was written.
+    _ = stream.read_to_string(&mut response);
+    assert_eq!(
+      response,
+      "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: Close\r\nContent-Length: 9\r\n\r\nHello TLS"
+    );
+
+    assert!(connector.shutdown_and_join(None));
+    Ok(())
+  }
+
+  pub(crate) fn work_sni() -> TiiResult<()> {
+    let tii_server =
+      TiiBuilder::builder_arc(|builder| builder.router(|rt| rt.route_get("/", sni_route)))?;
+    let connector = TlsTcpConnector::start_unpooled_with_cert(
+      "127.0.0.1:28444",
+      load_certs(),
+      load_private_key(),
+      tii_server,
+    )?;
+
+    let mut stream = client("127.0.0.1:28444", "tenant.example")?;
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: other.example\r\n\r\n")?;
+    let mut response = String::new();
+    _ = stream.read_to_string(&mut response);
+    assert!(response.ends_with("\r\n\r\ntenant.example"), "{response}");
+
+    assert!(connector.shutdown_and_join(None));
+    Ok(())
+  }
+}
+
+#[cfg(all(feature = "extras", feature = "tls"))]
+#[test]
+fn tls_get() {
+  inner::work().expect("ERROR");
+}
+
+#[cfg(all(feature = "extras", feature = "tls"))]
+#[test]
+fn tls_sni_hostname() {
+  inner::work_sni().expect("ERROR");
+}
@@ -0,0 +1,79 @@
+use crate::mock_stream::MockStream;
+use tii::tii_error::WebsocketError;
+use tii::websocket::stream;
+
+mod mock_stream;
+
+#[test]
+pub fn test_oversized_frame() {
+  #[rustfmt::skip]
+  let data = [
+    0b1000_0010, // fin, opcode binary
+    0b0_1111111, // not mask, payload length 127 (extended payload length 64 bit)
+    0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, // extended payload length of 2^44
+  ];
+
+  let mock = MockStream::with_slice(&data);
+  let con = mock.to_stream();
+  let (_sender, mut receiver) = stream::new(con.as_ref());
+  let err = receiver.read_message().unwrap_err();
+  assert!(matches!(
+    err.downcast_ref::<WebsocketError>(),
+    Some(WebsocketError::MessageTooBig(0x10_0000_0000))
+  ));
+}
+
+#[test]
+pub fn test_invalid_utf8() {
+  #[rustfmt::skip]
+  let data = [
+    0b1000_0001, // fin, opcode text
+    0b0_0000010, // not mask, payload length 2
+    0xC3, 0x28, // invalid 2 byte sequence
+  ];
+
+  let mock = MockStream::with_slice(&data);
+  let con = mock.to_stream();
+  let (_sender, mut receiver) = stream::new(con.as_ref());
+  let err = receiver.read_message().unwrap_err();
+  match err.downcast_ref::<WebsocketError>() {
+    Some(WebsocketError::InvalidUtf8(data)) => assert_eq!(data.as_slice(), &[0xC3, 0x28]),
+    other => panic!("unexpected error {:?}", other),
+  }
+}
+
+#[test]
+pub fn test_clean_close() {
+  #[rustfmt::skip]
+  let data = [
+    0b1000_1000, // fin, opcode close
+    0b0_0000101, // not mask, payload length 5
+    0x03, 0xE8, // status code 1000
+    b'b', b'y', b'e', // reason
+  ];
+
+  let mock = MockStream::with_slice(&data);
+  let con = mock.to_stream();
+  let (sender, mut receiver) = stream::new(con.as_ref());
+  assert!(receiver.read_message().unwrap().is_none());
+  assert!(sender.is_closed());
+  let err = sender.text("Hello").unwrap_err();
+  match err.downcast_ref::<WebsocketError>() {
+    Some(WebsocketError::Closed { code, reason }) => {
+      assert_eq!(code, &Some(1000));
+      assert_eq!(reason.as_str(), "bye");
+    }
+    other => panic!("unexpected error {:?}", other),
+  }
+  assert!(mock.copy_written_data().is_empty());
+}
+
+#[test]
+pub fn test_io_error() {
+  let mock = MockStream::with_slice(&[0b1000_0001]);
+  let con = mock.to_stream();
+  let (_sender, mut receiver) = stream::new(con.as_ref());
+  let err = receiver.read_message().unwrap_err();
+  assert!(matches!(err.downcast_ref::<WebsocketError>(), Some(WebsocketError::Io(_))));
+  assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
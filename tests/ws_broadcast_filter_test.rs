@@ -0,0 +1,113 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{BufRead, BufReader, Read, Write};
+  use std::net::{SocketAddr, TcpListener, TcpStream};
+  use std::thread;
+  use std::time::Duration;
+  use tii::extras;
+  use tii::extras::{ws_link_hook, Connector, WsBroadcastBuilder, WsHandle};
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+  use tii::websocket::message::WebsocketMessage;
+
+  fn message_handler(handle: WsHandle, message: WebsocketMessage) {
+    match message.text() {
+      Some("subscribe") => {
+        handle.info().set_attribute("subscriber", "true").unwrap();
+        handle.send(WebsocketMessage::new_text("subscribed"));
+      }
+      Some("publish") => handle.broadcast_filtered(
+        |client| client.has_attribute("subscriber"),
+        WebsocketMessage::new_text("news"),
+      ),
+      _ => {}
+    }
+  }
+
+  struct Client(BufReader<TcpStream>);
+
+  impl Client {
+    fn connect(addr: SocketAddr) -> TiiResult<Self> {
+      let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+      stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+      stream.write_all(b"GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n")?;
+      let mut reader = BufReader::new(stream);
+      let mut line = String::new();
+      reader.read_line(&mut line)?;
+      assert_eq!(line, "HTTP/1.1 101 Switching Protocols\r\n");
+      while line != "\r\n" {
+        line.clear();
+        reader.read_line(&mut line)?;
+      }
+
+      Ok(Self(reader))
+    }
+
+    fn send(&mut self, text: &str) -> TiiResult<()> {
+      // fin + text, masked with an all zero key so the payload can be written as is.
+      let mut frame = vec![0b1000_0001, 0b1000_0000 | text.len() as u8, 0, 0, 0, 0];
+      frame.extend_from_slice(text.as_bytes());
+      self.0.get_mut().write_all(frame.as_slice())?;
+      Ok(())
+    }
+
+    fn receive(&mut self) -> TiiResult<String> {
+      loop {
+        let mut head = [0u8; 2];
+        self.0.read_exact(&mut head)?;
+        let mut payload = vec![0u8; (head[1] & 0x7F) as usize];
+        self.0.read_exact(payload.as_mut_slice())?;
+        if head[0] & 0x0F == 0x1 {
+          return Ok(String::from_utf8(payload)?);
+        }
+        // Heartbeat pings are ignored
+      }
+    }
+  }
+
+  pub(crate) fn run() -> TiiResult<()> {
+    let linker = WsBroadcastBuilder::default().with_message_handler(message_handler);
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| router.ws_route_any("/ws", ws_link_hook(linker.connect_hook())))?.ok()
+    })?;
+    let sender = linker.sender();
+    thread::spawn(move || linker.finalize().run());
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let connector = extras::TcpConnector::from_listener_unpooled(listener, tii_server)?;
+
+    let mut subscriber1 = Client::connect(addr)?;
+    let mut subscriber2 = Client::connect(addr)?;
+    let mut bystander = Client::connect(addr)?;
+
+    subscriber1.send("subscribe")?;
+    assert_eq!(subscriber1.receive()?, "subscribed");
+    subscriber2.send("subscribe")?;
+    assert_eq!(subscriber2.receive()?, "subscribed");
+
+    bystander.send("publish")?;
+    assert_eq!(subscriber1.receive()?, "news");
+    assert_eq!(subscriber2.receive()?, "news");
+
+    sender.broadcast_filtered(
+      |client| !client.has_attribute("subscriber"),
+      WebsocketMessage::new_text("only you"),
+    );
+    sender.broadcast(WebsocketMessage::new_text("everyone"));
+    // The bystander never saw the news, the next message it gets is the one meant for it.
+    assert_eq!(bystander.receive()?, "only you");
+    assert_eq!(bystander.receive()?, "everyone");
+    assert_eq!(subscriber1.receive()?, "everyone");
+    assert_eq!(subscriber2.receive()?, "everyone");
+
+    assert!(connector.shutdown_and_join(None));
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn ws_broadcast_filtered() {
+  inner::run().unwrap();
+}
@@ -0,0 +1,85 @@
+#[cfg(feature = "extras")]
+mod inner {
+  use std::io::{BufRead, BufReader, Read, Write};
+  use std::net::{TcpListener, TcpStream};
+  use std::sync::mpsc;
+  use std::thread;
+  use std::time::{Duration, Instant};
+  use tii::extras;
+  use tii::extras::{ws_link_hook, Connector, WsBroadcastBuilder, WsHandle};
+  use tii::tii_builder::TiiBuilder;
+  use tii::tii_error::TiiResult;
+  use tii::websocket::message::WebsocketMessage;
+
+  pub(crate) fn run() -> TiiResult<()> {
+    let (disconnected, disconnects) = mpsc::channel();
+    let linker = WsBroadcastBuilder::default()
+      .with_heartbeat(Duration::from_millis(200))
+      .with_idle_timeout(Duration::from_millis(800))
+      .with_message_handler(|_: WsHandle, _: WebsocketMessage| {})
+      .with_disconnect_handler(move |handle: WsHandle| {
+        disconnected.send(handle.peer_addr()).unwrap();
+      });
+    let tii_server = TiiBuilder::builder_arc(|builder| {
+      builder.router(|router| router.ws_route_any("/ws", ws_link_hook(linker.connect_hook())))?.ok()
+    })?;
+    thread::spawn(move || linker.finalize().run());
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let connector = extras::TcpConnector::from_listener_unpooled(listener, tii_server)?;
+
+    let stream = TcpStream::connect_timeout(&addr, Duration::from_secs(30))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut writer = stream.try_clone()?;
+    writer.write_all(b"GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n")?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    assert_eq!(line, "HTTP/1.1 101 Switching Protocols\r\n");
+    while line != "\r\n" {
+      line.clear();
+      reader.read_line(&mut line)?;
+    }
+
+    // The client keeps pinging (masked with an all zero key) but never sends a message.
+    // Each pong must echo the application data of the ping.
+    let connected_at = Instant::now();
+    thread::spawn(move || {
+      while writer.write_all(&[0b1000_1001, 0b1000_0010, 0, 0, 0, 0, b'h', b'i']).is_ok() {
+        thread::sleep(Duration::from_millis(50));
+      }
+    });
+
+    let mut pongs = 0;
+    let close_payload = loop {
+      assert!(connected_at.elapsed() < Duration::from_secs(10), "idle connection was not closed");
+      let mut head = [0u8; 2];
+      reader.read_exact(&mut head)?;
+      let mut payload = vec![0u8; (head[1] & 0x7F) as usize];
+      reader.read_exact(payload.as_mut_slice())?;
+      match head[0] & 0x0F {
+        0x8 => break payload,
+        0xA => {
+          assert_eq!(payload, b"hi");
+          pongs += 1;
+        }
+        _ => (), // heartbeat pings are ignored
+      }
+    };
+
+    assert!(pongs > 0);
+    assert!(connected_at.elapsed() >= Duration::from_millis(700));
+    assert_eq!(close_payload.get(..2), Some(1000u16.to_be_bytes().as_slice()));
+    disconnects.recv_timeout(Duration::from_secs(10))?;
+
+    assert!(connector.shutdown_and_join(None));
+    Ok(())
+  }
+}
+
+#[cfg(feature = "extras")]
+#[test]
+pub fn ws_idle_timeout() {
+  inner::run().unwrap();
+}